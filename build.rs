@@ -0,0 +1,5 @@
+fn main () {
+  // links the Node.js addon against the symbols node provides at load time
+  #[cfg(feature = "node")]
+  napi_build::setup();
+}
@@ -0,0 +1,24 @@
+#ifndef GRADES_LIST_H
+#define GRADES_LIST_H
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/*
+ * Logs in to Passport York, reads the course list and logs out.
+ *
+ * Returns JSON of the GPA and the courses, {"gpa": {...}, "grades": [...]}, or of why that failed,
+ * {"error": "...", "exit_code": 2}, with the exit codes of the command line. Duo is answered with
+ * a push when passcode is NULL. The result is never NULL and must be given to grades_free().
+ */
+char *grades_fetch_json(const char *username, const char *password, const char *passcode);
+
+/* Frees a string returned by grades_fetch_json(), NULL is ignored */
+void grades_free(char *json);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif
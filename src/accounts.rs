@@ -0,0 +1,83 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{GradesError, files};
+
+/// A profile registered with `serve --multi-user`, its password is kept in the keychain
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Account {
+  pub username: String,
+  /// Bearer token that reads this account's grades, and only this account's
+  pub token: String,
+}
+
+/// The accounts of a multi-user server by profile name, saved so they're served again after a restart
+///
+/// The file holds the API tokens, so it is only readable by its owner.
+#[derive(Debug, Default)]
+pub struct Registry {
+  path: PathBuf,
+  accounts: BTreeMap<String, Account>,
+}
+
+/// Where the accounts are saved, next to the history
+pub fn default_path () -> Option<PathBuf> {
+  dirs::data_dir().map(|dir| dir.join("grades_list").join("accounts.json"))
+}
+
+/// A new random token, for the API or an account
+pub fn new_token () -> String {
+  base64::encode_config(rand::random::<[u8; 24]>(), base64::URL_SAFE_NO_PAD)
+}
+
+impl Registry {
+  /// Reads the saved accounts, none when the file doesn't exist yet
+  pub fn open (path: &Path) -> Result<Registry, GradesError> {
+    let accounts = match std::fs::read(path) {
+      Ok(bytes) => serde_json::from_slice(&bytes)
+        .map_err(|e| GradesError::Storage(format!("could not read the accounts in {}: {}", path.display(), e)))?,
+      Err(e) if e.kind() == std::io::ErrorKind::NotFound => BTreeMap::new(),
+      Err(e) => return Err(e.into()),
+    };
+
+    Ok(Registry { path: path.to_owned(), accounts })
+  }
+
+  pub fn accounts (&self) -> &BTreeMap<String, Account> {
+    &self.accounts
+  }
+
+  /// Adds an account with a new token and saves the registry
+  ///
+  /// A profile name or username can only be registered once, since the session and the password
+  /// of an account are found by its username.
+  pub fn add (&mut self, name: &str, username: &str) -> Result<Account, GradesError> {
+    if self.accounts.contains_key(name) {
+      return Err(GradesError::Config(format!("a profile named \"{}\" is already registered", name)));
+    }
+    if let Some((taken, _)) = self.accounts.iter().find(|(_, account)| account.username == username) {
+      return Err(GradesError::Config(format!("{} is already registered as \"{}\"", username, taken)));
+    }
+
+    let account = Account { username: username.to_owned(), token: new_token() };
+    self.accounts.insert(name.to_owned(), account.clone());
+    self.save()?;
+    Ok(account)
+  }
+
+  /// Removes the account and saves the registry, returning it if it was registered
+  pub fn remove (&mut self, name: &str) -> Result<Option<Account>, GradesError> {
+    let removed = self.accounts.remove(name);
+    if removed.is_some() {
+      self.save()?;
+    }
+    Ok(removed)
+  }
+
+  fn save (&self) -> Result<(), GradesError> {
+    files::write_private(&self.path, &serde_json::to_vec_pretty(&self.accounts).expect("accounts serialize"))
+  }
+
+}
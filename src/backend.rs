@@ -0,0 +1,124 @@
+use std::io::Write;
+use std::str::FromStr;
+
+use async_trait::async_trait;
+
+use crate::{ClientOptions, CourseData, Endpoints, GradesClient, GradesError, SecondFactor};
+
+/// A university's student portal, logging in and reading the courses taken
+///
+/// The GPA, filters and output only ever see the `CourseData` a backend returns, so adding an
+/// institution means implementing this and adding it to `School`. York's `GradesClient` is the
+/// first backend, its transcript, timetable and degree progress pages stay York's own and are
+/// reached through `york()`.
+#[async_trait]
+pub trait SisBackend: Send + Sync {
+  /// Short name used in messages, like `"york"`
+  fn name (&self) -> &'static str;
+
+  /// Whether the session kept from an earlier login still works
+  async fn is_logged_in (&self) -> Result<bool, GradesError>;
+
+  /// Logs in, answering a second factor prompt with the given factor if the portal asks for one
+  async fn login (&self, username: &str, password: &str, factor: &SecondFactor) -> Result<(), GradesError>;
+
+  /// Reads every course taken, must be called after a successful `login()`
+  async fn fetch_grades (&self) -> Result<Vec<CourseData>, GradesError>;
+
+  async fn logout (&self) -> Result<(), GradesError>;
+
+  /// Whether there is a session that could still be logged in, to check before logging in again
+  fn has_session (&self) -> bool {
+    false
+  }
+
+  /// Writes the session for `load_session()` in a later run, a backend that can't keep one
+  /// writes nothing and logs in every time
+  fn save_session (&self, _writer: &mut dyn Write) -> Result<(), GradesError> {
+    Ok(())
+  }
+
+  /// Picks up a session written by `save_session()`
+  fn load_session (&self, _data: &[u8]) -> Result<(), GradesError> {
+    Ok(())
+  }
+
+  /// York's client, for the SIS pages beyond the course list, `None` for any other school
+  fn york (&self) -> Option<&GradesClient> {
+    None
+  }
+}
+
+#[async_trait]
+impl SisBackend for GradesClient {
+  fn name (&self) -> &'static str { "york" }
+
+  async fn is_logged_in (&self) -> Result<bool, GradesError> {
+    GradesClient::is_logged_in(self).await
+  }
+
+  async fn login (&self, username: &str, password: &str, factor: &SecondFactor) -> Result<(), GradesError> {
+    self.login_with(username, password, factor).await
+  }
+
+  async fn fetch_grades (&self) -> Result<Vec<CourseData>, GradesError> {
+    GradesClient::fetch_grades(self).await
+  }
+
+  async fn logout (&self) -> Result<(), GradesError> {
+    GradesClient::logout(self).await
+  }
+
+  fn has_session (&self) -> bool {
+    self.has_cookies()
+  }
+
+  fn save_session (&self, writer: &mut dyn Write) -> Result<(), GradesError> {
+    self.save_cookies(writer)
+  }
+
+  fn load_session (&self, data: &[u8]) -> Result<(), GradesError> {
+    self.load_cookies(data)
+  }
+
+  fn york (&self) -> Option<&GradesClient> {
+    Some(self)
+  }
+}
+
+/// Institution whose portal the grades are read from, picked with `--school`
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum School {
+  /// York University, through Passport York and SIS
+  #[default]
+  York,
+}
+
+impl School {
+  pub const NAMES: &'static [&'static str] = &["york"];
+
+  /// The name `--school` takes
+  pub fn name (self) -> &'static str {
+    match self {
+      School::York => "york",
+    }
+  }
+
+  /// A client for the school's portal at its own URLs
+  pub fn backend (self, options: &ClientOptions) -> Result<Box<dyn SisBackend>, GradesError> {
+    match self {
+      School::York => Ok(Box::new(GradesClient::with_options(Endpoints::default(), options)?)),
+    }
+  }
+}
+
+impl FromStr for School {
+  type Err = String;
+
+  fn from_str (s: &str) -> Result<School, String> {
+    match s {
+      "york" => Ok(School::York),
+      _ => Err(format!("unknown school \"{}\", expected one of {}", s, School::NAMES.join(", "))),
+    }
+  }
+}
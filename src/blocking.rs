@@ -0,0 +1,106 @@
+use std::io::{Read, Write};
+
+use tokio::runtime::{Builder, Runtime};
+
+use crate::{ClientOptions, CourseData, Endpoints, Gpa, GradesError, SecondFactor};
+
+/// `crate::GradesClient` for scripts and GUIs that don't run an async runtime
+///
+/// Like `reqwest::blocking`, every method drives the async client to completion on a runtime of
+/// its own, so none of them may be called from within an async context.
+pub struct GradesClient {
+  inner: crate::GradesClient,
+  runtime: Runtime,
+}
+
+impl GradesClient {
+  pub fn new () -> Result<GradesClient, GradesError> {
+    GradesClient::with_options(Endpoints::default(), &ClientOptions::default())
+  }
+
+  /// A client for York's pages at other URLs, like a test server
+  pub fn with_endpoints (endpoints: Endpoints) -> Result<GradesClient, GradesError> {
+    GradesClient::with_options(endpoints, &ClientOptions::default())
+  }
+
+  pub fn with_options (endpoints: Endpoints, options: &ClientOptions) -> Result<GradesClient, GradesError> {
+    Ok(GradesClient {
+      inner: crate::GradesClient::with_options(endpoints, options)?,
+      runtime: Builder::new_current_thread().enable_all().build()?,
+    })
+  }
+
+  /// The async client underneath, sharing its cookies
+  pub fn get_ref (&self) -> &crate::GradesClient {
+    &self.inner
+  }
+
+  /// Replaces the cookies with ones written by `save_cookies()`
+  pub fn load_cookies<R: Read> (&self, reader: R) -> Result<(), GradesError> {
+    self.inner.load_cookies(reader)
+  }
+
+  /// Whether there are any cookies that could belong to a session
+  pub fn has_cookies (&self) -> bool {
+    self.inner.has_cookies()
+  }
+
+  /// Writes the current cookies as JSON
+  pub fn save_cookies<W: Write> (&self, writer: W) -> Result<(), GradesError> {
+    self.inner.save_cookies(writer)
+  }
+
+  /// Whether the current cookies still give access to the course list
+  pub fn is_logged_in (&self) -> Result<bool, GradesError> {
+    self.runtime.block_on(self.inner.is_logged_in())
+  }
+
+  /// Logs in with Passport York credentials, answering a Duo prompt with a push
+  pub fn login (&self, username: &str, password: &str) -> Result<(), GradesError> {
+    self.runtime.block_on(self.inner.login(username, password))
+  }
+
+  /// Logs in, answering a Duo prompt with the given factor
+  pub fn login_with (&self, username: &str, password: &str, factor: &SecondFactor) -> Result<(), GradesError> {
+    self.runtime.block_on(self.inner.login_with(username, password, factor))
+  }
+
+  /// Scrapes the course list, must be called after a successful `login()`
+  pub fn fetch_grades (&self) -> Result<Vec<CourseData>, GradesError> {
+    self.runtime.block_on(self.inner.fetch_grades())
+  }
+
+  /// Raw HTML of the course list page
+  pub fn fetch_page (&self) -> Result<String, GradesError> {
+    self.runtime.block_on(self.inner.fetch_page())
+  }
+
+  /// Raw HTML of the grade report
+  pub fn fetch_transcript_page (&self) -> Result<String, GradesError> {
+    self.runtime.block_on(self.inner.fetch_transcript_page())
+  }
+
+  /// Raw HTML of the Degree Progress Report
+  pub fn fetch_degree_progress_page (&self) -> Result<String, GradesError> {
+    self.runtime.block_on(self.inner.fetch_degree_progress_page())
+  }
+
+  /// Raw HTML of the timetable of the current session
+  pub fn fetch_schedule_page (&self) -> Result<String, GradesError> {
+    self.runtime.block_on(self.inner.fetch_schedule_page())
+  }
+
+  /// Raw HTML of the enrolment appointments and of the important dates
+  pub fn fetch_dates_pages (&self) -> Result<(String, String), GradesError> {
+    self.runtime.block_on(self.inner.fetch_dates_pages())
+  }
+
+  /// Calculates the four point and nine point GPA of the given grades, `None` when none count
+  pub fn gpa (&self, grades: &[CourseData]) -> Option<Gpa> {
+    self.inner.gpa(grades)
+  }
+
+  pub fn logout (&self) -> Result<(), GradesError> {
+    self.runtime.block_on(self.inner.logout())
+  }
+}
@@ -0,0 +1,158 @@
+use std::time::{Duration, Instant};
+
+use fantoccini::{Client, ClientBuilder, Locator};
+use reqwest::Url;
+use serde_json::json;
+use tracing::{debug, info};
+
+use crate::{GradesClient, GradesError, SecondFactor, duo, failure};
+
+/// How long the browser gets to make it through Passport York, a Duo push included
+const LOGIN_TIMEOUT: Duration = Duration::from_secs(120);
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+fn browser_error (e: impl std::fmt::Display) -> GradesError {
+  GradesError::Browser(e.to_string())
+}
+
+/// Logs the client in by driving a headless browser through the Passport York login, for when
+/// the form needs JavaScript a plain form post can't run
+///
+/// `webdriver` is the URL of a running chromedriver or geckodriver, like `http://localhost:4444`.
+/// Once the course list opens in the browser its cookies are handed to the client, which then
+/// scrapes like after `login_with()`.
+pub async fn login (client: &GradesClient, webdriver: &str, username: &str, password: &str, factor: &SecondFactor) -> Result<(), GradesError> {
+  let mut capabilities = serde_json::Map::new();
+  capabilities.insert("goog:chromeOptions".to_owned(), json!({ "args": ["--headless=new", "--disable-gpu"] }));
+  capabilities.insert("moz:firefoxOptions".to_owned(), json!({ "args": ["-headless"] }));
+
+  let browser = ClientBuilder::native().capabilities(capabilities).connect(webdriver).await
+    .map_err(|e| GradesError::Browser(format!("could not start a session on the WebDriver at {}: {}", webdriver, e)))?;
+
+  debug!(username, webdriver, "logging in through a headless browser");
+  let result = drive(&browser, client, username, password, factor).await;
+
+  // the browser is closed whether or not the login went through
+  if let Err(e) = browser.close().await {
+    debug!(error = %e, "could not close the browser");
+  }
+  result
+}
+
+async fn drive (browser: &Client, client: &GradesClient, username: &str, password: &str, factor: &SecondFactor) -> Result<(), GradesError> {
+  browser.goto(&client.endpoints.course_list).await.map_err(browser_error)?;
+
+  let form = match browser.wait().at_most(Duration::from_secs(30)).for_element(Locator::Css("input[name='mli']")).await {
+    Ok(form) => form,
+    // a maintenance page takes the place of the login form
+    Err(_) => return Err(failure::detect(&browser.source().await.unwrap_or_default())
+      .unwrap_or_else(|| GradesError::Layout("the login page has no username field".to_owned()))),
+  };
+  form.send_keys(username).await.map_err(browser_error)?;
+  browser.find(Locator::Css("input[name='password']")).await.map_err(browser_error)?
+    .send_keys(password).await.map_err(browser_error)?;
+  browser.find(Locator::Css("input[name='dologin'], input[type='submit'], button[type='submit']")).await.map_err(browser_error)?
+    .click().await.map_err(browser_error)?;
+
+  let deadline = Instant::now() + LOGIN_TIMEOUT;
+  let mut answered = false;
+  loop {
+    let page = browser.source().await.map_err(browser_error)?;
+
+    if page.contains(&client.selectors.authenticated) || on_course_list(browser, client).await {
+      info!("logged in");
+      break;
+    }
+    if let Some(e) = failure::detect(&page) {
+      return Err(e);
+    }
+
+    if duo::find_frame(&page).is_some() || page.contains("duo_iframe") {
+      if !answered {
+        answer_duo(browser, factor).await;
+        answered = true;
+      }
+    } else if page.contains("name=\"password\"") && page.to_lowercase().contains("authentication failed") {
+      return Err(GradesError::Auth);
+    }
+
+    if Instant::now() >= deadline {
+      return Err(if answered {
+        GradesError::Mfa("the Duo prompt was not answered in time".to_owned())
+      } else {
+        GradesError::Browser(format!("still not logged in after {}s", LOGIN_TIMEOUT.as_secs()))
+      });
+    }
+    tokio::time::sleep(POLL_INTERVAL).await;
+  }
+
+  // Passport York's cookies let the client log in again, SIS sets its own once the course list is opened
+  hand_over_cookies(browser, client).await?;
+  browser.goto(&client.endpoints.course_list).await.map_err(browser_error)?;
+  hand_over_cookies(browser, client).await?;
+
+  if client.is_logged_in().await? {
+    Ok(())
+  } else {
+    Err(GradesError::Browser("the browser logged in, but its cookies don't open the course list".to_owned()))
+  }
+}
+
+async fn on_course_list (browser: &Client, client: &GradesClient) -> bool {
+  let course_list = match Url::parse(&client.endpoints.course_list) {
+    Ok(url) => url,
+    Err(_) => return false,
+  };
+
+  match browser.current_url().await {
+    Ok(url) => url.host_str() == course_list.host_str() && url.path() == course_list.path(),
+    Err(_) => false,
+  }
+}
+
+// clicks through the Duo prompt in its iframe, an account set to push automatically needs nothing
+async fn answer_duo (browser: &Client, factor: &SecondFactor) {
+  info!("password accepted, answering the Duo prompt");
+
+  let result: Result<(), fantoccini::error::CmdError> = async {
+    browser.find(Locator::Css("iframe#duo_iframe, iframe[src*='duosecurity']")).await?.enter_frame().await?;
+
+    match factor {
+      SecondFactor::Push => {
+        browser.find(Locator::XPath("//button[contains(., 'Send Me a Push')]")).await?.click().await?;
+      },
+      SecondFactor::Passcode(code) => {
+        browser.find(Locator::XPath("//button[contains(., 'Enter a Passcode')]")).await?.click().await?;
+        browser.find(Locator::Css("input[name='passcode']")).await?.send_keys(code).await?;
+        browser.find(Locator::XPath("//button[contains(., 'Log In')]")).await?.click().await?;
+      },
+    }
+
+    browser.enter_parent_frame().await
+  }.await;
+
+  if let Err(e) = result {
+    debug!(error = %e, "could not click through the Duo prompt, waiting for it to be answered");
+  }
+}
+
+// the cookies of the site the browser is on, for the client's jar
+async fn hand_over_cookies (browser: &Client, client: &GradesClient) -> Result<(), GradesError> {
+  let url = browser.current_url().await.map_err(browser_error)?;
+  let cookies = browser.get_all_cookies().await.map_err(browser_error)?;
+
+  debug!(count = cookies.len(), site = url.host_str().unwrap_or_default(), "handing the browser's cookies to the client");
+  let mut jar = client.cookies.lock().unwrap();
+  for cookie in cookies {
+    let mut header = format!("{}={}; Path={}", cookie.name(), cookie.value(), cookie.path().unwrap_or("/"));
+    if let Some(domain) = cookie.domain() {
+      header.push_str(&format!("; Domain={}", domain));
+    }
+    if cookie.secure() == Some(true) {
+      header.push_str("; Secure");
+    }
+    jar.store(&url, &header);
+  }
+
+  Ok(())
+}
@@ -0,0 +1,93 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::{CourseData, GradesError, files};
+use crate::transcript::Source;
+use crate::vault::Vault;
+
+/// The courses of the last successful scrape, as parsed from the course list
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Cached {
+  pub fetched_at: DateTime<Utc>,
+  pub grades: Vec<CourseData>,
+}
+
+impl Cached {
+  /// Whether the scrape is no older than `max_age`
+  pub fn is_fresh (&self, max_age: Duration) -> bool {
+    match chrono::Duration::from_std(max_age) {
+      Ok(max_age) => Utc::now() - self.fetched_at <= max_age,
+      Err(_) => true,
+    }
+  }
+}
+
+fn dir () -> Option<PathBuf> {
+  dirs::cache_dir().map(|dir| dir.join("grades_list"))
+}
+
+/// Where the last scrape of the given user from the source is cached, under the XDG cache dir on
+/// Linux
+pub fn default_path (username: &str, source: Source) -> Option<PathBuf> {
+  let name = match source {
+    Source::CourseList => format!("grades-{}.json", files::name_part(username)),
+    other => format!("grades-{}.{}.json", files::name_part(username), other.name()),
+  };
+  dir().map(|dir| dir.join(name))
+}
+
+/// Reads the cached scrape, decrypting it with the vault when given, a missing or unreadable
+/// file has none
+pub fn load (path: &Path, vault: Option<&Vault>) -> Result<Option<Cached>, GradesError> {
+  let data = match fs::read(path) {
+    Ok(data) => data,
+    Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+    Err(e) => return Err(e.into()),
+  };
+
+  // a cache written by another version, or before encryption was turned on or off, is only a miss
+  let data = match vault {
+    Some(vault) => match vault.open(&data) {
+      Ok(data) => data,
+      Err(_) => return Ok(None),
+    },
+    None => data,
+  };
+  Ok(serde_json::from_slice(&data).ok())
+}
+
+/// Replaces the cached scrape with these courses, fetched now, encrypted with the vault when given,
+/// in a file only the user can read
+pub fn save (path: &Path, grades: &[CourseData], vault: Option<&Vault>) -> Result<(), GradesError> {
+  let cached = Cached { fetched_at: Utc::now(), grades: grades.to_vec() };
+  let data = serde_json::to_vec(&cached).expect("grades serialize");
+  files::write_private(path, &match vault {
+    Some(vault) => vault.seal(&data),
+    None => data,
+  })
+}
+
+/// Removes the cached scrapes of every user, returning how many there were
+pub fn clear () -> Result<usize, GradesError> {
+  let entries = match dir().map(fs::read_dir) {
+    Some(Ok(entries)) => entries,
+    Some(Err(e)) if e.kind() != std::io::ErrorKind::NotFound => return Err(e.into()),
+    _ => return Ok(0),
+  };
+
+  let mut removed = 0;
+  for entry in entries {
+    let path = entry?.path();
+    let name = path.file_name().and_then(|n| n.to_str()).unwrap_or("");
+    if name.starts_with("grades-") && name.ends_with(".json") {
+      fs::remove_file(&path)?;
+      removed += 1;
+    }
+  }
+
+  Ok(removed)
+}
@@ -0,0 +1,182 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use regex::Regex;
+use reqwest::{Method, Url};
+use serde::{Deserialize, Serialize};
+
+use crate::GradesError;
+use crate::redact::{Redactor, Secret};
+use crate::sanitize::strip_session;
+
+/// A request made to York and the response it got, with whatever could log someone in removed
+///
+/// Form bodies aren't kept since the login form holds the password, cookie values are replaced
+/// and pages go through `sanitize::strip_session()`. The username the login form was sent with,
+/// student numbers and email addresses are hashed out of the pages like `--redact` does, but the
+/// grades are kept since they are what a replay parses.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Interaction {
+  pub method: String,
+  pub url: String,
+  pub status: u16,
+  /// Where a redirect pointed to
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub location: Option<String>,
+  #[serde(default, skip_serializing_if = "Vec::is_empty")]
+  pub set_cookies: Vec<String>,
+  pub body: String,
+}
+
+/// A response from York, a real one or one played back
+pub(crate) struct Response {
+  pub status: u16,
+  pub location: Option<String>,
+  pub set_cookies: Vec<String>,
+  pub body: String,
+}
+
+#[derive(Debug)]
+enum Mode {
+  Record,
+  Replay,
+}
+
+/// Requests to York recorded to a file on a real run and played back later, VCR style
+///
+/// A client replaying a cassette makes no requests at all, so scraping changes can be worked on
+/// and tested without York credentials. A replayed request gets the first recorded response to
+/// the same method and URL that wasn't played yet.
+#[derive(Debug)]
+pub struct Cassette {
+  path: PathBuf,
+  mode: Mode,
+  interactions: Mutex<Vec<(Interaction, bool)>>,
+  /// Username of the login form, taken out of the pages recorded after it
+  username: Mutex<Option<String>>,
+  /// Key of the placeholders, a new one for every recording
+  secret: Secret,
+}
+
+pub(crate) fn strip_url (url: &str) -> String {
+  let session_param = Regex::new(r"(?i)\b(wosid|sid|jsessionid|session)=[^&]*").unwrap();
+  session_param.replace_all(url, "$1=").into_owned()
+}
+
+// keeps the name and attributes so the cookie is still set and sent back, only the value goes
+pub(crate) fn strip_cookie (header: &str) -> String {
+  match header.split_once('=') {
+    // an empty value removes the cookie
+    Some((_, rest)) if rest.starts_with(';') || rest.is_empty() => header.to_owned(),
+    Some((name, rest)) => {
+      let attributes = rest.find(';').map(|i| &rest[i..]).unwrap_or("");
+      format!("{}=recorded{}", name, attributes)
+    },
+    None => header.to_owned(),
+  }
+}
+
+/// The username a login form is sent with
+pub(crate) fn form_username (form: Option<&HashMap<String, String>>) -> Option<String> {
+  form.and_then(|form| form.get("mli")).filter(|username| !username.trim().is_empty()).cloned()
+}
+
+pub(crate) fn strip_body (body: &str, username: Option<&str>, secret: Secret) -> String {
+  // Duo's signed request, the replay never talks to Duo
+  let duo_signature = Regex::new(r#"(?i)(data-sig-request\s*=\s*)("[^"]*"|'[^']*')"#).unwrap();
+  let body = strip_session(body);
+  let body = duo_signature.replace_all(&body, "$1\"TX|recorded:APP|recorded\"");
+
+  // York prints the username, student number and email address on its pages
+  Redactor::new(secret, username.map(str::to_owned), false).text(&body)
+}
+
+impl Cassette {
+  /// Records every request to the file, replacing what it held
+  pub fn record (path: &Path) -> Cassette {
+    Cassette {
+      path: path.to_owned(),
+      mode: Mode::Record,
+      interactions: Mutex::new(Vec::new()),
+      username: Mutex::new(None),
+      secret: rand::random(),
+    }
+  }
+
+  /// Plays back the requests recorded in the file
+  pub fn replay (path: &Path) -> Result<Cassette, GradesError> {
+    let interactions: Vec<Interaction> = serde_json::from_slice(&fs::read(path)?)
+      .map_err(|e| GradesError::Parse(format!("cassette {} is corrupt: {}", path.display(), e)))?;
+
+    Ok(Cassette {
+      path: path.to_owned(),
+      mode: Mode::Replay,
+      interactions: Mutex::new(interactions.into_iter().map(|i| (i, false)).collect()),
+      username: Mutex::new(None),
+      secret: rand::random(),
+    })
+  }
+
+  pub fn is_replaying (&self) -> bool {
+    matches!(self.mode, Mode::Replay)
+  }
+
+  /// The recorded response to the request, when replaying
+  pub(crate) fn play (&self, method: &Method, url: &Url) -> Option<Result<Response, GradesError>> {
+    if !self.is_replaying() {
+      return None;
+    }
+
+    let url = strip_url(url.as_str());
+    let mut interactions = self.interactions.lock().unwrap();
+    let found = interactions.iter_mut().find(|(i, played)| !played && i.method == method.as_str() && i.url == url);
+
+    Some(match found {
+      Some((interaction, played)) => {
+        *played = true;
+        Ok(Response {
+          status: interaction.status,
+          location: interaction.location.clone(),
+          set_cookies: interaction.set_cookies.clone(),
+          body: interaction.body.clone(),
+        })
+      },
+      None => Err(GradesError::Io(std::io::Error::new(
+        std::io::ErrorKind::NotFound,
+        format!("{} has no recorded response to {} {}", self.path.display(), method, url),
+      ))),
+    })
+  }
+
+  /// Adds a response to the cassette when recording, writing the whole cassette out again
+  ///
+  /// The form the request was sent with is only looked at for the username.
+  pub(crate) fn store (&self, method: &Method, url: &Url, form: Option<&HashMap<String, String>>, resp: &Response) -> Result<(), GradesError> {
+    if self.is_replaying() {
+      return Ok(());
+    }
+
+    let mut username = self.username.lock().unwrap();
+    if let Some(sent) = form_username(form) {
+      *username = Some(sent);
+    }
+
+    let interaction = Interaction {
+      method: method.to_string(),
+      url: strip_url(url.as_str()),
+      status: resp.status,
+      location: resp.location.as_deref().map(strip_url),
+      set_cookies: resp.set_cookies.iter().map(|c| strip_cookie(c)).collect(),
+      body: strip_body(&resp.body, username.as_deref(), self.secret),
+    };
+
+    let mut interactions = self.interactions.lock().unwrap();
+    interactions.push((interaction, false));
+
+    let recorded = interactions.iter().map(|(i, _)| i).collect::<Vec<_>>();
+    fs::write(&self.path, serde_json::to_vec_pretty(&recorded).expect("interactions serialize"))?;
+    Ok(())
+  }
+}
@@ -0,0 +1,73 @@
+use std::path::Path;
+
+use plotters::prelude::*;
+
+use crate::{CourseData, Gpa, GradesError};
+
+// width of a full bar in the terminal, a nine point GPA of 9
+const BAR_WIDTH: usize = 36;
+const EIGHTHS: [&str; 8] = ["", "▏", "▎", "▍", "▌", "▋", "▊", "▉"];
+
+/// GPA of each session on its own, in chronological order
+///
+/// Sessions without a course counting towards the GPA are left out.
+pub fn session_gpas (grades: &[CourseData]) -> Vec<(String, Gpa)> {
+  crate::sort::by_session(grades).into_iter()
+    .filter_map(|(session, courses)| crate::calculate_gpa(&courses).map(|gpa| (session, gpa)))
+    .collect()
+}
+
+fn bar (nine: f32) -> String {
+  let eighths = (nine.clamp(0.0, 9.0) / 9.0 * (BAR_WIDTH * 8) as f32).round() as usize;
+  format!("{}{}", "█".repeat(eighths / 8), EIGHTHS[eighths % 8])
+}
+
+/// Horizontal bar chart of the nine point GPA of each session, for the terminal
+pub fn bars (sessions: &[(String, Gpa)]) -> String {
+  let label_width = sessions.iter().map(|(session, _)| session.chars().count()).max().unwrap_or(0);
+
+  sessions.iter()
+    .map(|(session, gpa)| format!(
+      "{:<label$}  {:<bar$}  {:.2} / {:.2}\n",
+      session, bar(gpa.nine), gpa.nine, gpa.four,
+      label = label_width, bar = BAR_WIDTH,
+    ))
+    .collect()
+}
+
+/// Writes the chart as an SVG image, one bar per session on the nine point scale
+pub fn svg (sessions: &[(String, Gpa)], path: &Path) -> Result<(), GradesError> {
+  let chart_error = |e: &dyn std::fmt::Display| GradesError::Io(std::io::Error::other(e.to_string()));
+
+  let width = (120 + 90 * sessions.len() as u32).max(480);
+  let root = SVGBackend::new(path, (width, 360)).into_drawing_area();
+  root.fill(&WHITE).map_err(|e| chart_error(&e))?;
+
+  let mut chart = ChartBuilder::on(&root)
+    .caption("GPA per session", ("sans-serif", 20))
+    .margin(16)
+    .x_label_area_size(40)
+    .y_label_area_size(40)
+    .build_cartesian_2d((0..sessions.len()).into_segmented(), 0.0f32..9.0)
+    .map_err(|e| chart_error(&e))?;
+
+  chart.configure_mesh()
+    .disable_x_mesh()
+    .y_desc("Nine point GPA")
+    .x_labels(sessions.len())
+    .x_label_formatter(&|x| match x {
+      SegmentValue::CenterOf(i) => sessions.get(*i).map(|(session, _)| session.clone()).unwrap_or_default(),
+      _ => String::new(),
+    })
+    .draw()
+    .map_err(|e| chart_error(&e))?;
+
+  chart.draw_series(sessions.iter().enumerate().map(|(i, (_, gpa))| {
+    let mut bar = Rectangle::new([(SegmentValue::Exact(i), 0.0), (SegmentValue::Exact(i + 1), gpa.nine)], BLUE.mix(0.6).filled());
+    bar.set_margin(0, 0, 12, 12);
+    bar
+  })).map_err(|e| chart_error(&e))?;
+
+  root.present().map_err(|e| chart_error(&e))?;
+  Ok(())
+}
@@ -0,0 +1,98 @@
+use serde::Deserialize;
+use serde_json::json;
+
+use crate::{Gpa, GradesError};
+use crate::diff::GradeChange;
+use crate::webhook::Webhook;
+
+static DEFAULT_TEMPLATE: &str = "{change}";
+
+/// Message for the changes, `template` is filled in once per change and the GPA follows on its own line
+///
+/// The template can use `{change}` (like "Grade changed for ...: B -> A"), `{course}`, `{title}`,
+/// `{session}`, `{old_grade}` and `{grade}`.
+pub fn render (template: Option<&str>, changes: &[GradeChange], gpa: Option<&Gpa>) -> String {
+  let template = template.unwrap_or(DEFAULT_TEMPLATE);
+  let mut message = String::new();
+
+  for change in changes {
+    let course = change.course();
+    let old_grade = match change {
+      GradeChange::Changed { old_grade, .. } => old_grade.as_str(),
+      _ => "",
+    };
+    let code = if course.code().is_empty() { course.course.clone() } else { course.code() };
+
+    message.push_str(&template
+      .replace("{change}", &change.to_string())
+      .replace("{course}", &code)
+      .replace("{title}", &course.title)
+      .replace("{session}", &course.session)
+      .replace("{old_grade}", old_grade)
+      .replace("{grade}", &course.grade));
+    message.push('\n');
+  }
+
+  match gpa {
+    Some(gpa) => message.push_str(&format!("GPA: {:.2} / 4, {:.2} / 9", gpa.four, gpa.nine)),
+    None => message.push_str("No GPA yet"),
+  }
+  message
+}
+
+/// `[notify.discord]` in the config file
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DiscordConfig {
+  /// Webhook URL from the channel's integration settings
+  pub webhook_url: String,
+  pub template: Option<String>,
+}
+
+/// `[notify.telegram]` in the config file
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TelegramConfig {
+  /// Token of the bot from @BotFather
+  pub bot_token: String,
+  /// Chat the bot sends to, the bot has to have been messaged there first
+  pub chat_id: String,
+  pub template: Option<String>,
+}
+
+/// Posts grade changes to a Discord channel
+pub struct Discord {
+  webhook: Webhook,
+  template: Option<String>,
+}
+
+impl Discord {
+  pub fn new (config: &DiscordConfig) -> Result<Discord, GradesError> {
+    Ok(Discord { webhook: Webhook::new(&config.webhook_url, &[])?, template: config.template.clone() })
+  }
+
+  pub async fn send (&self, changes: &[GradeChange], gpa: Option<&Gpa>) -> Result<(), GradesError> {
+    let content = render(self.template.as_deref(), changes, gpa);
+    self.webhook.send(&json!({ "content": content })).await
+  }
+}
+
+/// Sends grade changes from a Telegram bot
+pub struct Telegram {
+  webhook: Webhook,
+  chat_id: String,
+  template: Option<String>,
+}
+
+impl Telegram {
+  pub fn new (config: &TelegramConfig) -> Result<Telegram, GradesError> {
+    let url = format!("https://api.telegram.org/bot{}/sendMessage", config.bot_token);
+
+    Ok(Telegram { webhook: Webhook::new(&url, &[])?, chat_id: config.chat_id.clone(), template: config.template.clone() })
+  }
+
+  pub async fn send (&self, changes: &[GradeChange], gpa: Option<&Gpa>) -> Result<(), GradesError> {
+    let text = render(self.template.as_deref(), changes, gpa);
+    self.webhook.send(&json!({ "chat_id": self.chat_id, "text": text })).await
+  }
+}
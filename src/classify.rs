@@ -0,0 +1,162 @@
+use schemars::JsonSchema;
+use serde::Serialize;
+
+use crate::{CourseData, counted, nine_point_scale};
+
+/// What a grade means for the GPA
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum GradeKind {
+  /// A letter grade counted in the GPA
+  Letter,
+  /// `W`, dropped after the deadline
+  Withdrawn,
+  /// `P` or `NCR`, taken pass/fail or without credit retained
+  PassFail,
+  /// No grade yet
+  Pending,
+  /// Anything else York lists, never counted
+  Other,
+}
+
+impl GradeKind {
+  pub fn of (grade: &str) -> GradeKind {
+    let grade = grade.trim().to_ascii_uppercase();
+
+    if nine_point_scale().contains_key(&grade) {
+      return GradeKind::Letter;
+    }
+
+    match grade.as_str() {
+      "W" => GradeKind::Withdrawn,
+      "P" | "NCR" => GradeKind::PassFail,
+      "" | "IP" => GradeKind::Pending,
+      _ => GradeKind::Other,
+    }
+  }
+
+  /// Why a course with this kind of grade is left out of the GPA
+  pub fn describe (self) -> &'static str {
+    match self {
+      GradeKind::Letter => "letter grade",
+      GradeKind::Withdrawn => "withdrawn",
+      GradeKind::PassFail => "pass/fail",
+      GradeKind::Pending => "no grade yet",
+      GradeKind::Other => "not a letter grade",
+    }
+  }
+}
+
+/// Whether the course's credits count as earned
+///
+/// That's a passing letter grade or `P`, and an attempt replaced by a retake earns nothing since
+/// the credits are only earned once.
+pub fn earns_credit (course: &CourseData) -> bool {
+  match GradeKind::of(&course.grade) {
+    GradeKind::Letter => !course.superseded && !matches!(course.grade.trim(), "E" | "F"),
+    GradeKind::PassFail => course.grade.trim().eq_ignore_ascii_case("P"),
+    _ => false,
+  }
+}
+
+/// Whether the row isn't part of the degree: worth no credits, like a co-op work term or a
+/// placement listed at 0.00, or in one of the `non_degree` subjects of the config
+pub fn non_degree (course: &CourseData, subjects: &[String]) -> bool {
+  course.credits == Some(0.0) || subjects.iter().any(|subject| subject.eq_ignore_ascii_case(&course.subject))
+}
+
+/// Leaves the non-degree rows out of the GPA and the credits counted towards it, the way
+/// `in_gpa = false` does for a course entered by hand
+pub fn exclude_non_degree (grades: &mut [CourseData], subjects: &[String]) {
+  for course in grades.iter_mut().filter(|course| non_degree(course, subjects)) {
+    course.excluded = true;
+  }
+}
+
+/// A course that doesn't count towards the GPA and why
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct Excluded<'a> {
+  pub course: &'a CourseData,
+  pub kind: GradeKind,
+}
+
+/// Counts of the courses left out of the GPA because of their grade
+#[derive(Debug, Clone, Default, Serialize, JsonSchema)]
+pub struct ExcludedSummary {
+  pub withdrawn: usize,
+  pub withdrawn_credits: f32,
+  pub pass_fail: usize,
+  pub pass_fail_credits: f32,
+  pub other: usize,
+}
+
+/// Every course whose grade keeps it out of the GPA
+///
+/// The courses with no grade yet aren't among them, they are `projection::in_progress()`.
+pub fn excluded (grades: &[CourseData]) -> Vec<Excluded<'_>> {
+  grades.iter()
+    .map(|course| Excluded { course, kind: GradeKind::of(&course.grade) })
+    .filter(|e| e.kind != GradeKind::Letter && e.kind != GradeKind::Pending)
+    .collect()
+}
+
+pub fn summarize (excluded: &[Excluded]) -> ExcludedSummary {
+  let mut summary = ExcludedSummary::default();
+
+  for e in excluded {
+    let credits = e.course.credits.unwrap_or(0.0);
+
+    match e.kind {
+      GradeKind::Withdrawn => {
+        summary.withdrawn += 1;
+        summary.withdrawn_credits += credits;
+      },
+      GradeKind::PassFail => {
+        summary.pass_fail += 1;
+        summary.pass_fail_credits += credits;
+      },
+      GradeKind::Other => summary.other += 1,
+      GradeKind::Letter | GradeKind::Pending => {},
+    }
+  }
+
+  summary
+}
+
+/// Totals of the credits, computed once for the table, the JSON and the CSV
+#[derive(Debug, Clone, Default, PartialEq, Serialize, JsonSchema)]
+pub struct CreditSummary {
+  /// Every course with a grade, withdrawn, failed and repeated attempts included
+  pub attempted: f32,
+  /// Passed courses, with a retaken course earning its credits once
+  pub earned: f32,
+  /// The courses the GPA is calculated over
+  pub in_gpa: f32,
+  /// Graded courses that never count towards the GPA: pass/fail, withdrawn, and the ones left
+  /// out by hand like transfer credits
+  pub excluded: f32,
+}
+
+pub fn credit_summary (grades: &[CourseData]) -> CreditSummary {
+  // `sum` of no credits is -0, which would print as "-0"
+  let in_gpa = counted(grades).fold(0.0, |total, (_, credits)| total + credits);
+  let mut summary = CreditSummary { in_gpa, ..Default::default() };
+
+  for course in grades {
+    let credits = course.credits.unwrap_or(0.0);
+    let kind = GradeKind::of(&course.grade);
+    if kind == GradeKind::Pending {
+      continue;
+    }
+
+    summary.attempted += credits;
+    if earns_credit(course) {
+      summary.earned += credits;
+    }
+    if kind != GradeKind::Letter || course.excluded {
+      summary.excluded += credits;
+    }
+  }
+
+  summary
+}
@@ -0,0 +1,394 @@
+use std::collections::HashMap;
+use std::io::{Read, Write};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use reqwest::{Method, Url};
+use reqwest::header::{COOKIE, LOCATION, SET_COOKIE};
+use scraper::{Html, Selector};
+use tracing::{debug, info};
+
+use crate::{CourseData, Gpa, GradesError, Selectors, calculate_gpa, duo, failure, parse_lenient};
+use crate::cassette::{Cassette, Response};
+use crate::cookies::CookieJar;
+use crate::debug_dump::DebugDump;
+use crate::duo::SecondFactor;
+
+static COURSE_URL: &str = "https://wrem.sis.yorku.ca/Apps/WebObjects/ydml.woa/wa/DirectAction/document?name=CourseListv1";
+static TRANSCRIPT_URL: &str = "https://wrem.sis.yorku.ca/Apps/WebObjects/ydml.woa/wa/DirectAction/document?name=GradeReportv1";
+static DEGREE_PROGRESS_URL: &str = "https://wrem.sis.yorku.ca/Apps/WebObjects/ydml.woa/wa/DirectAction/document?name=DegreeProgressv1";
+static SCHEDULE_URL: &str = "https://wrem.sis.yorku.ca/Apps/WebObjects/ydml.woa/wa/DirectAction/document?name=CourseSchedulev1";
+static APPOINTMENTS_URL: &str = "https://wrem.sis.yorku.ca/Apps/WebObjects/ydml.woa/wa/DirectAction/document?name=EnrolmentAppointmentv1";
+static IMPORTANT_DATES_URL: &str = "https://wrem.sis.yorku.ca/Apps/WebObjects/ydml.woa/wa/DirectAction/document?name=ImportantDatesv1";
+static LOGIN_PAGE: &str = "https://passportyork.yorku.ca/ppylogin/ppylogin";
+static LOGOUT_PAGE: &str = "https://passportyork.yorku.ca/ppylogin/ppylogout";
+static USER_AGENT: &str = "Mozilla/5.0 (Macintosh; Intel Mac OS X 10_15_6) AppleWebKit/605.1.15 (KHTML, like Gecko) Version/14.0.2 Safari/605.1.15";
+
+/// URLs the client talks to, York's by default
+#[derive(Debug, Clone)]
+pub struct Endpoints {
+  pub course_list: String,
+  /// The grade report, York's unofficial transcript
+  pub transcript: String,
+  pub degree_progress: String,
+  /// Timetable of the current session
+  pub schedule: String,
+  /// Enrolment appointment windows of the student
+  pub appointments: String,
+  /// Sessional dates, like the last days to enrol and drop
+  pub important_dates: String,
+  pub login: String,
+  pub logout: String,
+}
+
+impl Default for Endpoints {
+  fn default () -> Endpoints {
+    Endpoints {
+      course_list: COURSE_URL.to_owned(),
+      transcript: TRANSCRIPT_URL.to_owned(),
+      degree_progress: DEGREE_PROGRESS_URL.to_owned(),
+      schedule: SCHEDULE_URL.to_owned(),
+      appointments: APPOINTMENTS_URL.to_owned(),
+      important_dates: IMPORTANT_DATES_URL.to_owned(),
+      login: LOGIN_PAGE.to_owned(),
+      logout: LOGOUT_PAGE.to_owned(),
+    }
+  }
+}
+
+impl Endpoints {
+  /// York's paths on a single other host, like a local mock server
+  pub fn with_base (base: &str) -> Endpoints {
+    let path = |url: &str| {
+      let url = Url::parse(url).unwrap();
+      format!("{}{}{}", base.trim_end_matches('/'), url.path(), url.query().map(|q| format!("?{}", q)).unwrap_or_default())
+    };
+
+    Endpoints {
+      course_list: path(COURSE_URL),
+      transcript: path(TRANSCRIPT_URL),
+      degree_progress: path(DEGREE_PROGRESS_URL),
+      schedule: path(SCHEDULE_URL),
+      appointments: path(APPOINTMENTS_URL),
+      important_dates: path(IMPORTANT_DATES_URL),
+      login: path(LOGIN_PAGE),
+      logout: path(LOGOUT_PAGE),
+    }
+  }
+}
+
+/// URL without its query or fragment, which can hold session ids and Duo signatures
+pub(crate) fn loggable (url: &Url) -> String {
+  format!("{}://{}{}", url.scheme(), url.host_str().unwrap_or_default(), url.path())
+}
+
+/// How the HTTP client connects, the defaults keep a request from hanging forever when SIS is down
+///
+/// `HTTP_PROXY` and `HTTPS_PROXY` are honored unless `proxy` is set.
+#[derive(Debug, Clone)]
+pub struct ClientOptions {
+  /// Limit on a whole request, from connecting to reading the body
+  pub timeout: Option<Duration>,
+  pub connect_timeout: Option<Duration>,
+  /// Proxy URL for every request, like `http://proxy.example.com:3128`
+  pub proxy: Option<String>,
+  /// How the course list and a successful login are recognized
+  pub selectors: Selectors,
+  /// Records the requests to York, or plays back recorded ones without making any
+  pub cassette: Option<Arc<Cassette>>,
+  /// Keeps a redacted copy of every request and response for a bug report
+  pub debug_dump: Option<Arc<DebugDump>>,
+}
+
+impl Default for ClientOptions {
+  fn default () -> ClientOptions {
+    ClientOptions {
+      timeout: Some(Duration::from_secs(60)),
+      connect_timeout: Some(Duration::from_secs(10)),
+      proxy: None,
+      selectors: Selectors::default(),
+      cassette: None,
+      debug_dump: None,
+    }
+  }
+}
+
+/// Client for logging into Passport York and reading the course list
+///
+/// The client keeps its own cookie jar, so a successful `login()` authenticates every
+/// following request made through the same `GradesClient`. The jar can be saved with
+/// `save_cookies()` and restored in a later process to skip logging in again.
+pub struct GradesClient {
+  pub(crate) client: reqwest::Client,
+  pub(crate) cookies: Mutex<CookieJar>,
+  pub(crate) endpoints: Endpoints,
+  pub(crate) selectors: Selectors,
+  pub(crate) cassette: Option<Arc<Cassette>>,
+  pub(crate) debug_dump: Option<Arc<DebugDump>>,
+}
+
+const MAX_REDIRECTS: usize = 10;
+
+impl GradesClient {
+  pub fn new () -> Result<GradesClient, GradesError> {
+    GradesClient::with_endpoints(Endpoints::default())
+  }
+
+  /// Creates a client talking to other URLs than York's, mostly for tests
+  pub fn with_endpoints (endpoints: Endpoints) -> Result<GradesClient, GradesError> {
+    GradesClient::with_options(endpoints, &ClientOptions::default())
+  }
+
+  /// Creates a client with its own timeouts or proxy
+  pub fn with_options (endpoints: Endpoints, options: &ClientOptions) -> Result<GradesClient, GradesError> {
+    // redirects are followed by hand so cookies set along the way end up in the jar
+    let mut builder = reqwest::Client::builder()
+      .user_agent(USER_AGENT)
+      .redirect(reqwest::redirect::Policy::none());
+
+    if let Some(timeout) = options.timeout {
+      builder = builder.timeout(timeout);
+    }
+    if let Some(timeout) = options.connect_timeout {
+      builder = builder.connect_timeout(timeout);
+    }
+    if let Some(ref proxy) = options.proxy {
+      builder = builder.proxy(reqwest::Proxy::all(proxy)?);
+    }
+
+    let client = builder.build()?;
+
+    // checked here rather than on the first page parsed
+    options.selectors.compile()?;
+
+    Ok(GradesClient {
+      client,
+      cookies: Mutex::new(CookieJar::default()),
+      endpoints,
+      selectors: options.selectors.clone(),
+      cassette: options.cassette.clone(),
+      debug_dump: options.debug_dump.clone(),
+    })
+  }
+
+  /// Replaces the cookies with ones written by `save_cookies()`
+  pub fn load_cookies<R: Read> (&self, reader: R) -> Result<(), GradesError> {
+    let jar: CookieJar = serde_json::from_reader(reader)
+      .map_err(|e| GradesError::Parse(format!("saved session is corrupt: {}", e)))?;
+
+    *self.cookies.lock().unwrap() = jar;
+    Ok(())
+  }
+
+  /// Whether there are any cookies that could belong to a session
+  pub fn has_cookies (&self) -> bool {
+    !self.cookies.lock().unwrap().is_empty()
+  }
+
+  /// Writes the current cookies as JSON
+  pub fn save_cookies<W: Write> (&self, writer: W) -> Result<(), GradesError> {
+    serde_json::to_writer(writer, &*self.cookies.lock().unwrap())
+      .map_err(|e| GradesError::Io(e.into()))
+  }
+
+  /// Makes the request to York, recording it when a cassette is
+  async fn exchange (&self, method: &Method, url: &Url, form: Option<&HashMap<String, String>>, req: reqwest::RequestBuilder) -> Result<Response, GradesError> {
+    let started = Instant::now();
+    let resp = req.send().await?;
+    debug!(%method, url = %loggable(url), status = resp.status().as_u16(), elapsed_ms = started.elapsed().as_millis() as u64, "request");
+
+    let status = resp.status().as_u16();
+    let location = resp.headers().get(LOCATION).and_then(|l| l.to_str().ok()).map(|l| l.to_owned());
+    let set_cookies = resp.headers().get_all(SET_COOKIE).iter()
+      .filter_map(|h| h.to_str().ok())
+      .map(|h| h.to_owned())
+      .collect::<Vec<_>>();
+    let resp = Response { status, location, set_cookies, body: resp.text().await? };
+
+    if let Some(ref cassette) = self.cassette {
+      cassette.store(method, url, form, &resp)?;
+    }
+    Ok(resp)
+  }
+
+  /// Sends a request and returns the final URL and body, recording the cookies of every redirect
+  pub(crate) async fn send (&self, method: Method, url: &str, form: Option<&HashMap<String, String>>) -> Result<(Url, String), GradesError> {
+    let mut url = Url::parse(url).map_err(|e| GradesError::Parse(e.to_string()))?;
+    let mut method = method;
+    let mut form = form;
+
+    for _ in 0..MAX_REDIRECTS {
+      let mut req = self.client.request(method.clone(), url.clone());
+      if let Some(cookie) = self.cookies.lock().unwrap().header(&url) {
+        req = req.header(COOKIE, cookie);
+      }
+      if let Some(form) = form {
+        req = req.form(form);
+      }
+
+      let resp = match self.cassette.as_ref().and_then(|c| c.play(&method, &url)) {
+        Some(replayed) => replayed,
+        None => self.exchange(&method, &url, form, req).await,
+      };
+      if let Some(ref dump) = self.debug_dump {
+        match resp {
+          Ok(ref r) => dump.response(&method, &url, form, r),
+          Err(ref e) => dump.failure(&method, &url, form, e),
+        }
+      }
+      let resp = resp?;
+
+      {
+        let mut jar = self.cookies.lock().unwrap();
+        for header in &resp.set_cookies {
+          jar.store(&url, header);
+        }
+      }
+
+      if !(300..400).contains(&resp.status) {
+        return Ok((url, resp.body));
+      }
+
+      let location = resp.location
+        .ok_or_else(|| GradesError::Layout("redirect without a location".to_owned()))?;
+      url = url.join(&location).map_err(|e| GradesError::Parse(e.to_string()))?;
+
+      // like a browser, the redirect target is fetched with a plain GET
+      method = Method::GET;
+      form = None;
+    }
+
+    Err(GradesError::Layout("too many redirects".to_owned()))
+  }
+
+  /// Whether the current cookies still give access to the course list
+  pub async fn is_logged_in (&self) -> Result<bool, GradesError> {
+    let (url, _) = self.send(Method::GET, &self.endpoints.course_list, None).await?;
+    let login = Url::parse(&self.endpoints.login).map_err(|e| GradesError::Parse(e.to_string()))?;
+
+    // an expired session gets sent back to the Passport York login page
+    let logged_in = url.host_str() != login.host_str() || url.path() != login.path();
+    debug!(logged_in, "checked the session");
+    Ok(logged_in)
+  }
+
+  /// Logs in with Passport York credentials, failing with `GradesError::Auth` if they are rejected
+  ///
+  /// Accounts with Duo enabled are sent a push, use `login_with()` to give a passcode instead.
+  pub async fn login (&self, username: &str, password: &str) -> Result<(), GradesError> {
+    self.login_with(username, password, &SecondFactor::Push).await
+  }
+
+  /// Logs in like `login()`, answering a Duo two-factor prompt with the given factor
+  pub async fn login_with (&self, username: &str, password: &str, factor: &SecondFactor) -> Result<(), GradesError> {
+    let (_, resp) = self.send(Method::GET, &self.endpoints.course_list, None).await?;
+
+    debug!(username, "logging in");
+
+    // a maintenance page takes the place of the login form, with nothing to log in to
+    if let Some(GradesError::Maintenance) = failure::detect(&resp) {
+      return Err(GradesError::Maintenance);
+    }
+
+    let mut login_fields: HashMap<String, String> = [
+      ("mli".to_owned(), username.to_owned()),
+      ("password".to_owned(), password.to_owned()),
+      ("dologin".to_owned(), "Login".to_owned()),
+    ].iter().cloned().collect();
+
+    // scoped so the parsed page, which isn't Send, is dropped before the next request
+    {
+      let document = Html::parse_document(&resp);
+      let hidden_selector = Selector::parse("input[type='hidden']").unwrap();
+
+      // append all the hiden fields for the auth
+      for element in document.select(&hidden_selector) {
+        let name = element.value().attr("name")
+          .ok_or_else(|| GradesError::Layout("login form has a hidden field without a name".to_owned()))?;
+        let value = element.value().attr("value").unwrap_or_default();
+
+        login_fields.insert(name.to_owned(), value.to_owned());
+      }
+    }
+
+    let (login_url, mut login_resp_content) = self.send(Method::POST, &self.endpoints.login, Some(&login_fields)).await?;
+
+    // the password was accepted but the account wants a second factor
+    if let Some(frame) = duo::find_frame(&login_resp_content) {
+      info!("password accepted, answering the Duo prompt");
+      // a replayed login has no Duo prompt to answer, the response to the signature was recorded
+      let sig_response = match self.cassette {
+        Some(ref cassette) if cassette.is_replaying() => String::new(),
+        _ => duo::authenticate(&self.client, &frame, &login_url, factor).await?,
+      };
+
+      let post_url = login_url.join(&frame.post_action).map_err(|e| GradesError::Parse(e.to_string()))?;
+      let mut duo_fields = HashMap::new();
+      duo_fields.insert("sig_response".to_owned(), sig_response);
+
+      login_resp_content = self.send(Method::POST, post_url.as_str(), Some(&duo_fields)).await?.1;
+    }
+
+    // will be authenticated if this string is present in the page
+    if login_resp_content.contains(&self.selectors.authenticated) {
+      info!("logged in");
+      Ok(())
+    } else {
+      Err(failure::detect(&login_resp_content).unwrap_or(GradesError::Auth))
+    }
+  }
+
+  /// Scrapes the course list, must be called after a successful `login()`
+  pub async fn fetch_grades (&self) -> Result<Vec<CourseData>, GradesError> {
+    parse_lenient(&self.fetch_page().await?, &self.selectors)
+  }
+
+  /// Raw HTML of the course list page, for `parse_courses()` or saving a copy
+  pub async fn fetch_page (&self) -> Result<String, GradesError> {
+    let (_, courses_page) = self.send(Method::GET, &self.endpoints.course_list, None).await?;
+
+    Ok(courses_page)
+  }
+
+  /// Raw HTML of the grade report, parsed the same way as the course list, must be called after
+  /// a successful `login()`
+  pub async fn fetch_transcript_page (&self) -> Result<String, GradesError> {
+    let (_, transcript_page) = self.send(Method::GET, &self.endpoints.transcript, None).await?;
+
+    Ok(transcript_page)
+  }
+
+  /// Raw HTML of the Degree Progress Report, for `degree::parse()`
+  pub async fn fetch_degree_progress_page (&self) -> Result<String, GradesError> {
+    let (_, report) = self.send(Method::GET, &self.endpoints.degree_progress, None).await?;
+
+    Ok(report)
+  }
+
+  /// Raw HTML of the timetable of the current session, for `schedule::parse()`
+  pub async fn fetch_schedule_page (&self) -> Result<String, GradesError> {
+    let (_, timetable) = self.send(Method::GET, &self.endpoints.schedule, None).await?;
+
+    Ok(timetable)
+  }
+
+  /// Raw HTML of the enrolment appointments and of the important dates, for `dates::parse()`
+  pub async fn fetch_dates_pages (&self) -> Result<(String, String), GradesError> {
+    let (_, appointments) = self.send(Method::GET, &self.endpoints.appointments, None).await?;
+    let (_, important_dates) = self.send(Method::GET, &self.endpoints.important_dates, None).await?;
+
+    Ok((appointments, important_dates))
+  }
+
+  /// Calculates the four point and nine point GPA of the given grades, `None` when none count
+  pub fn gpa (&self, grades: &[CourseData]) -> Option<Gpa> {
+    calculate_gpa(grades)
+  }
+
+  pub async fn logout (&self) -> Result<(), GradesError> {
+    // a single request is all that is needed
+    self.send(Method::GET, &self.endpoints.logout, None).await?;
+    self.cookies.lock().unwrap().clear();
+    Ok(())
+  }
+}
@@ -0,0 +1,138 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+
+use crate::{CourseData, GradesError, Selectors, classify, extra, overrides};
+use crate::extra::ExtraCourse;
+use crate::notify::NotifyConfig;
+use crate::progress::Requirements;
+use crate::scale::ScaleRegistry;
+use crate::vault::Encryption;
+
+/// Settings read from `config.toml`, every key is optional
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+  /// Course code prefixes like `"EECS"` or `"MATH 2"` making up the major, for `--major-gpa`
+  #[serde(default)]
+  pub major: Vec<String>,
+  /// Subjects like `"COOP"` whose courses aren't part of the degree, left out of the GPA along
+  /// with the zero-credit rows unless `--include-non-degree` is given
+  #[serde(default)]
+  pub non_degree: Vec<String>,
+  /// Profile used when `--profile` isn't given
+  pub default_profile: Option<String>,
+  #[serde(default)]
+  pub profiles: HashMap<String, Profile>,
+  #[serde(default)]
+  pub notify: NotifyConfig,
+  /// Courses entered by hand, merged into the ones read from the course list
+  #[serde(default)]
+  pub extra_courses: Vec<ExtraCourse>,
+  /// Grades to use instead of the ones in the course list, keyed by course code like `"LE EECS 3101"`
+  #[serde(default)]
+  pub overrides: HashMap<String, String>,
+  /// Grades expected in the courses still in progress, keyed like `overrides`, for `--projection`
+  #[serde(default)]
+  pub expected: HashMap<String, String>,
+  /// Credits the degree needs, for `progress`
+  #[serde(default)]
+  pub requirements: Option<Requirements>,
+  /// Custom scales giving points to every letter grade, like `[scales.departmental]`
+  #[serde(default)]
+  pub scales: HashMap<String, HashMap<String, f32>>,
+  /// Where the grades are found on the course list page, to hot-fix a layout change
+  #[serde(default)]
+  pub selectors: Selectors,
+  /// Encrypts the history and the cached scrapes too, with the keychain's key or a passphrase
+  pub encryption: Option<Encryption>,
+}
+
+/// The user's own changes to the grades fetched for them, the courses entered by hand, the
+/// `[overrides]` and the `non_degree` subjects
+///
+/// The accounts `serve --multi-user` serves are other students, whose grades are served with
+/// `LocalChanges::default()`, as York lists them but for the zero-credit rows.
+#[derive(Debug, Clone, Default)]
+pub struct LocalChanges {
+  pub extra_courses: Vec<ExtraCourse>,
+  pub overrides: HashMap<String, String>,
+  pub non_degree: Vec<String>,
+  /// Counts the non-degree rows in the GPA, like `--include-non-degree`
+  pub include_non_degree: bool,
+}
+
+impl LocalChanges {
+  /// Adds the courses entered by hand, replaces the overridden grades, then leaves the
+  /// non-degree rows out of the GPA
+  pub fn apply (&self, mut grades: Vec<CourseData>) -> Vec<CourseData> {
+    extra::merge(&mut grades, &self.extra_courses);
+    overrides::apply(&mut grades, &self.overrides);
+    if !self.include_non_degree {
+      classify::exclude_non_degree(&mut grades, &self.non_degree);
+    }
+    grades
+  }
+}
+
+/// Named set of defaults for one account, command line flags take precedence
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Profile {
+  pub username: Option<String>,
+  /// Output format name, like `"json"`
+  pub format: Option<String>,
+  pub notify_webhook: Option<String>,
+  #[serde(default)]
+  pub webhook_headers: Vec<String>,
+}
+
+/// Where the config file is looked for, `~/.config/grades_list/config.toml` on Linux
+pub fn default_path () -> Option<PathBuf> {
+  dirs::config_dir().map(|dir| dir.join("grades_list").join("config.toml"))
+}
+
+impl Config {
+  /// Reads the config file, a missing file gives the defaults
+  pub fn load (path: &Path) -> Result<Config, GradesError> {
+    let data = match fs::read_to_string(path) {
+      Ok(data) => data,
+      Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Config::default()),
+      Err(e) => return Err(e.into()),
+    };
+
+    toml::from_str(&data).map_err(|e| GradesError::Config(format!("{}: {}", path.display(), e)))
+  }
+
+  /// The courses of `[[extra_courses]]` and `extra_courses.csv`, the `[overrides]` and the
+  /// `non_degree` subjects
+  pub fn local_changes (&self) -> Result<LocalChanges, GradesError> {
+    let mut extra_courses = self.extra_courses.clone();
+    if let Some(path) = extra::default_path() {
+      extra_courses.extend(extra::load_csv(&path)?);
+    }
+    Ok(LocalChanges {
+      extra_courses,
+      overrides: self.overrides.clone(),
+      non_degree: self.non_degree.clone(),
+      include_non_degree: false,
+    })
+  }
+
+  /// The built in scales along with the ones defined here
+  pub fn scale_registry (&self) -> Result<ScaleRegistry, GradesError> {
+    ScaleRegistry::with_custom(&self.scales)
+  }
+
+  /// The named profile, or the default one when no name is given
+  pub fn profile (&self, name: Option<&str>) -> Result<Option<&Profile>, GradesError> {
+    match name.or(self.default_profile.as_deref()) {
+      Some(name) => self.profiles.get(name)
+        .map(Some)
+        .ok_or_else(|| GradesError::Config(format!("no profile named \"{}\"", name))),
+      None => Ok(None),
+    }
+  }
+}
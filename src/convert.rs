@@ -0,0 +1,95 @@
+use std::str::FromStr;
+
+use serde::Serialize;
+
+use crate::CourseData;
+
+/// Scales other schools and application services use, converted from York letter grades
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Conversion {
+  /// Ontario medical school applications, following the OMSAS conversion table for York
+  Omsas,
+  /// Law school applications, LSAC's 4.33 scale
+  Lsac,
+  /// Middle of the percentage range York gives for each letter, only an approximation
+  Percentage,
+}
+
+impl Conversion {
+  pub const NAMES: &'static [&'static str] = &["omsas", "lsac", "percentage"];
+  pub const ALL: &'static [Conversion] = &[Conversion::Omsas, Conversion::Lsac, Conversion::Percentage];
+
+  /// Value of a York letter grade on this scale, `None` for grades that aren't letters
+  pub fn points (self, grade: &str) -> Option<f32> {
+    let points = match (self, grade) {
+      (Conversion::Omsas, "A+") => 4.0,
+      (Conversion::Omsas, "A") => 3.8,
+      (Conversion::Omsas, "B+") => 3.3,
+      (Conversion::Omsas, "B") => 3.0,
+      (Conversion::Omsas, "C+") => 2.3,
+      (Conversion::Omsas, "C") => 2.0,
+      (Conversion::Omsas, "D+") => 1.3,
+      (Conversion::Omsas, "D") => 1.0,
+      (Conversion::Omsas, "E") | (Conversion::Omsas, "F") => 0.0,
+
+      (Conversion::Lsac, "A+") => 4.33,
+      (Conversion::Lsac, "A") => 4.0,
+      (Conversion::Lsac, "B+") => 3.33,
+      (Conversion::Lsac, "B") => 3.0,
+      (Conversion::Lsac, "C+") => 2.33,
+      (Conversion::Lsac, "C") => 2.0,
+      (Conversion::Lsac, "D+") => 1.33,
+      (Conversion::Lsac, "D") => 1.0,
+      (Conversion::Lsac, "E") | (Conversion::Lsac, "F") => 0.0,
+
+      (Conversion::Percentage, "A+") => 95.0,
+      (Conversion::Percentage, "A") => 85.0,
+      (Conversion::Percentage, "B+") => 77.0,
+      (Conversion::Percentage, "B") => 72.0,
+      (Conversion::Percentage, "C+") => 67.0,
+      (Conversion::Percentage, "C") => 62.0,
+      (Conversion::Percentage, "D+") => 57.0,
+      (Conversion::Percentage, "D") => 52.0,
+      (Conversion::Percentage, "E") => 45.0,
+      (Conversion::Percentage, "F") => 20.0,
+
+      _ => return None,
+    };
+
+    Some(points)
+  }
+
+  /// Credit weighted average over the courses counting towards the GPA, `None` without any
+  pub fn gpa (self, grades: &[CourseData]) -> Option<f32> {
+    crate::weighted_average(grades, |grade| self.points(grade))
+  }
+
+  /// Name as given on the command line and used in JSON, like `"omsas"`
+  pub fn key (self) -> &'static str {
+    match self {
+      Conversion::Omsas => "omsas",
+      Conversion::Lsac => "lsac",
+      Conversion::Percentage => "percentage",
+    }
+  }
+
+  pub fn name (self) -> &'static str {
+    match self {
+      Conversion::Omsas => "OMSAS",
+      Conversion::Lsac => "LSAC",
+      Conversion::Percentage => "Percentage",
+    }
+  }
+}
+
+impl FromStr for Conversion {
+  type Err = String;
+
+  fn from_str (s: &str) -> Result<Conversion, String> {
+    match Conversion::ALL.iter().find(|c| c.key() == s) {
+      Some(&conversion) => Ok(conversion),
+      None => Err(format!("unknown conversion \"{}\", expected one of {}", s, Conversion::NAMES.join(", "))),
+    }
+  }
+}
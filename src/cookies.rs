@@ -0,0 +1,133 @@
+use chrono::{DateTime, Utc};
+use reqwest::Url;
+use serde::{Deserialize, Serialize};
+
+/// A cookie set by one of the York sites
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Cookie {
+  name: String,
+  value: String,
+  domain: String,
+  // false when the cookie had a Domain attribute and so applies to subdomains too
+  host_only: bool,
+  path: String,
+  secure: bool,
+  // from Expires or Max-Age, a cookie without either lasts as long as the session on the server
+  #[serde(default)]
+  expires: Option<DateTime<Utc>>,
+}
+
+impl Cookie {
+  fn is_expired (&self, now: DateTime<Utc>) -> bool {
+    self.expires.is_some_and(|expires| expires <= now)
+  }
+
+  fn matches (&self, url: &Url) -> bool {
+    let host = url.host_str().unwrap_or_default();
+
+    let domain_matches = if self.host_only {
+      host == self.domain
+    } else {
+      host == self.domain || host.ends_with(&format!(".{}", self.domain))
+    };
+
+    domain_matches
+      && url.path().starts_with(&self.path)
+      && (!self.secure || url.scheme() == "https")
+  }
+}
+
+/// Minimal cookie jar that can be written to disk, so a session outlives the process
+///
+/// Only what Passport York and SIS actually use is supported. Expired cookies are dropped, a
+/// `Set-Cookie` with an expiry in the past deletes the cookie.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct CookieJar {
+  cookies: Vec<Cookie>,
+}
+
+impl CookieJar {
+  /// Records a `Set-Cookie` header received from `url`
+  pub fn store (&mut self, url: &Url, header: &str) {
+    let mut parts = header.split(';').map(|p| p.trim());
+
+    let (name, value) = match parts.next().and_then(|p| split_pair(p)) {
+      Some(pair) => pair,
+      None => return,
+    };
+
+    let mut cookie = Cookie {
+      name: name.to_owned(),
+      value: value.to_owned(),
+      domain: url.host_str().unwrap_or_default().to_owned(),
+      host_only: true,
+      path: "/".to_owned(),
+      secure: false,
+      expires: None,
+    };
+    let mut max_age = None;
+
+    for attr in parts {
+      let (key, val) = split_pair(attr).unwrap_or((attr, ""));
+
+      match key.to_ascii_lowercase().as_str() {
+        "domain" if !val.is_empty() => {
+          cookie.domain = val.trim_start_matches('.').to_ascii_lowercase();
+          cookie.host_only = false;
+        },
+        "path" if val.starts_with('/') => cookie.path = val.to_owned(),
+        "secure" => cookie.secure = true,
+        "expires" => cookie.expires = parse_expires(val).or(cookie.expires),
+        "max-age" => max_age = val.parse::<i64>().ok(),
+        _ => {},
+      }
+    }
+
+    let now = Utc::now();
+    // Max-Age wins over Expires wherever they are in the header
+    if let Some(age) = max_age {
+      cookie.expires = Some(now + chrono::Duration::seconds(age.clamp(0, i64::from(i32::MAX))));
+    }
+
+    let replaced = |c: &Cookie| c.name == cookie.name && c.domain == cookie.domain && c.path == cookie.path;
+    self.cookies.retain(|c| !replaced(c) && !c.is_expired(now));
+
+    if !value.is_empty() && !cookie.is_expired(now) {
+      self.cookies.push(cookie);
+    }
+  }
+
+  /// Value for the `Cookie` header of a request to `url`, if any cookies apply
+  pub fn header (&self, url: &Url) -> Option<String> {
+    let now = Utc::now();
+    let pairs = self.cookies.iter()
+      .filter(|c| c.matches(url) && !c.is_expired(now))
+      .map(|c| format!("{}={}", c.name, c.value))
+      .collect::<Vec<_>>();
+
+    if pairs.is_empty() { None } else { Some(pairs.join("; ")) }
+  }
+
+  /// Whether every cookie is gone or expired
+  pub fn is_empty (&self) -> bool {
+    let now = Utc::now();
+    self.cookies.iter().all(|c| c.is_expired(now))
+  }
+
+  pub fn clear (&mut self) {
+    self.cookies.clear();
+  }
+}
+
+// `Wed, 21 Oct 2026 07:28:00 GMT`, or with dashes in the date like older servers send it
+fn parse_expires (value: &str) -> Option<DateTime<Utc>> {
+  DateTime::parse_from_rfc2822(value)
+    .or_else(|_| DateTime::parse_from_rfc2822(&value.replacen('-', " ", 2)))
+    .ok()
+    .map(|date| date.with_timezone(&Utc))
+}
+
+fn split_pair (s: &str) -> Option<(&str, &str)> {
+  let idx = s.find('=')?;
+  Some((s[..idx].trim(), s[idx + 1..].trim()))
+}
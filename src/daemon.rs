@@ -0,0 +1,182 @@
+use std::path::PathBuf;
+
+use reqwest::Method;
+use serde::{Deserialize, Serialize};
+
+use crate::{GradesClient, GradesError};
+
+#[cfg(unix)]
+use std::path::Path;
+#[cfg(unix)]
+use std::sync::Arc;
+#[cfg(unix)]
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+#[cfg(unix)]
+use tokio::net::{UnixListener, UnixStream};
+#[cfg(unix)]
+use tracing::debug;
+
+#[cfg(unix)]
+use crate::SisBackend;
+
+/// A page of SIS, read by the client or asked of a running daemon
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Page {
+  CourseList,
+  Transcript,
+  DegreeProgress,
+  Schedule,
+  Appointments,
+  ImportantDates,
+}
+
+impl Page {
+  /// Raw HTML of the page, the client must be logged in
+  pub async fn fetch (self, client: &GradesClient) -> Result<String, GradesError> {
+    let endpoints = &client.endpoints;
+    let url = match self {
+      Page::CourseList => &endpoints.course_list,
+      Page::Transcript => &endpoints.transcript,
+      Page::DegreeProgress => &endpoints.degree_progress,
+      Page::Schedule => &endpoints.schedule,
+      Page::Appointments => &endpoints.appointments,
+      Page::ImportantDates => &endpoints.important_dates,
+    };
+
+    let (_, page) = client.send(Method::GET, url, None).await?;
+    Ok(page)
+  }
+}
+
+/// Reads the pages in order, stopping at the first that fails
+pub async fn fetch_all (client: &GradesClient, pages: &[Page]) -> Result<Vec<String>, GradesError> {
+  let mut fetched = Vec::with_capacity(pages.len());
+  for page in pages {
+    fetched.push(page.fetch(client).await?);
+  }
+  Ok(fetched)
+}
+
+/// A line sent to the daemon
+#[derive(Debug, Serialize, Deserialize)]
+struct Request {
+  pages: Vec<Page>,
+}
+
+/// The line the daemon answers with
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum Reply {
+  Pages(Vec<String>),
+  Failed(String),
+}
+
+/// Where the daemon of the given user listens, in the runtime directory when there is one
+pub fn default_path (username: &str) -> Option<PathBuf> {
+  dirs::runtime_dir().or_else(dirs::data_dir)
+    .map(|dir| dir.join("grades_list").join(format!("daemon-{}.sock", crate::files::name_part(username))))
+}
+
+/// Binds the socket, only its owner may connect since whoever does reads the grades
+///
+/// The socket goes in a directory only its owner can enter, made so before binding even when it
+/// was already there, so nobody else can reach the socket in the moment before its own mode is
+/// set. A socket left behind by a daemon that is gone is replaced, one that still answers is an
+/// error.
+#[cfg(unix)]
+pub fn listen (path: &Path) -> Result<UnixListener, GradesError> {
+  use std::os::unix::fs::{DirBuilderExt, PermissionsExt};
+
+  if let Some(dir) = path.parent() {
+    std::fs::DirBuilder::new().recursive(true).mode(0o700).create(dir)?;
+    // only the owner can change the mode, a directory of someone else's fails here
+    std::fs::set_permissions(dir, std::fs::Permissions::from_mode(0o700))
+      .map_err(|e| GradesError::Daemon(format!("could not make {} private: {}", dir.display(), e)))?;
+    if std::fs::metadata(dir)?.permissions().mode() & 0o077 != 0 {
+      return Err(GradesError::Daemon(format!("{} can be entered by other users", dir.display())));
+    }
+  }
+  if path.exists() {
+    if std::os::unix::net::UnixStream::connect(path).is_ok() {
+      return Err(GradesError::Daemon(format!("another daemon is already listening on {}", path.display())));
+    }
+    std::fs::remove_file(path)?;
+  }
+
+  let listener = UnixListener::bind(path)?;
+  std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o600))?;
+  Ok(listener)
+}
+
+/// Answers the requests of other runs with the backend's session, until accepting a connection fails
+///
+/// Keeping the session alive is up to the caller, a request is answered with whatever the
+/// client's cookies get from SIS at the time. The pages are York's, a backend of another school
+/// fails every request.
+#[cfg(unix)]
+pub async fn serve (listener: UnixListener, backend: Arc<dyn SisBackend>) -> Result<(), GradesError> {
+  loop {
+    let (stream, _) = listener.accept().await?;
+    let backend = backend.clone();
+    tokio::spawn(async move {
+      if let Err(e) = answer(stream, &*backend).await {
+        debug!(error = %e, "could not answer a request");
+      }
+    });
+  }
+}
+
+#[cfg(unix)]
+async fn answer (stream: UnixStream, backend: &dyn SisBackend) -> Result<(), GradesError> {
+  let (read, mut write) = stream.into_split();
+  let mut line = String::new();
+  BufReader::new(read).read_line(&mut line).await?;
+
+  let reply = match serde_json::from_str::<Request>(&line) {
+    Ok(request) => {
+      debug!(pages = ?request.pages, "fetching for another run");
+      let fetched = match backend.york() {
+        Some(client) => fetch_all(client, &request.pages).await,
+        None => Err(GradesError::Config(format!("{} has no SIS pages to fetch", backend.name()))),
+      };
+      match fetched {
+        Ok(pages) => Reply::Pages(pages),
+        Err(e) => Reply::Failed(e.to_string()),
+      }
+    },
+    Err(e) => Reply::Failed(format!("not a request: {}", e)),
+  };
+
+  let mut reply = serde_json::to_vec(&reply).expect("replies serialize");
+  reply.push(b'\n');
+  write.write_all(&reply).await?;
+  Ok(())
+}
+
+/// Asks the daemon listening on `path` for the pages, `None` when no daemon is running there
+#[cfg(unix)]
+pub async fn request (path: &Path, pages: &[Page]) -> Result<Option<Vec<String>>, GradesError> {
+  let stream = match UnixStream::connect(path).await {
+    Ok(stream) => stream,
+    // no socket, or one left behind by a daemon that is gone
+    Err(e) if matches!(e.kind(), std::io::ErrorKind::NotFound | std::io::ErrorKind::ConnectionRefused) => return Ok(None),
+    Err(e) => return Err(e.into()),
+  };
+
+  let (read, mut write) = stream.into_split();
+  let mut line = serde_json::to_vec(&Request { pages: pages.to_vec() }).expect("requests serialize");
+  line.push(b'\n');
+  write.write_all(&line).await?;
+
+  let mut line = String::new();
+  BufReader::new(read).read_line(&mut line).await?;
+  if line.is_empty() {
+    return Err(GradesError::Daemon("it closed the connection without answering".to_owned()));
+  }
+
+  match serde_json::from_str(&line).map_err(|e| GradesError::Daemon(format!("unreadable answer: {}", e)))? {
+    Reply::Pages(pages) => Ok(Some(pages)),
+    Reply::Failed(e) => Err(GradesError::Daemon(e)),
+  }
+}
@@ -0,0 +1,193 @@
+use chrono::{DateTime, NaiveDate, NaiveDateTime, NaiveTime, Utc};
+use scraper::Html;
+use serde::Serialize;
+
+use crate::schedule::{parse_date, parse_dates};
+use crate::{GradesError, Selectors, clean_text, column, failure, header_table, ics};
+
+/// What a date in SIS is for
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Kind {
+  /// When the student's enrolment window opens
+  Appointment,
+  /// A date of the sessional calendar, like the first day of classes or the last day to drop
+  Academic,
+}
+
+impl Kind {
+  pub fn label (self) -> &'static str {
+    match self {
+      Kind::Appointment => "Enrolment appointment",
+      Kind::Academic => "Important date",
+    }
+  }
+}
+
+/// An enrolment appointment window or a date of the sessional calendar
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct KeyDate {
+  pub name: String,
+  pub kind: Kind,
+  pub start: NaiveDate,
+  /// Time it opens, none for a date that lasts the whole day
+  pub start_time: Option<NaiveTime>,
+  /// Last day, for a window or a range of days
+  pub end: Option<NaiveDate>,
+  pub end_time: Option<NaiveTime>,
+}
+
+impl KeyDate {
+  /// Dates like `2024-07-02 10:00 to 2024-09-17 23:59`
+  pub fn when (&self) -> String {
+    let at = |date: NaiveDate, time: Option<NaiveTime>| match time {
+      Some(time) => format!("{} {}", date, time.format("%H:%M")),
+      None => date.to_string(),
+    };
+
+    match self.end {
+      Some(end) => format!("{} to {}", at(self.start, self.start_time), at(end, self.end_time)),
+      None => at(self.start, self.start_time),
+    }
+  }
+}
+
+/// A date with an optional time, like `Jul 2, 2024 10:00 AM` or `2024-07-02 10:00`
+fn parse_moment (moment: &str) -> Option<(NaiveDate, Option<NaiveTime>)> {
+  let moment = moment.trim();
+  ["%Y-%m-%d %H:%M", "%b %d, %Y %I:%M %p", "%B %d, %Y %I:%M %p", "%b %d, %Y %H:%M"].iter()
+    .find_map(|f| NaiveDateTime::parse_from_str(moment, f).ok())
+    .map(|at| (at.date(), Some(at.time())))
+    .or_else(|| parse_date(moment).map(|date| (date, None)))
+}
+
+// the enrolment appointments, from the table with Session, Start and End headers
+fn appointments (data: &[Vec<String>]) -> Result<Vec<KeyDate>, GradesError> {
+  let header = &data[0];
+  let session = column(header, &["session"]).unwrap();
+  let start = column(header, &["start", "opens"]).unwrap();
+  let end = column(header, &["end", "closes"]);
+
+  let mut dates = Vec::new();
+  for (i, row) in data[1..].iter().enumerate() {
+    let cell = |i: usize| row.get(i).map(|c| clean_text(c)).unwrap_or_default();
+    if cell(session).is_empty() {
+      continue;
+    }
+
+    let (start, start_time) = parse_moment(&cell(start))
+      .ok_or_else(|| GradesError::Layout(format!("row {} of the enrolment appointments has an unknown start \"{}\"", i + 1, cell(start))))?;
+    let (end, end_time) = end.and_then(|end| parse_moment(&cell(end))).map_or((None, None), |(date, time)| (Some(date), time));
+
+    dates.push(KeyDate { name: cell(session), kind: Kind::Appointment, start, start_time, end, end_time });
+  }
+
+  Ok(dates)
+}
+
+// the sessional dates, from the table with Date and Event headers
+fn academic_dates (data: &[Vec<String>]) -> Result<Vec<KeyDate>, GradesError> {
+  let header = &data[0];
+  let date = column(header, &["date", "dates"]).unwrap();
+  let event = column(header, &["event", "description"]).unwrap();
+
+  let mut dates = Vec::new();
+  for (i, row) in data[1..].iter().enumerate() {
+    let cell = |i: usize| row.get(i).map(|c| clean_text(c)).unwrap_or_default();
+    if cell(event).is_empty() {
+      continue;
+    }
+
+    let (start, end) = parse_dates(&cell(date));
+    let start = start.ok_or_else(|| GradesError::Layout(format!("row {} of the important dates has an unknown date \"{}\"", i + 1, cell(date))))?;
+
+    dates.push(KeyDate { name: cell(event), kind: Kind::Academic, start, start_time: None, end, end_time: None });
+  }
+
+  Ok(dates)
+}
+
+/// Parses the enrolment appointments and important dates on a saved page of SIS
+///
+/// Appointments are read from a table with Session, Start and End headers and important dates
+/// from one with Date and Event headers. A page needs only one of them, and the dates come back
+/// in the order they fall.
+pub fn parse (page: &str, selectors: &Selectors) -> Result<Vec<KeyDate>, GradesError> {
+  read(page, selectors, true)
+}
+
+/// Parses a page of dates fetched from SIS like `parse()`, a page without either table has none
+///
+/// SIS leaves the table off the appointments page until the student is given an appointment.
+pub fn parse_fetched (page: &str, selectors: &Selectors) -> Result<Vec<KeyDate>, GradesError> {
+  read(page, selectors, false)
+}
+
+fn read (page: &str, selectors: &Selectors, needs_table: bool) -> Result<Vec<KeyDate>, GradesError> {
+  let compiled = selectors.compile()?;
+  let document = Html::parse_document(page);
+  let is_appointments = |header: &[String]| column(header, &["session"]).is_some() && column(header, &["start", "opens"]).is_some();
+  let is_academic = |header: &[String]| column(header, &["date", "dates"]).is_some() && column(header, &["event", "description"]).is_some();
+
+  let (on_appointments, on_academic) = (header_table(&document, &compiled, is_appointments), header_table(&document, &compiled, is_academic));
+  if on_appointments.is_none() && on_academic.is_none() {
+    return match failure::detect(page) {
+      Some(e) => Err(e),
+      None if !needs_table => Ok(Vec::new()),
+      None => Err(GradesError::Layout("could not find enrolment appointments or important dates, no table has Session and Start or Date and Event headers".to_owned())),
+    };
+  }
+
+  let mut dates = Vec::new();
+  if let Some(data) = on_appointments {
+    dates.extend(appointments(&data)?);
+  }
+  if let Some(data) = on_academic {
+    dates.extend(academic_dates(&data)?);
+  }
+  dates.sort_by_key(|d| (d.start, d.start_time));
+
+  Ok(dates)
+}
+
+// the content lines of the event of a date, a reminder going off the day before
+fn event (date: &KeyDate) -> Vec<String> {
+  let mut lines = vec![format!("UID:{}-{}@grades_list", ics::slug(&date.name), date.start.format("%Y%m%d"))];
+
+  match date.start_time {
+    // a window is marked when it opens instead of filling the calendar for weeks
+    Some(time) => lines.push(format!("DTSTART:{}", date.start.and_time(time).format("%Y%m%dT%H%M%S"))),
+    None => {
+      let last = date.end.unwrap_or(date.start);
+      lines.push(format!("DTSTART;VALUE=DATE:{}", date.start.format("%Y%m%d")));
+      lines.push(format!("DTEND;VALUE=DATE:{}", last.succ_opt().unwrap_or(last).format("%Y%m%d")));
+    },
+  }
+
+  let summary = match date.kind {
+    Kind::Appointment => format!("{}, {}", date.kind.label(), date.name),
+    Kind::Academic => date.name.clone(),
+  };
+  lines.push(format!("SUMMARY:{}", ics::escape(&summary)));
+  if date.kind == Kind::Appointment {
+    lines.push(format!("DESCRIPTION:{}", ics::escape(&format!("Enrolment is open {}", date.when()))));
+  }
+
+  lines.extend([
+    "BEGIN:VALARM".to_owned(),
+    "ACTION:DISPLAY".to_owned(),
+    format!("DESCRIPTION:{}", ics::escape(&summary)),
+    "TRIGGER:-P1D".to_owned(),
+    "END:VALARM".to_owned(),
+  ]);
+
+  lines
+}
+
+/// Renders the dates as an iCalendar file, each with a reminder the day before
+///
+/// An appointment is an event at the time the window opens, with when it closes in the
+/// description. Like the schedule, the times are floating.
+pub fn ics (dates: &[KeyDate], stamp: DateTime<Utc>) -> String {
+  ics::calendar(&dates.iter().map(event).collect::<Vec<_>>(), stamp)
+}
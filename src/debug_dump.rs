@@ -0,0 +1,160 @@
+use std::collections::{HashMap, VecDeque};
+use std::fs;
+use std::path::Path;
+use std::sync::Mutex;
+
+use regex::Regex;
+use reqwest::{Method, Url};
+use serde::Serialize;
+
+use crate::GradesError;
+use crate::cassette::{Response, form_username, strip_body, strip_cookie, strip_url};
+use crate::redact::{self, Secret};
+
+// form fields whose values are shown as they are, the rest are passwords, passcodes or tokens
+const SHOWN_FIELDS: &[&str] = &["dologin"];
+// the username, shown hashed like in the pages
+const USERNAME_FIELD: &str = "mli";
+
+/// Most requests kept, `watch` and `serve` make them for as long as they run and the last ones
+/// are the ones leading up to a failure
+pub const MAX_EXCHANGES: usize = 200;
+
+/// A request to York and what came of it, redacted like a cassette
+#[derive(Debug, Clone, Serialize)]
+pub struct Exchange {
+  pub method: String,
+  pub url: String,
+  /// Names of the form fields sent, with the values of all but the username blanked
+  #[serde(skip_serializing_if = "Vec::is_empty")]
+  pub form: Vec<String>,
+  pub status: Option<u16>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub location: Option<String>,
+  #[serde(skip_serializing_if = "Vec::is_empty")]
+  pub set_cookies: Vec<String>,
+  /// File the body was written to, next to `trace.json`
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub body_file: Option<String>,
+  /// Why no response came back
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub error: Option<String>,
+  #[serde(skip)]
+  body: String,
+}
+
+/// The last `MAX_EXCHANGES` requests of a run, kept to be written out for a bug report
+///
+/// Passwords, cookie values, session ids and hidden form tokens are scrubbed as the requests are
+/// made, so nothing that could log someone in is ever kept. The username, student numbers, email
+/// addresses and letter grades are taken out of the pages too, since the dump is made to be
+/// attached to a public issue.
+#[derive(Debug)]
+pub struct DebugDump {
+  exchanges: Mutex<VecDeque<Exchange>>,
+  /// Username of the login form, taken out of the pages after it
+  username: Mutex<Option<String>>,
+  /// Key of the placeholders, a new one for every run
+  secret: Secret,
+}
+
+fn redact_form (form: Option<&HashMap<String, String>>, secret: &Secret) -> Vec<String> {
+  let mut fields = form.into_iter().flatten()
+    .map(|(name, value)| match name.as_str() {
+      USERNAME_FIELD => format!("{}={}", name, redact::hash(secret, value)),
+      shown if SHOWN_FIELDS.contains(&shown) => format!("{}={}", name, value),
+      _ => format!("{}=<redacted>", name),
+    })
+    .collect::<Vec<_>>();
+  fields.sort();
+  fields
+}
+
+// the letter grades of table cells, how the page is laid out is what a bug report needs
+fn strip_grades (body: &str) -> String {
+  let grade = Regex::new(r"(<td[^>]*>\s*)(?:[A-D]\+?|E|F)(\s*</td>)").unwrap();
+  grade.replace_all(body, "${1}?${2}").into_owned()
+}
+
+impl Default for DebugDump {
+  fn default () -> DebugDump {
+    DebugDump { exchanges: Default::default(), username: Default::default(), secret: rand::random() }
+  }
+}
+
+impl DebugDump {
+  pub fn new () -> DebugDump {
+    DebugDump::default()
+  }
+
+  // the oldest request makes room once there are `MAX_EXCHANGES`
+  fn keep (&self, exchange: Exchange) {
+    let mut exchanges = self.exchanges.lock().unwrap();
+    if exchanges.len() == MAX_EXCHANGES {
+      exchanges.pop_front();
+    }
+    exchanges.push_back(exchange);
+  }
+
+  /// Keeps a request that got a response
+  pub(crate) fn response (&self, method: &Method, url: &Url, form: Option<&HashMap<String, String>>, resp: &Response) {
+    let mut username = self.username.lock().unwrap();
+    if let Some(sent) = form_username(form) {
+      *username = Some(sent);
+    }
+
+    self.keep(Exchange {
+      method: method.to_string(),
+      url: strip_url(url.as_str()),
+      form: redact_form(form, &self.secret),
+      status: Some(resp.status),
+      location: resp.location.as_deref().map(strip_url),
+      set_cookies: resp.set_cookies.iter().map(|c| strip_cookie(c)).collect(),
+      body_file: None,
+      error: None,
+      body: strip_grades(&strip_body(&resp.body, username.as_deref(), self.secret)),
+    });
+  }
+
+  /// Keeps a request that failed before a response came back
+  pub(crate) fn failure (&self, method: &Method, url: &Url, form: Option<&HashMap<String, String>>, error: &GradesError) {
+    self.keep(Exchange {
+      method: method.to_string(),
+      url: strip_url(url.as_str()),
+      form: redact_form(form, &self.secret),
+      status: None,
+      location: None,
+      set_cookies: Vec::new(),
+      body_file: None,
+      error: Some(error.to_string()),
+      body: String::new(),
+    });
+  }
+
+  pub fn len (&self) -> usize {
+    self.exchanges.lock().unwrap().len()
+  }
+
+  pub fn is_empty (&self) -> bool {
+    self.len() == 0
+  }
+
+  /// Writes `trace.json` listing the requests in order to the directory, with the body of each
+  /// response in its own numbered file
+  pub fn write (&self, dir: &Path) -> Result<(), GradesError> {
+    fs::create_dir_all(dir)?;
+
+    let mut exchanges = self.exchanges.lock().unwrap().iter().cloned().collect::<Vec<_>>();
+    for (i, exchange) in exchanges.iter_mut().enumerate() {
+      if exchange.body.is_empty() {
+        continue;
+      }
+      let name = format!("{:03}-{}.html", i + 1, exchange.method.to_lowercase());
+      fs::write(dir.join(&name), &exchange.body)?;
+      exchange.body_file = Some(name);
+    }
+
+    fs::write(dir.join("trace.json"), serde_json::to_vec_pretty(&exchanges).expect("exchanges serialize"))?;
+    Ok(())
+  }
+}
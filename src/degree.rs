@@ -0,0 +1,96 @@
+use scraper::Html;
+use serde::Serialize;
+
+use crate::{GradesError, Selectors, clean_text, column, failure, header_table};
+
+/// Whether the Degree Progress Report counts a requirement as met
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Status {
+  Satisfied,
+  /// Met once the courses in progress are passed
+  InProgress,
+  NotSatisfied,
+}
+
+impl Status {
+  fn parse (status: &str) -> Option<Status> {
+    let status = status.to_lowercase();
+    if status.contains("not") || status == "no" || status == "incomplete" {
+      Some(Status::NotSatisfied)
+    } else if status.contains("progress") || status == "ip" {
+      Some(Status::InProgress)
+    } else if status.contains("satisfied") || status.contains("complete") || status == "ok" || status == "yes" {
+      Some(Status::Satisfied)
+    } else {
+      None
+    }
+  }
+
+  pub fn label (self) -> &'static str {
+    match self {
+      Status::Satisfied => "Satisfied",
+      Status::InProgress => "In progress",
+      Status::NotSatisfied => "Not satisfied",
+    }
+  }
+}
+
+/// One requirement category of the degree, like the general education or major credits
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Requirement {
+  pub name: String,
+  pub status: Status,
+  pub required: Option<f32>,
+  pub completed: Option<f32>,
+  /// Credits still needed, the report's own figure or what is left of `required`
+  pub outstanding: Option<f32>,
+}
+
+/// Credits still needed over every requirement
+pub fn outstanding (requirements: &[Requirement]) -> f32 {
+  requirements.iter()
+    .filter(|r| r.status != Status::Satisfied)
+    .filter_map(|r| r.outstanding)
+    .fold(0.0, |total, credits| total + credits)
+}
+
+/// Parses a saved copy of the Degree Progress Report
+///
+/// The requirements are read from the table with Requirement and Status headers, with Required,
+/// Completed and Outstanding credits when it has them.
+pub fn parse (page: &str, selectors: &Selectors) -> Result<Vec<Requirement>, GradesError> {
+  let compiled = selectors.compile()?;
+  let document = Html::parse_document(page);
+  let has_headers = |header: &[String]| column(header, &["requirement"]).is_some() && column(header, &["status"]).is_some();
+
+  let data = header_table(&document, &compiled, has_headers).ok_or_else(|| failure::detect(page).unwrap_or_else(|| {
+    GradesError::Layout("could not find the requirements table of the Degree Progress Report".to_owned())
+  }))?;
+
+  let header = &data[0];
+  let name = column(header, &["requirement"]).unwrap();
+  let status = column(header, &["status"]).unwrap();
+  let required = column(header, &["required", "credits required"]);
+  let completed = column(header, &["completed", "credits completed", "applied"]);
+  let outstanding = column(header, &["outstanding", "needed", "remaining"]);
+
+  let mut requirements = Vec::new();
+  for (i, row) in data[1..].iter().enumerate() {
+    let cell = |i: usize| row.get(i).map(|c| clean_text(c)).unwrap_or_default();
+    let number = |i: Option<usize>| i.and_then(|i| cell(i).parse::<f32>().ok());
+
+    if cell(name).is_empty() {
+      continue;
+    }
+    let status = Status::parse(&cell(status))
+      .ok_or_else(|| GradesError::Layout(format!("row {} of the Degree Progress Report has an unknown status \"{}\"", i + 1, cell(status))))?;
+
+    let (required, completed) = (number(required), number(completed));
+    let outstanding = number(outstanding).or_else(|| Some((required? - completed.unwrap_or(0.0)).max(0.0)));
+
+    requirements.push(Requirement { name: cell(name), status, required, completed, outstanding });
+  }
+
+  Ok(requirements)
+}
@@ -0,0 +1,99 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use serde::Serialize;
+
+use crate::{CourseData, Gpa};
+
+/// Difference in a single course between two fetches of the course list
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum GradeChange {
+  /// The course wasn't listed before
+  Added { course: CourseData },
+  /// The grade changed, or was posted when `old_grade` is empty
+  Changed { course: CourseData, old_grade: String },
+  /// The course is no longer listed
+  Removed { course: CourseData },
+}
+
+impl GradeChange {
+  pub fn course (&self) -> &CourseData {
+    match self {
+      GradeChange::Added { course } | GradeChange::Changed { course, .. } | GradeChange::Removed { course } => course,
+    }
+  }
+}
+
+impl fmt::Display for GradeChange {
+  fn fmt (&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      GradeChange::Added { course } => write!(f, "New course {} {}: {}", course.session, course.course, course.grade),
+      GradeChange::Changed { course, old_grade } if old_grade.is_empty() => write!(f, "Grade posted for {}: {}", course.course, course.grade),
+      GradeChange::Changed { course, old_grade } => write!(f, "Grade changed for {}: {} -> {}", course.course, old_grade, course.grade),
+      GradeChange::Removed { course } => write!(f, "Course removed {} {}", course.session, course.course),
+    }
+  }
+}
+
+/// Compares two fetches of the course list, matching rows on their session and course
+///
+/// Grades are compared as listed, so a grade overridden in the config still shows up as changed
+/// once York changes it.
+pub fn diff (old: &[CourseData], new: &[CourseData]) -> Vec<GradeChange> {
+  let old = old.iter().map(CourseData::as_listed).collect::<Vec<_>>();
+  let new = new.iter().map(CourseData::as_listed).collect::<Vec<_>>();
+
+  let old_by_key: HashMap<(&str, &str), &CourseData> = old.iter()
+    .map(|c| ((c.session.as_str(), c.course.as_str()), c))
+    .collect();
+  let new_by_key: HashMap<(&str, &str), &CourseData> = new.iter()
+    .map(|c| ((c.session.as_str(), c.course.as_str()), c))
+    .collect();
+
+  let mut changes = Vec::new();
+
+  for course in &new {
+    match old_by_key.get(&(course.session.as_str(), course.course.as_str())) {
+      None => changes.push(GradeChange::Added { course: course.clone() }),
+      Some(prev) if prev.grade != course.grade => changes.push(GradeChange::Changed {
+        course: course.clone(),
+        old_grade: prev.grade.clone(),
+      }),
+      _ => {},
+    }
+  }
+
+  for course in &old {
+    if !new_by_key.contains_key(&(course.session.as_str(), course.course.as_str())) {
+      changes.push(GradeChange::Removed { course: course.clone() });
+    }
+  }
+
+  changes
+}
+
+/// Changes between two fetches along with how the GPA moved
+#[derive(Debug, Clone, Serialize)]
+pub struct DiffReport {
+  pub changes: Vec<GradeChange>,
+  /// `None` where no course counted towards the GPA
+  pub old_gpa: Option<Gpa>,
+  pub new_gpa: Option<Gpa>,
+  /// `new_gpa - old_gpa` on both scales, `None` unless both have a GPA
+  pub delta: Option<Gpa>,
+}
+
+impl DiffReport {
+  pub fn new (old: &[CourseData], old_gpa: Option<Gpa>, new: &[CourseData], new_gpa: Option<Gpa>) -> DiffReport {
+    DiffReport {
+      changes: diff(old, new),
+      delta: old_gpa.as_ref().zip(new_gpa.as_ref()).map(|(old_gpa, new_gpa)| Gpa {
+        four: new_gpa.four - old_gpa.four,
+        nine: new_gpa.nine - old_gpa.nine,
+      }),
+      old_gpa,
+      new_gpa,
+    }
+  }
+}
@@ -0,0 +1,159 @@
+use reqwest::{Method, Url};
+use scraper::{Html, Selector};
+use serde::Serialize;
+
+use crate::{GradesClient, ParseMode, failure, parse_courses_with};
+use crate::client::loggable;
+
+/// How a check of `doctor` went
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Outcome {
+  Pass,
+  Fail,
+  /// Couldn't be checked, like the course list without a session
+  Skip,
+}
+
+impl Outcome {
+  pub fn label (self) -> &'static str {
+    match self {
+      Outcome::Pass => "ok",
+      Outcome::Fail => "FAIL",
+      Outcome::Skip => "skipped",
+    }
+  }
+}
+
+/// One line of the checklist
+#[derive(Debug, Clone, Serialize)]
+pub struct Check {
+  pub name: String,
+  pub outcome: Outcome,
+  pub detail: String,
+}
+
+impl Check {
+  fn new (name: String, outcome: Outcome, detail: impl Into<String>) -> Check {
+    Check { name, outcome, detail: detail.into() }
+  }
+}
+
+// the hosts the client talks to, course list first, each once
+fn hosts (client: &GradesClient) -> Vec<Url> {
+  let mut hosts: Vec<Url> = Vec::new();
+  for url in [&client.endpoints.course_list, &client.endpoints.login] {
+    if let Ok(url) = Url::parse(url) {
+      if !hosts.iter().any(|h| h.host_str() == url.host_str() && h.port_or_known_default() == url.port_or_known_default()) {
+        hosts.push(url);
+      }
+    }
+  }
+  hosts
+}
+
+async fn resolves (url: &Url) -> Check {
+  let host = url.host_str().unwrap_or_default();
+  let name = format!("DNS lookup of {}", host);
+
+  match tokio::net::lookup_host((host, url.port_or_known_default().unwrap_or(443))).await {
+    Ok(addrs) => {
+      let addrs = addrs.map(|a| a.ip().to_string()).collect::<Vec<_>>();
+      Check::new(name, Outcome::Pass, addrs.join(", "))
+    },
+    Err(e) => Check::new(name, Outcome::Fail, e.to_string()),
+  }
+}
+
+// any response at all means the connection, and for https the TLS handshake, went through
+async fn connects (client: &GradesClient, url: &Url) -> Check {
+  let host = url.host_str().unwrap_or_default();
+  let name = match url.scheme() {
+    "https" => format!("TLS handshake with {}", host),
+    _ => format!("Connection to {}", host),
+  };
+
+  let mut root = url.clone();
+  root.set_path("/");
+  root.set_query(None);
+  match client.client.head(root).send().await {
+    Ok(resp) => Check::new(name, Outcome::Pass, format!("HTTP {}", resp.status().as_u16())),
+    Err(e) => Check::new(name, Outcome::Fail, root_cause(&e)),
+  }
+}
+
+// what actually went wrong, like a certificate that didn't verify, without every layer around it
+fn root_cause (e: &dyn std::error::Error) -> String {
+  let mut cause = e;
+  while let Some(source) = cause.source() {
+    cause = source;
+  }
+  cause.to_string()
+}
+
+// the login form, which a client without a session is sent to from the course list
+fn login_form (url: &Url, page: &str) -> Vec<Check> {
+  let document = Html::parse_document(page);
+  let count = |selector: &str| document.select(&Selector::parse(selector).unwrap()).count();
+
+  let reachable = "Login page reachable".to_owned();
+  if let Some(e) = failure::detect(page) {
+    return vec![Check::new(reachable, Outcome::Fail, e.to_string())];
+  }
+  if count("input[name='mli']") == 0 || count("input[name='password']") == 0 {
+    return vec![Check::new(reachable, Outcome::Fail, format!("{} has no username and password fields", loggable(url)))];
+  }
+
+  let hidden = document.select(&Selector::parse("input[type='hidden']").unwrap())
+    .map(|input| input.value().attr("name"))
+    .collect::<Vec<_>>();
+  let fields = "Hidden login form fields".to_owned();
+  let fields = if hidden.is_empty() {
+    Check::new(fields, Outcome::Fail, "the form has none, it probably needs JavaScript now, try --browser")
+  } else if hidden.iter().any(|name| name.is_none()) {
+    Check::new(fields, Outcome::Fail, "a hidden field has no name")
+  } else {
+    Check::new(fields, Outcome::Pass, hidden.iter().flatten().cloned().collect::<Vec<_>>().join(", "))
+  };
+
+  vec![Check::new(reachable, Outcome::Pass, loggable(url)), fields]
+}
+
+/// Works through what has to go right for a scrape, from resolving York's hosts to the selectors
+/// finding the courses
+///
+/// The client shouldn't be logged in, since the login form is only shown without a session. The
+/// selectors are tried on `course_list` when there is one, a page read with a saved session or
+/// from a file.
+pub async fn run (client: &GradesClient, course_list: Option<&str>) -> Vec<Check> {
+  let mut checks = Vec::new();
+
+  let hosts = hosts(client);
+  for url in &hosts {
+    checks.push(resolves(url).await);
+  }
+  for url in &hosts {
+    checks.push(connects(client, url).await);
+  }
+
+  match client.send(Method::GET, &client.endpoints.course_list, None).await {
+    Ok((url, page)) => checks.extend(login_form(&url, &page)),
+    Err(e) => checks.push(Check::new("Login page reachable".to_owned(), Outcome::Fail, e.to_string())),
+  }
+
+  let matched = "Selectors find the courses".to_owned();
+  checks.push(match course_list.map(|page| parse_courses_with(page, &client.selectors, ParseMode::Lenient)) {
+    Some(Ok(parsed)) if parsed.grades.is_empty() => Check::new(matched, Outcome::Fail, "the course table has no courses"),
+    Some(Ok(parsed)) => {
+      let skipped = match parsed.warnings.len() {
+        0 => String::new(),
+        n => format!(", {} rows left out", n),
+      };
+      Check::new(matched, Outcome::Pass, format!("{} courses{}", parsed.grades.len(), skipped))
+    },
+    Some(Err(e)) => Check::new(matched, Outcome::Fail, e.to_string()),
+    None => Check::new(matched, Outcome::Skip, "no saved session, log in once or give --from-file"),
+  });
+
+  checks
+}
@@ -0,0 +1,138 @@
+use std::collections::HashMap;
+use std::time::Duration;
+
+use reqwest::Url;
+use scraper::{Html, Selector};
+use serde_json::Value;
+use tracing::debug;
+
+use crate::GradesError;
+
+// a push that hasn't been answered by then has been ignored
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+const POLL_ATTEMPTS: usize = 45;
+
+/// How to answer the Duo two-factor prompt when Passport York asks for one
+#[derive(Debug, Clone)]
+pub enum SecondFactor {
+  /// Send a push to the first enrolled phone and wait for it to be approved
+  Push,
+  /// A code from the Duo app or a hardware token
+  Passcode(String),
+}
+
+/// The Duo iframe embedded in the Passport York page after the password was accepted
+pub(crate) struct DuoFrame {
+  host: String,
+  tx: String,
+  app: String,
+  /// Where the signed response is posted back to, relative to the page
+  pub post_action: String,
+}
+
+/// Finds the Duo iframe, if the page asks for a second factor
+pub(crate) fn find_frame (page: &str) -> Option<DuoFrame> {
+  let document = Html::parse_document(page);
+  let selector = Selector::parse("iframe#duo_iframe").unwrap();
+  let frame = document.select(&selector).next()?.value();
+
+  // the signed request is "TX|...:APP|...", duo only sees the first half
+  let mut sig = frame.attr("data-sig-request")?.splitn(2, ':');
+
+  Some(DuoFrame {
+    host: frame.attr("data-host")?.to_owned(),
+    tx: sig.next()?.to_owned(),
+    app: sig.next()?.to_owned(),
+    post_action: frame.attr("data-post-action").unwrap_or_default().to_owned(),
+  })
+}
+
+fn mfa_error (msg: &str) -> GradesError {
+  GradesError::Mfa(msg.to_owned())
+}
+
+/// Reads the `response` object out of a duo API reply, failing on anything but `"stat": "OK"`
+async fn response (resp: reqwest::Response) -> Result<Value, GradesError> {
+  let body: Value = resp.json().await?;
+
+  if body["stat"] != "OK" {
+    let message = body["message"].as_str().unwrap_or("duo rejected the request");
+    return Err(mfa_error(message));
+  }
+
+  Ok(body["response"].clone())
+}
+
+/// Runs the duo prompt with the given factor and returns the `sig_response` to post back to Passport York
+pub(crate) async fn authenticate (client: &reqwest::Client, frame: &DuoFrame, parent: &Url, factor: &SecondFactor) -> Result<String, GradesError> {
+  // Duo is reached the way Passport York was, which is always https but for a server in a test
+  let origin = format!("{}://{}", parent.scheme(), frame.host);
+  let base = format!("{}/frame", origin);
+
+  let mut form = HashMap::new();
+  form.insert("parent", parent.as_str().to_owned());
+
+  // duo answers with a redirect to the prompt page, carrying the session id
+  let resp = client.post(format!("{}/web/v1/auth", base))
+    .query(&[("tx", frame.tx.as_str()), ("parent", parent.as_str()), ("v", "2.6")])
+    .form(&form)
+    .send().await?;
+
+  let sid = resp.headers().get(reqwest::header::LOCATION)
+    .and_then(|l| l.to_str().ok())
+    .and_then(|l| Url::parse(&base).ok()?.join(l).ok())
+    .and_then(|url| url.query_pairs().find(|(k, _)| k == "sid").map(|(_, v)| v.into_owned()))
+    .ok_or_else(|| mfa_error("duo did not start a session"))?;
+
+  let mut prompt = HashMap::new();
+  prompt.insert("sid", sid.clone());
+  prompt.insert("device", "phone1".to_owned());
+  match factor {
+    SecondFactor::Push => {
+      prompt.insert("factor", "Duo Push".to_owned());
+    },
+    SecondFactor::Passcode(code) => {
+      prompt.insert("factor", "Passcode".to_owned());
+      prompt.insert("passcode", code.clone());
+    },
+  }
+
+  let txid = response(client.post(format!("{}/prompt", base)).form(&prompt).send().await?).await?
+    ["txid"].as_str()
+    .ok_or_else(|| mfa_error("duo did not start the prompt"))?
+    .to_owned();
+
+  let mut status = HashMap::new();
+  status.insert("sid", sid.clone());
+  status.insert("txid", txid);
+
+  for _ in 0..POLL_ATTEMPTS {
+    let state = response(client.post(format!("{}/status", base)).form(&status).send().await?).await?;
+
+    match state["result"].as_str() {
+      Some("SUCCESS") => {
+        let result_url = state["result_url"].as_str().ok_or_else(|| mfa_error("duo did not return a result"))?;
+
+        let mut result = HashMap::new();
+        result.insert("sid", sid);
+
+        let cookie = response(client.post(format!("{}{}", origin, result_url)).form(&result).send().await?).await?
+          ["cookie"].as_str()
+          .ok_or_else(|| mfa_error("duo did not sign the response"))?
+          .to_owned();
+
+        return Ok(format!("{}:{}", cookie, frame.app));
+      },
+      Some("FAILURE") => {
+        return Err(mfa_error(state["status"].as_str().unwrap_or("the second factor was denied")));
+      },
+      // still waiting on the push
+      _ => {
+        debug!(status = state["status"].as_str().unwrap_or_default(), "waiting on duo");
+        tokio::time::sleep(POLL_INTERVAL).await
+      },
+    }
+  }
+
+  Err(mfa_error("timed out waiting for the push to be approved"))
+}
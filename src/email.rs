@@ -0,0 +1,107 @@
+use lettre::{AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+use lettre::message::Mailbox;
+use lettre::transport::smtp::authentication::Credentials;
+use serde::Deserialize;
+
+use crate::{Gpa, GradesError};
+use crate::diff::GradeChange;
+
+/// How the connection to the SMTP server is secured
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Encryption {
+  /// Upgrade a plain connection, usually on port 587
+  #[default]
+  Starttls,
+  /// TLS from the start, usually on port 465
+  Tls,
+  /// Unencrypted, only for a relay on the same machine
+  None,
+}
+
+/// `[notify.email]` in the config file
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct EmailConfig {
+  /// SMTP server host name
+  pub server: String,
+  /// Defaults to the usual port of the encryption
+  pub port: Option<u16>,
+  #[serde(default)]
+  pub encryption: Encryption,
+  pub username: Option<String>,
+  pub password: Option<String>,
+  /// Sender address, the recipient when omitted
+  pub from: Option<String>,
+  pub to: String,
+}
+
+fn email_error<E: std::fmt::Display> (e: E) -> GradesError {
+  GradesError::Notify(format!("email: {}", e))
+}
+
+/// Sends a summary of changed grades by email
+pub struct Email {
+  transport: AsyncSmtpTransport<Tokio1Executor>,
+  from: Mailbox,
+  to: Mailbox,
+}
+
+impl Email {
+  pub fn new (config: &EmailConfig) -> Result<Email, GradesError> {
+    let mut builder = match config.encryption {
+      Encryption::Starttls => AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&config.server).map_err(email_error)?,
+      Encryption::Tls => AsyncSmtpTransport::<Tokio1Executor>::relay(&config.server).map_err(email_error)?,
+      Encryption::None => AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(&config.server),
+    };
+
+    if let Some(port) = config.port {
+      builder = builder.port(port);
+    }
+    if let (Some(username), Some(password)) = (&config.username, &config.password) {
+      builder = builder.credentials(Credentials::new(username.clone(), password.clone()));
+    }
+
+    let to: Mailbox = config.to.parse().map_err(email_error)?;
+    let from = match config.from {
+      Some(ref from) => from.parse().map_err(email_error)?,
+      None => to.clone(),
+    };
+
+    Ok(Email { transport: builder.build(), from, to })
+  }
+
+  pub async fn send (&self, changes: &[GradeChange], gpa: Option<&Gpa>) -> Result<(), GradesError> {
+    let message = Message::builder()
+      .from(self.from.clone())
+      .to(self.to.clone())
+      .subject(subject(changes))
+      .body(body(changes, gpa))
+      .map_err(email_error)?;
+
+    self.transport.send(message).await.map_err(email_error)?;
+    Ok(())
+  }
+}
+
+fn subject (changes: &[GradeChange]) -> String {
+  match changes {
+    [change] => change.to_string(),
+    _ => format!("{} grade changes", changes.len()),
+  }
+}
+
+/// Plain text listing of the changes followed by the GPA
+pub fn body (changes: &[GradeChange], gpa: Option<&Gpa>) -> String {
+  let mut body = String::new();
+
+  for change in changes {
+    body.push_str(&format!("{}\n", change));
+  }
+
+  match gpa {
+    Some(gpa) => body.push_str(&format!("\nGPA: {:.2} (four point), {:.2} (nine point)\n", gpa.four, gpa.nine)),
+    None => body.push_str("\nNo GPA yet\n"),
+  }
+  body
+}
@@ -0,0 +1,86 @@
+use std::io::Write;
+use std::process::{Command, Stdio};
+use std::str::FromStr;
+
+use crate::GradesError;
+
+/// Who an export is encrypted to with `export --encrypt-to`
+#[derive(Debug, Clone)]
+pub enum Recipient {
+  /// An age public key, `age1...`
+  Age(age::x25519::Recipient),
+  /// A key in the GPG keyring, by id, fingerprint or email, encrypted to by running `gpg`
+  Gpg(String),
+}
+
+impl FromStr for Recipient {
+  type Err = String;
+
+  fn from_str (s: &str) -> Result<Recipient, String> {
+    if s.starts_with("age1") {
+      return s.parse().map(Recipient::Age).map_err(|e| format!("{} is not an age public key: {}", s, e));
+    }
+    if s.trim().is_empty() {
+      return Err("the recipient is empty".to_owned());
+    }
+    Ok(Recipient::Gpg(s.to_owned()))
+  }
+}
+
+/// Encrypts the data so only the recipients can read it, all of them age keys or all of them GPG
+/// keys since one file can only be either
+pub fn encrypt (data: &[u8], recipients: &[Recipient]) -> Result<Vec<u8>, GradesError> {
+  let age_keys = recipients.iter().filter_map(|r| match r {
+    Recipient::Age(key) => Some(key as &dyn age::Recipient),
+    Recipient::Gpg(_) => None,
+  }).collect::<Vec<_>>();
+  let gpg_keys = recipients.iter().filter_map(|r| match r {
+    Recipient::Gpg(key) => Some(key.as_str()),
+    Recipient::Age(_) => None,
+  }).collect::<Vec<_>>();
+
+  match (age_keys.is_empty(), gpg_keys.is_empty()) {
+    (false, false) => Err(GradesError::Config("a file can't be encrypted to both age and GPG recipients".to_owned())),
+    (false, true) => encrypt_age(data, age_keys),
+    (true, false) => encrypt_gpg(data, &gpg_keys),
+    (true, true) => Ok(data.to_vec()),
+  }
+}
+
+fn encrypt_age (data: &[u8], recipients: Vec<&dyn age::Recipient>) -> Result<Vec<u8>, GradesError> {
+  let failed = |e: &dyn std::fmt::Display| GradesError::Export(format!("could not encrypt with age: {}", e));
+
+  let encryptor = age::Encryptor::with_recipients(recipients.into_iter()).map_err(|e| failed(&e))?;
+  let mut encrypted = Vec::new();
+  let mut writer = encryptor.wrap_output(&mut encrypted).map_err(|e| failed(&e))?;
+  writer.write_all(data).map_err(|e| failed(&e))?;
+  writer.finish().map_err(|e| failed(&e))?;
+  Ok(encrypted)
+}
+
+// the keys of other machines are imported but rarely signed or trusted, naming the recipient on
+// the command line is taken as trusting it, like age does
+fn encrypt_gpg (data: &[u8], recipients: &[&str]) -> Result<Vec<u8>, GradesError> {
+  let mut command = Command::new("gpg");
+  command.args(["--batch", "--yes", "--trust-model", "always", "--encrypt", "--output", "-"]);
+  for recipient in recipients {
+    command.arg("--recipient").arg(recipient);
+  }
+
+  let mut gpg = command.stdin(Stdio::piped()).stdout(Stdio::piped()).stderr(Stdio::piped()).spawn()
+    .map_err(|e| GradesError::Export(format!("could not run gpg: {}", e)))?;
+
+  // written from another thread, gpg can fill its output before it has read all of the input
+  let mut stdin = gpg.stdin.take().expect("stdin is piped");
+  let data = data.to_vec();
+  let writer = std::thread::spawn(move || stdin.write_all(&data));
+
+  let output = gpg.wait_with_output().map_err(|e| GradesError::Export(format!("could not run gpg: {}", e)))?;
+  let written = writer.join().expect("writing to gpg panicked");
+  if !output.status.success() {
+    return Err(GradesError::Export(format!("gpg failed: {}", String::from_utf8_lossy(&output.stderr).trim())));
+  }
+  written.map_err(|e| GradesError::Export(format!("could not write to gpg: {}", e)))?;
+
+  Ok(output.stdout)
+}
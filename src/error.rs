@@ -0,0 +1,155 @@
+use std::fmt;
+
+use crate::i18n::Lang;
+
+/// Everything that can go wrong while fetching grades
+#[derive(Debug)]
+pub enum GradesError {
+  /// Passport York rejected the credentials
+  Auth,
+  /// The request to York could not be completed
+  #[cfg(feature = "native")]
+  Network(reqwest::Error),
+  /// A value on the page could not be understood
+  Parse(String),
+  /// The page does not look the way it used to
+  Layout(String),
+  /// Reading or writing local input failed
+  Io(std::io::Error),
+  /// The OS keychain could not be used, or holds no credentials
+  Keychain(String),
+  /// The Duo second factor was denied or never answered
+  Mfa(String),
+  /// A notification about changed grades could not be sent
+  Notify(String),
+  /// The local history database could not be read or written
+  Storage(String),
+  /// The config file is invalid
+  Config(String),
+  /// York is down for maintenance
+  Maintenance,
+  /// Passport York locked the account after too many failed logins
+  Locked,
+  /// The password has to be changed before logging in again
+  PasswordExpired,
+  /// The grades could not be sent to another service like Google Sheets
+  Export(String),
+  /// A check asked for on the command line, like `--assert-gpa-min` or `doctor`, didn't hold
+  Assertion(String),
+  /// The headless browser logging in with `--browser` could not be driven
+  Browser(String),
+  /// The daemon keeping the session could not be asked for a page, or couldn't fetch it
+  Daemon(String),
+}
+
+impl GradesError {
+  /// Process exit code for this error, distinct per variant so scripts can tell them apart
+  pub fn exit_code (&self) -> i32 {
+    match self {
+      GradesError::Auth => 2,
+      #[cfg(feature = "native")]
+      GradesError::Network(_) => 3,
+      GradesError::Parse(_) => 4,
+      GradesError::Layout(_) => 5,
+      GradesError::Io(_) => 6,
+      GradesError::Keychain(_) => 7,
+      GradesError::Mfa(_) => 8,
+      GradesError::Notify(_) => 9,
+      GradesError::Storage(_) => 10,
+      GradesError::Config(_) => 11,
+      GradesError::Maintenance => 12,
+      GradesError::Locked => 13,
+      GradesError::PasswordExpired => 14,
+      GradesError::Export(_) => 15,
+      GradesError::Assertion(_) => 16,
+      GradesError::Browser(_) => 17,
+      GradesError::Daemon(_) => 18,
+    }
+  }
+}
+
+impl GradesError {
+  /// The message in the language of `--lang`, the details coming from elsewhere are left as they are
+  pub fn localized (&self, lang: Lang) -> String {
+    if lang == Lang::En {
+      return self.to_string();
+    }
+
+    match self {
+      GradesError::Auth => "Authentification impossible, vérifiez votre nom d'utilisateur et votre mot de passe Passport York".to_owned(),
+      #[cfg(feature = "native")]
+      GradesError::Network(e) => format!("Impossible de joindre York : {}", e),
+      GradesError::Parse(msg) => format!("Impossible de lire la liste des cours : {}", msg),
+      GradesError::Layout(msg) => format!("La page de la liste des cours a changé : {}", msg),
+      GradesError::Io(e) => format!("Impossible de lire l'entrée : {}", e),
+      GradesError::Keychain(msg) => format!("Impossible d'utiliser le trousseau : {}", msg),
+      GradesError::Mfa(msg) => format!("L'authentification à deux facteurs a échoué : {}", msg),
+      GradesError::Notify(msg) => format!("Impossible d'envoyer la notification : {}", msg),
+      GradesError::Storage(msg) => format!("Impossible d'utiliser l'historique des notes : {}", msg),
+      GradesError::Config(msg) => format!("Configuration invalide : {}", msg),
+      GradesError::Maintenance => "Les systèmes de York sont en maintenance, réessayez plus tard".to_owned(),
+      GradesError::Locked => "Passport York a verrouillé le compte après trop d'échecs de connexion, attendez avant de réessayer ou contactez UIT".to_owned(),
+      GradesError::PasswordExpired => "Le mot de passe Passport York a expiré, changez-le sur https://passportyork.yorku.ca avant de vous connecter".to_owned(),
+      GradesError::Export(msg) => format!("Impossible d'exporter les notes : {}", msg),
+      GradesError::Assertion(msg) => format!("Vérification échouée : {}", msg),
+      GradesError::Browser(msg) => format!("Impossible de se connecter par le navigateur : {}", msg),
+      GradesError::Daemon(msg) => format!("Le démon n'a pas pu récupérer la page : {}", msg),
+    }
+  }
+}
+
+impl fmt::Display for GradesError {
+  fn fmt (&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      GradesError::Auth => write!(f, "Could not authenticate, check your Passport York username and password"),
+      #[cfg(feature = "native")]
+      GradesError::Network(e) => write!(f, "Could not reach York: {}", e),
+      GradesError::Parse(msg) => write!(f, "Could not parse the course list: {}", msg),
+      GradesError::Layout(msg) => write!(f, "The course list page has changed: {}", msg),
+      GradesError::Io(e) => write!(f, "Could not read input: {}", e),
+      GradesError::Keychain(msg) => write!(f, "Could not use the keychain: {}", msg),
+      GradesError::Mfa(msg) => write!(f, "Two-factor authentication failed: {}", msg),
+      GradesError::Notify(msg) => write!(f, "Could not send the notification: {}", msg),
+      GradesError::Storage(msg) => write!(f, "Could not use the grades history: {}", msg),
+      GradesError::Config(msg) => write!(f, "Invalid config: {}", msg),
+      GradesError::Maintenance => write!(f, "York's systems are down for maintenance, try again later"),
+      GradesError::Locked => write!(f, "Passport York locked the account after too many failed logins, wait before trying again or contact UIT"),
+      GradesError::PasswordExpired => write!(f, "The Passport York password has expired, change it at https://passportyork.yorku.ca before logging in"),
+      GradesError::Export(msg) => write!(f, "Could not export the grades: {}", msg),
+      GradesError::Assertion(msg) => write!(f, "Assertion failed: {}", msg),
+      GradesError::Browser(msg) => write!(f, "Could not log in through the browser: {}", msg),
+      GradesError::Daemon(msg) => write!(f, "The daemon could not fetch the page: {}", msg),
+    }
+  }
+}
+
+impl std::error::Error for GradesError {
+  fn source (&self) -> Option<&(dyn std::error::Error + 'static)> {
+    match self {
+      #[cfg(feature = "native")]
+      GradesError::Network(e) => Some(e),
+      GradesError::Io(e) => Some(e),
+      _ => None,
+    }
+  }
+}
+
+#[cfg(feature = "native")]
+impl From<reqwest::Error> for GradesError {
+  fn from (e: reqwest::Error) -> Self {
+    GradesError::Network(e)
+  }
+}
+
+impl From<std::io::Error> for GradesError {
+  fn from (e: std::io::Error) -> Self {
+    GradesError::Io(e)
+  }
+}
+
+#[cfg(feature = "native")]
+impl From<rusqlite::Error> for GradesError {
+  fn from (e: rusqlite::Error) -> Self {
+    GradesError::Storage(e.to_string())
+  }
+}
@@ -0,0 +1,71 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+
+use crate::{CourseData, GradesError};
+
+fn yes () -> bool {
+  true
+}
+
+/// A course entered by hand, like a transfer credit or a grade not showing in the course list yet
+///
+/// Read from `[[extra_courses]]` in the config file or a row of `extra_courses.csv`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ExtraCourse {
+  pub session: String,
+  /// Listing like the course list has it, `"LE EECS 2030 3.00"`
+  pub course: String,
+  #[serde(default)]
+  pub title: String,
+  #[serde(default)]
+  pub grade: String,
+  /// Whether the grade counts towards the GPA, transfer credits usually don't
+  #[serde(default = "yes")]
+  pub in_gpa: bool,
+}
+
+impl ExtraCourse {
+  pub fn to_course (&self) -> CourseData {
+    let mut course = CourseData::new(self.session.clone(), self.course.clone(), self.title.clone(), self.grade.trim().to_ascii_uppercase());
+    course.manual = true;
+    course.excluded = !self.in_gpa;
+    course
+  }
+}
+
+/// Where the extra courses CSV is looked for, next to the config file
+pub fn default_path () -> Option<PathBuf> {
+  dirs::config_dir().map(|dir| dir.join("grades_list").join("extra_courses.csv"))
+}
+
+/// Reads the extra courses from a CSV file with a `session,course,title,grade,in_gpa` header,
+/// a missing file has none
+///
+/// Only `session` and `course` are needed, the other columns can be left out.
+pub fn load_csv (path: &Path) -> Result<Vec<ExtraCourse>, GradesError> {
+  let data = match fs::read(path) {
+    Ok(data) => data,
+    Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+    Err(e) => return Err(e.into()),
+  };
+
+  csv::Reader::from_reader(data.as_slice())
+    .deserialize()
+    .collect::<Result<Vec<ExtraCourse>, _>>()
+    .map_err(|e| GradesError::Config(format!("{}: {}", path.display(), e)))
+}
+
+/// Adds the extra courses to the ones read from the course list
+///
+/// Repeats are worked out again, so a course entered by hand can replace an earlier attempt.
+pub fn merge (grades: &mut Vec<CourseData>, extra: &[ExtraCourse]) {
+  if extra.is_empty() {
+    return;
+  }
+
+  grades.extend(extra.iter().map(ExtraCourse::to_course));
+  crate::repeats::mark_repeats(grades);
+}
@@ -0,0 +1,24 @@
+use crate::GradesError;
+
+// text York shows on pages explaining why a login or the course list didn't work, lowercased
+const MAINTENANCE: &[&str] = &["scheduled maintenance", "system maintenance", "temporarily unavailable"];
+const LOCKED: &[&str] = &["too many failed", "account has been locked", "account is locked"];
+const PASSWORD_EXPIRED: &[&str] = &["password has expired", "password is expired", "must change your password"];
+
+/// The error a known failure page stands for, `None` when the page isn't one of them
+///
+/// A maintenance notice wins over whatever else the page says.
+pub(crate) fn detect (page: &str) -> Option<GradesError> {
+  let page = page.to_lowercase();
+  let shows = |markers: &[&str]| markers.iter().any(|m| page.contains(m));
+
+  if shows(MAINTENANCE) {
+    Some(GradesError::Maintenance)
+  } else if shows(LOCKED) {
+    Some(GradesError::Locked)
+  } else if shows(PASSWORD_EXPIRED) {
+    Some(GradesError::PasswordExpired)
+  } else {
+    None
+  }
+}
@@ -0,0 +1,126 @@
+use chrono::{DateTime, Utc};
+
+use crate::GradesError;
+use crate::diff::{self, GradeChange};
+use crate::format::escape_html;
+use crate::history::{History, Snapshot};
+
+/// Entries kept in the feed, older changes drop off
+pub const ENTRIES: usize = 50;
+
+/// One grade change in the feed
+#[derive(Debug, Clone)]
+pub struct Entry {
+  /// Stays the same every time the feed is rebuilt, so readers don't show a change twice
+  pub id: String,
+  pub updated: DateTime<Utc>,
+  pub title: String,
+  pub summary: String,
+}
+
+impl Entry {
+  fn new (snapshot: &Snapshot, index: usize, change: &GradeChange) -> Entry {
+    let summary = match snapshot.summary.gpa {
+      Some(ref gpa) => format!("{}. GPA: {:.2} / 4, {:.2} / 9", change, gpa.four, gpa.nine),
+      None => format!("{}. No GPA yet", change),
+    };
+
+    Entry {
+      id: format!("urn:grades_list:snapshot:{}:{}", snapshot.summary.id, index),
+      updated: snapshot.summary.fetched_at,
+      title: crate::push::headline(change),
+      summary,
+    }
+  }
+}
+
+/// The latest `limit` grade changes in the user's history, newest first
+///
+/// Changes are found by comparing each snapshot to the one before it, so the feed can be rebuilt
+/// at any time and comes out the same.
+pub fn entries (history: &History, username: &str, limit: usize) -> Result<Vec<Entry>, GradesError> {
+  let mut entries = Vec::new();
+  let mut newer: Option<Snapshot> = None;
+
+  for summary in history.list(username)?.iter().rev() {
+    if entries.len() >= limit {
+      break;
+    }
+
+    let snapshot = match history.get(username, summary.id)? {
+      Some(snapshot) => snapshot,
+      None => continue,
+    };
+    if let Some(ref newer) = newer {
+      let changes = diff::diff(&snapshot.grades, &newer.grades);
+      entries.extend(changes.iter().enumerate().map(|(i, change)| Entry::new(newer, i, change)));
+    }
+    newer = Some(snapshot);
+  }
+
+  entries.truncate(limit);
+  Ok(entries)
+}
+
+/// The entries of one user's feed, kept up to date as fetches are recorded
+///
+/// The whole history is only read the first time, after that each new snapshot is compared to
+/// the one before it, so a server refreshing for months doesn't read every snapshot again.
+pub struct Feed {
+  username: String,
+  entries: Vec<Entry>,
+  latest: Option<Snapshot>,
+  loaded: bool,
+}
+
+impl Feed {
+  pub fn new (username: &str) -> Feed {
+    Feed { username: username.to_owned(), entries: Vec::new(), latest: None, loaded: false }
+  }
+
+  /// Brings the entries up to date with the snapshot just recorded as `id`, newest first
+  pub fn recorded (&mut self, history: &History, id: i64) -> Result<&[Entry], GradesError> {
+    let snapshot = history.get(&self.username, id)?;
+
+    if !self.loaded {
+      self.entries = entries(history, &self.username, ENTRIES)?;
+      self.loaded = true;
+    } else if let (Some(older), Some(newer)) = (&self.latest, &snapshot) {
+      let changes = diff::diff(&older.grades, &newer.grades);
+      let mut entries = changes.iter().enumerate().map(|(i, change)| Entry::new(newer, i, change)).collect::<Vec<_>>();
+      entries.append(&mut self.entries);
+      entries.truncate(ENTRIES);
+      self.entries = entries;
+    }
+
+    if snapshot.is_some() {
+      self.latest = snapshot;
+    }
+    Ok(&self.entries)
+  }
+}
+
+/// Renders the entries as an Atom feed, `updated` is used when there are none
+pub fn atom (entries: &[Entry], updated: DateTime<Utc>) -> String {
+  let updated = entries.first().map(|e| e.updated).unwrap_or(updated);
+  let mut out = String::new();
+
+  out.push_str("<?xml version=\"1.0\" encoding=\"utf-8\"?>\n");
+  out.push_str("<feed xmlns=\"http://www.w3.org/2005/Atom\">\n");
+  out.push_str("  <title>Grade changes</title>\n");
+  out.push_str("  <id>urn:grades_list:feed</id>\n");
+  out.push_str(&format!("  <updated>{}</updated>\n", updated.to_rfc3339()));
+  out.push_str("  <author><name>grades_list</name></author>\n");
+
+  for entry in entries {
+    out.push_str("  <entry>\n");
+    out.push_str(&format!("    <title>{}</title>\n", escape_html(&entry.title)));
+    out.push_str(&format!("    <id>{}</id>\n", entry.id));
+    out.push_str(&format!("    <updated>{}</updated>\n", entry.updated.to_rfc3339()));
+    out.push_str(&format!("    <summary>{}</summary>\n", escape_html(&entry.summary)));
+    out.push_str("  </entry>\n");
+  }
+
+  out.push_str("</feed>\n");
+  out
+}
@@ -0,0 +1,77 @@
+use std::ffi::{CStr, CString};
+use std::os::raw::c_char;
+use std::panic::{AssertUnwindSafe, catch_unwind};
+
+use serde::Serialize;
+
+use crate::blocking::GradesClient;
+use crate::{CourseData, Gpa, GradesError, SecondFactor};
+
+/// What `grades_fetch_json()` returns, the grades or why there are none
+#[derive(Debug, Serialize)]
+#[serde(untagged)]
+enum FfiOutput {
+  Grades { gpa: Option<Gpa>, grades: Vec<CourseData> },
+  Failed { error: String, exit_code: i32 },
+}
+
+// a null pointer or text that isn't UTF-8 is the caller's mistake, reported like any other error
+unsafe fn read_str<'a> (name: &str, ptr: *const c_char) -> Result<&'a str, GradesError> {
+  if ptr.is_null() {
+    return Err(GradesError::Config(format!("{} is null", name)));
+  }
+  CStr::from_ptr(ptr).to_str().map_err(|_| GradesError::Config(format!("{} is not UTF-8", name)))
+}
+
+fn fetch (username: &str, password: &str, factor: &SecondFactor) -> Result<FfiOutput, GradesError> {
+  let client = GradesClient::new()?;
+  client.login_with(username, password, factor)?;
+  let grades = client.fetch_grades();
+  client.logout()?;
+  let grades = grades?;
+  Ok(FfiOutput::Grades { gpa: client.gpa(&grades), grades })
+}
+
+/// Logs in to Passport York, reads the course list and logs out, returning JSON of the GPA and
+/// the courses, or of the error and its exit code when that failed
+///
+/// Duo is answered with a push when `passcode` is null. The string must be given back to
+/// `grades_free()`, it is never null.
+///
+/// # Safety
+///
+/// `username`, `password` and `passcode` must each be null or point to a NUL terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn grades_fetch_json (username: *const c_char, password: *const c_char, passcode: *const c_char) -> *mut c_char {
+  let result = catch_unwind(AssertUnwindSafe(|| {
+    let username = read_str("username", username)?;
+    let password = read_str("password", password)?;
+    let factor = match passcode.is_null() {
+      true => SecondFactor::Push,
+      false => SecondFactor::Passcode(read_str("passcode", passcode)?.to_owned()),
+    };
+    fetch(username, password, &factor)
+  }));
+
+  let output = match result {
+    Ok(Ok(output)) => output,
+    Ok(Err(e)) => FfiOutput::Failed { error: e.to_string(), exit_code: e.exit_code() },
+    // unwinding into C or Swift is undefined, so a panic becomes an error like the rest
+    Err(_) => FfiOutput::Failed { error: "grades_list panicked".to_owned(), exit_code: 1 },
+  };
+
+  let json = serde_json::to_string(&output).expect("the output serializes");
+  CString::new(json).expect("JSON has no NUL").into_raw()
+}
+
+/// Frees a string returned by `grades_fetch_json()`, doing nothing when it is null
+///
+/// # Safety
+///
+/// `json` must be null or a string from `grades_fetch_json()` that wasn't freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn grades_free (json: *mut c_char) {
+  if !json.is_null() {
+    drop(CString::from_raw(json));
+  }
+}
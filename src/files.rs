@@ -0,0 +1,53 @@
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+
+use crate::GradesError;
+
+/// Writes a file only its owner can read, for the ones holding cookies, grades or tokens
+///
+/// A file an older version left readable to others is made private before anything is written.
+pub fn write_private (path: &Path, data: &[u8]) -> Result<(), GradesError> {
+  if let Some(dir) = path.parent() {
+    fs::create_dir_all(dir)?;
+  }
+
+  let mut options = fs::OpenOptions::new();
+  options.write(true).create(true).truncate(true);
+  #[cfg(unix)]
+  std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+
+  let mut file = options.open(path)?;
+  #[cfg(unix)]
+  file.set_permissions(std::os::unix::fs::PermissionsExt::from_mode(0o600))?;
+  file.write_all(data)?;
+  Ok(())
+}
+
+/// Writes a temporary file next to `path` and renames it over `path`, so readers never see half a file
+///
+/// The temporary file is removed again if anything fails, leaving `path` as it was.
+pub fn write_atomically (path: &Path, data: &[u8]) -> Result<(), GradesError> {
+  let name = path.file_name()
+    .ok_or_else(|| GradesError::Config(format!("{} is not a file", path.display())))?;
+  let temp = path.with_file_name(format!(".{}.tmp", name.to_string_lossy()));
+
+  let written = fs::File::create(&temp).and_then(|mut file| {
+    file.write_all(data)?;
+    file.sync_all()?;
+    fs::rename(&temp, path)
+  });
+  if written.is_err() {
+    let _ = fs::remove_file(&temp);
+  }
+
+  Ok(written?)
+}
+
+/// The username made safe to put after a prefix in a file name, a path separator in it can't
+/// point the file somewhere else
+pub fn name_part (username: &str) -> String {
+  username.chars()
+    .map(|c| if c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.' | '@') { c } else { '_' })
+    .collect()
+}
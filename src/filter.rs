@@ -0,0 +1,90 @@
+use crate::{CourseData, GradesError, nine_point_scale};
+use crate::sessions;
+
+/// Restricts the course list to a subset, every criteria that is set has to match
+#[derive(Debug, Clone, Default)]
+pub struct CourseFilter {
+  /// Session like `FW2023`, matching `FW 2023-2024`, or a year like `2023` matching every session
+  /// running through it
+  pub session: Option<String>,
+  /// Subject code like `EECS`
+  pub subject: Option<String>,
+  /// Lowest letter grade to keep, courses without a letter grade are dropped when set
+  pub min_grade: Option<String>,
+}
+
+impl CourseFilter {
+  /// Checks that the minimum grade is a letter grade, so a typo doesn't silently drop everything
+  pub fn validate (&self) -> Result<(), GradesError> {
+    if let Some(ref grade) = self.min_grade {
+      if !nine_point_scale().contains_key(&grade.to_ascii_uppercase()) {
+        return Err(GradesError::Parse(format!("\"{}\" is not a letter grade", grade)));
+      }
+    }
+
+    Ok(())
+  }
+
+  pub fn is_empty (&self) -> bool {
+    self.session.is_none() && self.subject.is_none() && self.min_grade.is_none()
+  }
+
+  pub fn matches (&self, course: &CourseData) -> bool {
+    self.matches_session(course) && self.matches_subject(course) && self.matches_grade(course)
+  }
+
+  /// Keeps only the courses matching the filter
+  pub fn apply (&self, grades: Vec<CourseData>) -> Vec<CourseData> {
+    grades.into_iter().filter(|c| self.matches(c)).collect()
+  }
+
+  fn matches_session (&self, course: &CourseData) -> bool {
+    let wanted = match self.session {
+      Some(ref session) => session.trim(),
+      None => return true,
+    };
+    let session = match sessions::parse(&course.session) {
+      Some(session) => session,
+      None => return course.session.eq_ignore_ascii_case(wanted),
+    };
+
+    // a year on its own is every session that runs through it
+    if let Ok(year) = wanted.parse::<u32>() {
+      return session.start_year == year || session.end_year == year;
+    }
+
+    // "FW2023" is the fall/winter session starting in 2023, "SU2023" the whole summer with its halves
+    match sessions::parse(wanted) {
+      Some(wanted) => (wanted.period, wanted.start_year, wanted.end_year) == (session.period, session.start_year, session.end_year)
+        && (wanted.subsession.is_none() || wanted.subsession == session.subsession),
+      None => false,
+    }
+  }
+
+  fn matches_subject (&self, course: &CourseData) -> bool {
+    match self.subject {
+      Some(ref subject) => course.subject.eq_ignore_ascii_case(subject),
+      None => true,
+    }
+  }
+
+  fn matches_grade (&self, course: &CourseData) -> bool {
+    let min = match self.min_grade {
+      Some(ref grade) => grade.to_ascii_uppercase(),
+      None => return true,
+    };
+
+    let points = nine_point_scale();
+    match (points.get(&course.grade), points.get(&min)) {
+      (Some(grade), Some(min)) => grade >= min,
+      _ => false,
+    }
+  }
+}
+
+/// Whether the course code (e.g. `EECS 2030`) starts with any of the prefixes
+pub fn matches_prefixes (course: &CourseData, prefixes: &[String]) -> bool {
+  let code = course.code().to_ascii_uppercase();
+
+  prefixes.iter().any(|p| code.starts_with(&p.trim().to_ascii_uppercase()))
+}
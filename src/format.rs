@@ -0,0 +1,410 @@
+use std::str::FromStr;
+
+use serde::Serialize;
+
+use crate::{CourseData, CoursePoints, Gpa, GradesError};
+use crate::classify::CreditSummary;
+use crate::i18n::{Lang, Text};
+
+/// Ways the grades can be printed
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Format {
+  Table,
+  Json,
+  Ndjson,
+  Csv,
+  Markdown,
+  Html,
+  Yaml,
+  Toml,
+  Xlsx,
+  Pdf,
+  /// iCalendar, only for the schedule and dates
+  Ics,
+}
+
+impl Format {
+  pub const NAMES: &'static [&'static str] = &["table", "json", "ndjson", "csv", "markdown", "html", "yaml", "toml", "xlsx", "pdf", "ics"];
+}
+
+impl Format {
+  /// The name `--format` takes
+  pub fn name (self) -> &'static str {
+    match self {
+      Format::Table => "table",
+      Format::Json => "json",
+      Format::Ndjson => "ndjson",
+      Format::Csv => "csv",
+      Format::Markdown => "markdown",
+      Format::Html => "html",
+      Format::Yaml => "yaml",
+      Format::Toml => "toml",
+      Format::Xlsx => "xlsx",
+      Format::Pdf => "pdf",
+      Format::Ics => "ics",
+    }
+  }
+}
+
+impl FromStr for Format {
+  type Err = String;
+
+  fn from_str (s: &str) -> Result<Format, String> {
+    match s {
+      "table" => Ok(Format::Table),
+      "json" => Ok(Format::Json),
+      "ndjson" => Ok(Format::Ndjson),
+      "csv" => Ok(Format::Csv),
+      "markdown" => Ok(Format::Markdown),
+      "html" => Ok(Format::Html),
+      "yaml" => Ok(Format::Yaml),
+      "toml" => Ok(Format::Toml),
+      "xlsx" => Ok(Format::Xlsx),
+      "pdf" => Ok(Format::Pdf),
+      "ics" => Ok(Format::Ics),
+      _ => Err(format!("unknown format \"{}\", expected one of {}", s, Format::NAMES.join(", "))),
+    }
+  }
+}
+
+fn csv_error (e: csv::Error) -> GradesError {
+  GradesError::Io(e.into())
+}
+
+fn finish (writer: csv::Writer<Vec<u8>>) -> Result<String, GradesError> {
+  let data = writer.into_inner().map_err(|e| GradesError::Io(std::io::Error::new(e.error().kind(), e.to_string())))?;
+  Ok(String::from_utf8(data).expect("csv output is built from strings"))
+}
+
+/// Columns of `CourseData` in the order it serializes them
+const CSV_HEADER: [&str; 15] = ["session", "course", "title", "grade", "faculty", "subject", "number", "credits", "section", "superseded", "manual", "excluded", "overridden", "grade_points", "notes"];
+
+// puts the GPA in the grade column, padded out to as many columns as the course rows
+fn gpa_footer (scale: &str, value: f32) -> Vec<String> {
+  let mut row = vec![String::new(); CSV_HEADER.len()];
+  row[0] = "GPA".to_owned();
+  row[2] = scale.to_owned();
+  row[3] = value.to_string();
+  row
+}
+
+// and the credit totals in the credits column
+fn credits_footer (total: &str, value: f32) -> Vec<String> {
+  let mut row = vec![String::new(); CSV_HEADER.len()];
+  row[0] = "Credits".to_owned();
+  row[2] = total.to_owned();
+  row[7] = value.to_string();
+  row
+}
+
+/// Decimals of the numbers written with `--machine`
+pub const MACHINE_DECIMALS: u32 = 4;
+
+/// How a GPA is cut down to its decimals, `--rounding`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Rounding {
+  Round,
+  Truncate,
+  Ceil,
+}
+
+impl Rounding {
+  pub const NAMES: &'static [&'static str] = &["round", "truncate", "ceil"];
+}
+
+impl FromStr for Rounding {
+  type Err = String;
+
+  fn from_str (s: &str) -> Result<Rounding, String> {
+    match s {
+      "round" => Ok(Rounding::Round),
+      "truncate" => Ok(Rounding::Truncate),
+      "ceil" => Ok(Rounding::Ceil),
+      _ => Err(format!("unknown rounding \"{}\", expected one of {}", s, Rounding::NAMES.join(", "))),
+    }
+  }
+}
+
+/// The decimals a GPA is given with, `--precision` and `--rounding`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Precision {
+  pub decimals: u32,
+  pub rounding: Rounding,
+}
+
+impl Precision {
+  /// Most decimals `--precision` takes, an f32 GPA has no more that mean anything
+  pub const MAX_DECIMALS: u32 = 6;
+
+  /// The decimals and rounding, failing past `MAX_DECIMALS`
+  pub fn new (decimals: u32, rounding: Rounding) -> Result<Precision, GradesError> {
+    if decimals > Precision::MAX_DECIMALS {
+      return Err(GradesError::Config(format!("--precision is at most {} decimals, not {}", Precision::MAX_DECIMALS, decimals)));
+    }
+    Ok(Precision { decimals, rounding })
+  }
+
+  /// The value cut down to the decimals, which prints without the noise of an f32 like 7.3333335
+  pub fn apply (self, value: f32) -> f32 {
+    // from the shortest decimal that reads back as the f32, or truncating 3.3, stored as
+    // 3.2999999, would give 3.2
+    let exact: f64 = value.to_string().parse().unwrap_or_else(|_| f64::from(value));
+    let scale = 10f64.powi(self.decimals as i32);
+    let scaled = (exact * scale * 1e6).round() / 1e6;
+
+    let cut = match self.rounding {
+      Rounding::Round => scaled.round(),
+      Rounding::Truncate => scaled.trunc(),
+      Rounding::Ceil => scaled.ceil(),
+    };
+    (cut / scale) as f32
+  }
+
+  pub fn gpa (self, gpa: &Gpa) -> Gpa {
+    Gpa { four: self.apply(gpa.four), nine: self.apply(gpa.nine) }
+  }
+
+  /// The value with exactly the decimals, for the table
+  pub fn show (self, value: f32) -> String {
+    format!("{:.*}", self.decimals as usize, self.apply(value))
+  }
+}
+
+/// Rounds every number with a fraction anywhere in the JSON to the decimals
+pub fn round_numbers (value: &mut serde_json::Value, decimals: u32) {
+  match value {
+    serde_json::Value::Number(n) if n.is_f64() => {
+      let scale = 10f64.powi(decimals as i32);
+      let rounded = (n.as_f64().unwrap_or_default() * scale).round() / scale;
+      if let Some(rounded) = serde_json::Number::from_f64(rounded) {
+        *n = rounded;
+      }
+    },
+    serde_json::Value::Array(items) => items.iter_mut().for_each(|item| round_numbers(item, decimals)),
+    serde_json::Value::Object(fields) => fields.values_mut().for_each(|field| round_numbers(field, decimals)),
+    _ => {},
+  }
+}
+
+/// Removes the nulls from the JSON, which TOML has no way to write, like the `None` fields
+/// `toml()` leaves out
+pub fn drop_nulls (value: &mut serde_json::Value) {
+  match value {
+    serde_json::Value::Array(items) => {
+      items.retain(|item| !item.is_null());
+      items.iter_mut().for_each(drop_nulls);
+    },
+    serde_json::Value::Object(fields) => {
+      fields.retain(|_, field| !field.is_null());
+      fields.values_mut().for_each(drop_nulls);
+    },
+    _ => {},
+  }
+}
+
+/// Renders the grades as RFC 4180 CSV with a header row, optionally followed by two GPA rows and
+/// four rows of credit totals
+pub fn csv (grades: &[CourseData], gpa_row: Option<&Gpa>, credits_rows: Option<&CreditSummary>) -> Result<String, GradesError> {
+  let mut writer = csv::Writer::from_writer(Vec::new());
+
+  for course in grades {
+    writer.serialize(course).map_err(csv_error)?;
+  }
+
+  // an empty grades list still needs the header before the footer
+  if grades.is_empty() && (gpa_row.is_some() || credits_rows.is_some()) {
+    writer.write_record(CSV_HEADER).map_err(csv_error)?;
+  }
+  if let Some(gpa) = gpa_row {
+    writer.write_record(gpa_footer("Four Point", gpa.four)).map_err(csv_error)?;
+    writer.write_record(gpa_footer("Nine Point", gpa.nine)).map_err(csv_error)?;
+  }
+  if let Some(credits) = credits_rows {
+    writer.write_record(credits_footer("Attempted", credits.attempted)).map_err(csv_error)?;
+    writer.write_record(credits_footer("Earned", credits.earned)).map_err(csv_error)?;
+    writer.write_record(credits_footer("In GPA", credits.in_gpa)).map_err(csv_error)?;
+    writer.write_record(credits_footer("Excluded", credits.excluded)).map_err(csv_error)?;
+  }
+
+  finish(writer)
+}
+
+/// Renders the GPA on its own as CSV, for writing next to the grades
+///
+/// With no GPA yet the row under the header is left empty.
+pub fn gpa_csv (gpa: Option<&Gpa>) -> Result<String, GradesError> {
+  let mut writer = csv::Writer::from_writer(Vec::new());
+  match gpa {
+    Some(gpa) => writer.serialize(gpa).map_err(csv_error)?,
+    None => {
+      writer.write_record(["four", "nine"]).map_err(csv_error)?;
+      writer.write_record(["", ""]).map_err(csv_error)?;
+    },
+  }
+  finish(writer)
+}
+
+/// A line of `--format ndjson`, told apart by its `type`
+#[derive(Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum Record<'a> {
+  Course {
+    #[serde(flatten)]
+    course: &'a CourseData,
+    #[serde(flatten)]
+    points: Option<CoursePoints>,
+  },
+  Gpa {
+    #[serde(flatten)]
+    gpa: Option<&'a Gpa>,
+  },
+}
+
+/// Renders one JSON object per line, a `"type": "course"` record for each course and a final
+/// `"type": "gpa"` record, without the GPA fields while there is none
+pub fn ndjson (grades: &[CourseData], gpa: Option<&Gpa>) -> String {
+  grades.iter()
+    .map(|course| Record::Course { course, points: course.points() })
+    .chain(std::iter::once(Record::Gpa { gpa }))
+    .map(|record| serde_json::to_string(&record).expect("records serialize") + "\n")
+    .collect()
+}
+
+/// Renders the same document as the JSON output as YAML
+pub fn yaml<T: Serialize> (value: &T) -> Result<String, GradesError> {
+  serde_yaml::to_string(value).map_err(|e| GradesError::Io(std::io::Error::other(e)))
+}
+
+// `Value` widens the f32 points and GPAs to f64, which would print 3.433333396911621 for 3.4333334
+fn shorten_floats (value: &mut toml::Value) {
+  match value {
+    toml::Value::Float(f) => *f = (*f as f32).to_string().parse().unwrap_or(*f),
+    toml::Value::Array(items) => items.iter_mut().for_each(shorten_floats),
+    toml::Value::Table(table) => table.iter_mut().for_each(|(_, v)| shorten_floats(v)),
+    _ => {},
+  }
+}
+
+/// Renders the same document as the JSON output as TOML
+///
+/// Fields without a value are left out since TOML has no null.
+pub fn toml<T: Serialize> (value: &T) -> Result<String, GradesError> {
+  let toml_error = |e: toml::ser::Error| GradesError::Io(std::io::Error::other(e));
+
+  // going through `Value` puts the plain keys of each table before its subtables like TOML needs
+  let mut value = toml::Value::try_from(value).map_err(toml_error)?;
+  shorten_floats(&mut value);
+  toml::to_string(&value).map_err(toml_error)
+}
+
+/// Grade as shown in reports, noting overrides, attempts that a retake replaced and courses left out by hand
+pub fn display_grade (course: &CourseData) -> String {
+  display_grade_in(course, Lang::En)
+}
+
+/// `display_grade()` with the notes in the language of `--lang`
+pub fn display_grade_in (course: &CourseData, lang: Lang) -> String {
+  if let Some(ref original) = course.overridden {
+    let original = if original.is_empty() { lang.text(Text::Ungraded) } else { original };
+    lang.format(Text::Overridden, &[&course.grade, &original])
+  } else if course.superseded {
+    lang.format(Text::RepeatedLater, &[&course.grade])
+  } else if course.excluded {
+    lang.format(Text::LeftOut, &[&course.grade])
+  } else {
+    course.grade.clone()
+  }
+}
+
+/// prettytable style spec coloring a letter grade by its range, `None` for grades outside the scale
+pub fn grade_style (grade: &str) -> Option<&'static str> {
+  match grade.chars().next()? {
+    _ if !crate::nine_point_scale().contains_key(grade) => None,
+    'A' => Some("Fg"),
+    'B' | 'C' => Some("Fy"),
+    _ => Some("Fr"),
+  }
+}
+
+// "-" in place of a GPA there is none of yet
+fn gpa_cells (gpa: Option<&Gpa>) -> (String, String) {
+  gpa.map(|gpa| (gpa.four.to_string(), gpa.nine.to_string()))
+    .unwrap_or_else(|| ("-".to_owned(), "-".to_owned()))
+}
+
+// pipes would end the cell early and newlines the row
+fn markdown_cell (s: &str) -> String {
+  s.replace('|', "\\|").replace('\n', " ")
+}
+
+/// Renders a GPA summary and the grades as Markdown tables
+pub fn markdown (grades: &[CourseData], gpa: Option<&Gpa>) -> String {
+  let mut out = String::new();
+
+  out.push_str("## GPA\n\n");
+  out.push_str("| Four Point | Nine Point |\n");
+  out.push_str("| ---------- | ---------- |\n");
+  let (four, nine) = gpa_cells(gpa);
+  out.push_str(&format!("| {} | {} |\n\n", four, nine));
+
+  out.push_str("## Grades\n\n");
+  out.push_str("| Session | Course | Title | Grade |\n");
+  out.push_str("| ------- | ------ | ----- | ----- |\n");
+
+  for course in grades {
+    out.push_str(&format!(
+      "| {} | {} | {} | {} |\n",
+      markdown_cell(&course.session), markdown_cell(&course.course), markdown_cell(&course.title), markdown_cell(&display_grade(course)),
+    ));
+  }
+
+  out
+}
+
+pub(crate) fn escape_html (s: &str) -> String {
+  s.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+static HTML_STYLE: &str = "
+body { font-family: -apple-system, Helvetica, Arial, sans-serif; margin: 2em auto; max-width: 60em; color: #222; }
+table { border-collapse: collapse; margin-bottom: 2em; width: 100%; }
+th, td { border: 1px solid #ccc; padding: 0.4em 0.8em; text-align: left; }
+th { background: #e31837; color: #fff; }
+tr:nth-child(even) td { background: #f6f6f6; }
+";
+
+/// Renders a self-contained HTML report with the GPA summary and the grades
+pub fn html (grades: &[CourseData], gpa: Option<&Gpa>) -> String {
+  let (four, nine) = gpa_cells(gpa);
+  let mut rows = String::new();
+
+  for course in grades {
+    rows.push_str(&format!(
+      "      <tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>\n",
+      escape_html(&course.session), escape_html(&course.course), escape_html(&course.title), escape_html(&display_grade(course)),
+    ));
+  }
+
+  format!(r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+  <meta charset="utf-8">
+  <title>Grades</title>
+  <style>{style}</style>
+</head>
+<body>
+  <h2>GPA</h2>
+  <table>
+    <thead><tr><th>Four Point</th><th>Nine Point</th></tr></thead>
+    <tbody><tr><td>{four}</td><td>{nine}</td></tr></tbody>
+  </table>
+  <h2>Grades</h2>
+  <table>
+    <thead><tr><th>Session</th><th>Course</th><th>Title</th><th>Grade</th></tr></thead>
+    <tbody>
+{rows}    </tbody>
+  </table>
+</body>
+</html>
+"#, style = HTML_STYLE, four = four, nine = nine, rows = rows)
+}
@@ -0,0 +1,113 @@
+use async_graphql::{Context, EmptyMutation, EmptySubscription, Enum, Object, Result, Schema, SimpleObject};
+use chrono::{DateTime, Utc};
+
+use crate::CourseData;
+use crate::filter::CourseFilter;
+use crate::server::{Latest, Shared};
+
+/// The schema served at `/graphql`, reading the grades of the last fetch
+///
+/// Every request carries the `Shared` state of the account whose token it was sent with.
+pub type GradesSchema = Schema<Query, EmptyMutation, EmptySubscription>;
+
+pub fn schema () -> GradesSchema {
+  Schema::build(Query, EmptyMutation, EmptySubscription).finish()
+}
+
+/// One of the two GPA scales York reports, `FOUR` or `NINE`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Enum)]
+pub enum Scale {
+  Four,
+  Nine,
+}
+
+impl From<Scale> for crate::Scale {
+  fn from (scale: Scale) -> crate::Scale {
+    match scale {
+      Scale::Four => crate::Scale::Four,
+      Scale::Nine => crate::Scale::Nine,
+    }
+  }
+}
+
+/// A row of the course list, with the fields of the JSON output in camelCase
+#[derive(Debug, Clone, SimpleObject)]
+pub struct Course {
+  pub session: String,
+  pub course: String,
+  pub title: String,
+  pub grade: String,
+  pub faculty: String,
+  pub subject: String,
+  pub number: String,
+  pub credits: Option<f32>,
+  pub section: String,
+  pub superseded: bool,
+  pub manual: bool,
+  pub excluded: bool,
+  pub overridden: Option<String>,
+  pub grade_points: Option<f32>,
+  pub notes: String,
+}
+
+impl From<CourseData> for Course {
+  fn from (c: CourseData) -> Course {
+    Course {
+      session: c.session,
+      course: c.course,
+      title: c.title,
+      grade: c.grade,
+      faculty: c.faculty,
+      subject: c.subject,
+      number: c.number,
+      credits: c.credits,
+      section: c.section,
+      superseded: c.superseded,
+      manual: c.manual,
+      excluded: c.excluded,
+      overridden: c.overridden,
+      grade_points: c.grade_points,
+      notes: c.notes,
+    }
+  }
+}
+
+pub struct Query;
+
+async fn latest (ctx: &Context<'_>) -> Result<Latest> {
+  match ctx.data::<Shared>()?.read().await.latest {
+    Some(ref latest) => Ok(latest.clone()),
+    None => Err("the grades haven't been fetched yet".into()),
+  }
+}
+
+// same matching as `--session` and `--subject`, so `FW2021` finds "FW 2021-2022"
+fn filtered (latest: Latest, session: Option<String>, subject: Option<String>) -> Vec<CourseData> {
+  CourseFilter { session, subject, ..Default::default() }.apply(latest.grades)
+}
+
+#[Object]
+impl Query {
+  /// When the grades were last fetched
+  async fn fetched_at (&self, ctx: &Context<'_>) -> Result<DateTime<Utc>> {
+    Ok(latest(ctx).await?.fetched_at)
+  }
+
+  /// The courses, of one session like `FW2023` and one subject like `EECS` when given
+  async fn grades (&self, ctx: &Context<'_>, session: Option<String>, subject: Option<String>) -> Result<Vec<Course>> {
+    Ok(filtered(latest(ctx).await?, session, subject).into_iter().map(Course::from).collect())
+  }
+
+  /// GPA on the scale, nine point unless another is asked for, of the courses `grades` would
+  /// return with the same arguments. Null when none of them count towards the GPA
+  async fn gpa (
+    &self,
+    ctx: &Context<'_>,
+    #[graphql(default_with = "Scale::Nine")] scale: Scale,
+    session: Option<String>,
+    subject: Option<String>,
+  ) -> Result<Option<f32>> {
+    let grades = filtered(latest(ctx).await?, session, subject);
+    Ok(crate::calculate_gpa(&grades).map(|gpa| crate::Scale::from(scale).of(&gpa)))
+  }
+}
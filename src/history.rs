@@ -0,0 +1,300 @@
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Utc};
+use rusqlite::{Connection, OptionalExtension, params};
+use serde::{Deserialize, Serialize};
+
+use crate::{CourseData, Gpa, GradesError};
+use crate::vault::Vault;
+
+/// One recorded fetch, without its courses
+#[derive(Debug, Clone, Serialize)]
+pub struct SnapshotSummary {
+  pub id: i64,
+  pub fetched_at: DateTime<Utc>,
+  pub courses: usize,
+  /// `None` when no course counted towards the GPA yet
+  pub gpa: Option<Gpa>,
+}
+
+/// One recorded fetch with every course it returned
+#[derive(Debug, Clone, Serialize)]
+pub struct Snapshot {
+  #[serde(flatten)]
+  pub summary: SnapshotSummary,
+  pub grades: Vec<CourseData>,
+}
+
+/// SQLite database of every fetch, kept per user
+///
+/// Opened with a vault, the GPA and courses of every snapshot are encrypted into its `sealed`
+/// column, leaving only the username and the time of the fetch readable.
+pub struct History {
+  conn: Connection,
+  vault: Option<Vault>,
+}
+
+/// What an encrypted snapshot keeps in its `sealed` column
+#[derive(Debug, Serialize, Deserialize)]
+struct Sealed {
+  // null when nothing was graded, which is also how a NaN GPA was once written
+  four: Option<f32>,
+  nine: Option<f32>,
+  courses: Vec<SealedCourse>,
+}
+
+/// The columns of `courses`
+#[derive(Debug, Serialize, Deserialize)]
+struct SealedCourse {
+  session: String,
+  course: String,
+  title: String,
+  grade: String,
+}
+
+type SealedCourses = Vec<SealedCourse>;
+
+/// Where the history database is kept, under the XDG data dir on Linux
+pub fn default_path () -> Option<PathBuf> {
+  dirs::data_dir().map(|dir| dir.join("grades_list").join("history.sqlite3"))
+}
+
+impl History {
+  /// Opens the database, creating it and its tables if needed
+  pub fn open (path: &Path) -> Result<History, GradesError> {
+    History::open_with(path, None)
+  }
+
+  /// Opens the database with the grades encrypted, encrypting the snapshots recorded without
+  pub fn open_encrypted (path: &Path, vault: Vault) -> Result<History, GradesError> {
+    let mut history = History::open_with(path, Some(vault))?;
+    history.seal_plain()?;
+    Ok(history)
+  }
+
+  fn open_with (path: &Path, vault: Option<Vault>) -> Result<History, GradesError> {
+    if let Some(dir) = path.parent() {
+      std::fs::create_dir_all(dir)?;
+    }
+
+    let conn = Connection::open(path)?;
+    conn.execute_batch("
+      CREATE TABLE IF NOT EXISTS snapshots (
+        id INTEGER PRIMARY KEY,
+        username TEXT NOT NULL,
+        fetched_at TEXT NOT NULL,
+        four REAL,
+        nine REAL
+      );
+      CREATE TABLE IF NOT EXISTS courses (
+        snapshot_id INTEGER NOT NULL REFERENCES snapshots(id) ON DELETE CASCADE,
+        session TEXT NOT NULL,
+        course TEXT NOT NULL,
+        title TEXT NOT NULL,
+        grade TEXT NOT NULL
+      );
+      CREATE INDEX IF NOT EXISTS courses_snapshot ON courses(snapshot_id);
+    ")?;
+
+    // databases from before encryption don't have the column yet
+    let sealed = conn.query_row("SELECT 1 FROM pragma_table_info('snapshots') WHERE name = 'sealed'", params![], |_| Ok(()))
+      .optional()?;
+    if sealed.is_none() {
+      conn.execute_batch("ALTER TABLE snapshots ADD COLUMN sealed BLOB")?;
+    }
+
+    // nor can they record a fetch without a GPA, SQLite only drops NOT NULL by copying the table
+    let not_null = conn.query_row("SELECT \"notnull\" FROM pragma_table_info('snapshots') WHERE name = 'four'", params![], |row| row.get::<_, bool>(0))?;
+    if not_null {
+      // dropping the old table would otherwise cascade to the courses of every snapshot
+      let foreign_keys = conn.query_row("PRAGMA foreign_keys", params![], |row| row.get::<_, bool>(0))?;
+      conn.execute_batch("PRAGMA foreign_keys = OFF")?;
+      let copied = copy_nullable(&conn);
+      // back on whether the copy went through or was rolled back
+      if foreign_keys {
+        conn.execute_batch("PRAGMA foreign_keys = ON")?;
+      }
+      copied?;
+    }
+
+    Ok(History { conn, vault })
+  }
+
+  /// Encrypts the snapshots recorded without a vault, wiping their plain rows off the disk
+  fn seal_plain (&mut self) -> Result<(), GradesError> {
+    let vault = self.vault.clone().expect("only called with a vault");
+    let tx = self.conn.transaction()?;
+
+    let plain = tx.prepare("SELECT id, four, nine FROM snapshots WHERE sealed IS NULL")?
+      .query_map(params![], |row| Ok((row.get::<_, i64>(0)?, row.get::<_, Option<f64>>(1)?, row.get::<_, Option<f64>>(2)?)))?
+      .collect::<Result<Vec<_>, _>>()?;
+    if plain.is_empty() {
+      return Ok(());
+    }
+
+    for (id, four, nine) in plain {
+      let courses = plain_courses(&tx, id)?;
+      let sealed = seal(&vault, gpa(four, nine).as_ref(), courses);
+      tx.execute("UPDATE snapshots SET four = NULL, nine = NULL, sealed = ?2 WHERE id = ?1", params![id, sealed])?;
+      tx.execute("DELETE FROM courses WHERE snapshot_id = ?1", params![id])?;
+    }
+    tx.commit()?;
+
+    // the deleted rows stay in free pages until the file is rewritten
+    self.conn.execute_batch("VACUUM")?;
+    Ok(())
+  }
+
+  /// Stores a fetch, returning the id of the new snapshot
+  pub fn record (&mut self, username: &str, grades: &[CourseData], gpa: Option<&Gpa>) -> Result<i64, GradesError> {
+    let tx = self.conn.transaction()?;
+
+    if let Some(ref vault) = self.vault {
+      let courses = grades.iter()
+        .map(|c| SealedCourse { session: c.session.clone(), course: c.course.clone(), title: c.title.clone(), grade: c.grade.clone() })
+        .collect();
+      tx.execute(
+        "INSERT INTO snapshots (username, fetched_at, sealed) VALUES (?1, ?2, ?3)",
+        params![username, Utc::now().to_rfc3339(), seal(vault, gpa, courses)],
+      )?;
+      let id = tx.last_insert_rowid();
+      tx.commit()?;
+      return Ok(id);
+    }
+
+    tx.execute(
+      "INSERT INTO snapshots (username, fetched_at, four, nine) VALUES (?1, ?2, ?3, ?4)",
+      params![username, Utc::now().to_rfc3339(), gpa.map(|gpa| gpa.four as f64), gpa.map(|gpa| gpa.nine as f64)],
+    )?;
+    let id = tx.last_insert_rowid();
+
+    {
+      let mut insert = tx.prepare("INSERT INTO courses (snapshot_id, session, course, title, grade) VALUES (?1, ?2, ?3, ?4, ?5)")?;
+      for course in grades {
+        insert.execute(params![id, course.session, course.course, course.title, course.grade])?;
+      }
+    }
+
+    tx.commit()?;
+    Ok(id)
+  }
+
+  /// Every snapshot of the user, oldest first
+  pub fn list (&self, username: &str) -> Result<Vec<SnapshotSummary>, GradesError> {
+    Ok(self.summaries(username, None)?.into_iter().map(|(summary, _)| summary).collect())
+  }
+
+  // all of the user's snapshots, or only the one with this id, with the courses of the encrypted ones
+  fn summaries (&self, username: &str, id: Option<i64>) -> Result<Vec<(SnapshotSummary, Option<SealedCourses>)>, GradesError> {
+    let mut stmt = self.conn.prepare("
+      SELECT s.id, s.fetched_at, s.four, s.nine, COUNT(c.snapshot_id), s.sealed
+      FROM snapshots s LEFT JOIN courses c ON c.snapshot_id = s.id
+      WHERE s.username = ?1 AND (?2 IS NULL OR s.id = ?2)
+      GROUP BY s.id
+      ORDER BY s.id
+    ")?;
+
+    let rows = stmt.query_map(params![username, id], |row| {
+      Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?, row.get::<_, Option<f64>>(2)?, row.get::<_, Option<f64>>(3)?, row.get::<_, i64>(4)?,
+        row.get::<_, Option<Vec<u8>>>(5)?))
+    })?;
+
+    let mut summaries = Vec::new();
+    for row in rows {
+      let (id, fetched_at, four, nine, courses, sealed) = row?;
+      let fetched_at = parse_time(&fetched_at)?;
+
+      summaries.push(match sealed {
+        Some(sealed) => {
+          let sealed = self.unseal(&sealed)?;
+          let summary = SnapshotSummary { id, fetched_at, courses: sealed.courses.len(), gpa: gpa(sealed.four, sealed.nine) };
+          (summary, Some(sealed.courses))
+        },
+        None => (SnapshotSummary { id, fetched_at, courses: courses as usize, gpa: gpa(four, nine) }, None),
+      });
+    }
+
+    Ok(summaries)
+  }
+
+  fn unseal (&self, sealed: &[u8]) -> Result<Sealed, GradesError> {
+    let vault = self.vault.as_ref()
+      .ok_or_else(|| GradesError::Storage("the history is encrypted, set `encryption` in the config to read it".to_owned()))?;
+    serde_json::from_slice(&vault.open(sealed)?)
+      .map_err(|e| GradesError::Storage(format!("unreadable snapshot: {}", e)))
+  }
+
+  /// The snapshot with the given id, if it belongs to the user
+  pub fn get (&self, username: &str, id: i64) -> Result<Option<Snapshot>, GradesError> {
+    let (summary, sealed) = match self.summaries(username, Some(id))?.pop() {
+      Some(found) => found,
+      None => return Ok(None),
+    };
+
+    let courses = match sealed {
+      Some(courses) => courses,
+      None => plain_courses(&self.conn, id)?,
+    };
+    let mut grades = courses.into_iter()
+      .map(|c| CourseData::new(c.session, c.course, c.title, c.grade))
+      .collect::<Vec<_>>();
+    crate::repeats::mark_repeats(&mut grades);
+
+    Ok(Some(Snapshot { summary, grades }))
+  }
+
+  /// The most recent snapshot of the user
+  pub fn latest (&self, username: &str) -> Result<Option<Snapshot>, GradesError> {
+    match self.list(username)?.last() {
+      Some(summary) => self.get(username, summary.id),
+      None => Ok(None),
+    }
+  }
+}
+
+/// Copies the snapshots into a table whose GPA can be NULL in one transaction, rolled back when
+/// any of it fails
+fn copy_nullable (conn: &Connection) -> Result<(), GradesError> {
+  let tx = conn.unchecked_transaction()?;
+  tx.execute_batch("
+    CREATE TABLE snapshots_nullable (
+      id INTEGER PRIMARY KEY,
+      username TEXT NOT NULL,
+      fetched_at TEXT NOT NULL,
+      four REAL,
+      nine REAL,
+      sealed BLOB
+    );
+    INSERT INTO snapshots_nullable SELECT id, username, fetched_at, four, nine, sealed FROM snapshots;
+    DROP TABLE snapshots;
+    ALTER TABLE snapshots_nullable RENAME TO snapshots;
+  ")?;
+  tx.commit()?;
+  Ok(())
+}
+
+fn plain_courses (conn: &Connection, id: i64) -> Result<Vec<SealedCourse>, GradesError> {
+  let mut stmt = conn.prepare("SELECT session, course, title, grade FROM courses WHERE snapshot_id = ?1 ORDER BY rowid")?;
+  let courses = stmt.query_map(params![id], |row| {
+    Ok(SealedCourse { session: row.get(0)?, course: row.get(1)?, title: row.get(2)?, grade: row.get(3)? })
+  })?.collect::<Result<Vec<_>, _>>()?;
+  Ok(courses)
+}
+
+fn seal (vault: &Vault, gpa: Option<&Gpa>, courses: Vec<SealedCourse>) -> Vec<u8> {
+  let sealed = Sealed { four: gpa.map(|gpa| gpa.four), nine: gpa.map(|gpa| gpa.nine), courses };
+  vault.seal(&serde_json::to_vec(&sealed).expect("snapshots serialize"))
+}
+
+fn gpa<T: Into<f64>> (four: Option<T>, nine: Option<T>) -> Option<Gpa> {
+  match (four, nine) {
+    (Some(four), Some(nine)) => Some(Gpa { four: four.into() as f32, nine: nine.into() as f32 }),
+    _ => None,
+  }
+}
+
+fn parse_time (s: &str) -> Result<DateTime<Utc>, GradesError> {
+  DateTime::parse_from_rfc3339(s)
+    .map(|t| t.with_timezone(&Utc))
+    .map_err(|e| GradesError::Storage(format!("bad timestamp \"{}\": {}", s, e)))
+}
@@ -0,0 +1,311 @@
+use std::fmt;
+use std::str::FromStr;
+
+use crate::classify::GradeKind;
+use crate::dates::Kind;
+use crate::degree::Status;
+use crate::standing::Standing;
+
+/// Language of the table and of the error messages, `--lang`
+///
+/// French is for the students of the bilingual Glendon campus. What York itself writes, like
+/// course titles, is shown as it is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Lang {
+  #[default]
+  En,
+  Fr,
+}
+
+impl Lang {
+  pub const NAMES: &'static [&'static str] = &["en", "fr"];
+}
+
+impl FromStr for Lang {
+  type Err = String;
+
+  fn from_str (s: &str) -> Result<Lang, String> {
+    match s {
+      "en" => Ok(Lang::En),
+      "fr" => Ok(Lang::Fr),
+      _ => Err(format!("unknown language \"{}\", expected one of {}", s, Lang::NAMES.join(", "))),
+    }
+  }
+}
+
+/// A heading or line of the table output or a prefix on stderr, `{}` being filled in by
+/// `Lang::format()`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Text {
+  Gpa,
+  FourPoint,
+  NinePoint,
+  Overall,
+  Grades,
+  Session,
+  Course,
+  Title,
+  Grade,
+  Nine,
+  Four,
+  NineCredits,
+  FourCredits,
+  Cumulative,
+  SessionGpa,
+  OnScale,
+  OnScaleUngraded,
+  NotInGpa,
+  Reason,
+  ExcludedSummary,
+  Standing,
+  NoStanding,
+  ToNextStanding,
+  AboveStanding,
+  GpaPerSession,
+  Overridden,
+  Ungraded,
+  RepeatedLater,
+  LeftOut,
+  Credits,
+  Attempted,
+  Earned,
+  InGpa,
+  ExcludedCredits,
+  InProgress,
+  Expected,
+  Projected,
+  NineFour,
+  Now,
+  BestCase,
+  WithoutExpected,
+  Warning,
+  Check,
+  CheckResult,
+  Detail,
+  Id,
+  FetchedAt,
+  Courses,
+  Before,
+  After,
+  Difference,
+  Changes,
+  Change,
+  Added,
+  Changed,
+  Removed,
+  Scale,
+  GpaHeading,
+  YorkFour,
+  YorkNine,
+  Mean,
+  Median,
+  CreditsAttempted,
+  CreditsEarned,
+  BestSession,
+  WorstSession,
+  ByLevel,
+  Level,
+  CreditsHeading,
+  Requirement,
+  Required,
+  Completed,
+  InProgressHeading,
+  Remaining,
+  Status,
+  Outstanding,
+  CreditsOutstanding,
+  Type,
+  Days,
+  Time,
+  Location,
+  Date,
+  Event,
+  Kind,
+  Current,
+  ProjectedHeading,
+  TargetSummary,
+  AverageNeeded,
+  Unreachable,
+  RowsSkipped,
+  CredentialsOk,
+  CourseListOk,
+  LoggedIn,
+  LoggedInSaved,
+  Watching,
+  YorkUnreachable,
+  CacheCleared,
+  SheetWritten,
+  NotionSynced,
+  Committed,
+  NoChanges,
+}
+
+impl Lang {
+  pub fn text (self, text: Text) -> &'static str {
+    let (en, fr) = match text {
+      Text::Gpa => ("GPA:", "MPC :"),
+      Text::FourPoint => ("Four Point", "Sur 4"),
+      Text::NinePoint => ("Nine Point", "Sur 9"),
+      Text::Overall => ("Overall", "Globale"),
+      Text::Grades => ("Grades:", "Notes :"),
+      Text::Session => ("Session", "Session"),
+      Text::Course => ("Course", "Cours"),
+      Text::Title => ("Title", "Titre"),
+      Text::Grade => ("Grade", "Note"),
+      Text::Nine => ("Nine", "Sur 9"),
+      Text::Four => ("Four", "Sur 4"),
+      Text::NineCredits => ("Nine x Credits", "Sur 9 x crédits"),
+      Text::FourCredits => ("Four x Credits", "Sur 4 x crédits"),
+      Text::Cumulative => ("Cumulative Nine / Four", "Cumulative sur 9 / 4"),
+      Text::SessionGpa => ("{} GPA", "MPC {}"),
+      Text::OnScale => ("On the {} scale: {}", "Sur l'échelle {} : {}"),
+      Text::OnScaleUngraded => ("On the {} scale: no graded courses", "Sur l'échelle {} : aucun cours noté"),
+      Text::NotInGpa => ("Not included in GPA:", "Non compris dans la MPC :"),
+      Text::Reason => ("Reason", "Raison"),
+      Text::ExcludedSummary => ("Withdrawn: {} ({} credits), pass/fail: {} ({} credits)", "Abandons : {} ({} crédits), réussite/échec : {} ({} crédits)"),
+      Text::Standing => ("Academic standing: {} (nine point GPA {})", "Situation scolaire : {} (MPC sur 9 de {})"),
+      Text::NoStanding => ("Academic standing: {}, no course counts towards the GPA", "Situation scolaire : {}, aucun cours ne compte dans la MPC"),
+      Text::ToNextStanding => ("{} more points to reach {} at {}", "{} points de plus pour atteindre {} à {}"),
+      Text::AboveStanding => ("{} points above the threshold of {}", "{} points au-dessus du seuil de {}"),
+      Text::GpaPerSession => ("GPA per session:", "MPC par session :"),
+      Text::Overridden => ("{} (override, was {})", "{} (remplacée, était {})"),
+      Text::Ungraded => ("ungraded", "sans note"),
+      Text::RepeatedLater => ("{} (repeated later)", "{} (reprise plus tard)"),
+      Text::LeftOut => ("{} (not in GPA)", "{} (hors MPC)"),
+      Text::Credits => ("Credits:", "Crédits :"),
+      Text::Attempted => ("Attempted", "Tentés"),
+      Text::Earned => ("Earned", "Obtenus"),
+      Text::InGpa => ("In GPA", "Dans la MPC"),
+      Text::ExcludedCredits => ("Excluded", "Exclus"),
+      Text::InProgress => ("In progress:", "En cours :"),
+      Text::Expected => ("Expected", "Prévue"),
+      Text::Projected => ("Projected GPA:", "MPC projetée :"),
+      Text::NineFour => ("Nine / Four", "Sur 9 / 4"),
+      Text::Now => ("Now", "Actuelle"),
+      Text::BestCase => ("All A+", "Que des A+"),
+      Text::WithoutExpected => ("No expected grade in the config for {}, left out of the expected GPA", "Aucune note prévue dans la configuration pour {}, hors de la MPC prévue"),
+      Text::Warning => ("Warning: {}", "Avertissement : {}"),
+      Text::Check => ("Check", "Vérification"),
+      Text::CheckResult => ("Result", "Résultat"),
+      Text::Detail => ("Detail", "Détail"),
+      Text::Id => ("Id", "No"),
+      Text::FetchedAt => ("Fetched At", "Récupérées le"),
+      Text::Courses => ("Courses", "Cours"),
+      Text::Before => ("Before", "Avant"),
+      Text::After => ("After", "Après"),
+      Text::Difference => ("Change", "Écart"),
+      Text::Changes => ("Changes:", "Changements :"),
+      Text::Change => ("Change", "Changement"),
+      Text::Added => ("Added", "Ajouté"),
+      Text::Changed => ("Changed", "Modifié"),
+      Text::Removed => ("Removed", "Retiré"),
+      Text::Scale => ("Scale", "Échelle"),
+      Text::GpaHeading => ("GPA", "MPC"),
+      Text::YorkFour => ("York four point", "York sur 4"),
+      Text::YorkNine => ("York nine point", "York sur 9"),
+      Text::Mean => ("Mean (GPA)", "Moyenne (MPC)"),
+      Text::Median => ("Median grade", "Note médiane"),
+      Text::CreditsAttempted => ("Credits attempted", "Crédits tentés"),
+      Text::CreditsEarned => ("Credits earned", "Crédits obtenus"),
+      Text::BestSession => ("Best session", "Meilleure session"),
+      Text::WorstSession => ("Worst session", "Moins bonne session"),
+      Text::ByLevel => ("By level:", "Par niveau :"),
+      Text::Level => ("Level", "Niveau"),
+      Text::CreditsHeading => ("Credits", "Crédits"),
+      Text::Requirement => ("Requirement", "Exigence"),
+      Text::Required => ("Required", "Requis"),
+      Text::Completed => ("Completed", "Obtenus"),
+      Text::InProgressHeading => ("In progress", "En cours"),
+      Text::Remaining => ("Remaining", "Restants"),
+      Text::Status => ("Status", "Statut"),
+      Text::Outstanding => ("Outstanding", "Manquants"),
+      Text::CreditsOutstanding => ("Credits outstanding: {}", "Crédits manquants : {}"),
+      Text::Type => ("Type", "Type"),
+      Text::Days => ("Days", "Jours"),
+      Text::Time => ("Time", "Heure"),
+      Text::Location => ("Location", "Lieu"),
+      Text::Date => ("Date", "Date"),
+      Text::Event => ("Event", "Événement"),
+      Text::Kind => ("Kind", "Genre"),
+      Text::Current => ("Current", "Actuelle"),
+      Text::ProjectedHeading => ("Projected", "Projetée"),
+      Text::TargetSummary => ("Current GPA: {}, target: {} over {} more credits", "MPC actuelle : {}, cible : {} sur {} crédits de plus"),
+      Text::AverageNeeded => ("Average needed: {}", "Moyenne nécessaire : {}"),
+      Text::Unreachable => ("Not reachable this term, even with an A+ in every course", "Impossible à atteindre ce trimestre, même avec un A+ dans chaque cours"),
+      // the second value is the English plural "s", French puts the count after
+      Text::RowsSkipped => ("{} row{} of the course list could not be read, --strict fails on them instead", "Lignes illisibles dans la liste de cours : {}, --strict échoue dessus à la place"),
+      Text::CredentialsOk => ("Credentials: ok", "Identifiants : ok"),
+      Text::CourseListOk => ("Course list: ok, {} courses", "Liste de cours : ok, {} cours"),
+      Text::LoggedIn => ("Logged in", "Connecté"),
+      Text::LoggedInSaved => ("Logged in, credentials saved to the keychain", "Connecté, identifiants enregistrés dans le trousseau"),
+      Text::Watching => ("Watching {} courses, checking every {}", "Suivi de {} cours, vérifiés toutes les {}"),
+      Text::YorkUnreachable => ("Could not reach York, trying again later: {}", "York est injoignable, nouvel essai plus tard : {}"),
+      Text::CacheCleared => ("Removed {} cached scrape{}", "Copies en cache supprimées : {}"),
+      Text::SheetWritten => ("Wrote {} courses to the {} worksheet", "{} cours écrits dans la feuille {}"),
+      Text::NotionSynced => ("Notion: {} pages added, {} updated", "Notion : {} pages ajoutées, {} mises à jour"),
+      Text::Committed => ("Committed: {}", "Enregistré : {}"),
+      Text::NoChanges => ("No changes since the last snapshot", "Aucun changement depuis le dernier instantané"),
+    };
+
+    match self {
+      Lang::En => en,
+      Lang::Fr => fr,
+    }
+  }
+
+  /// The text with each `{}` replaced by the next of the values
+  pub fn format (self, text: Text, values: &[&dyn fmt::Display]) -> String {
+    let mut values = values.iter();
+    let mut formatted = String::new();
+    for (i, part) in self.text(text).split("{}").enumerate() {
+      if i > 0 {
+        formatted.push_str(&values.next().map(|v| v.to_string()).unwrap_or_default());
+      }
+      formatted.push_str(part);
+    }
+    formatted
+  }
+
+  /// `GradeKind::describe()` in this language
+  pub fn grade_kind (self, kind: GradeKind) -> &'static str {
+    match (self, kind) {
+      (Lang::En, kind) => kind.describe(),
+      (Lang::Fr, GradeKind::Letter) => "note en lettre",
+      (Lang::Fr, GradeKind::Withdrawn) => "abandon",
+      (Lang::Fr, GradeKind::PassFail) => "réussite/échec",
+      (Lang::Fr, GradeKind::Pending) => "pas encore de note",
+      (Lang::Fr, GradeKind::Other) => "pas une note en lettre",
+    }
+  }
+
+  /// `Status::label()` of a degree requirement in this language
+  pub fn requirement_status (self, status: Status) -> &'static str {
+    match (self, status) {
+      (Lang::En, status) => status.label(),
+      (Lang::Fr, Status::Satisfied) => "Satisfaite",
+      (Lang::Fr, Status::InProgress) => "En cours",
+      (Lang::Fr, Status::NotSatisfied) => "Non satisfaite",
+    }
+  }
+
+  /// `Kind::label()` of a key date in this language
+  pub fn date_kind (self, kind: Kind) -> &'static str {
+    match (self, kind) {
+      (Lang::En, kind) => kind.label(),
+      (Lang::Fr, Kind::Appointment) => "Rendez-vous d'inscription",
+      (Lang::Fr, Kind::Academic) => "Date importante",
+    }
+  }
+
+  /// `Standing::describe()` in this language
+  pub fn standing (self, standing: Standing) -> &'static str {
+    match (self, standing) {
+      (Lang::En, standing) => standing.describe(),
+      (Lang::Fr, Standing::Honours) => "statut spécialisé (honours)",
+      (Lang::Fr, Standing::Good) => "bonne situation",
+      (Lang::Fr, Standing::Warning) => "avertissement scolaire",
+      (Lang::Fr, Standing::Probation) => "probation scolaire",
+      (Lang::Fr, Standing::NotAssessed) => "pas encore évaluée",
+    }
+  }
+}
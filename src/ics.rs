@@ -0,0 +1,46 @@
+use chrono::{DateTime, Utc};
+
+/// Escapes the characters RFC 5545 gives a meaning in text values
+pub(crate) fn escape (text: &str) -> String {
+  text.replace('\\', "\\\\").replace(';', "\\;").replace(',', "\\,").replace('\n', "\\n")
+}
+
+/// Part of a UID from free text, only letters, digits and dashes
+pub(crate) fn slug (text: &str) -> String {
+  text.split(|c: char| !c.is_ascii_alphanumeric()).filter(|w| !w.is_empty()).collect::<Vec<_>>().join("-").to_lowercase()
+}
+
+// content lines are folded at 75 octets, the continuations starting with a space
+fn fold (line: &str, out: &mut String) {
+  let mut width = 0;
+  for c in line.chars() {
+    if width + c.len_utf8() > 75 {
+      out.push_str("\r\n ");
+      width = 1;
+    }
+    out.push(c);
+    width += c.len_utf8();
+  }
+  out.push_str("\r\n");
+}
+
+/// A VCALENDAR holding the events, each given as its content lines without BEGIN and END
+pub(crate) fn calendar (events: &[Vec<String>], stamp: DateTime<Utc>) -> String {
+  let mut out = String::new();
+  fold("BEGIN:VCALENDAR", &mut out);
+  fold("VERSION:2.0", &mut out);
+  fold(&format!("PRODID:-//grades_list//{}//EN", env!("CARGO_PKG_VERSION")), &mut out);
+  fold("CALSCALE:GREGORIAN", &mut out);
+
+  for event in events {
+    fold("BEGIN:VEVENT", &mut out);
+    fold(&format!("DTSTAMP:{}", stamp.format("%Y%m%dT%H%M%SZ")), &mut out);
+    for line in event {
+      fold(line, &mut out);
+    }
+    fold("END:VEVENT", &mut out);
+  }
+
+  fold("END:VCALENDAR", &mut out);
+  out
+}
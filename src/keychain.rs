@@ -0,0 +1,73 @@
+use keyring::{Keyring, KeyringError};
+
+use crate::GradesError;
+
+static SERVICE: &str = "grades_list";
+// the username is stored under its own entry so it can be found without being typed
+static USERNAME_ENTRY: &str = "__username__";
+static SESSION_KEY_ENTRY: &str = "__session_key__";
+
+fn keychain_error (e: KeyringError) -> GradesError {
+  GradesError::Keychain(e.to_string())
+}
+
+/// Stores the credentials in the OS keychain, replacing any that were saved before
+pub fn save (username: &str, password: &str) -> Result<(), GradesError> {
+  Keyring::new(SERVICE, USERNAME_ENTRY).set_password(username).map_err(keychain_error)?;
+  save_password(username, password)
+}
+
+/// Stores the password of the username without making it the one used when none is given
+pub fn save_password (username: &str, password: &str) -> Result<(), GradesError> {
+  Keyring::new(SERVICE, username).set_password(password).map_err(keychain_error)
+}
+
+/// Username saved by `save()`, if any
+pub fn username () -> Result<Option<String>, GradesError> {
+  match Keyring::new(SERVICE, USERNAME_ENTRY).get_password() {
+    Ok(username) => Ok(Some(username)),
+    Err(KeyringError::NoPasswordFound) => Ok(None),
+    Err(e) => Err(keychain_error(e)),
+  }
+}
+
+/// Password saved by `save()` for the given username, if any
+pub fn password (username: &str) -> Result<Option<String>, GradesError> {
+  match Keyring::new(SERVICE, username).get_password() {
+    Ok(password) => Ok(Some(password)),
+    Err(KeyringError::NoPasswordFound) => Ok(None),
+    Err(e) => Err(keychain_error(e)),
+  }
+}
+
+/// Removes the password saved for the username, if there is one
+pub fn forget (username: &str) -> Result<(), GradesError> {
+  match Keyring::new(SERVICE, username).delete_password() {
+    Ok(()) | Err(KeyringError::NoPasswordFound) => Ok(()),
+    Err(e) => Err(keychain_error(e)),
+  }
+}
+
+/// Key used to encrypt saved sessions, and with `encryption = "keychain"` the history and cache,
+/// generated and stored the first time it is needed
+pub fn session_key () -> Result<[u8; 32], GradesError> {
+  let entry = Keyring::new(SERVICE, SESSION_KEY_ENTRY);
+
+  match entry.get_password() {
+    Ok(encoded) => {
+      let decoded = base64::decode(&encoded).map_err(|e| GradesError::Keychain(e.to_string()))?;
+      let mut key = [0u8; 32];
+      if decoded.len() != key.len() {
+        return Err(GradesError::Keychain("saved session key has the wrong length".to_owned()));
+      }
+      key.copy_from_slice(&decoded);
+      Ok(key)
+    },
+    Err(KeyringError::NoPasswordFound) => {
+      let key: [u8; 32] = rand::random();
+      entry.set_password(&base64::encode(key)).map_err(keychain_error)?;
+      Ok(key)
+    },
+    Err(e) => Err(keychain_error(e)),
+  }
+}
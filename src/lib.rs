@@ -0,0 +1,655 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use schemars::JsonSchema;
+use scraper::{Html, Node, element_ref::ElementRef};
+use serde::{Deserialize, Serialize};
+use tracing::{debug, info, warn};
+use unicode_normalization::UnicodeNormalization;
+
+#[cfg(feature = "native")]
+pub mod accounts;
+#[cfg(feature = "native")]
+pub mod backend;
+#[cfg(feature = "blocking")]
+pub mod blocking;
+#[cfg(feature = "browser")]
+pub mod browser;
+#[cfg(feature = "native")]
+pub mod cache;
+#[cfg(feature = "native")]
+pub mod cassette;
+pub mod chart;
+#[cfg(feature = "native")]
+pub mod chat;
+pub mod classify;
+#[cfg(feature = "native")]
+mod client;
+#[cfg(feature = "native")]
+pub mod config;
+pub mod convert;
+#[cfg(feature = "native")]
+pub mod daemon;
+pub mod dates;
+#[cfg(feature = "native")]
+pub mod debug_dump;
+pub mod degree;
+#[cfg(feature = "native")]
+mod cookies;
+pub mod diff;
+#[cfg(feature = "native")]
+pub mod doctor;
+#[cfg(feature = "native")]
+mod duo;
+#[cfg(feature = "native")]
+pub mod email;
+#[cfg(feature = "native")]
+pub mod encrypt;
+mod error;
+#[cfg(feature = "native")]
+pub mod extra;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+#[cfg(feature = "native")]
+pub mod feed;
+mod failure;
+pub mod files;
+pub mod filter;
+pub mod format;
+#[cfg(feature = "native")]
+pub mod graphql;
+#[cfg(feature = "native")]
+pub mod history;
+pub mod i18n;
+mod ics;
+#[cfg(feature = "native")]
+pub mod keychain;
+#[cfg(feature = "native")]
+pub mod metrics;
+#[cfg(feature = "native")]
+pub mod netrc;
+#[cfg(feature = "node")]
+mod node;
+#[cfg(feature = "native")]
+pub mod notify;
+#[cfg(feature = "native")]
+pub mod notion;
+#[cfg(feature = "native")]
+pub mod openapi;
+pub mod overrides;
+#[cfg(feature = "native")]
+pub mod pdf;
+pub mod progress;
+pub mod projection;
+#[cfg(feature = "native")]
+pub mod push;
+#[cfg(feature = "python")]
+// pyo3's macros check a cfg of pyo3's own and convert their errors with `into()`
+#[allow(unexpected_cfgs, clippy::useless_conversion)]
+pub mod python;
+#[cfg(feature = "native")]
+pub mod rate_limit;
+pub mod redact;
+pub mod repeats;
+pub mod sanitize;
+pub mod scale;
+pub mod schedule;
+pub mod selectors;
+#[cfg(feature = "native")]
+pub mod server;
+#[cfg(feature = "native")]
+pub mod session;
+pub mod sessions;
+#[cfg(feature = "native")]
+pub mod sheets;
+#[cfg(feature = "native")]
+pub mod snapshot;
+pub mod sort;
+pub mod standing;
+pub mod stats;
+pub mod target;
+pub mod transcript;
+#[cfg(feature = "native")]
+pub mod tui;
+#[cfg(feature = "native")]
+pub mod vault;
+#[cfg(feature = "wasm")]
+mod wasm;
+#[cfg(feature = "native")]
+pub mod webhook;
+pub mod whatif;
+#[cfg(feature = "native")]
+pub mod xlsx;
+
+use selectors::Compiled;
+
+#[cfg(feature = "native")]
+pub use backend::SisBackend;
+#[cfg(feature = "native")]
+pub use client::{ClientOptions, Endpoints, GradesClient};
+#[cfg(feature = "native")]
+pub use duo::SecondFactor;
+pub use error::GradesError;
+pub use selectors::Selectors;
+
+/// A single row of the York course list
+///
+/// `course` is the text as listed, like `"LE EECS 2030 3.00 A"`, the fields after `grade` are
+/// parsed out of it by `CourseData::new()` and left empty when it doesn't have that shape.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct CourseData {
+  pub session: String,
+  pub course: String,
+  pub title: String,
+  #[serde(deserialize_with = "deserialize_grade")]
+  pub grade: String,
+  #[serde(default)]
+  pub faculty: String,
+  #[serde(default)]
+  pub subject: String,
+  #[serde(default)]
+  pub number: String,
+  #[serde(default)]
+  pub credits: Option<f32>,
+  #[serde(default)]
+  pub section: String,
+  /// A later attempt of the same course replaces this one in the GPA
+  #[serde(default)]
+  pub superseded: bool,
+  /// Entered by hand in the config or `extra_courses.csv` instead of read from the course list
+  #[serde(default)]
+  pub manual: bool,
+  /// Left out of the GPA on request, see `ExtraCourse::in_gpa`
+  #[serde(default)]
+  pub excluded: bool,
+  /// The grade from the course list when `[overrides]` in the config replaced it
+  #[serde(default)]
+  pub overridden: Option<String>,
+  /// Grade points the transcript lists for the course, read with `--source transcript` or `merged`
+  #[serde(default)]
+  pub grade_points: Option<f32>,
+  /// Remarks the transcript has on the course, like an aegrotat standing
+  #[serde(default)]
+  pub notes: String,
+}
+
+impl CourseData {
+  /// Creates a row, parsing the faculty, subject, catalog number, credits and section out of `course`
+  pub fn new (session: String, course: String, title: String, grade: String) -> CourseData {
+    // older listings separate the faculty with a slash, "LE/EECS 2030 3.00 A"
+    let parts = course.split(|c: char| c.is_ascii_whitespace() || c == '/')
+      .filter(|p| !p.is_empty())
+      .map(|p| p.to_owned())
+      .collect::<Vec<_>>();
+    let part = |i: usize| parts.get(i).cloned().unwrap_or_default();
+
+    CourseData {
+      faculty: part(0),
+      subject: part(1),
+      number: part(2),
+      credits: parts.get(3).and_then(|c| c.parse::<f32>().ok()),
+      section: part(4),
+      superseded: false,
+      manual: false,
+      excluded: false,
+      overridden: None,
+      grade_points: None,
+      notes: String::new(),
+      grade: normalize_grade(&grade),
+      session,
+      course,
+      title,
+    }
+  }
+
+  /// Subject and catalog number, like `"EECS 2030"`
+  pub fn code (&self) -> String {
+    format!("{} {}", self.subject, self.number).trim().to_owned()
+  }
+
+  /// The row the way the course list has it, without an override from the config
+  pub fn as_listed (&self) -> CourseData {
+    let mut listed = self.clone();
+    if let Some(grade) = listed.overridden.take() {
+      listed.grade = grade;
+    }
+    listed
+  }
+
+  /// Year level from the catalog number, `2000` for EECS 2030
+  pub fn level (&self) -> Option<u32> {
+    let digit = self.number.chars().next()?.to_digit(10)?;
+    if digit == 0 { None } else { Some(digit * 1000) }
+  }
+
+  /// Value of the grade on both scales and what it adds to the GPA, `None` unless it is a letter grade
+  pub fn points (&self) -> Option<CoursePoints> {
+    let nine_point = *nine_point_scale().get(&self.grade)?;
+    let four_point = *four_point_scale().get(&self.grade)?;
+    let credits = counted(std::slice::from_ref(self)).next().map(|(_, credits)| credits);
+
+    Some(CoursePoints {
+      nine_point,
+      four_point,
+      nine_point_weighted: credits.map(|c| nine_point * c),
+      four_point_weighted: credits.map(|c| four_point * c),
+    })
+  }
+}
+
+/// A course's grade points, the GPA is the sum of the weighted points over the credits counted
+#[derive(Debug, Clone, PartialEq, Serialize, JsonSchema)]
+pub struct CoursePoints {
+  pub nine_point: f32,
+  pub four_point: f32,
+  /// Points times credits, `None` when the course doesn't count towards the GPA
+  pub nine_point_weighted: Option<f32>,
+  pub four_point_weighted: Option<f32>,
+}
+
+/// Cumulative GPA on both York scales
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct Gpa {
+  pub four: f32,
+  pub nine: f32
+}
+
+/// One of the two GPA scales York reports
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Scale {
+  Four,
+  Nine,
+}
+
+impl Scale {
+  pub const NAMES: &'static [&'static str] = &["four", "nine"];
+
+  /// Grade points of each letter grade on this scale
+  pub fn points (self) -> HashMap<String, f32> {
+    match self {
+      Scale::Four => four_point_scale(),
+      Scale::Nine => nine_point_scale(),
+    }
+  }
+
+  pub fn of (self, gpa: &Gpa) -> f32 {
+    match self {
+      Scale::Four => gpa.four,
+      Scale::Nine => gpa.nine,
+    }
+  }
+}
+
+impl std::str::FromStr for Scale {
+  type Err = String;
+
+  fn from_str (s: &str) -> Result<Scale, String> {
+    match s {
+      "four" | "4" => Ok(Scale::Four),
+      "nine" | "9" => Ok(Scale::Nine),
+      _ => Err(format!("unknown scale \"{}\", expected four or nine", s)),
+    }
+  }
+}
+
+/// Rows of the table itself, leaving out those of tables nested in its cells
+fn own_rows<'a> (table: ElementRef<'a>, selectors: &Compiled) -> Vec<ElementRef<'a>> {
+  table.select(&selectors.row)
+    .filter(|row| selectors::directly_in(row, &selectors.table, &table))
+    .collect()
+}
+
+/// The cells of a row, without those of tables nested in them
+fn own_cells<'a> (row: ElementRef<'a>, selectors: &Compiled) -> Vec<ElementRef<'a>> {
+  row.select(&selectors.cell)
+    .filter(|cell| selectors::directly_in(cell, &selectors.row, &row))
+    .collect()
+}
+
+/// The first table with a row of headers `is_header` accepts, laid out by `grid()` from that row on
+///
+/// Layout tables around it and other tables on the page are passed over.
+pub(crate) fn header_table<F: Fn(&[String]) -> bool> (document: &Html, selectors: &Compiled, is_header: F) -> Option<Vec<Vec<String>>> {
+  let header_row = |row: &ElementRef| is_header(&own_cells(*row, selectors).into_iter().map(cell_text).collect::<Vec<_>>());
+
+  let mut found = document.select(&selectors.table).filter_map(|table| {
+    let rows = own_rows(table, selectors);
+    let header = rows.iter().position(header_row)?;
+    Some(rows[header..].to_vec())
+  });
+
+  let rows = found.next()?;
+  let others = found.count();
+  if others > 0 {
+    warn!(others, "more than one table has the headers looked for, using the first");
+  }
+
+  Some(grid(&rows, selectors))
+}
+
+/// Column of the first header cell with one of the names, in any case
+pub(crate) fn column (header: &[String], names: &[&str]) -> Option<usize> {
+  header.iter().position(|h| names.contains(&clean_text(h).to_lowercase().as_str()))
+}
+
+/// Text of a cell and the markup nested in it, a line break reading as a space
+fn cell_text (cell: ElementRef) -> String {
+  cell.descendants()
+    .filter_map(|node| match node.value() {
+      Node::Text(text) => Some(&**text),
+      Node::Element(e) if e.name() == "br" => Some(" "),
+      _ => None,
+    })
+    .collect()
+}
+
+fn span (cell: ElementRef, attr: &str) -> usize {
+  cell.value().attr(attr).and_then(|s| s.trim().parse().ok()).unwrap_or(1).clamp(1, 100)
+}
+
+/// Text of every row laid out in columns, a cell spanning rows or columns repeated in each
+///
+/// Rows with a single cell spanning every column, like a session heading, are left out.
+fn grid (rows: &[ElementRef], selectors: &Compiled) -> Vec<Vec<String>> {
+  // text and rows left of a cell spanning down into the rows below
+  let mut spanning: Vec<Option<(String, usize)>> = Vec::new();
+  let mut grid = Vec::new();
+
+  for row in rows {
+    let cells = own_cells(*row, selectors);
+    let mut texts: Vec<String> = Vec::new();
+    let heading = cells.len() == 1 && spanning.iter().all(|s| s.is_none()) && span(cells[0], "colspan") > 1;
+
+    for cell in &cells {
+      fill_spanning(&mut texts, &mut spanning);
+
+      let text = cell_text(*cell);
+      let rowspan = span(*cell, "rowspan");
+      for _ in 0..span(*cell, "colspan") {
+        if rowspan > 1 {
+          if spanning.len() <= texts.len() {
+            spanning.resize(texts.len() + 1, None);
+          }
+          spanning[texts.len()] = Some((text.clone(), rowspan - 1));
+        }
+        texts.push(text.clone());
+      }
+    }
+    fill_spanning(&mut texts, &mut spanning);
+
+    if heading {
+      debug!(text = %texts[0].trim(), "skipped a heading row");
+      continue;
+    }
+    grid.push(texts);
+  }
+
+  grid
+}
+
+// Takes the cells spanning down from rows above up to the next free column
+fn fill_spanning (texts: &mut Vec<String>, spanning: &mut [Option<(String, usize)>]) {
+  while let Some(Some((text, left))) = spanning.get_mut(texts.len()) {
+    texts.push(text.clone());
+    *left -= 1;
+    if *left == 0 {
+      spanning[texts.len() - 1] = None;
+    }
+  }
+}
+
+/// Columns of the session, course, title and grade, found by their header
+///
+/// The transcript has credits, grade points and notes columns the course list doesn't.
+struct Columns {
+  session: usize,
+  course: usize,
+  title: Option<usize>,
+  grade: usize,
+  credits: Option<usize>,
+  points: Option<usize>,
+  notes: Option<usize>,
+}
+
+impl Columns {
+  /// Columns of the header cells, in any case and order, only the session, course and grade
+  /// being needed
+  fn find (header: &[String]) -> Option<Columns> {
+    let column = |names: &[&str]| column(header, names);
+
+    Some(Columns {
+      session: column(&["session"])?,
+      course: column(&["course"])?,
+      title: column(&["title"]),
+      grade: column(&["grade"])?,
+      credits: column(&["credits", "credit"]),
+      points: column(&["grade points", "grade point"]),
+      notes: column(&["notes", "note"]),
+    })
+  }
+
+  fn count (&self) -> usize {
+    let optional = [self.title, self.credits, self.points, self.notes];
+    optional.iter().flatten().chain(&[self.session, self.course, self.grade]).max().unwrap() + 1
+  }
+}
+
+/// Text of a cell as it reads on the page: every entity decoded, runs of whitespace (non-breaking
+/// spaces too) collapsed to one space and the Unicode in NFC, so an accent typed either way
+/// compares and prints the same
+pub(crate) fn clean_text (s: &str) -> String {
+  html_escape::decode_html_entities(s)
+    .split_whitespace()
+    .collect::<Vec<_>>()
+    .join(" ")
+    .nfc()
+    .collect()
+}
+
+/// What `parse_courses_with()` does with a row of the grades table it can't read
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum ParseMode {
+  /// Leave the row out with a warning, so the rest of the grades can still be used
+  #[default]
+  Lenient,
+  /// Fail with `GradesError::Layout`
+  Strict,
+}
+
+/// A row of the grades table that was left out
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ParseWarning {
+  /// Row under the header, counting from 1
+  pub row: usize,
+  pub message: String,
+}
+
+impl fmt::Display for ParseWarning {
+  fn fmt (&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(f, "row {} of the course list {}, left out", self.row, self.message)
+  }
+}
+
+/// The courses read from the course list along with the rows that couldn't be
+#[derive(Debug, Clone, Default)]
+pub struct Parsed {
+  pub grades: Vec<CourseData>,
+  pub warnings: Vec<ParseWarning>,
+}
+
+/// Parses a saved copy of the course list page, as fetched by `GradesClient::fetch_grades()`
+///
+/// Rows that can't be read are left out and logged, see `parse_courses_with()` to get them.
+pub fn parse_courses (courses_page: &str) -> Result<Vec<CourseData>, GradesError> {
+  parse_lenient(courses_page, &Selectors::default())
+}
+
+// the rows left out are only logged, the caller doesn't get to see them
+pub(crate) fn parse_lenient (courses_page: &str, selectors: &Selectors) -> Result<Vec<CourseData>, GradesError> {
+  let parsed = parse_courses_with(courses_page, selectors, ParseMode::Lenient)?;
+  for warning in &parsed.warnings {
+    warn!(%warning, "skipped a row");
+  }
+
+  Ok(parsed.grades)
+}
+
+/// Parses the course list page like `parse_courses()`, finding the grades with other selectors
+/// and failing on a malformed row in `ParseMode::Strict`
+pub fn parse_courses_with (courses_page: &str, selectors: &Selectors, mode: ParseMode) -> Result<Parsed, GradesError> {
+  let compiled = selectors.compile()?;
+  let document = Html::parse_document(courses_page);
+  let data = match header_table(&document, &compiled, |header| Columns::find(header).is_some()) {
+    Some(data) => data,
+    None => {
+      let tables = document.select(&compiled.table).count();
+      return Err(failure::detect(courses_page).unwrap_or_else(|| GradesError::Layout(format!(
+        "could not find the grades table among {} tables, none has Session, Course and Grade headers. \
+         Run again with --save-html page.html and attach the page to a bug report", tables))));
+    },
+  };
+
+  let mut resp: Vec<CourseData> = Vec::new();
+
+  // the table was picked for having these headers
+  let columns = Columns::find(&data[0]).expect("the grades table has its headers");
+  debug!(rows = data.len() - 1, "found the grades table");
+
+  let mut warnings = Vec::new();
+  for (i, row) in data[1..].iter().enumerate() {
+    if row.iter().all(|cell| cell.trim().is_empty()) { continue; }
+
+    let cell = |i: usize| clean_text(&row[i]);
+    let problem = if row.len() < columns.count() {
+      Some(format!("has {} columns instead of {}", row.len(), columns.count()))
+    } else if cell(columns.course).is_empty() {
+      Some("has no course".to_owned())
+    } else {
+      None
+    };
+
+    if let Some(message) = problem {
+      let warning = ParseWarning { row: i + 1, message };
+      if mode == ParseMode::Strict {
+        return Err(GradesError::Layout(format!("row {} {}", warning.row, warning.message)));
+      }
+      debug!(%warning, "skipped a row");
+      warnings.push(warning);
+      continue;
+    }
+
+    let mut course = CourseData::new(
+      cell(columns.session),
+      cell(columns.course),
+      columns.title.map(cell).unwrap_or_default(),
+      cell(columns.grade),
+    );
+    let number = |i: Option<usize>| i.and_then(|i| cell(i).parse::<f32>().ok());
+    course.credits = number(columns.credits).or(course.credits);
+    course.grade_points = number(columns.points);
+    course.notes = columns.notes.map(cell).unwrap_or_default();
+    resp.push(course);
+  }
+
+  repeats::mark_repeats(&mut resp);
+  info!(courses = resp.len(), superseded = resp.iter().filter(|c| c.superseded).count(), "parsed the course list");
+
+  Ok(Parsed { grades: resp, warnings })
+}
+
+/// The grade the way the scales have it, `" a+"` reads as `"A+"`
+///
+/// Only letter grades are changed, anything else York lists is kept as written.
+pub fn normalize_grade (grade: &str) -> String {
+  let upper = grade.trim().to_ascii_uppercase();
+  if scale::NINE_POINT.iter().any(|(letter, _)| *letter == upper) { upper } else { grade.to_owned() }
+}
+
+// grades saved or handed over as JSON are read like the ones parsed from the course list
+fn deserialize_grade<'de, D: serde::Deserializer<'de>> (deserializer: D) -> Result<String, D::Error> {
+  String::deserialize(deserializer).map(|grade| normalize_grade(&grade))
+}
+
+/// Grade points of each letter grade on York's nine point scale
+pub fn nine_point_scale () -> HashMap<String, f32> {
+  scale::NINE_POINT.iter().map(|(l, p)| (l.to_string(), *p)).collect()
+}
+
+/// Grade points of each letter grade on the four point scale
+pub fn four_point_scale () -> HashMap<String, f32> {
+  scale::FOUR_POINT.iter().map(|(l, p)| (l.to_string(), *p)).collect()
+}
+
+/// Credit weighted grade points of the courses that count towards the GPA
+struct GpaTotals {
+  credits: f32,
+  four: f32,
+  nine: f32,
+}
+
+/// Courses that count towards the GPA with their credits
+///
+/// Only letter graded rows count, without the attempts a retake replaced. Rows without credits
+/// (transfer credits, odd listings) are reported by `credit_warnings()` instead.
+pub(crate) fn counted (grades: &[CourseData]) -> impl Iterator<Item = (&CourseData, f32)> {
+  let nine = nine_point_scale();
+
+  grades.iter()
+    .filter(move |c| nine.contains_key(&c.grade) && !c.superseded && !c.excluded)
+    .filter_map(|c| c.credits.map(|credit| (c, credit)))
+}
+
+/// Credit weighted average of `points` over the counted courses, `None` without any
+pub(crate) fn weighted_average<F: Fn(&str) -> Option<f32>> (grades: &[CourseData], points: F) -> Option<f32> {
+  let (total, credits) = counted(grades)
+    .filter_map(|(c, credit)| points(&c.grade).map(|p| (p * credit, credit)))
+    .fold((0.0, 0.0), |(total, credits), (p, c)| (total + p, credits + c));
+
+  if credits > 0.0 { Some(total / credits) } else { None }
+}
+
+fn gpa_totals (grades: &[CourseData]) -> GpaTotals {
+  let nine = nine_point_scale();
+  let four = four_point_scale();
+
+  let mut totals = GpaTotals { credits: 0.0, four: 0.0, nine: 0.0 };
+  for (grade, credit) in counted(grades) {
+    totals.nine += nine[&grade.grade] * credit;
+    totals.four += four[&grade.grade] * credit;
+
+    totals.credits += credit;
+  }
+
+  totals
+}
+
+/// Describes every graded row that was left out of the GPA because its credits couldn't be read
+pub fn credit_warnings (grades: &[CourseData]) -> Vec<String> {
+  let nine = nine_point_scale();
+
+  grades.iter()
+    .filter(|c| nine.contains_key(&c.grade) && !c.excluded && c.credits.is_none())
+    .map(|c| format!("no credit value in \"{}\", left out of the GPA", c.course))
+    .collect()
+}
+
+/// Credits of the courses that count towards the GPA
+pub fn gpa_credits (grades: &[CourseData]) -> f32 {
+  gpa_totals(grades).credits
+}
+
+/// Calculates both four point and nine point GPA
+///
+/// `None` while no course counts towards it, in a first session or with every grade pending.
+pub fn calculate_gpa (grades: &[CourseData]) -> Option<Gpa> {
+  let totals = gpa_totals(grades);
+  if totals.credits <= 0.0 {
+    return None;
+  }
+
+  Some(Gpa {
+    four: totals.four / totals.credits,
+    nine: totals.nine / totals.credits,
+  })
+}
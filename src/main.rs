@@ -1,212 +1,2969 @@
-use std::collections::HashMap;
+use std::collections::BTreeMap;
+use std::io::{self, BufRead, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, OnceLock};
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
 use structopt::StructOpt;
+use structopt::clap::Shell;
+use tracing_subscriber::EnvFilter;
 
-use scraper::{Html, Selector, element_ref::ElementRef};
-use serde::{Serialize};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
 
-use prettytable::{ptable, table, row, cell};
+use prettytable::{Cell, Row, Table, ptable, table, row};
 
-static COURSE_URL: &str = "https://wrem.sis.yorku.ca/Apps/WebObjects/ydml.woa/wa/DirectAction/document?name=CourseListv1";
-static LOGIN_PAGE: &str = "https://passportyork.yorku.ca/ppylogin/ppylogin";
-static LOGOUT_PAGE: &str = "https://passportyork.yorku.ca/ppylogin/ppylogout";
-static USER_AGENT: &str = "Mozilla/5.0 (Macintosh; Intel Mac OS X 10_15_6) AppleWebKit/605.1.15 (KHTML, like Gecko) Version/14.0.2 Safari/605.1.15";
+use grades_list::{ClientOptions, CourseData, CoursePoints, Gpa, GradesClient, GradesError, ParseMode, ParseWarning, Parsed, SecondFactor, SisBackend, keychain, netrc, session, target};
+use grades_list::backend::School;
+use grades_list::cassette::Cassette;
+use grades_list::daemon::{self, Page};
+use grades_list::debug_dump::DebugDump;
+use grades_list::history::{self, History};
+use grades_list::i18n::{Lang, Text};
+use grades_list::{accounts, cache, chart, dates, degree, feed, files, notion, pdf, progress, repeats, sanitize, schedule, sheets, snapshot};
+use grades_list::whatif;
+use grades_list::diff::{self, DiffReport, GradeChange};
+use grades_list::doctor::{self, Outcome};
+use grades_list::encrypt::{self, Recipient};
+use grades_list::notify::{self, Notifier};
+use grades_list::push::Ntfy;
+use grades_list::classify::{self, CreditSummary, Excluded, ExcludedSummary};
+use grades_list::config::{self, Config, LocalChanges};
+use grades_list::convert::Conversion;
+use grades_list::projection::{self, Projection};
+use grades_list::filter::{self, CourseFilter};
+use grades_list::format::{self, Format, Precision, Rounding};
+use grades_list::rate_limit::RateLimits;
+use grades_list::redact::{self, Redactor};
+use grades_list::server::{self, Latest, Shared};
+use grades_list::sessions::{self, ParsedSession};
+use grades_list::sort::{self, CumulativeGpa, SortKey};
+use grades_list::standing::{self, StandingReport};
+use grades_list::stats;
+use grades_list::webhook::Webhook;
+use grades_list::transcript::{self, Source};
+use grades_list::vault::{self, Encryption, Vault};
+use grades_list::xlsx;
 
 #[derive(Debug, StructOpt)]
 #[structopt(name = "grades_list", about = "A simple command line program to print out York grades and GPA")]
 struct Cli {
-  #[structopt(help = "York Username")]
-  username: String,
-  #[structopt(help = "York Password")]
-  password: String,
-  #[structopt(short, long, help = "Output in JSON or as a table")]
+  #[structopt(help = "York Username, loaded from the keychain when omitted")]
+  username: Option<String>,
+  #[structopt(help = "York Password, prompted for when omitted")]
+  password: Option<String>,
+  #[structopt(long = "username", raw(global = "true"), help = "York Username, instead of giving it before the subcommand")]
+  user: Option<String>,
+  #[structopt(short, long, raw(global = "true"), help = "Output in JSON, same as --format json")]
   json: bool,
+  #[structopt(long, raw(possible_values = "Format::NAMES", global = "true"), help = "Output format [default: table]")]
+  format: Option<Format>,
+  #[structopt(short = "v", long = "verbose", parse(from_occurrences), raw(global = "true"), help = "Log what is happening to stderr, -vv for more detail")]
+  verbose: u8,
+  #[structopt(long = "log-level", raw(possible_values = r#"&["error", "warn", "info", "debug", "trace"]"#, global = "true"), help = "Log level, instead of -v or RUST_LOG")]
+  log_level: Option<String>,
+  #[structopt(long = "log-file", parse(from_os_str), raw(global = "true"), help = "Append the log to this file instead of stderr")]
+  log_file: Option<PathBuf>,
+  #[structopt(long, default_value = "en", raw(possible_values = "Lang::NAMES", global = "true"), help = "Language of the table and the error messages")]
+  lang: Lang,
+  #[structopt(long, raw(global = "true"), help = "Profile from the config file to take defaults from")]
+  profile: Option<String>,
+  #[structopt(flatten)]
+  list: ListArgs,
+  #[structopt(long = "from-file", parse(from_os_str), raw(global = "true"), help = "Parse a saved course list page instead of logging in, - reads stdin")]
+  from_file: Option<PathBuf>,
+  #[structopt(long = "save-html", parse(from_os_str), raw(global = "true"), help = "Also write the course list page to this file, with session ids removed")]
+  save_html: Option<PathBuf>,
+  #[structopt(long, raw(global = "true"), help = "Replace the username, student numbers and email addresses in what is written with a hash, so it can be shared")]
+  redact: bool,
+  #[structopt(long = "redact-sections", raw(global = "true"), help = "With --redact, also leave out the course sections")]
+  redact_sections: bool,
+  #[structopt(long, raw(global = "true"), help = "Indent the JSON output")]
+  pretty: bool,
+  #[structopt(long, raw(global = "true"), help = "Output for diffing between runs: JSON unless another format is given, courses by session then course, keys sorted, numbers to 4 decimals, no fetch time and always in English")]
+  machine: bool,
+  #[structopt(long, raw(global = "true"), help = "Give the GPA with this many decimals, up to 6, instead of all of them")]
+  precision: Option<u32>,
+  #[structopt(long, raw(possible_values = "Rounding::NAMES", global = "true"), help = "With --precision, round the GPA, truncate it like some offices do, or round it up [default: round]")]
+  rounding: Option<Rounding>,
+  #[structopt(short, long, parse(from_os_str), raw(global = "true"), help = "Write the grades to this file instead of stdout, replacing it only once complete")]
+  output: Option<PathBuf>,
+  #[structopt(long, help = "Print the JSON Schema of the --format json output and exit")]
+  schema: bool,
+  #[structopt(long = "count-all-attempts", raw(global = "true"), help = "Count every attempt of a repeated course in the GPA, not just the latest")]
+  count_all_attempts: bool,
+  #[structopt(long = "include-non-degree", raw(global = "true"), help = "Count zero-credit rows like co-op work terms, and the non_degree subjects of the config, in the GPA")]
+  include_non_degree: bool,
+  #[structopt(long = "no-color", raw(global = "true"), help = "Don't color the grades in the table, also turned off by NO_COLOR or when not writing to a terminal")]
+  no_color: bool,
+  #[structopt(long = "no-persist", raw(global = "true"), help = "Log in and out every run instead of keeping the session between runs")]
+  no_persist: bool,
+  #[structopt(long, raw(global = "true"), help = "Look up the credentials for passportyork.yorku.ca in ~/.netrc")]
+  netrc: bool,
+  #[structopt(long, raw(global = "true"), help = "Fail on a course list row that can't be read instead of leaving it out with a warning")]
+  strict: bool,
+  #[structopt(long, default_value = "courselist", raw(possible_values = "Source::NAMES", global = "true"), help = "Read the grades from the course list, the transcript with grade points and notes, or both merged")]
+  source: Source,
+  #[structopt(flatten)]
+  mfa: MfaArgs,
+  #[structopt(flatten)]
+  filter: FilterArgs,
+  #[structopt(flatten)]
+  network: NetworkArgs,
+  #[structopt(flatten)]
+  cache: CacheArgs,
+  #[structopt(long = "notify-webhook", raw(global = "true"), help = "POST new or changed grades as JSON to this URL")]
+  notify_webhook: Option<String>,
+  #[structopt(long = "webhook-header", raw(number_of_values = "1", global = "true"), help = "Extra \"Name: value\" header for the webhook, can be repeated")]
+  webhook_headers: Vec<String>,
+  #[structopt(long = "notify-ntfy", raw(global = "true"), help = "Push new or changed grades to this ntfy.sh topic, or the URL of a topic on another server")]
+  notify_ntfy: Option<String>,
+  #[structopt(long = "notify-pushover", raw(global = "true"), help = "Push new or changed grades with Pushover, using [notify.pushover] from the config")]
+  notify_pushover: bool,
+  #[structopt(subcommand)]
+  cmd: Option<Command>,
+}
+
+impl Cli {
+  /// Folds the ways of giving the same thing into one, like the listing options given after `fetch`
+  fn normalize(&mut self) {
+    if self.username.is_none() {
+      self.username = self.user.take();
+    }
+    if let Some(Command::Fetch { ref mut list }) = self.cmd {
+      self.list.merge(std::mem::take(list));
+    }
+    if self.machine {
+      self.lang = Lang::En;
+    }
+  }
+
+  /// The decimals of the GPA, `--precision` or the ones of `--machine`, `None` for all of them
+  fn precision(&self) -> Result<Option<Precision>, GradesError> {
+    match (self.precision, self.rounding) {
+      (Some(decimals), rounding) => Precision::new(decimals, rounding.unwrap_or(Rounding::Round)).map(Some),
+      (None, Some(_)) => Err(GradesError::Config("--rounding needs --precision".to_owned())),
+      (None, None) if self.machine => Ok(Some(Precision { decimals: format::MACHINE_DECIMALS, rounding: Rounding::Round })),
+      (None, None) => Ok(None),
+    }
+  }
+
+  fn format(&self) -> Format {
+    match (self.json, self.format) {
+      (true, _) => Format::Json,
+      (false, Some(format)) => format,
+      (false, None) if self.machine => Format::Json,
+      (false, None) => Format::Table,
+    }
+  }
+
+  /// Fills in whatever wasn't given on the command line from the selected profile
+  fn apply_profile(&mut self, config: &Config) -> Result<(), GradesError> {
+    let profile = match config.profile(self.profile.as_deref())? {
+      Some(profile) => profile.clone(),
+      None => return Ok(()),
+    };
+
+    if self.username.is_none() {
+      self.username = profile.username;
+    }
+    if self.format.is_none() {
+      if let Some(ref format) = profile.format {
+        self.format = Some(format.parse().map_err(GradesError::Config)?);
+      }
+    }
+    if self.notify_webhook.is_none() {
+      self.notify_webhook = profile.notify_webhook;
+    }
+    if self.webhook_headers.is_empty() {
+      self.webhook_headers = profile.webhook_headers;
+    }
+
+    Ok(())
+  }
+}
+
+/// Options of listing the grades, the default command and `fetch`
+#[derive(Debug, Clone, Default, StructOpt)]
+struct ListArgs {
+  #[structopt(long = "gpa-row", help = "With --format csv, append the GPA and the credit totals as rows after the grades")]
+  gpa_row: bool,
+  #[structopt(long = "gpa-file", parse(from_os_str), help = "With --format csv, write the GPA as CSV to this file")]
+  gpa_file: Option<PathBuf>,
+  #[structopt(long = "major-gpa", help = "Also calculate the GPA of the major courses set in the config")]
+  major_gpa: bool,
+  #[structopt(long, raw(possible_values = "SortKey::NAMES"), help = "Order the courses by this instead of by session")]
+  sort: Option<SortKey>,
+  #[structopt(long, help = "With --sort, order from highest to lowest")]
+  descending: bool,
+  #[structopt(long = "group-by", raw(possible_values = r#"&["session"]"#), help = "In the table, group the courses by session with the GPA of each")]
+  group_by: Option<String>,
+  #[structopt(long, help = "Add a column with the cumulative GPA after each session, ordering the courses by session")]
+  cumulative: bool,
+  #[structopt(long = "last-credits", help = "Also calculate the GPA of the most recent sessions making up this many credits")]
+  last_credits: Option<f32>,
+  #[structopt(long = "assert-gpa-min", help = "Exit with an error if the cumulative nine point GPA is below this")]
+  assert_gpa_min: Option<f32>,
+  #[structopt(long = "assert-no-missing", help = "Exit with an error if a course still has no grade after this date (YYYY-MM-DD)")]
+  assert_no_missing: Option<String>,
+  #[structopt(long, help = "Report the academic standing the GPA meets and how far it is from the next one")]
+  standing: bool,
+  #[structopt(long, help = "Project the GPA once the courses in progress are graded, with all A+ and with the grades of [expected] in the config")]
+  projection: bool,
+  #[structopt(long, help = "In the table, also draw a bar chart of the GPA of each session")]
+  chart: bool,
+  #[structopt(long = "chart-svg", parse(from_os_str), help = "Write a chart of the GPA of each session to this SVG file")]
+  chart_svg: Option<PathBuf>,
+  #[structopt(long = "scale", help = "Also show the GPA on this scale, like lsac or one from [scales] in the config")]
+  gpa_scale: Option<String>,
+  #[structopt(long = "all-profiles", help = "Fetch the grades of every profile in the config at once, reported per profile")]
+  all_profiles: bool,
+  #[structopt(long, help = "With --all-profiles, how many accounts are fetched at the same time [default: 3]")]
+  parallel: Option<usize>,
+}
+
+impl ListArgs {
+  /// Takes the options given after `fetch` on top of the ones given before it
+  fn merge(&mut self, other: ListArgs) {
+    self.gpa_row |= other.gpa_row;
+    self.gpa_file = other.gpa_file.or(self.gpa_file.take());
+    self.major_gpa |= other.major_gpa;
+    self.sort = other.sort.or(self.sort);
+    self.descending |= other.descending;
+    self.group_by = other.group_by.or(self.group_by.take());
+    self.cumulative |= other.cumulative;
+    self.last_credits = other.last_credits.or(self.last_credits);
+    self.assert_gpa_min = other.assert_gpa_min.or(self.assert_gpa_min);
+    self.assert_no_missing = other.assert_no_missing.or(self.assert_no_missing.take());
+    self.standing |= other.standing;
+    self.projection |= other.projection;
+    self.chart |= other.chart;
+    self.chart_svg = other.chart_svg.or(self.chart_svg.take());
+    self.gpa_scale = other.gpa_scale.or(self.gpa_scale.take());
+    self.all_profiles |= other.all_profiles;
+    self.parallel = other.parallel.or(self.parallel);
+  }
+}
+
+#[derive(Debug, Clone, StructOpt)]
+struct FilterArgs {
+  #[structopt(long, raw(global = "true"), help = "Only include courses from this session, e.g. FW2023")]
+  session: Option<String>,
+  #[structopt(long, raw(global = "true"), help = "Only include courses of this subject, e.g. EECS")]
+  subject: Option<String>,
+  #[structopt(long = "min-grade", raw(global = "true"), help = "Only include courses graded at least this, e.g. C+")]
+  min_grade: Option<String>,
+}
+
+impl FilterArgs {
+  fn filter(&self) -> Result<CourseFilter, GradesError> {
+    let filter = CourseFilter {
+      session: self.session.clone(),
+      subject: self.subject.clone(),
+      min_grade: self.min_grade.clone(),
+    };
+
+    filter.validate()?;
+    Ok(filter)
+  }
+}
+
+#[derive(Debug, Clone, StructOpt)]
+struct NetworkArgs {
+  #[structopt(long, default_value = "60s", parse(try_from_str = "humantime::parse_duration"), raw(global = "true"), help = "Give up on a request to York after this long")]
+  timeout: Duration,
+  #[structopt(long = "connect-timeout", default_value = "10s", parse(try_from_str = "humantime::parse_duration"), raw(global = "true"), help = "Give up connecting to York after this long")]
+  connect_timeout: Duration,
+  #[structopt(long, raw(global = "true"), help = "Proxy for every request, HTTP_PROXY and HTTPS_PROXY are used when omitted")]
+  proxy: Option<String>,
+  #[structopt(long, default_value = "york", raw(possible_values = "School::NAMES", global = "true"), help = "Institution whose portal the grades are read from")]
+  school: School,
+  #[structopt(long, parse(from_os_str), raw(global = "true", conflicts_with = r#""replay""#), help = "Record the requests to York and their sanitized responses to this cassette file, which still holds the grades")]
+  record: Option<PathBuf>,
+  #[structopt(long, parse(from_os_str), raw(global = "true"), help = "Play back a cassette recorded with --record instead of contacting York")]
+  replay: Option<PathBuf>,
+  #[structopt(long = "debug-dump", parse(from_os_str), raw(global = "true"), help = "When the run fails, write redacted copies of the requests to York and their responses to this directory")]
+  debug_dump: Option<PathBuf>,
+  #[structopt(long = "debug-dump-always", raw(global = "true", requires = r#""debug_dump""#), help = "Write the --debug-dump even when the run succeeds")]
+  debug_dump_always: bool,
+  #[structopt(long = "no-daemon", raw(global = "true"), help = "Log in even when a daemon is keeping a session for the user")]
+  no_daemon: bool,
+}
+
+/// The requests of this run, kept for `--debug-dump`
+static DEBUG_DUMP: OnceLock<Arc<DebugDump>> = OnceLock::new();
+/// `--lang`, for the warnings printed where the arguments aren't at hand
+static LANG: OnceLock<Lang> = OnceLock::new();
+
+/// Prints a warning to stderr, leaving stdout to the output
+fn warning(message: impl std::fmt::Display) {
+  eprintln!("{}", LANG.get().copied().unwrap_or_default().format(Text::Warning, &[&message]));
+}
+
+/// How many accounts `--all-profiles` fetches at the same time when `--parallel` isn't given
+const DEFAULT_PARALLEL: usize = 3;
+
+/// How old a cached scrape `--cached` takes when `--max-age` isn't given
+const DEFAULT_MAX_AGE: Duration = Duration::from_secs(6 * 60 * 60);
+
+#[derive(Debug, Clone, StructOpt)]
+struct CacheArgs {
+  #[structopt(long, raw(global = "true"), help = "Use the last scrape when it is newer than --max-age (6h) instead of fetching")]
+  cached: bool,
+  #[structopt(long = "max-age", parse(try_from_str = "humantime::parse_duration"), raw(global = "true"), help = "Use the last scrape when it is newer than this, e.g. 30m or 1d, implies --cached")]
+  max_age: Option<Duration>,
+  #[structopt(long = "no-cache", raw(global = "true", conflicts_with_all = r#"&["cached", "max_age"]"#), help = "Neither read nor write the cached scrape")]
+  no_cache: bool,
+}
+
+impl CacheArgs {
+  /// How old a cached scrape may be to be used instead of fetching, if one may be at all
+  fn max_age(&self) -> Option<Duration> {
+    match self.max_age {
+      Some(max_age) => Some(max_age),
+      None if self.cached => Some(DEFAULT_MAX_AGE),
+      None => None,
+    }
+  }
+}
+
+impl NetworkArgs {
+  /// The portal of `--school`, logging in and reading the courses through `SisBackend`
+  fn backend(&self) -> Result<Box<dyn SisBackend>, GradesError> {
+    let options = ClientOptions {
+      timeout: Some(self.timeout),
+      connect_timeout: Some(self.connect_timeout),
+      proxy: self.proxy.clone(),
+      selectors: load_config()?.selectors,
+      cassette: match (&self.record, &self.replay) {
+        (Some(path), _) => Some(Arc::new(Cassette::record(path))),
+        (_, Some(path)) => Some(Arc::new(Cassette::replay(path)?)),
+        _ => None,
+      },
+      debug_dump: DEBUG_DUMP.get().cloned(),
+    };
+
+    self.school.backend(&options)
+  }
+
+  /// Whether the requests go to a cassette, which needs a login of its own and not the saved session
+  fn uses_cassette(&self) -> bool {
+    self.record.is_some() || self.replay.is_some()
+  }
+}
+
+#[derive(Debug, Clone, StructOpt)]
+struct MfaArgs {
+  #[structopt(long, default_value = "push", raw(possible_values = r#"&["push", "totp"]"#, global = "true"), help = "How to answer a Duo two-factor prompt")]
+  mfa: String,
+  #[structopt(long, raw(global = "true"), help = "Duo passcode, prompted for when --mfa totp is given without one")]
+  totp: Option<String>,
+  #[structopt(long, raw(global = "true"), help = "Log in through a headless browser run by the WebDriver at this URL, e.g. http://localhost:4444")]
+  browser: Option<String>,
+}
+
+impl MfaArgs {
+  fn second_factor(&self) -> Result<SecondFactor, GradesError> {
+    match self.totp {
+      Some(ref code) => Ok(SecondFactor::Passcode(code.clone())),
+      None if self.mfa == "totp" => Ok(SecondFactor::Passcode(rpassword::prompt_password_stderr("Duo passcode: ")?)),
+      None => Ok(SecondFactor::Push),
+    }
+  }
+
+  /// Logs in by posting the form, or through the browser with `--browser`
+  async fn login(&self, backend: &dyn SisBackend, username: &str, password: &str) -> Result<(), GradesError> {
+    match self.browser {
+      Some(ref webdriver) => browser_login(sis_pages(backend)?, webdriver, username, password, &self.second_factor()?).await,
+      None => backend.login(username, password, &self.second_factor()?).await,
+    }
+  }
 }
 
+#[cfg(feature = "browser")]
+async fn browser_login(client: &GradesClient, webdriver: &str, username: &str, password: &str, factor: &SecondFactor) -> Result<(), GradesError> {
+  grades_list::browser::login(client, webdriver, username, password, factor).await
+}
+
+#[cfg(not(feature = "browser"))]
+async fn browser_login(_: &GradesClient, _: &str, _: &str, _: &str, _: &SecondFactor) -> Result<(), GradesError> {
+  Err(GradesError::Config("--browser needs grades_list built with the browser feature, cargo build --features browser".to_owned()))
+}
+
+/// York's client for what reads the pages of SIS rather than the courses, failing on another `--school`
+fn sis_pages(backend: &dyn SisBackend) -> Result<&GradesClient, GradesError> {
+  backend.york().ok_or_else(|| GradesError::Config(format!("this reads York's SIS pages, --school {} only gives its courses", backend.name())))
+}
+
+#[derive(Debug, StructOpt)]
+enum Command {
+  #[structopt(name = "fetch", about = "Lists the grades and GPA, what runs without a subcommand")]
+  Fetch {
+    #[structopt(flatten)]
+    list: ListArgs,
+  },
+  #[structopt(name = "gpa", about = "Prints only the GPA of the courses")]
+  Gpa,
+  #[structopt(name = "login", about = "Checks the credentials and optionally saves them to the system keychain")]
+  Login {
+    #[structopt(help = "York Username")]
+    username: String,
+    #[structopt(help = "York Password, prompted for when omitted")]
+    password: Option<String>,
+    #[structopt(long, help = "Save the credentials so later runs don't need them")]
+    save: bool,
+  },
+  #[structopt(name = "watch", about = "Keeps fetching the grades and prints the ones that change")]
+  Watch {
+    #[structopt(long, default_value = "30m", parse(try_from_str = "humantime::parse_duration"), help = "Time between fetches, e.g. 30m or 1h")]
+    interval: Duration,
+    #[structopt(long, parse(from_os_str), help = "Keep an Atom feed of the grade changes in this file")]
+    feed: Option<PathBuf>,
+  },
+  #[structopt(name = "diff", about = "Compares two snapshots from the history or two files saved with --json")]
+  Diff {
+    #[structopt(help = "Snapshot id or JSON file to compare from, defaults to the snapshot before <to>")]
+    from: Option<String>,
+    #[structopt(help = "Snapshot id or JSON file to compare to, defaults to the latest snapshot")]
+    to: Option<String>,
+    #[structopt(long, help = "Compare from the last snapshot taken before this date (YYYY-MM-DD) instead")]
+    since: Option<String>,
+  },
+  #[structopt(name = "whatif", about = "Projects the GPA with hypothetical courses added to the real ones")]
+  WhatIf {
+    #[structopt(long, raw(number_of_values = "1", required = "true"), help = "Hypothetical course like \"EECS 3101 3.00 A+\", can be repeated")]
+    add: Vec<String>,
+  },
+  #[structopt(name = "target", about = "Finds the average grade needed this term to reach a cumulative GPA")]
+  Target {
+    #[structopt(help = "Cumulative GPA to reach")]
+    gpa: f32,
+    #[structopt(long, help = "Credits being taken this term")]
+    credits: f32,
+    #[structopt(long, default_value = "nine", help = "Scale of the target GPA, four, nine or one from the config")]
+    scale: String,
+  },
+  #[structopt(name = "convert", about = "Converts the grades to the scales used by OMSAS, LSAC and to percentages")]
+  Convert {
+    #[structopt(long, raw(possible_values = "Conversion::NAMES", number_of_values = "1"), help = "Scale to convert to, can be repeated, all of them when omitted")]
+    to: Vec<Conversion>,
+  },
+  #[structopt(name = "stats", about = "Shows the grade distribution, median grade, credit totals and best and worst sessions")]
+  Stats,
+  #[structopt(name = "progress", about = "Shows completed and remaining credits of the degree requirements set in the config")]
+  Progress,
+  #[structopt(name = "degree-progress", about = "Shows the requirements of the Degree Progress Report in SIS and the credits they still need")]
+  DegreeProgress,
+  #[structopt(name = "schedule", about = "Shows the timetable of the courses enrolled in this session")]
+  Schedule,
+  #[structopt(name = "dates", about = "Shows the enrolment appointments and important dates of the session")]
+  Dates,
+  #[structopt(name = "export", about = "Writes the grades to the --output file in any --format, or sends them to another service")]
+  Export {
+    #[structopt(long, help = "Student name to print on the transcript")]
+    name: Option<String>,
+    #[structopt(long = "student-id", help = "Student number to print on the transcript")]
+    student_id: Option<String>,
+    #[structopt(long = "encrypt-to", raw(number_of_values = "1"), help = "Encrypt the file to this age public key, or GPG key id or email, can be repeated")]
+    encrypt_to: Vec<Recipient>,
+    #[structopt(subcommand)]
+    to: Option<ExportTarget>,
+  },
+  #[structopt(name = "snapshot", about = "Writes the grades to a git repository and commits them when they change")]
+  Snapshot {
+    #[structopt(long, parse(from_os_str), help = "Repository to write grades.json and grades.csv to, created when missing")]
+    git: PathBuf,
+  },
+  #[structopt(name = "cache", about = "Manages the cached last scrape used by --cached")]
+  Cache {
+    #[structopt(subcommand)]
+    action: CacheAction,
+  },
+  #[structopt(name = "history", about = "Lists the GPA of every past fetch, or the grades of one of them")]
+  History {
+    #[structopt(help = "Id of the snapshot to show")]
+    id: Option<i64>,
+  },
+  #[structopt(name = "check", about = "Logs in, checks that the course list can be read and logs out again")]
+  Check,
+  #[structopt(name = "doctor", about = "Checks the connection to York, the login form and the selectors step by step")]
+  Doctor,
+  #[structopt(name = "tui", about = "Shows the grades in an interactive dashboard")]
+  Tui,
+  #[structopt(name = "completions", about = "Prints the completion script for a shell, to be sourced from its config")]
+  Completions {
+    #[structopt(raw(possible_values = "&Shell::variants()", case_insensitive = "true"), help = "Shell to complete in")]
+    shell: Shell,
+  },
+  #[structopt(name = "serve", about = "Serves the grades as a JSON REST API, fetching them again periodically")]
+  Serve {
+    #[structopt(long, default_value = "8080", help = "Port to listen on")]
+    port: u16,
+    #[structopt(long, default_value = "127.0.0.1", help = "Address to listen on")]
+    bind: String,
+    #[structopt(long, help = "Bearer token clients must send, read from GRADES_LIST_TOKEN or generated when omitted")]
+    token: Option<String>,
+    #[structopt(long, default_value = "30m", parse(try_from_str = "humantime::parse_duration"), help = "Time between fetches, e.g. 30m or 1h")]
+    interval: Duration,
+    #[structopt(long = "multi-user", help = "Serves the accounts registered through /admin/profiles, each with its own token, instead of logging in")]
+    multi_user: bool,
+    #[structopt(long = "rate-limit", default_value = "60", help = "Requests a minute answered for each token, 0 for no limit")]
+    rate_limit: u32,
+    #[structopt(long = "global-rate-limit", default_value = "600", help = "Requests a minute answered in all, 0 for no limit")]
+    global_rate_limit: u32,
+  },
+  #[structopt(name = "daemon", about = "Logs in once and keeps the session alive, serving York's pages to other runs over a Unix socket")]
+  Daemon {
+    #[structopt(long = "keep-alive", default_value = "5m", parse(try_from_str = "humantime::parse_duration"), help = "Time between checks that the session is alive, logging in again when it isn't")]
+    keep_alive: Duration,
+  },
+}
+
+#[derive(Debug, StructOpt)]
+enum CacheAction {
+  #[structopt(name = "clear", about = "Removes the cached scrapes of every user")]
+  Clear,
+}
+
+#[derive(Debug, StructOpt)]
+enum ExportTarget {
+  #[structopt(name = "sheets", about = "Writes the grades and GPA to a worksheet of a Google Sheets spreadsheet")]
+  Sheets {
+    #[structopt(long, parse(from_os_str), help = "JSON key file of a service account the spreadsheet is shared with")]
+    key: PathBuf,
+    #[structopt(long, help = "Id of the spreadsheet, from its URL")]
+    spreadsheet: String,
+    #[structopt(long, default_value = "Grades", help = "Worksheet to write, added when missing")]
+    sheet: String,
+    #[structopt(long, help = "Add the grades under what's on the worksheet instead of replacing it")]
+    append: bool,
+  },
+  #[structopt(name = "notion", about = "Adds or updates a page per course in a Notion database, matched on the course code")]
+  Notion {
+    #[structopt(long, help = "Id of the database, from its URL")]
+    database: String,
+    #[structopt(long, help = "Secret of the Notion integration the database is shared with, read from NOTION_TOKEN when omitted")]
+    token: Option<String>,
+  },
+}
+
+const OUTPUT_VERSION: u32 = 2;
+
+/// The grades as printed with `--format json`
+#[derive(Debug, Serialize, JsonSchema)]
+struct Output<'a> {
+  /// Version of this layout, bumped whenever a field is renamed, removed or changes meaning
+  version: u32,
+  /// Left out with `--machine`, so unchanged grades give unchanged output
+  #[serde(skip_serializing_if = "Option::is_none")]
+  fetched_at: Option<chrono::DateTime<chrono::Utc>>,
+  /// Version of grades_list that wrote this
+  tool_version: &'static str,
+  /// `null` while no course counts towards the GPA
+  gpa: Option<&'a Gpa>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  major_gpa: Option<&'a Gpa>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  scale_gpa: Option<ScaledGpa<'a>>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  last_credits: Option<LastCreditsGpa>,
+  grades: Vec<GradeOutput<'a>>,
+  not_in_gpa: &'a [Excluded<'a>],
+  not_in_gpa_summary: &'a ExcludedSummary,
+  credits: &'a CreditSummary,
+  /// Courses with no grade yet, also in `grades`
+  #[serde(default, skip_serializing_if = "Vec::is_empty")]
+  in_progress: Vec<&'a CourseData>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  projection: Option<Projection>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  standing: Option<StandingReport>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  cumulative: Option<Vec<CumulativeGpa>>,
+  #[serde(default, skip_serializing_if = "Vec::is_empty")]
+  warnings: Vec<String>,
+}
+
+/// A course in the JSON output, with its grade points so the GPA can be checked by hand
+#[derive(Debug, Serialize, JsonSchema)]
+struct GradeOutput<'a> {
+  #[serde(flatten)]
+  course: &'a CourseData,
+  #[serde(flatten)]
+  points: Option<CoursePoints>,
+  /// `None` for a session that isn't FW or SU with its years, like the what-if courses
+  parsed_session: Option<ParsedSession>,
+}
+
+impl<'a> GradeOutput<'a> {
+  fn new(course: &'a CourseData) -> GradeOutput<'a> {
+    GradeOutput { course, points: course.points(), parsed_session: sessions::parse(&course.session) }
+  }
+}
+
+/// GPA on the scale picked with `--scale`
+#[derive(Debug, Serialize, JsonSchema)]
+struct ScaledGpa<'a> {
+  scale: &'a str,
+  gpa: Option<f32>,
+}
+
+/// GPA of the most recent sessions, with `--last-credits`
+#[derive(Debug, Serialize, JsonSchema)]
+struct LastCreditsGpa {
+  /// Credits counted, at least the ones asked for unless there weren't that many
+  credits: f32,
+  /// Earliest session included
+  since: Option<String>,
+  gpa: Option<Gpa>,
+}
+
+/// The grades of every profile as printed by `fetch --all-profiles`
 #[derive(Debug, Serialize)]
-struct CourseData {
-  session: String,
-  course: String,
-  title: String,
-  grade: String,
+struct AllProfilesOutput<'a> {
+  version: u32,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  fetched_at: Option<chrono::DateTime<chrono::Utc>>,
+  tool_version: &'static str,
+  profiles: BTreeMap<&'a str, ProfileOutput<'a>>,
 }
 
+/// One account of `AllProfilesOutput`, with its grades or why they couldn't be fetched
 #[derive(Debug, Serialize)]
-struct GPA {
-  four: f32,
-  nine: f32
+struct ProfileOutput<'a> {
+  username: &'a str,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  gpa: Option<&'a Gpa>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  credits: Option<CreditSummary>,
+  #[serde(skip_serializing_if = "Vec::is_empty")]
+  grades: Vec<GradeOutput<'a>>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  error: Option<String>,
 }
 
 #[derive(Debug, Serialize)]
-struct Output<'a> {
-  gpa: &'a GPA,
-  grades: &'a Vec<CourseData>,
+struct WhatIfOutput<'a> {
+  current: Option<&'a Gpa>,
+  projected: Option<&'a Gpa>,
+  hypothetical: &'a [CourseData],
+}
+
+/// `Output` as read back from a file
+#[derive(Debug, Deserialize)]
+struct SavedOutput {
+  gpa: Option<Gpa>,
+  grades: Vec<CourseData>,
 }
 
-async fn auth (client: &reqwest::Client, args: &Cli) -> Result<bool, Box<dyn std::error::Error>> {
-  let resp = client.get(COURSE_URL).send().await?.text().await?;
-  
-  let mut login_fields: HashMap<String, String> = [
-    ("mli".to_owned(), args.username.to_owned()),
-    ("password".to_owned(), args.password.to_owned()),
-    ("dologin".to_owned(), "Login".to_owned()),
-  ].iter().cloned().collect();
+/// Sets up logging, `--log-level` wins over `-v` which wins over `RUST_LOG`, with only warnings logged by default
+fn init_logging(args: &Cli) -> Result<(), GradesError> {
+  let level = match (args.log_level.as_deref(), args.verbose) {
+    (Some(level), _) => Some(level),
+    (None, 0) => None,
+    (None, 1) => Some("info"),
+    (None, 2) => Some("debug"),
+    (None, _) => Some("trace"),
+  };
 
-  let document = Html::parse_document(&resp);
-  let hidden_selector = Selector::parse("input[type='hidden']").unwrap();
+  // the crate's own logs only, dependencies are noisy and can be enabled through RUST_LOG
+  let filter = match level {
+    Some(level) => EnvFilter::new(format!("grades_list={}", level)),
+    None => EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("grades_list=warn")),
+  };
 
-  // append all the hiden fields for the auth
-  document.select(&hidden_selector).for_each(|element| {
-    login_fields.insert(element.value().attr("name").unwrap().to_owned(), element.value().attr("value").unwrap().to_owned());
-  });
+  let builder = tracing_subscriber::fmt().with_env_filter(filter);
+  match args.log_file {
+    Some(ref path) => {
+      let file = std::fs::OpenOptions::new().create(true).append(true).open(path)?;
+      builder.with_ansi(false).with_writer(std::sync::Mutex::new(file)).init();
+    },
+    None => {
+      let color = std::env::var_os("NO_COLOR").is_none_or(|v| v.is_empty()) && atty::is(atty::Stream::Stderr);
+      builder.with_ansi(color).with_writer(std::io::stderr).init()
+    },
+  }
+
+  Ok(())
+}
+
+#[tokio::main]
+async fn main() {
+  let mut args = Cli::from_args();
+  args.normalize();
+  LANG.get_or_init(|| args.lang);
+
+  if let Err(e) = init_logging(&args) {
+    eprintln!("Could not open the log file: {}", e);
+    std::process::exit(e.exit_code());
+  }
+
+  if let Some(ref path) = args.network.record {
+    warning(format!("{} will hold your grades as York shows them, share it only with someone who may see them", path.display()));
+  }
+
+  let dump_to = args.network.debug_dump.clone().map(|dir| (dir, args.network.debug_dump_always));
+  if dump_to.is_some() {
+    DEBUG_DUMP.get_or_init(|| Arc::new(DebugDump::new()));
+  }
+
+  let lang = args.lang;
+  let result = match load_config().and_then(|config| args.apply_profile(&config)) {
+    Ok(()) => run(args).await,
+    Err(e) => Err(e),
+  };
+
+  if let (Some((dir, always)), Some(dump)) = (dump_to, DEBUG_DUMP.get()) {
+    if result.is_err() || always {
+      match dump.write(&dir) {
+        Ok(()) => eprintln!("Wrote {} redacted requests to {} for a bug report", dump.len(), dir.display()),
+        Err(e) => eprintln!("Could not write the debug dump: {}", e),
+      }
+    }
+  }
 
-  let login_resp = client.post(LOGIN_PAGE).form(&login_fields).send().await?;
+  if let Err(e) = result {
+    eprintln!("{}", e.localized(lang));
+    std::process::exit(e.exit_code());
+  }
+}
+
+/// Asks for the password with hidden input, or reads a line from stdin when it is piped in
+fn read_password(prompt: &str) -> Result<String, GradesError> {
+  if atty::is(atty::Stream::Stdin) {
+    return Ok(rpassword::prompt_password_stderr(prompt)?);
+  }
 
-  let login_resp_content = &login_resp.text().await?;
+  let mut password = String::new();
+  std::io::stdin().lock().read_line(&mut password)?;
 
-  // will be authenticated if this string is present in the page
-  Ok(login_resp_content.contains("You have successfully authenticated"))
+  Ok(password.trim_end_matches(&['\r', '\n'][..]).to_owned())
 }
 
-fn select_cells(element: ElementRef, selector: &Selector) -> Vec<String> {
-  element.select(selector).map(|e| e.inner_html().trim().to_owned()).collect()
+/// The `~/.netrc` entry for Passport York when `--netrc` is given
+fn netrc_entry(args: &Cli) -> Result<Option<netrc::Entry>, GradesError> {
+  match netrc::default_path() {
+    Some(ref path) if args.netrc => netrc::load(path, netrc::MACHINE),
+    _ => Ok(None),
+  }
 }
 
-fn html_entities (s: &str) -> String {
-  s.replace("&nbsp;", "").replace("&amp;", "&").replace("&lt;", "<").replace("&gt;", ">")
+/// Username from the command line or profile, then `GRADES_LIST_USERNAME`, `~/.netrc` with
+/// `--netrc` and lastly the one saved in the keychain
+fn username(args: &Cli) -> Result<String, GradesError> {
+  if let Some(ref username) = args.username {
+    return Ok(username.clone());
+  }
+  if let Ok(username) = std::env::var("GRADES_LIST_USERNAME") {
+    return Ok(username);
+  }
+  if let Some(login) = netrc_entry(args)?.and_then(|entry| entry.login) {
+    return Ok(login);
+  }
+  // a replay logs nobody in, any name does
+  if args.network.replay.is_some() {
+    return Ok("replay".to_owned());
+  }
+
+  keychain::username()?
+    .ok_or_else(|| GradesError::Keychain("no saved username, run `grades_list login <username> --save` first".to_owned()))
 }
 
-async fn scrape_table (client: &reqwest::Client) -> Result<Vec<CourseData>, Box<dyn std::error::Error>> {
-  let courses_page = client.get(COURSE_URL).send().await?.text().await?;
+/// Password from the command line, then `GRADES_LIST_PASSWORD` or `~/.netrc` with `--netrc`, when
+/// one was given some way other than the keychain
+fn given_password(args: &Cli, username: &str) -> Result<Option<String>, GradesError> {
+  if args.password.is_some() {
+    return Ok(args.password.clone());
+  }
+  if let Ok(password) = std::env::var("GRADES_LIST_PASSWORD") {
+    return Ok(Some(password));
+  }
+  if args.network.replay.is_some() {
+    return Ok(Some(String::new()));
+  }
 
-  let document = Html::parse_document(&courses_page);
-  let table_selector = Selector::parse("table.bodytext").unwrap();
-  let tables = document.select(&table_selector).collect::<Vec<_>>();
+  // a netrc entry for another account isn't this one's password
+  Ok(netrc_entry(args)?
+    .filter(|entry| entry.login.as_deref().is_none_or(|login| login == username))
+    .and_then(|entry| entry.password))
+}
 
-  if tables.is_empty() {
-    panic!("Could not find table!")
+/// Password from the command line or the keychain, prompting for it as a last resort
+fn password(username: &str, password: Option<String>) -> Result<String, GradesError> {
+  match password {
+    Some(password) => Ok(password),
+    None => match saved_password(username) {
+      Some(password) => Ok(password),
+      None => read_password("York Password: "),
+    },
   }
+}
+
+/// The password saved in the keychain, a keychain that can't be used (like under cron, with no
+/// Secret Service to ask) leaves the password to be prompted for or piped in
+fn saved_password(username: &str) -> Option<String> {
+  keychain::password(username).unwrap_or_else(|e| {
+    warning(e);
+    None
+  })
+}
 
-  let mut resp: Vec<CourseData> = Vec::new();
+/// Everything needed to log in, kept so long running commands can log in again once the session expires
+struct Login {
+  username: String,
+  password: Option<String>,
+  mfa: MfaArgs,
+  session_path: Option<PathBuf>,
+}
 
-  let sel_tr = Selector::parse("tr").unwrap();
-  let sel_td = Selector::parse("td").unwrap();
+impl Login {
+  fn new(args: &Cli) -> Result<Login, GradesError> {
+    let username = username(args)?;
+    let password = given_password(args, &username)?;
+    let session_path = if args.no_persist || args.network.uses_cassette() { None } else { session::default_path(&username) };
 
-  let rows = tables[0].select(&sel_tr).peekable();
-  let data: Vec<Vec<String>> = rows.map(|tr| select_cells(tr, &sel_td)).collect();
+    Ok(Login { username, password, mfa: args.mfa.clone(), session_path })
+  }
 
-  for row in &data {
-    // skip the headers row
-    if row.is_empty() { continue; }
+  /// Makes sure the client is logged in, reusing the saved or current session while it is still valid
+  async fn connect(&mut self, backend: &dyn SisBackend) -> Result<(), GradesError> {
+    let restored = match self.session_path {
+      Some(ref path) => session::load(backend, path, &session_vault()?)?,
+      None => backend.has_session(),
+    };
 
-    resp.push(CourseData {
-      session: html_entities(&row[0]),
-      course: html_entities(&row[1]),
-      title: html_entities(&row[2]),
-      grade: html_entities(&row[3]),
-    });
+    if restored && backend.is_logged_in().await? {
+      return Ok(());
+    }
+
+    if self.password.is_none() {
+      self.password = Some(password(&self.username, None)?);
+    }
+
+    let password = self.password.as_ref().unwrap();
+    self.mfa.login(backend, &self.username, password).await
   }
 
-  Ok(resp)
-}
-
-// calculate both four point and nine point gpa
-fn calculate_gpa (grades: &[CourseData]) -> Result<GPA, Box<dyn std::error::Error>> {
-  let nine: HashMap<String, f32> = [
-    ("A+".into(), 9.0),
-    ("A".into(), 8.0),
-    ("B+".into(), 7.0),
-    ("B".into(), 6.0),
-    ("C+".into(), 5.0),
-    ("C".into(), 4.0),
-    ("D+".into(), 3.0),
-    ("D".into(), 2.0),
-    ("E".into(), 1.0),
-    ("F".into(), 0.0),
-  ].iter().cloned().collect();
-
-  let four: HashMap<String, f32> = [
-    ("A+".into(), 4.0),
-    ("A".into(), 3.8),
-    ("B+".into(), 3.3),
-    ("B".into(), 3.0),
-    ("C+".into(), 2.3),
-    ("C".into(), 2.0),
-    ("D+".into(), 1.3),
-    ("D".into(), 1.0),
-    ("E".into(), 0.7),
-    ("F".into(), 0.0),
-  ].iter().cloned().collect();
-
-  let mut total_credits = 0.0;
-  let mut nine_point = 0.0;
-  let mut four_point = 0.0;
-  for grade in grades {
-    if nine.contains_key(&grade.grade) {
-      let course_parts = &grade.course.split_ascii_whitespace().map(|p| p.trim()).collect::<Vec<_>>();
-      // parse the credit value
-      let credit = course_parts[3].parse::<f32>().unwrap();
-
-      nine_point += *nine.get(&grade.grade).unwrap() * credit;
-      four_point += *four.get(&grade.grade).unwrap() * credit;
-
-      total_credits += credit;
-    }
-  }
-
-  Ok(GPA {
-    four: four_point / total_credits,
-    nine: nine_point / total_credits,
-  })
+  /// Saves the session for the next run, or logs out when sessions aren't kept
+  async fn finish(&self, backend: &dyn SisBackend) -> Result<(), GradesError> {
+    match self.session_path {
+      Some(ref path) => session::save(backend, path, &session_vault()?),
+      None => backend.logout().await,
+    }
+  }
 }
 
-async fn logout (client: &reqwest::Client) -> Result<(), Box<dyn std::error::Error>> {
-  // a single request is all that is needed
-  client.get(LOGOUT_PAGE).send().await?;
-  Ok(())
+fn print_change(change: &GradeChange) {
+  println!("[{}] {}", chrono::Local::now().format("%Y-%m-%d %H:%M"), change);
 }
 
-#[tokio::main]
-async fn main() -> Result<(), Box<dyn std::error::Error>>{
-  let args = Cli::from_args();
+/// Where grade changes are sent, the channels in the config file and the ones given on the command line
+fn notifiers(args: &Cli) -> Result<Vec<Box<dyn Notifier>>, GradesError> {
+  let config = load_config()?.notify;
+  // the section turns Pushover on by itself, the flag is kept for the scripts that still pass it
+  if args.notify_pushover && config.pushover.is_none() {
+    return Err(GradesError::Config("--notify-pushover needs `token` and `user` under [notify.pushover] in the config file".to_owned()));
+  }
+  let mut notifiers = notify::from_config(&config)?;
+
+  if let Some(ref url) = args.notify_webhook {
+    notifiers.push(Box::new(Webhook::new(url, &args.webhook_headers)?));
+  }
+  if let Some(ref topic) = args.notify_ntfy {
+    notifiers.push(Box::new(Ntfy::new(topic)?));
+  }
 
-  let client = reqwest::Client::builder()
-    .user_agent(USER_AGENT)
-    .cookie_store(true)
-    .build()?;
+  Ok(notifiers)
+}
 
-  let authenticated = auth(&client, &args).await?;
-  if !authenticated {
-    panic!("Could not authenticate!");
+/// Sends the changes everywhere, a failed notification is reported but not fatal
+async fn notify(notifiers: &[Box<dyn Notifier>], changes: &[GradeChange], gpa: Option<&Gpa>) {
+  for e in notify::send_all(notifiers, changes, gpa).await {
+    eprintln!("{}", e);
   }
+}
 
-  let table_content = scrape_table(&client).await?;
+fn open_history() -> Result<History, GradesError> {
+  let path = history::default_path()
+    .ok_or_else(|| GradesError::Storage("could not find a data directory".to_owned()))?;
+
+  match vault()? {
+    Some(vault) => History::open_encrypted(&path, vault),
+    None => History::open(&path),
+  }
+}
 
-  logout(&client).await?;
+/// Fetches the grades every `interval`, printing only what changed since the previous fetch
+async fn watch(args: &Cli, interval: Duration, feed: &Option<PathBuf>) -> Result<(), GradesError> {
+  let mut login = Login::new(args)?;
+  let backend = args.network.backend()?;
+  let notifiers = notifiers(args)?;
+  let changes = local_changes(args)?;
 
-  let gpa = calculate_gpa(&table_content)?;
+  // pick up where the last run left off, so changes made while not watching are still reported
+  let mut history = open_history()?;
+  let mut last = history.latest(&login.username)?.map(|s| s.grades);
+  let mut feed_entries = feed::Feed::new(&login.username);
 
-  if args.json {
-    let output = Output {
-      gpa: &gpa,
-      grades: &table_content
+  loop {
+    let fetched = match login.connect(&*backend).await {
+      Ok(()) => backend.fetch_grades().await.map(|grades| changes.apply(grades)),
+      Err(e) => Err(e),
     };
 
-    println!("{}", serde_json::to_string(&output).unwrap());
-  } else {
-    println!("GPA:");
-    ptable!(["Four Point", "Nine Point"], [ gpa.four, gpa.nine ]);
+    match fetched {
+      Ok(grades) => {
+        let gpa = grades_list::calculate_gpa(&grades);
 
-    println!();
+        match last {
+          Some(ref prev) => {
+            let changes = diff::diff(prev, &grades);
+            changes.iter().for_each(print_change);
+            notify(&notifiers, &changes, gpa.as_ref()).await;
+          },
+          None => println!("{}", args.lang.format(Text::Watching, &[&grades.len(), &humantime::format_duration(interval)])),
+        }
+
+        if let Some(ref path) = login.session_path {
+          session::save(&*backend, path, &session_vault()?)?;
+        }
+        let id = history.record(&login.username, &grades, gpa.as_ref())?;
 
-    println!("Grades:");
-    let mut pretty = table!(["Session", "Course", "Title", "Grade"]);
+        if let Some(ref path) = feed {
+          let entries = feed_entries.recorded(&history, id)?;
+          files::write_atomically(path, feed::atom(entries, chrono::Utc::now()).as_bytes())?;
+        }
 
-    for row in &table_content {
-      pretty.add_row(row![ row.session, row.course, row.title, row.grade ]);
+        last = Some(grades);
+      },
+      // York being unreachable for a while shouldn't end the watch
+      Err(GradesError::Network(e)) => eprintln!("{}", args.lang.format(Text::YorkUnreachable, &[&e])),
+      Err(e) => return Err(e),
     }
 
+    tokio::time::sleep(interval).await;
+  }
+}
+
+/// The course list to try the selectors on, from `--from-file` or read with the saved session
+async fn saved_course_list(args: &Cli) -> Result<Option<String>, GradesError> {
+  if let Some(ref path) = args.from_file {
+    return Ok(Some(read_page(path)?));
+  }
+
+  // without a saved session there is nothing to read the course list with, and no asking for a password
+  let path = match username(args).ok().and_then(|username| session::default_path(&username)) {
+    Some(path) => path,
+    None => return Ok(None),
+  };
+  let backend = args.network.backend()?;
+  let client = sis_pages(&*backend)?;
+  if !session::load(client, &path, &session_vault()?)? || !client.is_logged_in().await? {
+    return Ok(None);
+  }
+
+  Ok(Some(client.fetch_page().await?))
+}
+
+/// Prints a checklist of what a scrape needs, failing when something on it failed
+async fn doctor(args: &Cli) -> Result<(), GradesError> {
+  let course_list = match saved_course_list(args).await {
+    Ok(page) => page,
+    Err(e) => {
+      warning(format!("could not read the course list with the saved session: {}", e));
+      None
+    },
+  };
+  let backend = args.network.backend()?;
+  let checks = doctor::run(sis_pages(&*backend)?, course_list.as_deref()).await;
+
+  if args.format() == Format::Json {
+    println!("{}", to_json(args, &checks));
+  } else {
+    let color = use_color(args);
+    let lang = args.lang;
+    let mut pretty = table!([lang.text(Text::Check), lang.text(Text::CheckResult), lang.text(Text::Detail)]);
+    for check in &checks {
+      let result = Cell::new(check.outcome.label());
+      let result = match check.outcome {
+        _ if !color => result,
+        Outcome::Pass => result.style_spec("Fg"),
+        Outcome::Fail => result.style_spec("Fr"),
+        Outcome::Skip => result.style_spec("Fy"),
+      };
+      pretty.add_row(Row::new(vec![Cell::new(&check.name), result, Cell::new(&check.detail)]));
+    }
     pretty.printstd();
   }
 
+  match checks.iter().filter(|c| c.outcome == Outcome::Fail).count() {
+    0 => Ok(()),
+    failed => Err(GradesError::Assertion(format!("{} of the {} checks failed", failed, checks.len()))),
+  }
+}
+
+/// Logs in from scratch with the given or saved credentials and makes sure the course list parses
+///
+/// The saved session is left alone, so this checks the credentials themselves.
+async fn check(args: &Cli) -> Result<(), GradesError> {
+  let username = username(args)?;
+  let password = password(&username, given_password(args, &username)?)?;
+  let backend = args.network.backend()?;
+
+  args.mfa.login(&*backend, &username, &password).await?;
+  if args.format() != Format::Json {
+    println!("{}", args.lang.text(Text::CredentialsOk));
+  }
+
+  let parsed = backend.fetch_grades().await;
+  backend.logout().await?;
+  let grades = parsed?;
+
+  if args.format() == Format::Json {
+    println!("{}", to_json(args, &serde_json::json!({ "credentials": true, "course_list": true, "courses": grades.len() })));
+  } else {
+    println!("{}", args.lang.format(Text::CourseListOk, &[&grades.len()]));
+  }
+
   Ok(())
 }
+
+/// Runs the dashboard, with its refresh key fetching the grades again on the same session
+async fn tui(args: &Cli) -> Result<(), GradesError> {
+  let mut login = Login::new(args)?;
+  let backend = args.network.backend()?;
+
+  login.connect(&*backend).await?;
+  let changes = local_changes(args)?;
+  let grades = changes.apply(backend.fetch_grades().await?);
+
+  let (requests, mut requested) = tokio::sync::mpsc::unbounded_channel::<()>();
+  let (results, received) = std::sync::mpsc::channel();
+
+  let fetcher = tokio::spawn(async move {
+    while requested.recv().await.is_some() {
+      let fetched = match login.connect(&*backend).await {
+        Ok(()) => backend.fetch_grades().await.map(|grades| changes.apply(grades)),
+        Err(e) => Err(e),
+      };
+
+      if results.send(fetched).is_err() {
+        break;
+      }
+    }
+
+    // the dashboard is closed, keep the session for the next run
+    login.finish(&*backend).await
+  });
+
+  let dashboard = tokio::task::spawn_blocking(move || {
+    grades_list::tui::run(grades, || { let _ = requests.send(()); }, received)
+  });
+
+  let shown = dashboard.await.map_err(|e| GradesError::Io(std::io::Error::other(e)))?;
+  fetcher.await.map_err(|e| GradesError::Io(std::io::Error::other(e)))??;
+
+  shown
+}
+
+/// Shortest `serve --interval`, fetching more often would only get the account noticed
+const MIN_SERVE_INTERVAL: Duration = Duration::from_secs(5 * 60);
+/// Longest wait between fetches after they kept failing
+const MAX_SERVE_BACKOFF: Duration = Duration::from_secs(6 * 60 * 60);
+
+/// Token from the command line or environment, or a random one that is printed so it can be given to clients
+fn api_token(token: &Option<String>) -> String {
+  if let Some(token) = token.clone().or_else(|| std::env::var("GRADES_LIST_TOKEN").ok()) {
+    return token;
+  }
+
+  let token = accounts::new_token();
+  eprintln!("API token: {}", token);
+  token
+}
+
+/// Fetches the grades for the server with `changes` applied, saving the session and recording
+/// them in the history
+async fn refresh(login: &mut Login, backend: &dyn SisBackend, changes: &LocalChanges, history: &mut History, feed: &mut feed::Feed) -> Result<(Latest, Vec<feed::Entry>), GradesError> {
+  // reusing the session keeps it alive, connect only logs in again once York has expired it
+  login.connect(backend).await?;
+  let grades = changes.apply(backend.fetch_grades().await?);
+  let gpa = grades_list::calculate_gpa(&grades);
+  if let Some(ref path) = login.session_path {
+    session::save(backend, path, &session_vault()?)?;
+  }
+  let id = history.record(&login.username, &grades, gpa.as_ref())?;
+  let changes = feed.recorded(history, id)?.to_vec();
+
+  Ok((Latest { fetched_at: chrono::Utc::now(), gpa, grades }, changes))
+}
+
+/// Keeps the outcome of a fetch for the server, the last grades keep being served until a
+/// fetch works again
+async fn record(shared: &Shared, fetched: Result<(Latest, Vec<feed::Entry>), GradesError>, duration: Duration) -> Result<(), GradesError> {
+  let mut state = shared.write().await;
+  let fetched = fetched.map(|(latest, changes)| {
+    state.changes = changes;
+    latest
+  });
+  state.record(fetched, duration)
+}
+
+/// Fetches the grades into `shared` every `interval`, forever
+///
+/// The wait doubles after every fetch that failed in a row, up to `MAX_SERVE_BACKOFF`, so a
+/// password that stopped working isn't tried until Passport York locks the account.
+async fn keep_refreshing(mut login: Login, backend: Box<dyn SisBackend>, changes: LocalChanges, mut history: History, mut feed: feed::Feed, shared: Shared, interval: Duration) {
+  let mut wait = interval;
+  loop {
+    tokio::time::sleep(wait).await;
+    let started = Instant::now();
+    let fetched = refresh(&mut login, &*backend, &changes, &mut history, &mut feed).await;
+    match record(&shared, fetched, started.elapsed()).await {
+      Ok(()) => wait = interval,
+      Err(e) => {
+        wait = (wait * 2).min(MAX_SERVE_BACKOFF.max(interval));
+        eprintln!("Could not refresh the grades of {}, trying again in {}: {}", login.username, humantime::format_duration(wait), e);
+      },
+    }
+  }
+}
+
+/// Logs in once and serves the grades over HTTP, refreshing them every `interval` in the background
+async fn serve(args: &Cli, addr: &str, token: &Option<String>, interval: Duration, limits: RateLimits) -> Result<(), GradesError> {
+  let mut login = Login::new(args)?;
+  let backend = args.network.backend()?;
+  let changes = local_changes(args)?;
+  let mut history = open_history()?;
+
+  // the first fetch happens up front so bad credentials fail before anything is listening
+  let shared: Shared = Default::default();
+  let mut feed = feed::Feed::new(&login.username);
+  let started = Instant::now();
+  let fetched = refresh(&mut login, &*backend, &changes, &mut history, &mut feed).await;
+  record(&shared, fetched, started.elapsed()).await?;
+
+  tokio::spawn(keep_refreshing(login, backend, changes, history, feed, shared.clone(), interval));
+
+  let router = server::limited(server::router(shared, &api_token(token)), limits);
+  eprintln!("Listening on http://{}", addr);
+  server::serve(addr, router).await
+}
+
+/// Logs in the accounts of `serve --multi-user`, each with a client, session and refresh of its own
+///
+/// Their grades are served as York lists them, the courses, overrides and `non_degree` subjects
+/// of the config are the owner's.
+struct Registrar {
+  network: NetworkArgs,
+  mfa: MfaArgs,
+  no_persist: bool,
+  interval: Duration,
+  /// None of the owner's, only leaving out the zero-credit rows unless `--include-non-degree`
+  changes: LocalChanges,
+  registry: tokio::sync::Mutex<accounts::Registry>,
+  refreshing: std::sync::Mutex<BTreeMap<String, tokio::task::AbortHandle>>,
+}
+
+impl Registrar {
+  fn login(&self, username: &str, password: String) -> Login {
+    let session_path = if self.no_persist { None } else { session::default_path(username) };
+    Login { username: username.to_owned(), password: Some(password), mfa: self.mfa.clone(), session_path }
+  }
+
+  /// Starts refreshing the grades of an account into `shared`, fetching them right away unless
+  /// that was just done
+  fn start(&self, name: &str, mut login: Login, backend: Box<dyn SisBackend>, mut history: History, shared: Shared, fetched: bool) {
+    let interval = self.interval;
+    let changes = self.changes.clone();
+    let task = tokio::spawn(async move {
+      let mut feed = feed::Feed::new(&login.username);
+      if !fetched {
+        let started = Instant::now();
+        let fetched = refresh(&mut login, &*backend, &changes, &mut history, &mut feed).await;
+        if let Err(e) = record(&shared, fetched, started.elapsed()).await {
+          eprintln!("Could not fetch the grades of {}, trying again later: {}", login.username, e);
+        }
+      }
+      keep_refreshing(login, backend, changes, history, feed, shared, interval).await
+    });
+
+    self.refreshing.lock().unwrap().insert(name.to_owned(), task.abort_handle());
+  }
+}
+
+#[async_trait]
+impl server::Registrar for Registrar {
+  async fn register(&self, name: &str, username: &str, password: &str) -> Result<server::Served, GradesError> {
+    let mut login = self.login(username, password.to_owned());
+    let backend = self.network.backend()?;
+    let mut history = open_history()?;
+
+    let shared: Shared = Default::default();
+    let started = Instant::now();
+    let fetched = refresh(&mut login, &*backend, &self.changes, &mut history, &mut feed::Feed::new(username)).await;
+    record(&shared, fetched, started.elapsed()).await?;
+
+    // saved only once the credentials worked
+    let account = self.registry.lock().await.add(name, username)?;
+    keychain::save_password(username, password)?;
+    self.start(name, login, backend, history, shared.clone(), true);
+    eprintln!("Registered {} as \"{}\"", username, name);
+
+    Ok(server::Served { username: account.username, token: account.token, state: shared })
+  }
+
+  async fn remove(&self, name: &str) -> Result<(), GradesError> {
+    if let Some(task) = self.refreshing.lock().unwrap().remove(name) {
+      task.abort();
+    }
+
+    if let Some(account) = self.registry.lock().await.remove(name)? {
+      keychain::forget(&account.username)?;
+      if let Some(path) = session::default_path(&account.username) {
+        session::remove(&path)?;
+      }
+      eprintln!("Removed {} (\"{}\")", account.username, name);
+    }
+    Ok(())
+  }
+}
+
+/// Serves the grades of every account registered by the admin, refreshing each every `interval`
+async fn serve_accounts(args: &Cli, addr: &str, token: &Option<String>, interval: Duration, limits: RateLimits) -> Result<(), GradesError> {
+  if args.username.is_some() || args.password.is_some() || args.profile.is_some() {
+    return Err(GradesError::Config("serve --multi-user serves the accounts registered through /admin/profiles, a username or profile can't be given".to_owned()));
+  }
+  if args.network.uses_cassette() {
+    return Err(GradesError::Config("serve --multi-user fetches from York, it can't replay a cassette".to_owned()));
+  }
+  if args.mfa.totp.is_some() || args.mfa.mfa == "totp" {
+    return Err(GradesError::Config("serve --multi-user answers Duo with pushes, a passcode only works once".to_owned()));
+  }
+
+  let path = accounts::default_path()
+    .ok_or_else(|| GradesError::Storage("could not find a data directory".to_owned()))?;
+  let registry = accounts::Registry::open(&path)?;
+  let saved = registry.accounts().clone();
+
+  let registrar = Arc::new(Registrar {
+    network: args.network.clone(),
+    mfa: args.mfa.clone(),
+    no_persist: args.no_persist,
+    interval,
+    changes: LocalChanges { include_non_degree: args.include_non_degree, ..Default::default() },
+    registry: tokio::sync::Mutex::new(registry),
+    refreshing: Default::default(),
+  });
+
+  // every account is fetched in the background, one that can't be is served once it can
+  let served: server::Accounts = Default::default();
+  for (name, account) in saved {
+    let password = match keychain::password(&account.username)? {
+      Some(password) => password,
+      None => {
+        eprintln!("No password in the keychain for {} (\"{}\"), register it again", account.username, name);
+        continue;
+      },
+    };
+
+    let shared: Shared = Default::default();
+    let login = registrar.login(&account.username, password);
+    registrar.start(&name, login, args.network.backend()?, open_history()?, shared.clone(), false);
+    served.write().await.insert(name, server::Served { username: account.username, token: account.token, state: shared });
+  }
+
+  let router = server::limited(server::multi_user_router(served.clone(), &api_token(token), registrar), limits);
+  let count = served.read().await.len();
+  eprintln!("Serving {} account{} on http://{}", count, if count == 1 { "" } else { "s" }, addr);
+  server::serve(addr, router).await
+}
+
+/// Logs in once and serves York's pages to other runs of the user until interrupted, checking the
+/// session every `keep_alive` so it never expires between them
+#[cfg(unix)]
+async fn run_daemon(args: &Cli, keep_alive: Duration) -> Result<(), GradesError> {
+  let mut login = Login::new(args)?;
+  let path = daemon::default_path(&login.username)
+    .ok_or_else(|| GradesError::Config("could not find a directory for the daemon's socket".to_owned()))?;
+  let backend: Arc<dyn SisBackend> = Arc::from(args.network.backend()?);
+  sis_pages(&*backend)?;
+
+  login.connect(&*backend).await?;
+  let listener = daemon::listen(&path)?;
+  eprintln!("Logged in as {}, listening on {}", login.username, path.display());
+
+  let server = daemon::serve(listener, backend.clone());
+  tokio::pin!(server);
+  let mut pings = tokio::time::interval(keep_alive);
+  pings.tick().await;
+
+  let result = loop {
+    tokio::select! {
+      result = &mut server => break result,
+      _ = pings.tick() => {
+        // reusing the session keeps it alive, connect only logs in again once York has expired it
+        let pinged = login.connect(&*backend).await.and_then(|()| match login.session_path {
+          Some(ref path) => session::save(&*backend, path, &session_vault()?),
+          None => Ok(()),
+        });
+        match pinged {
+          Ok(()) => {},
+          // York being unreachable for a while shouldn't take the daemon down
+          Err(GradesError::Network(e)) => eprintln!("{}", args.lang.format(Text::YorkUnreachable, &[&e])),
+          Err(e) => break Err(e),
+        }
+      },
+      _ = tokio::signal::ctrl_c() => break Ok(()),
+    }
+  };
+
+  let _ = std::fs::remove_file(&path);
+  login.finish(&*backend).await?;
+  result
+}
+
+#[cfg(not(unix))]
+async fn run_daemon(_: &Cli, _: Duration) -> Result<(), GradesError> {
+  Err(GradesError::Config("daemon needs Unix sockets, which this platform doesn't have".to_owned()))
+}
+
+/// The four and nine point GPA for a table, dashes while there is none
+fn dashed(gpa: Option<&Gpa>) -> (String, String) {
+  gpa.map_or_else(|| ("-".to_owned(), "-".to_owned()), |gpa| (gpa.four.to_string(), gpa.nine.to_string()))
+}
+
+/// Prints every snapshot of the user with its GPA, or the grades of a single snapshot
+fn show_history(args: &Cli, id: Option<i64>) -> Result<(), GradesError> {
+  let username = username(args)?;
+  let history = open_history()?;
+
+  match id {
+    Some(id) => {
+      let snapshot = history.get(&username, id)?
+        .ok_or_else(|| GradesError::Storage(format!("no snapshot with id {}", id)))?;
+
+      let filter = args.filter.filter()?;
+      if filter.is_empty() {
+        print_grades(args, snapshot.summary.gpa.as_ref(), &snapshot.grades, &[])?;
+      } else {
+        let grades = filter.apply(snapshot.grades);
+        print_grades(args, grades_list::calculate_gpa(&grades).as_ref(), &grades, &[])?;
+      }
+    },
+    None => {
+      let snapshots = history.list(&username)?;
+
+      if args.format() == Format::Json {
+        println!("{}", to_json(args, &snapshots));
+      } else {
+        let lang = args.lang;
+        let mut pretty = table!([lang.text(Text::Id), lang.text(Text::FetchedAt), lang.text(Text::Courses), lang.text(Text::FourPoint), lang.text(Text::NinePoint)]);
+
+        for s in &snapshots {
+          let fetched_at = s.fetched_at.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M");
+          let (four, nine) = dashed(s.gpa.as_ref());
+          pretty.add_row(row![ s.id, fetched_at, s.courses, four, nine ]);
+        }
+
+        pretty.printstd();
+      }
+    },
+  }
+
+  Ok(())
+}
+
+/// Loads a snapshot given either as a history id or a path to a file written with `--json`
+fn load_snapshot(history: &History, username: &str, spec: &str) -> Result<(Vec<CourseData>, Option<Gpa>), GradesError> {
+  if let Ok(id) = spec.parse::<i64>() {
+    let snapshot = history.get(username, id)?
+      .ok_or_else(|| GradesError::Storage(format!("no snapshot with id {}", id)))?;
+
+    return Ok((snapshot.grades, snapshot.summary.gpa));
+  }
+
+  let data = std::fs::read(spec)?;
+  let saved: SavedOutput = serde_json::from_slice(&data)
+    .map_err(|e| GradesError::Parse(format!("{} is not a saved --json output: {}", spec, e)))?;
+
+  Ok((saved.grades, saved.gpa))
+}
+
+/// Compares two snapshots, by default the two most recent ones
+fn show_diff(args: &Cli, from: &Option<String>, to: &Option<String>, since: &Option<String>) -> Result<(), GradesError> {
+  let username = username(args)?;
+  let history = open_history()?;
+  let snapshots = history.list(&username)?;
+
+  let (new, new_gpa) = match to {
+    Some(spec) => load_snapshot(&history, &username, spec)?,
+    None => {
+      let latest = snapshots.last().ok_or_else(|| GradesError::Storage("the history is empty".to_owned()))?;
+      load_snapshot(&history, &username, &latest.id.to_string())?
+    },
+  };
+
+  let (old, old_gpa) = match (from, since) {
+    (Some(spec), _) => load_snapshot(&history, &username, spec)?,
+    (None, Some(date)) => {
+      let date = chrono::NaiveDate::parse_from_str(date, "%Y-%m-%d")
+        .map_err(|e| GradesError::Parse(format!("--since must be a YYYY-MM-DD date: {}", e)))?;
+
+      let before = snapshots.iter().rev()
+        .find(|s| s.fetched_at.with_timezone(&chrono::Local).date_naive() < date)
+        .ok_or_else(|| GradesError::Storage(format!("no snapshot from before {}", date)))?;
+      load_snapshot(&history, &username, &before.id.to_string())?
+    },
+    (None, None) => {
+      let previous = snapshots.iter().rev().nth(1)
+        .ok_or_else(|| GradesError::Storage("the history needs at least two snapshots".to_owned()))?;
+      load_snapshot(&history, &username, &previous.id.to_string())?
+    },
+  };
+
+  let report = DiffReport::new(&old, old_gpa, &new, new_gpa);
+
+  if args.format() == Format::Json {
+    println!("{}", to_json(args, &report));
+  } else {
+    let lang = args.lang;
+    println!("{}", lang.text(Text::Gpa));
+    ptable!(
+      ["", lang.text(Text::FourPoint), lang.text(Text::NinePoint)],
+      [lang.text(Text::Before), dashed(report.old_gpa.as_ref()).0, dashed(report.old_gpa.as_ref()).1],
+      [lang.text(Text::After), dashed(report.new_gpa.as_ref()).0, dashed(report.new_gpa.as_ref()).1],
+      [lang.text(Text::Difference), report.delta.as_ref().map_or("-".to_owned(), |d| format!("{:+}", d.four)), report.delta.as_ref().map_or("-".to_owned(), |d| format!("{:+}", d.nine))]
+    );
+
+    println!();
+
+    println!("{}", lang.text(Text::Changes));
+    let mut pretty = table!([lang.text(Text::Change), lang.text(Text::Session), lang.text(Text::Course), lang.text(Text::Title), lang.text(Text::Grade)]);
+
+    for change in &report.changes {
+      let course = change.course();
+      let (kind, grade) = match change {
+        GradeChange::Added { .. } => (lang.text(Text::Added), course.grade.clone()),
+        GradeChange::Changed { old_grade, .. } => (lang.text(Text::Changed), format!("{} -> {}", old_grade, course.grade)),
+        GradeChange::Removed { .. } => (lang.text(Text::Removed), course.grade.clone()),
+      };
+
+      pretty.add_row(row![ kind, course.session, course.course, course.title, grade ]);
+    }
+
+    pretty.printstd();
+  }
+
+  Ok(())
+}
+
+fn load_config() -> Result<Config, GradesError> {
+  match config::default_path() {
+    Some(path) => Config::load(&path),
+    None => Ok(Config::default()),
+  }
+}
+
+/// The key of the grades kept between runs, none unless `encryption` is set in the config
+fn vault() -> Result<Option<Vault>, GradesError> {
+  static VAULT: OnceLock<Option<Vault>> = OnceLock::new();
+  if let Some(vault) = VAULT.get() {
+    return Ok(vault.clone());
+  }
+
+  let vault = match load_config()?.encryption {
+    None => None,
+    Some(Encryption::Keychain) => Some(Vault::from_keychain()?),
+    Some(Encryption::Passphrase) => {
+      let passphrase = match std::env::var("GRADES_LIST_PASSPHRASE") {
+        Ok(passphrase) => passphrase,
+        Err(_) => rpassword::prompt_password_stderr("Passphrase of the saved grades: ")?,
+      };
+      let path = vault::default_passphrase_path()
+        .ok_or_else(|| GradesError::Storage("could not find a data directory".to_owned()))?;
+      Some(Vault::from_passphrase(&passphrase, &path)?)
+    },
+  };
+
+  Ok(VAULT.get_or_init(|| vault).clone())
+}
+
+/// The key saved sessions are encrypted with, the keychain's unless a passphrase is used
+fn session_vault() -> Result<Vault, GradesError> {
+  match vault()? {
+    Some(vault) => Ok(vault),
+    None => Vault::from_keychain(),
+  }
+}
+
+/// GPA over only the courses of the major, when `--major-gpa` was given
+fn major_gpa(args: &Cli, config: &Config, grades: &[CourseData]) -> Result<Option<Gpa>, GradesError> {
+  if !args.list.major_gpa {
+    return Ok(None);
+  }
+
+  if config.major.is_empty() {
+    return Err(GradesError::Config("--major-gpa needs `major = [\"EECS\", ...]` in the config file".to_owned()));
+  }
+
+  let major = grades.iter().filter(|c| filter::matches_prefixes(c, &config.major)).cloned().collect::<Vec<_>>();
+  Ok(grades_list::calculate_gpa(&major))
+}
+
+fn print_grades(args: &Cli, gpa: Option<&Gpa>, table_content: &[CourseData], skipped: &[ParseWarning]) -> Result<(), GradesError> {
+  let format = args.format();
+  if args.machine && !matches!(format, Format::Json | Format::Ndjson | Format::Csv | Format::Yaml | Format::Toml) {
+    return Err(GradesError::Config(format!("--machine writes json, ndjson, csv, yaml or toml, not {}", format.name())));
+  }
+  let precision = args.precision()?;
+  let precise = |gpa: Gpa| match precision {
+    Some(precision) => precision.gpa(&gpa),
+    None => gpa,
+  };
+  let gpa = gpa.cloned().map(precise);
+  let gpa = gpa.as_ref();
+  let config = load_config()?;
+
+  let redactor = redactor(args, &[])?;
+  let mut grades = match redactor {
+    Some(ref redactor) => redactor.courses(table_content.to_vec()),
+    None => table_content.to_vec(),
+  };
+  // --sort is stable, so it still orders courses that tie by the machine order
+  if args.machine {
+    grades = snapshot::sorted(&grades);
+  }
+  match args.list.sort {
+    Some(key) => sort::sort(&mut grades, key, args.list.descending),
+    // oldest session first, the courses of a session stay in the course list order
+    None => sort::sort(&mut grades, SortKey::Session, false),
+  }
+  // the running GPA only reads top to bottom in session order, --sort still orders each session
+  if args.list.cumulative {
+    sort::sort(&mut grades, SortKey::Session, false);
+  }
+  let table_content = &grades;
+
+  let major_gpa = major_gpa(args, &config, table_content)?.map(precise);
+  // the custom scales are only checked when one is asked for
+  let scales;
+  let scale_gpa = match args.list.gpa_scale {
+    Some(ref name) => {
+      scales = config.scale_registry()?;
+      let scale = scales.get(name)?;
+      let value = scale.gpa(table_content);
+      Some(ScaledGpa { scale: &scale.name, gpa: value.map(|v| precision.map_or(v, |p| p.apply(v))) })
+    },
+    None => None,
+  };
+  let cumulative = if args.list.cumulative {
+    let mut cumulative = sort::cumulative(table_content, args.count_all_attempts);
+    cumulative.iter_mut().for_each(|c| c.gpa = c.gpa.take().map(precise));
+    Some(cumulative)
+  } else {
+    None
+  };
+  let last_credits = match args.list.last_credits {
+    Some(credits) => {
+      let mut last = last_credits_gpa(table_content, credits)?;
+      last.gpa = last.gpa.take().map(precise);
+      Some(last)
+    },
+    None => None,
+  };
+  let standing = if args.list.standing { Some(standing::report(gpa)) } else { None };
+  let mut warnings = skipped.iter().map(|w| w.to_string()).collect::<Vec<_>>();
+  warnings.extend(grades_list::credit_warnings(table_content));
+  if let Some(ref redactor) = redactor {
+    warnings = warnings.iter().map(|w| redactor.text(w)).collect();
+  }
+  let excluded = classify::excluded(table_content);
+  let summary = classify::summarize(&excluded);
+  let credits = classify::credit_summary(table_content);
+  let in_progress = projection::in_progress(table_content);
+  let projection = if args.list.projection {
+    let mut projection = projection::project(table_content, &config.expected, args.count_all_attempts)?;
+    for gpa in [&mut projection.current, &mut projection.best, &mut projection.expected] {
+      *gpa = gpa.take().map(precise);
+    }
+    Some(projection)
+  } else {
+    None
+  };
+
+  let mut out = match args.output {
+    Some(_) => Sink::Buffer(Vec::new()),
+    None => Sink::Stdout,
+  };
+
+  match format {
+    format @ (Format::Json | Format::Yaml | Format::Toml) => {
+      let output = Output {
+        version: OUTPUT_VERSION,
+        fetched_at: if args.machine { None } else { Some(chrono::Utc::now()) },
+        tool_version: env!("CARGO_PKG_VERSION"),
+        gpa,
+        major_gpa: major_gpa.as_ref(),
+        scale_gpa,
+        last_credits,
+        grades: table_content.iter().map(GradeOutput::new).collect(),
+        not_in_gpa: &excluded,
+        not_in_gpa_summary: &summary,
+        credits: &credits,
+        in_progress: in_progress.clone(),
+        projection: projection.clone(),
+        standing,
+        cumulative,
+        // already in the JSON, not repeated on stderr
+        warnings: std::mem::take(&mut warnings),
+      };
+
+      write_document(&mut out, args, format, &output)?;
+    },
+    Format::Csv => {
+      let (gpa_row, credits_rows) = if args.list.gpa_row { (gpa, Some(&credits)) } else { (None, None) };
+      write!(out, "{}", format::csv(table_content, gpa_row, credits_rows)?)?;
+
+      if let Some(ref path) = args.list.gpa_file {
+        write_output(args, path, format::gpa_csv(gpa)?.as_bytes())?;
+      }
+    },
+    Format::Ndjson if args.machine => {
+      for line in format::ndjson(table_content, gpa).lines() {
+        let mut record = serde_json::from_str(line).expect("ndjson lines are JSON");
+        format::round_numbers(&mut record, format::MACHINE_DECIMALS);
+        writeln!(out, "{}", record)?;
+      }
+    },
+    Format::Ndjson => write!(out, "{}", format::ndjson(table_content, gpa))?,
+    Format::Markdown => write!(out, "{}", format::markdown(table_content, gpa))?,
+    Format::Html => write!(out, "{}", format::html(table_content, gpa))?,
+    Format::Ics => return Err(GradesError::Config("--format ics is only for schedule and dates".to_owned())),
+    format @ (Format::Xlsx | Format::Pdf) => {
+      if args.output.is_none() && atty::is(atty::Stream::Stdout) {
+        return Err(GradesError::Config(format!("--format {} needs --output or stdout redirected to a file", format.name())));
+      }
+      let data = match format {
+        Format::Xlsx => xlsx::xlsx(table_content, gpa)?,
+        _ => pdf::transcript(table_content, gpa, &pdf::Student::default())?,
+      };
+      out.write_all(&data)?;
+    },
+    Format::Table => {
+      let mut other_gpas = Vec::new();
+      if let Some(ref major) = major_gpa {
+        other_gpas.push(("Major".to_owned(), major));
+      }
+      if let Some(LastCreditsGpa { credits, gpa: Some(ref recent), .. }) = last_credits {
+        other_gpas.push((format!("Last {} credits", credits), recent));
+      }
+
+      print_table(&mut out, args, gpa, &other_gpas, scale_gpa.as_ref(), cumulative.as_deref(), table_content)?;
+      print_excluded(&mut out, args.lang, &excluded, &summary)?;
+      print_credits(&mut out, args.lang, &credits)?;
+      print_in_progress(&mut out, args, &in_progress, projection.as_ref())?;
+
+      if let Some(ref standing) = standing {
+        print_standing(&mut out, args.lang, standing)?;
+      }
+
+      if args.list.chart {
+        writeln!(out)?;
+        writeln!(out, "{}", args.lang.text(Text::GpaPerSession))?;
+        write!(out, "{}", chart::bars(&chart::session_gpas(table_content)))?;
+      }
+    },
+  }
+
+  if let (Some(ref path), Sink::Buffer(data)) = (&args.output, out) {
+    write_output(args, path, &data)?;
+  }
+
+  if let Some(ref path) = args.list.chart_svg {
+    chart::svg(&chart::session_gpas(table_content), path)?;
+  }
+
+  // kept off stdout so the other formats stay machine readable
+  for message in &warnings {
+    warning(message);
+  }
+  warn_skipped(skipped, false);
+
+  Ok(())
+}
+
+/// The key of the `--redact` placeholders, kept so this install always gives the same ones
+fn redaction_secret() -> Result<redact::Secret, GradesError> {
+  let path = redact::default_secret_path()
+    .ok_or_else(|| GradesError::Storage("could not find a data directory".to_owned()))?;
+  redact::load_secret(&path)
+}
+
+/// What `--redact` takes out of the output, the username along with the other identifiers given
+fn redactor(args: &Cli, also: &[&Option<String>]) -> Result<Option<Redactor>, GradesError> {
+  if !args.redact {
+    return Ok(None);
+  }
+  let identifiers = also.iter().filter_map(|identifier| (*identifier).clone()).chain(username(args).ok());
+  Ok(Some(Redactor::new(redaction_secret()?, identifiers, args.redact_sections)))
+}
+
+/// Whether the table can be colored, see https://no-color.org for `NO_COLOR`
+fn use_color(args: &Cli) -> bool {
+  !args.no_color && args.output.is_none() && std::env::var_os("NO_COLOR").is_none_or(|v| v.is_empty()) && atty::is(atty::Stream::Stdout)
+}
+
+fn grade_row(course: &CourseData, lang: Lang, color: bool) -> Row {
+  let grade = Cell::new(&format::display_grade_in(course, lang));
+  let grade = match format::grade_style(&course.grade) {
+    Some(style) if color => grade.style_spec(style),
+    _ => grade,
+  };
+
+  let points = course.points();
+  let value = |v: Option<f32>| Cell::new(&v.map(|v| format!("{:.2}", v)).unwrap_or_default()).style_spec("r");
+
+  Row::new(vec![
+    Cell::new(&course.session), Cell::new(&course.course), Cell::new(&course.title), grade,
+    value(points.as_ref().map(|p| p.nine_point)),
+    value(points.as_ref().map(|p| p.four_point)),
+    value(points.as_ref().and_then(|p| p.nine_point_weighted)),
+    value(points.as_ref().and_then(|p| p.four_point_weighted)),
+  ])
+}
+
+/// GPA of the sessions making up the last `credits` counted credits
+fn last_credits_gpa(grades: &[CourseData], credits: f32) -> Result<LastCreditsGpa, GradesError> {
+  let recent = sort::last_credits(grades, credits);
+  let counted = grades_list::gpa_credits(&recent);
+
+  Ok(LastCreditsGpa {
+    credits: counted,
+    since: recent.first().map(|c| c.session.clone()),
+    gpa: grades_list::calculate_gpa(&recent),
+  })
+}
+
+/// Prints the GPA, the rows of `other_gpas` under it, and the grades
+fn print_table(out: &mut Sink, args: &Cli, gpa: Option<&Gpa>, other_gpas: &[(String, &Gpa)], scale_gpa: Option<&ScaledGpa>, cumulative: Option<&[CumulativeGpa]>, table_content: &[CourseData]) -> Result<(), GradesError> {
+  let color = use_color(args);
+  let lang = args.lang;
+  let precision = args.precision()?;
+  // every decimal unless --precision, or two where the table always had two
+  let show = |value: f32| precision.map_or_else(|| value.to_string(), |p| p.show(value));
+  let fixed = |value: f32| precision.map_or_else(|| format!("{:.2}", value), |p| p.show(value));
+  // a dash until a course counts towards the GPA
+  let (four, nine) = gpa.map_or_else(|| ("-".to_owned(), "-".to_owned()), |gpa| (show(gpa.four), show(gpa.nine)));
+
+  writeln!(out, "{}", lang.text(Text::Gpa))?;
+  if other_gpas.is_empty() {
+    out.table(&table!([lang.text(Text::FourPoint), lang.text(Text::NinePoint)], [ four, nine ]))?;
+  } else {
+    let mut pretty = table!(["", lang.text(Text::FourPoint), lang.text(Text::NinePoint)], [lang.text(Text::Overall), four, nine]);
+    for (name, other) in other_gpas {
+      pretty.add_row(row![ name, show(other.four), show(other.nine) ]);
+    }
+    out.table(&pretty)?;
+  }
+
+  if let Some(scaled) = scale_gpa {
+    match scaled.gpa {
+      Some(value) => writeln!(out, "{}", lang.format(Text::OnScale, &[&scaled.scale, &fixed(value)]))?,
+      None => writeln!(out, "{}", lang.format(Text::OnScaleUngraded, &[&scaled.scale]))?,
+    }
+  }
+
+  writeln!(out)?;
+
+  writeln!(out, "{}", lang.text(Text::Grades))?;
+  let headings = [Text::Session, Text::Course, Text::Title, Text::Grade, Text::Nine, Text::Four, Text::NineCredits, Text::FourCredits];
+  let mut pretty = Table::init(vec![Row::new(headings.iter().map(|&text| Cell::new(lang.text(text))).collect())]);
+  let letters = |c: &&CourseData| classify::GradeKind::of(&c.grade) == classify::GradeKind::Letter;
+
+  // the running GPA goes on the last row of each session, blank cells keep the others aligned
+  let after = |session: &str| cumulative
+    .and_then(|all| all.iter().find(|c| c.session == session))
+    .and_then(|c| c.gpa.as_ref())
+    .map(|gpa| format!("{} / {}", fixed(gpa.nine), fixed(gpa.four)))
+    .unwrap_or_default();
+  let with_cumulative = |mut row: Row, value: String| {
+    if cumulative.is_some() {
+      row.add_cell(Cell::new(&value).style_spec("r"));
+    }
+    row
+  };
+  if cumulative.is_some() {
+    pretty.get_mut_row(0).unwrap().add_cell(Cell::new(lang.text(Text::Cumulative)));
+  }
+
+  if args.list.group_by.is_some() {
+    for (session, courses) in sort::by_session(table_content) {
+      let graded = courses.iter().filter(letters).collect::<Vec<_>>();
+      if graded.is_empty() {
+        continue;
+      }
+
+      // sessions with only superseded attempts have no GPA of their own
+      let subtotal = grades_list::calculate_gpa(&courses);
+
+      for (i, c) in graded.iter().enumerate() {
+        let last = subtotal.is_none() && i + 1 == graded.len();
+        pretty.add_row(with_cumulative(grade_row(c, lang, color), if last { after(&session) } else { String::new() }));
+      }
+
+      if let Some(subtotal) = subtotal {
+        let row = row![ "", "", lang.format(Text::SessionGpa, &[&session]), "", r->fixed(subtotal.nine), r->fixed(subtotal.four), "", "" ];
+        pretty.add_row(with_cumulative(row, after(&session)));
+      }
+    }
+  } else {
+    let graded = table_content.iter().filter(letters).collect::<Vec<_>>();
+
+    for (i, c) in graded.iter().enumerate() {
+      let last = graded.get(i + 1).is_none_or(|next| next.session != c.session);
+      pretty.add_row(with_cumulative(grade_row(c, lang, color), if last { after(&c.session) } else { String::new() }));
+    }
+  }
+
+  out.table(&pretty)?;
+  Ok(())
+}
+
+/// Lists the courses whose grade keeps them out of the GPA, with withdrawn and pass/fail totals
+fn print_excluded(out: &mut Sink, lang: Lang, excluded: &[Excluded], summary: &ExcludedSummary) -> io::Result<()> {
+  if excluded.is_empty() {
+    return Ok(());
+  }
+
+  writeln!(out)?;
+
+  writeln!(out, "{}", lang.text(Text::NotInGpa))?;
+  let headings = [Text::Session, Text::Course, Text::Title, Text::Grade, Text::Reason];
+  let mut pretty = Table::init(vec![Row::new(headings.iter().map(|&text| Cell::new(lang.text(text))).collect())]);
+
+  for e in excluded {
+    pretty.add_row(row![ e.course.session, e.course.course, e.course.title, e.course.grade, lang.grade_kind(e.kind) ]);
+  }
+
+  out.table(&pretty)?;
+
+  writeln!(
+    out,
+    "{}",
+    lang.format(Text::ExcludedSummary, &[&summary.withdrawn, &summary.withdrawn_credits, &summary.pass_fail, &summary.pass_fail_credits]),
+  )
+}
+
+fn print_credits(out: &mut Sink, lang: Lang, credits: &CreditSummary) -> io::Result<()> {
+  writeln!(out)?;
+
+  writeln!(out, "{}", lang.text(Text::Credits))?;
+  let headings = [Text::Attempted, Text::Earned, Text::InGpa, Text::ExcludedCredits];
+  let mut pretty = Table::init(vec![Row::new(headings.iter().map(|&text| Cell::new(lang.text(text))).collect())]);
+  pretty.add_row(row![ r->credits.attempted, r->credits.earned, r->credits.in_gpa, r->credits.excluded ]);
+  out.table(&pretty)
+}
+
+/// Lists the courses with no grade yet, with the GPA they could lead to under `--projection`
+fn print_in_progress(out: &mut Sink, args: &Cli, in_progress: &[&CourseData], projection: Option<&Projection>) -> Result<(), GradesError> {
+  let lang = args.lang;
+  let precision = args.precision()?;
+  let fixed = |gpa: &Option<Gpa>| match gpa {
+    Some(gpa) => precision.map_or_else(|| format!("{:.2} / {:.2}", gpa.nine, gpa.four), |p| format!("{} / {}", p.show(gpa.nine), p.show(gpa.four))),
+    None => "-".to_owned(),
+  };
+
+  if !in_progress.is_empty() {
+    writeln!(out)?;
+    writeln!(out, "{}", lang.text(Text::InProgress))?;
+    let mut headings = vec![Text::Session, Text::Course, Text::Title];
+    // the grade expected in each course is only read with --projection
+    let expected = match projection {
+      Some(_) => {
+        headings.push(Text::Expected);
+        Some(load_config()?.expected)
+      },
+      None => None,
+    };
+    let mut pretty = Table::init(vec![Row::new(headings.iter().map(|&text| Cell::new(lang.text(text))).collect())]);
+
+    for c in in_progress {
+      let mut row = row![ c.session, c.course, c.title ];
+      if let Some(ref expected) = expected {
+        row.add_cell(Cell::new(&projection::expected_grade(c, expected)?.unwrap_or_default()));
+      }
+      pretty.add_row(row);
+    }
+    out.table(&pretty)?;
+  }
+
+  if let Some(projection) = projection {
+    writeln!(out)?;
+    writeln!(out, "{}", lang.text(Text::Projected))?;
+    let mut pretty = table!(["", lang.text(Text::NineFour)]);
+    pretty.add_row(row![ lang.text(Text::Now), r->fixed(&projection.current) ]);
+    pretty.add_row(row![ lang.text(Text::Expected), r->fixed(&projection.expected) ]);
+    pretty.add_row(row![ lang.text(Text::BestCase), r->fixed(&projection.best) ]);
+    out.table(&pretty)?;
+
+    if !projection.without_expected.is_empty() {
+      writeln!(out, "{}", lang.format(Text::WithoutExpected, &[&projection.without_expected.join(", ")]))?;
+    }
+  }
+
+  Ok(())
+}
+
+fn print_standing(out: &mut Sink, lang: Lang, report: &StandingReport) -> io::Result<()> {
+  let points = |value: f32| format!("{:.2}", value);
+
+  writeln!(out)?;
+  match report.gpa {
+    Some(gpa) => writeln!(out, "{}", lang.format(Text::Standing, &[&lang.standing(report.standing), &points(gpa)]))?,
+    None => writeln!(out, "{}", lang.format(Text::NoStanding, &[&lang.standing(report.standing)]))?,
+  }
+
+  if let Some(ref next) = report.next {
+    writeln!(out, "{}", lang.format(Text::ToNextStanding, &[&points(next.needed), &lang.standing(next.standing), &points(next.gpa)]))?;
+  }
+  if let Some(margin) = report.margin {
+    writeln!(out, "{}", lang.format(Text::AboveStanding, &[&points(margin), &lang.standing(report.standing)]))?;
+  }
+
+  Ok(())
+}
+
+/// Where the grades are printed, stdout unless `--output` was given
+enum Sink {
+  Stdout,
+  Buffer(Vec<u8>),
+}
+
+impl Sink {
+  /// Prints a table, keeping its colors on stdout
+  fn table(&mut self, table: &Table) -> io::Result<()> {
+    match self {
+      Sink::Stdout => {
+        table.printstd();
+        Ok(())
+      },
+      Sink::Buffer(data) => table.print(data).map(|_| ()),
+    }
+  }
+}
+
+impl Write for Sink {
+  fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+    match self {
+      Sink::Stdout => io::stdout().write(buf),
+      Sink::Buffer(data) => data.write(buf),
+    }
+  }
+
+  fn flush(&mut self) -> io::Result<()> {
+    match self {
+      Sink::Stdout => io::stdout().flush(),
+      Sink::Buffer(_) => Ok(()),
+    }
+  }
+}
+
+/// Writes a file of grades with `files::write_atomically()`, encrypted first with `export --encrypt-to`
+/// so not even the temporary file is readable
+fn write_output(args: &Cli, path: &Path, data: &[u8]) -> Result<(), GradesError> {
+  match args.cmd {
+    Some(Command::Export { ref encrypt_to, .. }) if !encrypt_to.is_empty() => files::write_atomically(path, &encrypt::encrypt(data, encrypt_to)?),
+    _ => files::write_atomically(path, data),
+  }
+}
+
+/// Writes the output as JSON, YAML or TOML, with the keys sorted and the numbers rounded with
+/// `--machine`
+fn write_document<T: Serialize>(out: &mut Sink, args: &Cli, format: Format, document: &T) -> Result<(), GradesError> {
+  if args.machine {
+    // without serde_json's preserve_order a `Value` keeps its keys sorted
+    let mut value = serde_json::to_value(document).expect("the output serializes");
+    format::round_numbers(&mut value, format::MACHINE_DECIMALS);
+    if format == Format::Toml {
+      format::drop_nulls(&mut value);
+    }
+    return write_document_as(out, args, format, &value);
+  }
+  write_document_as(out, args, format, document)
+}
+
+fn write_document_as<T: Serialize>(out: &mut Sink, args: &Cli, format: Format, document: &T) -> Result<(), GradesError> {
+  match format {
+    Format::Yaml => write!(out, "{}", format::yaml(document)?)?,
+    Format::Toml => write!(out, "{}", format::toml(document)?)?,
+    _ => writeln!(out, "{}", to_json(args, document))?,
+  }
+  Ok(())
+}
+
+/// Serializes for printing, indented with `--pretty`
+fn to_json<T: Serialize>(args: &Cli, value: &T) -> String {
+  if args.pretty {
+    serde_json::to_string_pretty(value).unwrap()
+  } else {
+    serde_json::to_string(value).unwrap()
+  }
+}
+
+/// Reads the course list page given with `--from-file`
+fn read_page(path: &Path) -> Result<String, GradesError> {
+  if path.as_os_str() == "-" {
+    let mut page = String::new();
+    std::io::stdin().read_to_string(&mut page)?;
+    return Ok(page);
+  }
+
+  Ok(std::fs::read_to_string(path)?)
+}
+
+/// The courses entered by hand in `[[extra_courses]]` and `extra_courses.csv`, the `[overrides]`
+/// and the non-degree rows to leave out unless `--include-non-degree`
+fn local_changes(args: &Cli) -> Result<LocalChanges, GradesError> {
+  let mut changes = load_config()?.local_changes()?;
+  changes.include_non_degree = args.include_non_degree;
+  Ok(changes)
+}
+
+/// Reads the course list with the selectors from the config, in the `--strict` or lenient mode
+fn parse_page(args: &Cli, page: &str) -> Result<Parsed, GradesError> {
+  let mode = if args.strict { ParseMode::Strict } else { ParseMode::Lenient };
+  grades_list::parse_courses_with(page, &load_config()?.selectors, mode)
+}
+
+/// Fetches the grades like `fetch_parsed()`, reporting the rows that were left out on stderr
+async fn fetch(args: &Cli) -> Result<(Option<Login>, Vec<CourseData>), GradesError> {
+  let (login, grades, skipped) = fetch_parsed(args).await?;
+  warn_skipped(&skipped, true);
+  Ok((login, grades))
+}
+
+/// Prints a summary of the course list rows that couldn't be read, with each of them unless
+/// they were already printed
+fn warn_skipped(skipped: &[ParseWarning], each: bool) {
+  if skipped.is_empty() {
+    return;
+  }
+  if each {
+    for skipped in skipped {
+      warning(skipped);
+    }
+  }
+  let lang = LANG.get().copied().unwrap_or_default();
+  warning(lang.format(Text::RowsSkipped, &[&skipped.len(), &if skipped.len() == 1 { "" } else { "s" }]));
+}
+
+/// The grades from `--from-file`, the cache or York with the local changes applied, along with
+/// the course list rows that were left out
+async fn fetch_parsed(args: &Cli) -> Result<(Option<Login>, Vec<CourseData>, Vec<ParseWarning>), GradesError> {
+  let (login, parsed) = match args.from_file {
+    Some(_) if args.source == Source::Merged => {
+      return Err(GradesError::Config("--source merged needs both pages, it can't read a saved one".to_owned()));
+    },
+    Some(ref path) => (None, parse_page(args, &read_page(path)?)?),
+    None => {
+      // a replayed scrape isn't York's current one
+      let cache_path = if args.cache.no_cache || args.network.replay.is_some() { None } else { cache::default_path(&username(args)?, args.source) };
+
+      // a cached scrape was already recorded in the history when it was fetched
+      match (&cache_path, args.cache.max_age()) {
+        (Some(path), Some(max_age)) => match cache::load(path, vault()?.as_ref())? {
+          Some(cached) if cached.is_fresh(max_age) => (None, Parsed { grades: cached.grades, warnings: Vec::new() }),
+          _ => scrape(args, &cache_path).await?,
+        },
+        _ => scrape(args, &cache_path).await?,
+      }
+    },
+  };
+  let mut grades = local_changes(args)?.apply(parsed.grades);
+
+  if args.count_all_attempts {
+    grades.iter_mut().for_each(|c| c.superseded = false);
+  }
+
+  Ok((login, grades, parsed.warnings))
+}
+
+/// Reads pages of SIS through the user's `daemon` when one is running, or else by logging in (or
+/// picking up the saved session) and finishing the login again
+async fn fetch_pages(args: &Cli, pages: &[Page]) -> Result<(Login, Vec<String>), GradesError> {
+  let mut login = Login::new(args)?;
+  let backend = args.network.backend()?;
+  let fetched = read_pages(args, &mut login, &*backend, pages).await?;
+  Ok((login, fetched))
+}
+
+/// Reads the pages like `fetch_pages()` with the login and backend already made
+async fn read_pages(args: &Cli, login: &mut Login, backend: &dyn SisBackend, pages: &[Page]) -> Result<Vec<String>, GradesError> {
+  let client = sis_pages(backend)?;
+  if let Some(fetched) = from_daemon(args, &login.username, pages).await? {
+    return Ok(fetched);
+  }
+
+  login.connect(backend).await?;
+  let fetched = daemon::fetch_all(client, pages).await;
+  login.finish(backend).await?;
+  fetched
+}
+
+/// The pages from the user's running `daemon`, `None` when there is none to ask
+#[cfg(unix)]
+async fn from_daemon(args: &Cli, username: &str, pages: &[Page]) -> Result<Option<Vec<String>>, GradesError> {
+  // a cassette has to see the requests itself
+  if args.network.no_daemon || args.network.uses_cassette() {
+    return Ok(None);
+  }
+
+  match daemon::default_path(username) {
+    Some(path) => daemon::request(&path, pages).await,
+    None => Ok(None),
+  }
+}
+
+#[cfg(not(unix))]
+async fn from_daemon(_: &Cli, _: &str, _: &[Page]) -> Result<Option<Vec<String>>, GradesError> {
+  Ok(None)
+}
+
+/// The pages the grades are read from with `--source`, the course list coming before the transcript
+fn source_pages(source: Source) -> Vec<Page> {
+  match source {
+    Source::CourseList => vec![Page::CourseList],
+    Source::Transcript => vec![Page::Transcript],
+    Source::Merged => vec![Page::CourseList, Page::Transcript],
+  }
+}
+
+/// Reads the grades from the pages of `source_pages()`, adding the grade points and notes of the
+/// transcript to the course list with `--source merged`
+fn parse_source(args: &Cli, pages: &[String]) -> Result<Parsed, GradesError> {
+  let mut parsed = parse_page(args, &pages[0])?;
+  if let Some(transcript_page) = pages.get(1) {
+    let transcript = parse_page(args, transcript_page)?;
+    parsed.grades = transcript::merge(parsed.grades, transcript.grades);
+    parsed.warnings.extend(transcript.warnings);
+  }
+  Ok(parsed)
+}
+
+/// Logs in and parses the course list, caching the courses for `--cached`
+async fn scrape(args: &Cli, cache_path: &Option<PathBuf>) -> Result<(Option<Login>, Parsed), GradesError> {
+  let mut login = Login::new(args)?;
+  let backend = args.network.backend()?;
+
+  let parsed = if backend.york().is_none() && args.source == Source::CourseList && args.save_html.is_none() {
+    // another school's courses come from its backend, without pages to read them from
+    login.connect(&*backend).await?;
+    let grades = backend.fetch_grades().await;
+    login.finish(&*backend).await?;
+    Parsed { grades: grades?, warnings: Vec::new() }
+  } else {
+    let pages = read_pages(args, &mut login, &*backend, &source_pages(args.source)).await?;
+
+    // saved before parsing, so a page that fails to parse can still be attached to a bug report
+    if let Some(ref path) = args.save_html {
+      let page = match redactor(args, &[])? {
+        Some(redactor) => redactor.page(&pages[0]),
+        None => sanitize::strip_session(&pages[0]),
+      };
+      std::fs::write(path, page)?;
+    }
+
+    parse_source(args, &pages)?
+  };
+
+  if let Some(ref path) = cache_path {
+    cache::save(path, &parsed.grades, vault()?.as_ref())?;
+  }
+
+  Ok((Some(login), parsed))
+}
+
+/// How to log in to the account of every profile for `--all-profiles`, by profile name
+///
+/// The passwords are all asked for up front, one account at a time, since the logins happen at
+/// the same time.
+fn profile_logins(args: &Cli, config: &Config) -> Result<BTreeMap<String, Login>, GradesError> {
+  if config.profiles.is_empty() {
+    return Err(GradesError::Config("--all-profiles needs [profiles.<name>] sections in the config".to_owned()));
+  }
+  if args.username.is_some() || args.password.is_some() {
+    return Err(GradesError::Config("--all-profiles logs in with the username of each profile, it can't be given too".to_owned()));
+  }
+  if args.from_file.is_some() || args.network.uses_cassette() {
+    return Err(GradesError::Config("--all-profiles fetches from York, it can't read a saved page or cassette".to_owned()));
+  }
+  if args.mfa.totp.is_some() || args.mfa.mfa == "totp" {
+    return Err(GradesError::Config("--all-profiles answers Duo with pushes, a passcode only works for one account".to_owned()));
+  }
+
+  let netrc = netrc_entry(args)?;
+  let mut logins = BTreeMap::new();
+  for (name, profile) in &config.profiles {
+    let username = profile.username.clone()
+      .ok_or_else(|| GradesError::Config(format!("profile \"{}\" has no username", name)))?;
+    let password = match netrc.as_ref().filter(|entry| entry.login.as_deref() == Some(username.as_str())).and_then(|entry| entry.password.clone()) {
+      Some(password) => password,
+      None => match saved_password(&username) {
+        Some(password) => password,
+        // piped in, a line for each profile missing one, in the order of the profile names
+        None => read_password(&format!("York Password for {}: ", username))?,
+      },
+    };
+    let session_path = if args.no_persist { None } else { session::default_path(&username) };
+
+    logins.insert(name.clone(), Login { username, password: Some(password), mfa: args.mfa.clone(), session_path });
+  }
+
+  Ok(logins)
+}
+
+/// Fetches the grades of every profile, `--parallel` accounts at a time, and prints them by
+/// profile, failing with the first error only once every account is reported
+async fn fetch_all_profiles(args: &Cli, filter: &CourseFilter) -> Result<(), GradesError> {
+  let format = args.format();
+  if !matches!(format, Format::Table | Format::Json | Format::Yaml | Format::Toml) {
+    return Err(GradesError::Config(format!("--all-profiles prints a table, JSON, YAML or TOML, not {}", format.name())));
+  }
+  let parallel = args.list.parallel.unwrap_or(DEFAULT_PARALLEL);
+  if parallel == 0 {
+    return Err(GradesError::Config("--parallel has to be at least 1".to_owned()));
+  }
+
+  let precision = args.precision()?;
+  // the courses and overrides of the config belong to one account, like with serve --multi-user
+  let changes = LocalChanges { include_non_degree: args.include_non_degree, ..Default::default() };
+
+  let permits = Arc::new(tokio::sync::Semaphore::new(parallel));
+  let mut tasks = tokio::task::JoinSet::new();
+  for (name, mut login) in profile_logins(args, &load_config()?)? {
+    let backend = args.network.backend()?;
+    let permits = permits.clone();
+    let wanted = source_pages(args.source);
+
+    tasks.spawn(async move {
+      let _permit = permits.acquire_owned().await.expect("the semaphore is never closed");
+      let pages = match sis_pages(&*backend) {
+        Ok(client) => match login.connect(&*backend).await {
+          Ok(()) => {
+            let pages = daemon::fetch_all(client, &wanted).await;
+            login.finish(&*backend).await.and(pages)
+          },
+          Err(e) => Err(e),
+        },
+        Err(e) => Err(e),
+      };
+      (name, login.username, pages)
+    });
+  }
+
+  let mut history = open_history()?;
+  let mut results = BTreeMap::new();
+  while let Some(joined) = tasks.join_next().await {
+    let (name, username, pages) = joined.expect("fetching a profile panicked");
+
+    // the history keeps everything, only what is shown is filtered, like a run for one account
+    let fetched = pages.and_then(|pages| {
+      let parsed = parse_source(args, &pages)?;
+      for skipped in &parsed.warnings {
+        warning(format!("{}: {}", name, skipped));
+      }
+      let mut grades = changes.apply(parsed.grades);
+      if args.count_all_attempts {
+        grades.iter_mut().for_each(|c| c.superseded = false);
+      }
+      sort::sort(&mut grades, SortKey::Session, false);
+      let gpa = grades_list::calculate_gpa(&grades);
+      history.record(&username, &grades, gpa.as_ref())?;
+
+      if filter.is_empty() {
+        return Ok((grades, gpa));
+      }
+      let grades = filter.apply(grades);
+      let gpa = grades_list::calculate_gpa(&grades);
+      Ok((grades, gpa))
+    });
+    let fetched = fetched.map(|(mut grades, gpa)| {
+      let gpa = precision.map_or(gpa.clone(), |p| gpa.as_ref().map(|gpa| p.gpa(gpa)));
+      if args.machine {
+        grades = snapshot::sorted(&grades);
+      }
+      if let Some(key) = args.list.sort {
+        sort::sort(&mut grades, key, args.list.descending);
+      }
+      (grades, gpa)
+    });
+
+    // each account is redacted with its own username, which is itself replaced by its hash
+    let (username, fetched) = if args.redact {
+      let redactor = Redactor::new(redaction_secret()?, Some(username.clone()), args.redact_sections);
+      (redactor.hash(&username), fetched.map(|(grades, gpa)| (redactor.courses(grades), gpa)))
+    } else {
+      (username, fetched)
+    };
+    results.insert(name, (username, fetched));
+  }
+
+  let mut out = match args.output {
+    Some(_) => Sink::Buffer(Vec::new()),
+    None => Sink::Stdout,
+  };
+
+  if format == Format::Table {
+    for (i, (name, (username, fetched))) in results.iter().enumerate() {
+      if i > 0 {
+        writeln!(out)?;
+      }
+      writeln!(out, "{} ({})", name, username)?;
+      match fetched {
+        Ok((grades, gpa)) => {
+          print_table(&mut out, args, gpa.as_ref(), &[], None, None, grades)?;
+          print_credits(&mut out, args.lang, &classify::credit_summary(grades))?;
+        },
+        Err(e) => writeln!(out, "{}", e)?,
+      }
+    }
+  } else {
+    let output = AllProfilesOutput {
+      version: OUTPUT_VERSION,
+      fetched_at: if args.machine { None } else { Some(chrono::Utc::now()) },
+      tool_version: env!("CARGO_PKG_VERSION"),
+      profiles: results.iter().map(|(name, (username, fetched))| (name.as_str(), match fetched {
+        Ok((grades, gpa)) => ProfileOutput {
+          username,
+          gpa: gpa.as_ref(),
+          credits: Some(classify::credit_summary(grades)),
+          grades: grades.iter().map(GradeOutput::new).collect(),
+          error: None,
+        },
+        Err(e) => ProfileOutput { username, gpa: None, credits: None, grades: Vec::new(), error: Some(e.to_string()) },
+      })).collect(),
+    };
+
+    write_document(&mut out, args, format, &output)?;
+  }
+
+  if let (Some(ref path), Sink::Buffer(data)) = (&args.output, out) {
+    files::write_atomically(path, &data)?;
+  }
+
+  match results.into_values().find_map(|(_, fetched)| fetched.err()) {
+    Some(e) => Err(e),
+    None => Ok(()),
+  }
+}
+
+/// Prints the current GPA next to the GPA with the hypothetical courses added
+async fn what_if(args: &Cli, add: &[String]) -> Result<(), GradesError> {
+  let hypothetical = add.iter().map(|spec| whatif::hypothetical(spec)).collect::<Result<Vec<_>, _>>()?;
+
+  let (_, grades) = fetch(args).await?;
+  let current = grades_list::calculate_gpa(&grades);
+
+  // a hypothetical retake replaces the real attempt, like it would on the transcript
+  let mut combined = grades;
+  combined.extend(hypothetical.iter().cloned());
+  if !args.count_all_attempts {
+    repeats::mark_repeats(&mut combined);
+  }
+  let projected = grades_list::calculate_gpa(&combined);
+
+  if args.format() == Format::Json {
+    let output = WhatIfOutput { current: current.as_ref(), projected: projected.as_ref(), hypothetical: &hypothetical };
+    println!("{}", to_json(args, &output));
+  } else {
+    let lang = args.lang;
+    println!("{}", lang.text(Text::Gpa));
+    ptable!(
+      ["", lang.text(Text::FourPoint), lang.text(Text::NinePoint)],
+      [lang.text(Text::Current), dashed(current.as_ref()).0, dashed(current.as_ref()).1],
+      [lang.text(Text::ProjectedHeading), dashed(projected.as_ref()).0, dashed(projected.as_ref()).1]
+    );
+  }
+
+  Ok(())
+}
+
+#[derive(Debug, Serialize)]
+struct ConvertedCourse<'a> {
+  session: &'a str,
+  course: &'a str,
+  grade: &'a str,
+  points: BTreeMap<&'static str, f32>,
+}
+
+/// Prints the GPA on each conversion next to the York GPA, then every letter graded course converted
+async fn convert(args: &Cli, to: &[Conversion]) -> Result<(), GradesError> {
+  let to = if to.is_empty() { Conversion::ALL } else { to };
+
+  let (_, grades) = fetch(args).await?;
+  let grades = args.filter.filter()?.apply(grades);
+  let gpa = grades_list::calculate_gpa(&grades);
+
+  let converted = grades.iter()
+    .filter(|c| classify::GradeKind::of(&c.grade) == classify::GradeKind::Letter)
+    .map(|c| ConvertedCourse {
+      session: &c.session,
+      course: &c.course,
+      grade: &c.grade,
+      points: to.iter().filter_map(|t| Some((t.key(), t.points(&c.grade)?))).collect(),
+    })
+    .collect::<Vec<_>>();
+
+  if args.format() == Format::Json {
+    let conversions = to.iter().map(|t| (t.key(), t.gpa(&grades))).collect::<BTreeMap<_, _>>();
+    println!("{}", to_json(args, &serde_json::json!({ "gpa": gpa, "conversions": conversions, "grades": converted })));
+    return Ok(());
+  }
+
+  let lang = args.lang;
+  println!("{}", lang.text(Text::Gpa));
+  let mut pretty = table!([lang.text(Text::Scale), lang.text(Text::GpaHeading)]);
+  let york = |value: Option<f32>| value.map(|v| format!("{:.2}", v)).unwrap_or_default();
+  pretty.add_row(row![ lang.text(Text::YorkFour), york(gpa.as_ref().map(|g| g.four)) ]);
+  pretty.add_row(row![ lang.text(Text::YorkNine), york(gpa.as_ref().map(|g| g.nine)) ]);
+  for t in to {
+    let converted = t.gpa(&grades).map(|g| format!("{:.2}", g)).unwrap_or_default();
+    pretty.add_row(row![ t.name(), converted ]);
+  }
+  pretty.printstd();
+
+  println!();
+
+  println!("{}", lang.text(Text::Grades));
+  let mut header = vec![Cell::new(lang.text(Text::Session)), Cell::new(lang.text(Text::Course)), Cell::new(lang.text(Text::Grade))];
+  header.extend(to.iter().map(|t| Cell::new(t.name())));
+  let mut pretty = table!();
+  pretty.add_row(Row::new(header));
+
+  for c in &converted {
+    let mut cells = vec![Cell::new(c.session), Cell::new(c.course), Cell::new(c.grade)];
+    cells.extend(to.iter().map(|t| Cell::new(&c.points[t.key()].to_string())));
+    pretty.add_row(Row::new(cells));
+  }
+  pretty.printstd();
+
+  Ok(())
+}
+
+/// Prints the distribution of grades and other totals of the courses
+async fn show_stats(args: &Cli) -> Result<(), GradesError> {
+  let (_, grades) = fetch(args).await?;
+  let stats = stats::stats(&args.filter.filter()?.apply(grades));
+
+  if args.format() == Format::Json {
+    println!("{}", to_json(args, &stats));
+    return Ok(());
+  }
+
+  let lang = args.lang;
+  println!("{}", lang.text(Text::Grades));
+  let mut pretty = table!([lang.text(Text::Grade), lang.text(Text::Courses)]);
+  for count in &stats.distribution {
+    pretty.add_row(row![ count.grade, r->count.count ]);
+  }
+  pretty.printstd();
+
+  println!();
+
+  let mut pretty = table!();
+  if let Some(ref mean) = stats.mean {
+    pretty.add_row(row![ lang.text(Text::Mean), format!("{:.2} / {:.2}", mean.nine, mean.four) ]);
+  }
+  pretty.add_row(row![ lang.text(Text::Median), stats.median.unwrap_or("-") ]);
+  pretty.add_row(row![ lang.text(Text::CreditsAttempted), stats.credits_attempted ]);
+  pretty.add_row(row![ lang.text(Text::CreditsEarned), stats.credits_earned ]);
+  for (name, session) in [(lang.text(Text::BestSession), &stats.best_session), (lang.text(Text::WorstSession), &stats.worst_session)] {
+    if let Some(s) = session {
+      pretty.add_row(row![ name, format!("{} ({:.2} / {:.2})", s.session, s.gpa.nine, s.gpa.four) ]);
+    }
+  }
+  pretty.printstd();
+
+  if !stats.levels.is_empty() {
+    println!();
+
+    println!("{}", lang.text(Text::ByLevel));
+    let mut pretty = table!([lang.text(Text::Level), lang.text(Text::CreditsHeading), lang.text(Text::NinePoint), lang.text(Text::FourPoint)]);
+    for level in &stats.levels {
+      let (nine, four) = level.gpa.as_ref().map(|g| (format!("{:.2}", g.nine), format!("{:.2}", g.four))).unwrap_or_default();
+      pretty.add_row(row![ level.level, r->level.credits, r->nine, r->four ]);
+    }
+    pretty.printstd();
+  }
+
+  Ok(())
+}
+
+/// Prints how far along the degree requirements are
+async fn show_progress(args: &Cli) -> Result<(), GradesError> {
+  let config = load_config()?;
+  let requirements = config.requirements.as_ref()
+    .ok_or_else(|| GradesError::Config("progress needs a [requirements] section in the config file".to_owned()))?;
+  if requirements.major.is_some() && config.major.is_empty() {
+    return Err(GradesError::Config("the major requirement needs `major = [\"EECS\", ...]` in the config file".to_owned()));
+  }
+
+  let (_, grades) = fetch(args).await?;
+  let buckets = progress::progress(&grades, requirements, &config.major);
+
+  if args.format() == Format::Json {
+    println!("{}", to_json(args, &buckets));
+    return Ok(());
+  }
+
+  let lang = args.lang;
+  let mut pretty = table!([lang.text(Text::Requirement), lang.text(Text::Required), lang.text(Text::Completed), lang.text(Text::InProgressHeading), lang.text(Text::Remaining)]);
+  for b in &buckets {
+    pretty.add_row(row![ b.name, r->b.required, r->b.completed, r->b.in_progress, r->b.remaining ]);
+  }
+  pretty.printstd();
+
+  Ok(())
+}
+
+/// `degree-progress` as printed with `--format json`
+#[derive(Serialize)]
+struct DegreeProgressOutput<'a> {
+  requirements: &'a [degree::Requirement],
+  outstanding: f32,
+}
+
+/// Prints the requirements of York's Degree Progress Report, read from `--from-file` when given
+async fn show_degree_progress(args: &Cli) -> Result<(), GradesError> {
+  let page = match args.from_file {
+    Some(ref path) => read_page(path)?,
+    None => fetch_pages(args, &[Page::DegreeProgress]).await?.1.remove(0),
+  };
+  let requirements = degree::parse(&page, &load_config()?.selectors)?;
+  let outstanding = degree::outstanding(&requirements);
+
+  if args.format() == Format::Json {
+    println!("{}", to_json(args, &DegreeProgressOutput { requirements: &requirements, outstanding }));
+    return Ok(());
+  }
+
+  let credits = |c: Option<f32>| c.map(|c| format!("{:.2}", c)).unwrap_or_default();
+  let lang = args.lang;
+  let mut pretty = table!([lang.text(Text::Requirement), lang.text(Text::Status), lang.text(Text::Required), lang.text(Text::Completed), lang.text(Text::Outstanding)]);
+  for r in &requirements {
+    pretty.add_row(row![ r.name, lang.requirement_status(r.status), r->credits(r.required), r->credits(r.completed), r->credits(r.outstanding) ]);
+  }
+  pretty.printstd();
+  println!("{}", lang.format(Text::CreditsOutstanding, &[&format!("{:.2}", outstanding)]));
+
+  Ok(())
+}
+
+/// Prints the meetings of the enrolled courses, read from `--from-file` when given
+async fn show_schedule(args: &Cli) -> Result<(), GradesError> {
+  let page = match args.from_file {
+    Some(ref path) => read_page(path)?,
+    None => fetch_pages(args, &[Page::Schedule]).await?.1.remove(0),
+  };
+  let meetings = schedule::parse(&page, &load_config()?.selectors)?;
+
+  match args.format() {
+    Format::Json => {
+      println!("{}", to_json(args, &meetings));
+      return Ok(());
+    },
+    Format::Ics => {
+      for m in meetings.iter().filter(|m| !m.is_scheduled()) {
+        warning(format!("{} {} has no weekly meeting time or first day of classes, left out of the calendar", m.course, m.kind));
+      }
+
+      let calendar = schedule::ics(&meetings, chrono::Utc::now());
+      match args.output {
+        Some(ref path) => files::write_atomically(path, calendar.as_bytes())?,
+        None => print!("{}", calendar),
+      }
+      return Ok(());
+    },
+    _ => {},
+  }
+
+  let lang = args.lang;
+  let mut pretty = table!([lang.text(Text::Course), lang.text(Text::Title), lang.text(Text::Type), lang.text(Text::Days), lang.text(Text::Time), lang.text(Text::Location)]);
+  for m in &meetings {
+    pretty.add_row(row![ m.course, m.title, m.kind, m.day_letters(), m.time_range(), m.location ]);
+  }
+  pretty.printstd();
+
+  Ok(())
+}
+
+/// Prints the enrolment appointments and important dates, read from `--from-file` when given
+async fn show_dates(args: &Cli) -> Result<(), GradesError> {
+  let selectors = load_config()?.selectors;
+  let key_dates = match args.from_file {
+    Some(ref path) => dates::parse(&read_page(path)?, &selectors)?,
+    None => {
+      let (_, pages) = fetch_pages(args, &[Page::Appointments, Page::ImportantDates]).await?;
+
+      let mut key_dates = Vec::new();
+      for page in &pages {
+        key_dates.extend(dates::parse_fetched(page, &selectors)?);
+      }
+      key_dates.sort_by_key(|d| (d.start, d.start_time));
+      key_dates
+    },
+  };
+
+  match args.format() {
+    Format::Json => println!("{}", to_json(args, &key_dates)),
+    Format::Ics => {
+      let calendar = dates::ics(&key_dates, chrono::Utc::now());
+      match args.output {
+        Some(ref path) => files::write_atomically(path, calendar.as_bytes())?,
+        None => print!("{}", calendar),
+      }
+    },
+    _ => {
+      let lang = args.lang;
+      let mut pretty = table!([lang.text(Text::Date), lang.text(Text::Event), lang.text(Text::Kind)]);
+      for d in &key_dates {
+        pretty.add_row(row![ d.when(), d.name, lang.date_kind(d.kind) ]);
+      }
+      pretty.printstd();
+    },
+  }
+
+  Ok(())
+}
+
+/// Writes the courses that pass the filters to the `--output` file, a PDF being an unofficial
+/// transcript with the student's name and number
+async fn export(args: &Cli, student: &pdf::Student) -> Result<(), GradesError> {
+  let output = args.output.as_ref().ok_or_else(|| GradesError::Config("export needs --output, or a service to export to".to_owned()))?;
+
+  let (_, grades) = fetch(args).await?;
+  let grades = args.filter.filter()?.apply(grades);
+  let gpa = grades_list::calculate_gpa(&grades);
+
+  match (args.format(), redactor(args, &[&student.name, &student.id])?) {
+    (Format::Pdf, Some(redactor)) => {
+      let student = pdf::Student { name: student.name.as_deref().map(|name| redactor.hash(name)), id: student.id.as_deref().map(|id| redactor.hash(id)) };
+      write_output(args, output, &pdf::transcript(&redactor.courses(grades), gpa.as_ref(), &student)?)
+    },
+    (Format::Pdf, None) => write_output(args, output, &pdf::transcript(&grades, gpa.as_ref(), student)?),
+    _ => print_grades(args, gpa.as_ref(), &grades, &[]),
+  }
+}
+
+/// Prints the GPA of the courses that pass the filters
+async fn show_gpa(args: &Cli) -> Result<(), GradesError> {
+  let (_, grades) = fetch(args).await?;
+  let gpa = grades_list::calculate_gpa(&args.filter.filter()?.apply(grades));
+  let gpa = match args.precision()? {
+    Some(precision) => gpa.map(|gpa| precision.gpa(&gpa)),
+    None => gpa,
+  };
+
+  let mut out = match args.output {
+    Some(_) => Sink::Buffer(Vec::new()),
+    None => Sink::Stdout,
+  };
+
+  match args.format() {
+    // TOML has no null, no GPA yet is an empty document
+    Format::Toml if gpa.is_none() => {},
+    format @ (Format::Json | Format::Yaml | Format::Toml) => write_document(&mut out, args, format, &gpa)?,
+    Format::Csv => write!(out, "{}", format::gpa_csv(gpa.as_ref())?)?,
+    Format::Table => {
+      let (four, nine) = dashed(gpa.as_ref());
+      out.table(&table!([args.lang.text(Text::FourPoint), args.lang.text(Text::NinePoint)], [ four, nine ]))?;
+    },
+    format => return Err(GradesError::Config(format!("gpa writes a table, json, yaml, toml or csv, not {}", format.name()))),
+  }
+
+  if let (Some(ref path), Sink::Buffer(data)) = (&args.output, out) {
+    write_output(args, path, &data)?;
+  }
+  Ok(())
+}
+
+/// Replaces a worksheet with the courses that pass the filters, or appends them to it
+async fn export_sheets(args: &Cli, key: &Path, spreadsheet: &str, sheet: &str, append: bool) -> Result<(), GradesError> {
+  let account = sheets::ServiceAccount::load(key)?;
+
+  let (_, grades) = fetch(args).await?;
+  let grades = args.filter.filter()?.apply(grades);
+  let gpa = grades_list::calculate_gpa(&grades);
+  let grades = match redactor(args, &[])? {
+    Some(redactor) => redactor.courses(grades),
+    None => grades,
+  };
+
+  let sheets = sheets::Sheets::connect(&account, spreadsheet).await?;
+  sheets.write(sheet, sheets::rows(&grades, gpa.as_ref(), chrono::Utc::now()), append).await?;
+
+  println!("{}", args.lang.format(Text::SheetWritten, &[&grades.len(), &sheet]));
+  Ok(())
+}
+
+/// Keeps a Notion database in sync with the courses that pass the filters
+async fn export_notion(args: &Cli, database: &str, token: &Option<String>) -> Result<(), GradesError> {
+  let token = token.clone().or_else(|| std::env::var("NOTION_TOKEN").ok())
+    .ok_or_else(|| GradesError::Config("export notion needs --token or NOTION_TOKEN".to_owned()))?;
+
+  let (_, grades) = fetch(args).await?;
+  let grades = args.filter.filter()?.apply(grades);
+  let grades = match redactor(args, &[])? {
+    Some(redactor) => redactor.courses(grades),
+    None => grades,
+  };
+
+  let upserted = notion::Notion::new(&token, database)?.upsert(&grades).await?;
+
+  println!("{}", args.lang.format(Text::NotionSynced, &[&upserted.created, &upserted.updated]));
+  Ok(())
+}
+
+/// Commits the grades to a git repository, giving a history of them that can be browsed and pushed
+async fn git_snapshot(args: &Cli, dir: &Path) -> Result<(), GradesError> {
+  let (_, grades) = fetch(args).await?;
+  let gpa = grades_list::calculate_gpa(&grades);
+  let grades = match redactor(args, &[])? {
+    Some(redactor) => redactor.courses(grades),
+    None => grades,
+  };
+
+  match snapshot::commit(dir, &grades, gpa.as_ref())? {
+    Some(message) => println!("{}", args.lang.format(Text::Committed, &[&message.lines().next().unwrap_or_default()])),
+    None => println!("{}", args.lang.text(Text::NoChanges)),
+  }
+
+  Ok(())
+}
+
+/// Prints the average needed in this term's credits to reach the target GPA
+async fn target_gpa(args: &Cli, gpa: f32, credits: f32, scale: &str) -> Result<(), GradesError> {
+  let scales = load_config()?.scale_registry()?;
+  let scale = scales.get(scale)?;
+
+  let (_, grades) = fetch(args).await?;
+  let solved = target::solve(&grades, gpa, scale, credits)?;
+
+  if args.format() == Format::Json {
+    println!("{}", to_json(args, &solved));
+  } else {
+    let lang = args.lang;
+    println!("{}", lang.format(Text::TargetSummary, &[&solved.current, &solved.target, &solved.remaining_credits]));
+    println!("{}", lang.format(Text::AverageNeeded, &[&format!("{:.2}", solved.required)]));
+
+    match solved.grade {
+      Some(ref grade) => {
+        println!();
+        let points = |p: Option<f32>| p.map_or_else(|| "-".to_owned(), |p| p.to_string());
+        ptable!([lang.text(Text::Grade), lang.text(Text::NinePoint), lang.text(Text::FourPoint)], [ grade, points(solved.nine), points(solved.four) ]);
+      },
+      None => println!("{}", lang.text(Text::Unreachable)),
+    }
+  }
+
+  Ok(())
+}
+
+async fn run(args: Cli) -> Result<(), GradesError> {
+  if args.schema {
+    println!("{}", serde_json::to_string_pretty(&schemars::schema_for!(Output)).unwrap());
+    return Ok(());
+  }
+
+  if let Some(Command::Completions { shell }) = args.cmd {
+    Cli::clap().gen_completions_to("grades_list", shell, &mut io::stdout());
+    return Ok(());
+  }
+
+  if let Some(Command::Gpa) = args.cmd {
+    return show_gpa(&args).await;
+  }
+
+  if let Some(Command::Convert { ref to }) = args.cmd {
+    return convert(&args, to).await;
+  }
+
+  if let Some(Command::Stats) = args.cmd {
+    return show_stats(&args).await;
+  }
+
+  if let Some(Command::Progress) = args.cmd {
+    return show_progress(&args).await;
+  }
+
+  if let Some(Command::DegreeProgress) = args.cmd {
+    return show_degree_progress(&args).await;
+  }
+
+  if let Some(Command::Schedule) = args.cmd {
+    return show_schedule(&args).await;
+  }
+
+  if let Some(Command::Dates) = args.cmd {
+    return show_dates(&args).await;
+  }
+
+  if let Some(Command::Export { ref name, ref student_id, ref to, ref encrypt_to }) = args.cmd {
+    if to.is_some() && !encrypt_to.is_empty() {
+      return Err(GradesError::Config("--encrypt-to encrypts the --output file, not an export to a service".to_owned()));
+    }
+    match to {
+      Some(ExportTarget::Sheets { ref key, ref spreadsheet, ref sheet, append }) => return export_sheets(&args, key, spreadsheet, sheet, *append).await,
+      Some(ExportTarget::Notion { ref database, ref token }) => return export_notion(&args, database, token).await,
+      None => {},
+    }
+
+    let student = pdf::Student { name: name.clone(), id: student_id.clone() };
+    return export(&args, &student).await;
+  }
+
+  if let Some(Command::Target { gpa, credits, ref scale }) = args.cmd {
+    return target_gpa(&args, gpa, credits, scale).await;
+  }
+
+  if let Some(Command::WhatIf { ref add }) = args.cmd {
+    return what_if(&args, add).await;
+  }
+
+  if let Some(Command::Watch { interval, ref feed }) = args.cmd {
+    return watch(&args, interval, feed).await;
+  }
+
+  if let Some(Command::Check) = args.cmd {
+    return check(&args).await;
+  }
+
+  if let Some(Command::Doctor) = args.cmd {
+    return doctor(&args).await;
+  }
+
+  if let Some(Command::Tui) = args.cmd {
+    return tui(&args).await;
+  }
+
+  if let Some(Command::Serve { port, ref bind, ref token, interval, multi_user, rate_limit, global_rate_limit }) = args.cmd {
+    if interval < MIN_SERVE_INTERVAL {
+      return Err(GradesError::Config(format!("--interval has to be at least {} so York isn't asked too often",
+        humantime::format_duration(MIN_SERVE_INTERVAL))));
+    }
+
+    let addr = format!("{}:{}", bind, port);
+    let limits = RateLimits { per_token: rate_limit, global: global_rate_limit };
+    return match multi_user {
+      true => serve_accounts(&args, &addr, token, interval, limits).await,
+      false => serve(&args, &addr, token, interval, limits).await,
+    };
+  }
+
+  if let Some(Command::Daemon { keep_alive }) = args.cmd {
+    return run_daemon(&args, keep_alive).await;
+  }
+
+  if let Some(Command::Snapshot { ref git }) = args.cmd {
+    return git_snapshot(&args, git).await;
+  }
+
+  if let Some(Command::Cache { action: CacheAction::Clear }) = args.cmd {
+    let removed = cache::clear()?;
+    println!("{}", args.lang.format(Text::CacheCleared, &[&removed, &if removed == 1 { "" } else { "s" }]));
+    return Ok(());
+  }
+
+  if let Some(Command::History { id }) = args.cmd {
+    return show_history(&args, id);
+  }
+
+  if let Some(Command::Diff { ref from, ref to, ref since }) = args.cmd {
+    return show_diff(&args, from, to, since);
+  }
+
+  if let Some(Command::Login { ref username, ref password, save }) = args.cmd {
+    let password = match password {
+      Some(password) => password.clone(),
+      None => read_password("York Password: ")?,
+    };
+
+    let backend = args.network.backend()?;
+    args.mfa.login(&*backend, username, &password).await?;
+    backend.logout().await?;
+
+    if save {
+      keychain::save(username, &password)?;
+      println!("{}", args.lang.text(Text::LoggedInSaved));
+    } else {
+      println!("{}", args.lang.text(Text::LoggedIn));
+    }
+
+    return Ok(());
+  }
+
+  let filter = args.filter.filter()?;
+
+  if args.list.all_profiles {
+    return fetch_all_profiles(&args, &filter).await;
+  }
+
+  let (login, table_content, skipped) = fetch_parsed(&args).await?;
+
+  let gpa = grades_list::calculate_gpa(&table_content);
+
+  // a one-shot run compares against the grades seen by the previous run, saved pages aren't recorded
+  if let Some(login) = login {
+    let mut history = open_history()?;
+    if let Some(prev) = history.latest(&login.username)? {
+      notify(&notifiers(&args)?, &diff::diff(&prev.grades, &table_content), gpa.as_ref()).await;
+    }
+    history.record(&login.username, &table_content, gpa.as_ref())?;
+  }
+
+  // the history keeps everything, only what is shown is filtered
+  let cumulative = gpa.clone();
+  let table_content = if filter.is_empty() {
+    print_grades(&args, gpa.as_ref(), &table_content, &skipped)?;
+    table_content
+  } else {
+    let table_content = filter.apply(table_content);
+    print_grades(&args, grades_list::calculate_gpa(&table_content).as_ref(), &table_content, &skipped)?;
+    table_content
+  };
+
+  check_assertions(&args, cumulative.as_ref(), &table_content)
+}
+
+/// Fails if the cumulative GPA is under `--assert-gpa-min` or there is none yet, or a course
+/// shown has no grade past the `--assert-no-missing` date
+fn check_assertions(args: &Cli, cumulative: Option<&Gpa>, grades: &[CourseData]) -> Result<(), GradesError> {
+  let mut failed = Vec::new();
+
+  if let Some(min) = args.list.assert_gpa_min {
+    match cumulative {
+      Some(cumulative) if cumulative.nine < min => failed.push(format!("cumulative GPA {:.2} is below {:.2}", cumulative.nine, min)),
+      Some(_) => {},
+      None => failed.push(format!("no cumulative GPA yet to compare to {:.2}", min)),
+    }
+  }
+
+  if let Some(ref date) = args.list.assert_no_missing {
+    let date = chrono::NaiveDate::parse_from_str(date, "%Y-%m-%d")
+      .map_err(|e| GradesError::Config(format!("--assert-no-missing must be a YYYY-MM-DD date: {}", e)))?;
+
+    if chrono::Local::now().date_naive() > date {
+      let missing = grades.iter()
+        .filter(|c| classify::GradeKind::of(&c.grade) == classify::GradeKind::Pending)
+        .map(|c| c.code())
+        .collect::<Vec<_>>();
+      if !missing.is_empty() {
+        failed.push(format!("no grade yet after {} for {}", date, missing.join(", ")));
+      }
+    }
+  }
+
+  if failed.is_empty() { Ok(()) } else { Err(GradesError::Assertion(failed.join("; "))) }
+}
@@ -0,0 +1,83 @@
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+use crate::classify;
+use crate::server::ServerState;
+
+/// Writes one metric with its help and type lines, `samples` are the label set and value of each series
+fn metric (out: &mut String, name: &str, kind: &str, help: &str, samples: &[(String, f64)]) {
+  let _ = writeln!(out, "# HELP grades_list_{} {}", name, help);
+  let _ = writeln!(out, "# TYPE grades_list_{} {}", name, kind);
+
+  for (labels, value) in samples {
+    let _ = writeln!(out, "grades_list_{}{} {}", name, labels, value);
+  }
+}
+
+fn label (value: &str) -> String {
+  value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+/// The server state in the Prometheus text exposition format
+///
+/// The GPA and credit gauges are left out until the first fetch worked, the scrape counters are always there.
+pub fn render (state: &ServerState) -> String {
+  let mut out = String::new();
+
+  metric(&mut out, "scrapes_total", "counter", "Fetches of the course list by outcome", &[
+    ("{result=\"success\"}".to_owned(), state.scrapes.successes as f64),
+    ("{result=\"failure\"}".to_owned(), state.scrapes.failures as f64),
+  ]);
+  metric(&mut out, "scrape_duration_seconds", "gauge", "Time the last fetch took", &[
+    (String::new(), state.scrapes.last_duration),
+  ]);
+
+  let latest = match state.latest {
+    Some(ref latest) => latest,
+    None => return out,
+  };
+
+  metric(&mut out, "last_success_timestamp_seconds", "gauge", "When the grades were last fetched", &[
+    (String::new(), latest.fetched_at.timestamp() as f64),
+  ]);
+  // no sample at all while nothing counts, rather than a GPA of zero
+  if let Some(ref gpa) = latest.gpa {
+    metric(&mut out, "gpa", "gauge", "Cumulative GPA", &[
+      ("{scale=\"four\"}".to_owned(), gpa.four as f64),
+      ("{scale=\"nine\"}".to_owned(), gpa.nine as f64),
+    ]);
+  }
+
+  let mut sessions = BTreeMap::new();
+  for course in &latest.grades {
+    sessions.entry(course.session.as_str()).or_insert_with(Vec::new).push(course.clone());
+  }
+
+  let mut session_gpa = Vec::new();
+  let mut session_credits = Vec::new();
+  for (session, grades) in &sessions {
+    // sessions without graded credits yet have no GPA to show
+    let credits = crate::gpa_credits(grades);
+    if credits == 0.0 {
+      continue;
+    }
+
+    if let Some(gpa) = crate::calculate_gpa(grades) {
+      session_gpa.push((format!("{{session=\"{}\",scale=\"four\"}}", label(session)), gpa.four as f64));
+      session_gpa.push((format!("{{session=\"{}\",scale=\"nine\"}}", label(session)), gpa.nine as f64));
+    }
+    session_credits.push((format!("{{session=\"{}\"}}", label(session)), credits as f64));
+  }
+
+  metric(&mut out, "session_gpa", "gauge", "GPA of the courses of one session", &session_gpa);
+  metric(&mut out, "session_gpa_credits", "gauge", "Credits counted in the GPA of one session", &session_credits);
+
+  let summary = classify::summarize(&classify::excluded(&latest.grades));
+  metric(&mut out, "credits", "gauge", "Credits by how they count towards the GPA", &[
+    ("{counted=\"gpa\"}".to_owned(), crate::gpa_credits(&latest.grades) as f64),
+    ("{counted=\"withdrawn\"}".to_owned(), summary.withdrawn_credits as f64),
+    ("{counted=\"pass_fail\"}".to_owned(), summary.pass_fail_credits as f64),
+  ]);
+
+  out
+}
@@ -0,0 +1,92 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::GradesError;
+
+/// Machine the Passport York credentials are looked up under
+pub const MACHINE: &str = "passportyork.yorku.ca";
+
+/// Login and password of a `machine` or `default` entry, either may be left out
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Entry {
+  pub login: Option<String>,
+  pub password: Option<String>,
+}
+
+/// Where the netrc file is looked for, `$NETRC` or `~/.netrc`
+pub fn default_path () -> Option<PathBuf> {
+  match std::env::var_os("NETRC") {
+    Some(path) => Some(PathBuf::from(path)),
+    None => dirs::home_dir().map(|dir| dir.join(".netrc")),
+  }
+}
+
+// Whitespace separated tokens, leaving out comments and the bodies of macro definitions, which
+// run until an empty line
+fn tokens (contents: &str) -> Vec<&str> {
+  let mut tokens = Vec::new();
+  let mut in_macro = false;
+
+  for line in contents.lines() {
+    if in_macro {
+      in_macro = !line.trim().is_empty();
+      continue;
+    }
+
+    let line = line.split('#').next().unwrap_or("");
+    in_macro = line.split_whitespace().next() == Some("macdef");
+    if !in_macro {
+      tokens.extend(line.split_whitespace());
+    }
+  }
+
+  tokens
+}
+
+/// Entry for the machine, falling back to the `default` entry like curl and ftp do
+pub fn find (contents: &str, machine: &str) -> Option<Entry> {
+  let tokens = tokens(contents);
+  let mut found = None;
+  let mut default = None;
+  // the entry being read, if it is one of the two wanted
+  let mut current: Option<&mut Option<Entry>> = None;
+
+  let mut i = 0;
+  while i < tokens.len() {
+    let value = tokens.get(i + 1).map(|v| v.to_string());
+    match tokens[i] {
+      "machine" => {
+        current = match value.as_deref() {
+          Some(name) if name == machine && found.is_none() => Some(&mut found),
+          _ => None,
+        };
+        i += 1;
+      },
+      "default" => current = if default.is_none() { Some(&mut default) } else { None },
+      key @ ("login" | "password" | "account") => {
+        if let Some(entry) = current.as_mut() {
+          let entry = entry.get_or_insert_with(Entry::default);
+          match key {
+            "login" => entry.login = value,
+            "password" => entry.password = value,
+            _ => {},
+          }
+        }
+        i += 1;
+      },
+      _ => {},
+    }
+    i += 1;
+  }
+
+  found.or(default)
+}
+
+/// Reads the entry for the machine from the netrc file, a missing file has none
+pub fn load (path: &Path, machine: &str) -> Result<Option<Entry>, GradesError> {
+  match fs::read_to_string(path) {
+    Ok(contents) => Ok(find(&contents, machine)),
+    Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+    Err(e) => Err(e.into()),
+  }
+}
@@ -0,0 +1,65 @@
+use napi_derive::napi;
+use serde_json::Value;
+
+use crate::{ClientOptions, CourseData, Endpoints, SecondFactor};
+
+fn to_js_err (e: impl std::fmt::Display) -> napi::Error {
+  napi::Error::from_reason(e.to_string())
+}
+
+// through the text, so the GPA is 3.4333334 like in the JSON output instead of the f32 widened
+fn to_js<T: serde::Serialize> (value: &T) -> napi::Result<Value> {
+  serde_json::from_str(&serde_json::to_string(value).map_err(to_js_err)?).map_err(to_js_err)
+}
+
+/// York's client for JavaScript, each method returning a promise
+///
+/// The session is kept between calls, so a bot can log in once and fetch the grades whenever
+/// it's asked.
+#[napi(js_name = "GradesClient")]
+pub struct NodeClient {
+  inner: crate::GradesClient,
+}
+
+#[napi]
+impl NodeClient {
+  #[napi(constructor)]
+  pub fn new () -> napi::Result<NodeClient> {
+    let inner = crate::GradesClient::with_options(Endpoints::default(), &ClientOptions::default()).map_err(to_js_err)?;
+    Ok(NodeClient { inner })
+  }
+
+  /// Logs in, answering Duo with a push unless a passcode is given
+  #[napi]
+  pub async fn login (&self, username: String, password: String, passcode: Option<String>) -> napi::Result<()> {
+    let factor = match passcode {
+      Some(code) => SecondFactor::Passcode(code),
+      None => SecondFactor::Push,
+    };
+    self.inner.login_with(&username, &password, &factor).await.map_err(to_js_err)
+  }
+
+  /// Whether the session from the last login still works
+  #[napi]
+  pub async fn is_logged_in (&self) -> napi::Result<bool> {
+    self.inner.is_logged_in().await.map_err(to_js_err)
+  }
+
+  /// Every course on the course list, as objects with the keys of the JSON output
+  #[napi]
+  pub async fn fetch_grades (&self) -> napi::Result<Value> {
+    to_js(&self.inner.fetch_grades().await.map_err(to_js_err)?)
+  }
+
+  #[napi]
+  pub async fn logout (&self) -> napi::Result<()> {
+    self.inner.logout().await.map_err(to_js_err)
+  }
+}
+
+/// The four point and nine point GPA of courses from `fetchGrades()`
+#[napi]
+pub fn calculate_gpa (grades: Value) -> napi::Result<Value> {
+  let grades: Vec<CourseData> = serde_json::from_value(grades).map_err(to_js_err)?;
+  to_js(&crate::calculate_gpa(&grades))
+}
@@ -0,0 +1,163 @@
+use async_trait::async_trait;
+use serde::Deserialize;
+
+use crate::{Gpa, GradesError};
+use crate::chat::{Discord, DiscordConfig, Telegram, TelegramConfig};
+use crate::diff::GradeChange;
+use crate::email::{Email, EmailConfig};
+use crate::push::{Ntfy, NtfyConfig, Pushover, PushoverConfig};
+use crate::webhook::{ChangePayload, Webhook, WebhookConfig};
+
+/// A channel grade changes are sent through
+///
+/// Adding a channel means implementing this and building it in `from_config()`, whatever
+/// notices the changes just calls `send_all()`.
+#[async_trait]
+pub trait Notifier: Send + Sync {
+  /// Short name used in error messages, like `"email"`
+  fn name (&self) -> &'static str;
+
+  /// Sends the changes, only called when there are some
+  async fn notify (&self, changes: &[GradeChange], gpa: Option<&Gpa>) -> Result<(), GradesError>;
+}
+
+/// `[notify]` in the config file, every section present turns that channel on
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct NotifyConfig {
+  pub webhook: Option<WebhookConfig>,
+  pub email: Option<EmailConfig>,
+  pub discord: Option<DiscordConfig>,
+  pub telegram: Option<TelegramConfig>,
+  pub ntfy: Option<NtfyConfig>,
+  pub pushover: Option<PushoverConfig>,
+  pub desktop: Option<DesktopConfig>,
+}
+
+/// `[notify.desktop]` in the config file, an empty section is enough to turn it on
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DesktopConfig {}
+
+/// Shows grade changes as a desktop notification
+pub struct Desktop;
+
+/// Every channel configured in `[notify]`
+pub fn from_config (config: &NotifyConfig) -> Result<Vec<Box<dyn Notifier>>, GradesError> {
+  let mut notifiers: Vec<Box<dyn Notifier>> = Vec::new();
+
+  if let Some(ref webhook) = config.webhook {
+    notifiers.push(Box::new(Webhook::new(&webhook.url, &webhook.headers)?));
+  }
+  if let Some(ref email) = config.email {
+    notifiers.push(Box::new(Email::new(email)?));
+  }
+  if let Some(ref discord) = config.discord {
+    notifiers.push(Box::new(Discord::new(discord)?));
+  }
+  if let Some(ref telegram) = config.telegram {
+    notifiers.push(Box::new(Telegram::new(telegram)?));
+  }
+  if let Some(ref ntfy) = config.ntfy {
+    notifiers.push(Box::new(Ntfy::new(&ntfy.topic)?));
+  }
+  if let Some(ref pushover) = config.pushover {
+    notifiers.push(Box::new(Pushover::new(pushover)?));
+  }
+  if config.desktop.is_some() {
+    notifiers.push(Box::new(Desktop));
+  }
+
+  Ok(notifiers)
+}
+
+/// Sends the changes through every notifier, returning the errors of the ones that failed
+///
+/// One channel failing doesn't keep the others from being tried.
+pub async fn send_all (notifiers: &[Box<dyn Notifier>], changes: &[GradeChange], gpa: Option<&Gpa>) -> Vec<GradesError> {
+  let mut errors = Vec::new();
+  if changes.is_empty() {
+    return errors;
+  }
+
+  for notifier in notifiers {
+    if let Err(e) = notifier.notify(changes, gpa).await {
+      errors.push(match e {
+        GradesError::Notify(msg) => GradesError::Notify(format!("{}: {}", notifier.name(), msg)),
+        e => e,
+      });
+    }
+  }
+
+  errors
+}
+
+#[async_trait]
+impl Notifier for Webhook {
+  fn name (&self) -> &'static str { "webhook" }
+
+  async fn notify (&self, changes: &[GradeChange], gpa: Option<&Gpa>) -> Result<(), GradesError> {
+    self.send(&ChangePayload { changes, gpa }).await
+  }
+}
+
+#[async_trait]
+impl Notifier for Email {
+  fn name (&self) -> &'static str { "email" }
+
+  async fn notify (&self, changes: &[GradeChange], gpa: Option<&Gpa>) -> Result<(), GradesError> {
+    self.send(changes, gpa).await
+  }
+}
+
+#[async_trait]
+impl Notifier for Discord {
+  fn name (&self) -> &'static str { "discord" }
+
+  async fn notify (&self, changes: &[GradeChange], gpa: Option<&Gpa>) -> Result<(), GradesError> {
+    self.send(changes, gpa).await
+  }
+}
+
+#[async_trait]
+impl Notifier for Telegram {
+  fn name (&self) -> &'static str { "telegram" }
+
+  async fn notify (&self, changes: &[GradeChange], gpa: Option<&Gpa>) -> Result<(), GradesError> {
+    self.send(changes, gpa).await
+  }
+}
+
+#[async_trait]
+impl Notifier for Ntfy {
+  fn name (&self) -> &'static str { "ntfy" }
+
+  async fn notify (&self, changes: &[GradeChange], _gpa: Option<&Gpa>) -> Result<(), GradesError> {
+    self.send(changes).await
+  }
+}
+
+#[async_trait]
+impl Notifier for Pushover {
+  fn name (&self) -> &'static str { "pushover" }
+
+  async fn notify (&self, changes: &[GradeChange], _gpa: Option<&Gpa>) -> Result<(), GradesError> {
+    self.send(changes).await
+  }
+}
+
+#[async_trait]
+impl Notifier for Desktop {
+  fn name (&self) -> &'static str { "desktop" }
+
+  async fn notify (&self, changes: &[GradeChange], _gpa: Option<&Gpa>) -> Result<(), GradesError> {
+    let (summary, body) = crate::push::summary(changes);
+
+    // showing it talks to the notification daemon synchronously
+    tokio::task::spawn_blocking(move || {
+      notify_rust::Notification::new().appname("grades_list").summary(&summary).body(&body).show().map(|_| ())
+    }).await
+      .map_err(|e| GradesError::Notify(e.to_string()))?
+      .map_err(|e| GradesError::Notify(e.to_string()))
+  }
+}
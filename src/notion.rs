@@ -0,0 +1,163 @@
+use std::collections::HashMap;
+
+use reqwest::{RequestBuilder, Response, Url};
+use serde_json::{Value, json};
+
+use crate::{CourseData, GradesError};
+
+static NOTION_API: &str = "https://api.notion.com";
+static NOTION_VERSION: &str = "2022-06-28";
+
+/// Title property of the database holding the course code, the key pages are matched on
+pub const KEY_PROPERTY: &str = "Course";
+
+/// What a course's page is matched on, its code or, for a row without one like a transfer
+/// credit, the session, listing and title so those don't all end up on one page
+pub fn key (course: &CourseData) -> String {
+  match course.code() {
+    code if !code.is_empty() => code,
+    _ => [&course.session, &course.course, &course.title].iter()
+      .map(|part| part.trim())
+      .filter(|part| !part.is_empty())
+      .collect::<Vec<_>>()
+      .join(" "),
+  }
+}
+
+/// How many pages `Notion::upsert()` added and changed
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Upserted {
+  pub created: usize,
+  pub updated: usize,
+}
+
+fn text (content: &str) -> Value {
+  json!([{ "text": { "content": content } }])
+}
+
+fn number (value: Option<f32>) -> Value {
+  json!({ "number": value })
+}
+
+/// Page properties of a course, named like the columns of the table
+pub fn properties (course: &CourseData) -> Value {
+  let points = course.points();
+
+  json!({
+    KEY_PROPERTY: { "title": text(&key(course)) },
+    "Session": { "rich_text": text(&course.session) },
+    "Title": { "rich_text": text(&course.title) },
+    "Grade": { "rich_text": text(&course.grade) },
+    "Credits": number(course.credits),
+    "Nine Point": number(points.as_ref().map(|p| p.nine_point)),
+    "Four Point": number(points.as_ref().map(|p| p.four_point)),
+  })
+}
+
+// Notion's errors come as {"code": ..., "message": ...}
+async fn check (resp: Response) -> Result<Response, GradesError> {
+  if resp.status().is_success() {
+    return Ok(resp);
+  }
+
+  let status = resp.status();
+  let body = resp.json::<Value>().await.unwrap_or_default();
+  let message = body["message"].as_str().map(|m| format!(": {}", m)).unwrap_or_default();
+
+  Err(GradesError::Export(format!("Notion responded with {}{}", status, message)))
+}
+
+/// A Notion database the integration with this token was added to
+pub struct Notion {
+  client: reqwest::Client,
+  api: Url,
+  token: String,
+  database: String,
+}
+
+impl Notion {
+  pub fn new (token: &str, database: &str) -> Result<Notion, GradesError> {
+    Notion::with_api(token, database, NOTION_API)
+  }
+
+  /// Like `new()` against another server than Notion's, mostly for tests
+  pub fn with_api (token: &str, database: &str, api: &str) -> Result<Notion, GradesError> {
+    let api = Url::parse(api).map_err(|e| GradesError::Config(format!("invalid Notion API URL {}: {}", api, e)))?;
+    Ok(Notion { client: reqwest::Client::new(), api, token: token.to_owned(), database: database.to_owned() })
+  }
+
+  fn request (&self, method: reqwest::Method, path: &[&str]) -> RequestBuilder {
+    let mut url = self.api.clone();
+    url.path_segments_mut()
+      .expect("the API URL is a base")
+      .pop_if_empty()
+      .push("v1")
+      .extend(path);
+
+    self.client.request(method, url)
+      .bearer_auth(&self.token)
+      .header("Notion-Version", NOTION_VERSION)
+  }
+
+  /// Ids of the pages already in the database by `key()`
+  async fn pages (&self) -> Result<HashMap<String, String>, GradesError> {
+    let mut pages = HashMap::new();
+    let mut cursor: Option<String> = None;
+
+    loop {
+      let mut body = json!({ "page_size": 100 });
+      if let Some(ref cursor) = cursor {
+        body["start_cursor"] = json!(cursor);
+      }
+
+      let resp = self.request(reqwest::Method::POST, &["databases", &self.database, "query"]).json(&body).send().await?;
+      let resp = check(resp).await?.json::<Value>().await?;
+
+      for page in resp["results"].as_array().into_iter().flatten() {
+        let key = page["properties"][KEY_PROPERTY]["title"].as_array().into_iter().flatten()
+          .filter_map(|t| t["plain_text"].as_str())
+          .collect::<String>();
+        if let Some(id) = page["id"].as_str() {
+          pages.insert(key, id.to_owned());
+        }
+      }
+
+      match resp["next_cursor"].as_str() {
+        Some(next) if resp["has_more"].as_bool() == Some(true) => cursor = Some(next.to_owned()),
+        _ => return Ok(pages),
+      }
+    }
+  }
+
+  /// Updates the page of each course, or adds one for courses not in the database yet
+  ///
+  /// Attempts replaced by a retake are skipped, so a repeated course's page shows the attempt
+  /// that counts.
+  pub async fn upsert (&self, grades: &[CourseData]) -> Result<Upserted, GradesError> {
+    let mut pages = self.pages().await?;
+    let mut upserted = Upserted::default();
+
+    for course in grades.iter().filter(|c| !c.superseded) {
+      let properties = properties(course);
+
+      match pages.get(&key(course)) {
+        Some(id) => {
+          let resp = self.request(reqwest::Method::PATCH, &["pages", id]).json(&json!({ "properties": properties })).send().await?;
+          check(resp).await?;
+          upserted.updated += 1;
+        },
+        None => {
+          let body = json!({ "parent": { "database_id": self.database }, "properties": properties });
+          let resp = check(self.request(reqwest::Method::POST, &["pages"]).json(&body).send().await?).await?;
+          // counting all attempts lists a course more than once, later ones update this page
+          if let Some(id) = resp.json::<Value>().await?["id"].as_str() {
+            pages.insert(key(course), id.to_owned());
+          }
+          upserted.created += 1;
+        },
+      }
+    }
+
+    Ok(upserted)
+  }
+}
@@ -0,0 +1,140 @@
+use schemars::JsonSchema;
+use schemars::gen::{SchemaGenerator, SchemaSettings};
+use serde_json::{Value, json};
+
+use crate::server::{ErrorBody, Latest, LatestGpa, Profile, Registered, Registration, SessionGrades};
+
+fn content (schema: Value, description: &str) -> Value {
+  json!({ "description": description, "content": { "application/json": { "schema": schema } } })
+}
+
+fn schema<T: JsonSchema> (gen: &mut SchemaGenerator) -> Value {
+  serde_json::to_value(gen.subschema_for::<T>()).expect("schemas serialize")
+}
+
+static UNAUTHORIZED: &str = "The bearer token is missing or wrong";
+
+// every route answers 401 without the token and 503 before the first fetch
+fn responses (gen: &mut SchemaGenerator, ok: Value, more: &[(&str, &str)]) -> Value {
+  let error = schema::<ErrorBody>(gen);
+  let mut responses = json!({
+    "200": ok,
+    "401": content(error.clone(), UNAUTHORIZED),
+    "503": content(error.clone(), "The grades haven't been fetched yet"),
+  });
+  for (status, description) in more {
+    responses[*status] = content(error.clone(), description);
+  }
+  responses
+}
+
+/// The OpenAPI 3.0 document of the routes of `server::router()`, served at `/openapi.json`
+///
+/// The payloads are described with the same `JsonSchema` derives as `--schema`, so the document
+/// can't drift from what the handlers serialize.
+pub fn document () -> Value {
+  let mut gen = SchemaSettings::openapi3().into_generator();
+
+  let grades = schema::<Latest>(&mut gen);
+  let gpa = schema::<LatestGpa>(&mut gen);
+  let session = schema::<SessionGrades>(&mut gen);
+
+  let grades = content(grades, "GPA and every course, with when they were fetched");
+  let gpa = content(gpa, "GPA only");
+  let session = content(session, "Courses and GPA of the session");
+  let error = schema::<ErrorBody>(&mut gen);
+  let registration = schema::<Registration>(&mut gen);
+  let registered = content(schema::<Registered>(&mut gen), "The new account and its token, which is only ever shown here");
+  let profiles = content(json!({ "type": "array", "items": schema::<Profile>(&mut gen) }), "The accounts and how fetching their grades is going");
+  let admin_error = |description: &str| content(error.clone(), description);
+  let unauthorized = content(error.clone(), UNAUTHORIZED);
+  let text = |media: &str, description: &str| json!({ "description": description, "content": { media: { "schema": { "type": "string" } } } });
+
+  let paths = json!({
+    "/grades": { "get": {
+      "operationId": "getGrades",
+      "responses": responses(&mut gen, grades, &[]),
+    } },
+    "/gpa": { "get": {
+      "operationId": "getGpa",
+      "responses": responses(&mut gen, gpa, &[]),
+    } },
+    "/sessions/{id}": { "get": {
+      "operationId": "getSession",
+      "parameters": [{
+        "name": "id", "in": "path", "required": true, "schema": { "type": "string" },
+        "description": "Session like FW2023, matching FW 2022-2023",
+      }],
+      "responses": responses(&mut gen, session, &[("400", "The session is not valid"), ("404", "No courses in the session")]),
+    } },
+    "/metrics": { "get": {
+      "operationId": "getMetrics",
+      "responses": { "200": text("text/plain", "Prometheus text format"), "401": unauthorized },
+    } },
+    "/feed": { "get": {
+      "operationId": "getFeed",
+      "parameters": [{
+        "name": "token", "in": "query", "required": false, "schema": { "type": "string" },
+        "description": "The bearer token, for feed readers that can't send headers",
+      }],
+      "responses": { "200": text("application/atom+xml", "Atom feed of the latest grade changes"), "401": unauthorized },
+    } },
+    "/graphql": { "post": {
+      "operationId": "graphql",
+      "requestBody": { "required": true, "content": { "application/json": { "schema": {
+        "type": "object",
+        "required": ["query"],
+        "properties": {
+          "query": { "type": "string" },
+          "operationName": { "type": "string" },
+          "variables": { "type": "object" },
+        },
+      } } } },
+      "responses": { "200": { "description": "The GraphQL response, with `data` and `errors`", "content": {
+        "application/json": { "schema": { "type": "object" } },
+      } }, "401": unauthorized },
+    } },
+    "/admin/profiles": {
+      "description": "Only with serve --multi-user, where every account has a token of its own and the admin token only manages them",
+      "get": {
+        "operationId": "listProfiles",
+        "responses": { "200": profiles, "401": admin_error("Not the admin token") },
+      },
+      "post": {
+        "operationId": "registerProfile",
+        "requestBody": { "required": true, "content": { "application/json": { "schema": registration } } },
+        "responses": {
+          "201": registered,
+          "401": admin_error("Not the admin token"),
+          "409": admin_error("The name or username is already registered"),
+          "422": admin_error("York rejected the credentials or the Duo push"),
+          "502": admin_error("The grades could not be fetched"),
+        },
+      },
+    },
+    "/admin/profiles/{name}": { "delete": {
+      "operationId": "removeProfile",
+      "parameters": [{ "name": "name", "in": "path", "required": true, "schema": { "type": "string" } }],
+      "responses": {
+        "204": { "description": "The account is no longer served" },
+        "401": admin_error("Not the admin token"),
+        "404": admin_error("No account of that name"),
+      },
+    } },
+  });
+
+  json!({
+    "openapi": "3.0.3",
+    "info": {
+      "title": "grades_list",
+      "description": "York grades and GPA as last fetched by `grades_list serve`",
+      "version": env!("CARGO_PKG_VERSION"),
+    },
+    "security": [{ "bearer": [] }],
+    "paths": paths,
+    "components": {
+      "securitySchemes": { "bearer": { "type": "http", "scheme": "bearer" } },
+      "schemas": gen.take_definitions(),
+    },
+  })
+}
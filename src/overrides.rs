@@ -0,0 +1,40 @@
+use std::collections::HashMap;
+
+use tracing::warn;
+
+use crate::CourseData;
+
+pub(crate) fn matches (course: &CourseData, key: &str) -> bool {
+  let key = key.split_ascii_whitespace().collect::<Vec<_>>().join(" ").to_ascii_uppercase();
+  let code = course.code().to_ascii_uppercase();
+
+  key == code || key == format!("{} {}", course.faculty.to_ascii_uppercase(), code)
+}
+
+/// Replaces the grades of the courses in `overrides`, like an appeal expected to go through
+///
+/// Keys are course codes with or without the faculty, `"LE EECS 3101"` or `"EECS 3101"`, and
+/// only the latest attempt of a repeated course is changed. The grade from the course list is
+/// kept in `overridden`. Keys matching no course are logged as a warning.
+pub fn apply (grades: &mut [CourseData], overrides: &HashMap<String, String>) {
+  if overrides.is_empty() {
+    return;
+  }
+
+  for (key, grade) in overrides {
+    let latest = grades.iter_mut()
+      .filter(|c| matches(c, key))
+      .max_by_key(|c| crate::sessions::order(&c.session));
+
+    match latest {
+      Some(course) => {
+        let original = std::mem::replace(&mut course.grade, grade.trim().to_ascii_uppercase());
+        course.overridden.get_or_insert(original);
+      },
+      None => warn!(course = %key, "no course matches the grade override"),
+    }
+  }
+
+  // a grade posted or taken away by the override can change which attempt counts
+  crate::repeats::mark_repeats(grades);
+}
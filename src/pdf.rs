@@ -0,0 +1,156 @@
+use printpdf::lopdf;
+use printpdf::{BuiltinFont, IndirectFontRef, Line, Mm, PdfDocument, PdfDocumentReference, PdfLayerReference, Point};
+
+use crate::{CourseData, Gpa, GradesError};
+use crate::classify::earns_credit;
+use crate::format::display_grade;
+
+// A4 portrait
+const WIDTH: f32 = 210.0;
+const HEIGHT: f32 = 297.0;
+const MARGIN: f32 = 20.0;
+const LINE: f32 = 5.5;
+const SIZE: f32 = 9.5;
+
+// left edge of the course, title, credits and grade columns
+const COLUMNS: [f32; 4] = [MARGIN, 48.0, 138.0, 156.0];
+const TITLE_CHARS: usize = 52;
+
+/// Who the transcript is for, both printed only when given
+#[derive(Debug, Clone, Default)]
+pub struct Student {
+  pub name: Option<String>,
+  pub id: Option<String>,
+}
+
+fn pdf_error (e: printpdf::Error) -> GradesError {
+  GradesError::Io(std::io::Error::other(e))
+}
+
+// the builtin fonts are written in WinAnsiEncoding, Latin-1 with dashes and quotes, and a
+// character it doesn't have would be dropped without a trace
+fn win_ansi (s: &str) -> String {
+  s.chars()
+    .map(|c| if lopdf::Document::encode_text(Some("WinAnsiEncoding"), c.encode_utf8(&mut [0; 4])).is_empty() { '?' } else { c })
+    .collect()
+}
+
+fn truncate (s: &str, chars: usize) -> String {
+  if s.chars().count() <= chars {
+    return s.to_owned();
+  }
+  s.chars().take(chars - 3).collect::<String>() + "..."
+}
+
+/// Writes lines top to bottom, starting a new page when one fills up
+struct Pages {
+  doc: PdfDocumentReference,
+  layer: PdfLayerReference,
+  regular: IndirectFontRef,
+  bold: IndirectFontRef,
+  y: f32,
+}
+
+impl Pages {
+  fn new (title: &str) -> Result<Pages, GradesError> {
+    let (doc, page, layer) = PdfDocument::new(title, Mm(WIDTH), Mm(HEIGHT), "Transcript");
+    let regular = doc.add_builtin_font(BuiltinFont::Helvetica).map_err(pdf_error)?;
+    let bold = doc.add_builtin_font(BuiltinFont::HelveticaBold).map_err(pdf_error)?;
+    let layer = doc.get_page(page).get_layer(layer);
+
+    Ok(Pages { doc, layer, regular, bold, y: HEIGHT - MARGIN })
+  }
+
+  /// Moves down a line, or `lines` of them at once to keep a block on one page
+  fn advance (&mut self, lines: f32) {
+    if self.y - LINE * lines < MARGIN {
+      let (page, layer) = self.doc.add_page(Mm(WIDTH), Mm(HEIGHT), "Transcript");
+      self.layer = self.doc.get_page(page).get_layer(layer);
+      self.y = HEIGHT - MARGIN;
+    }
+    self.y -= LINE;
+  }
+
+  fn text (&self, text: &str, x: f32, size: f32, bold: bool) {
+    let font = if bold { &self.bold } else { &self.regular };
+    self.layer.use_text(win_ansi(text), size, Mm(x), Mm(self.y), font);
+  }
+
+  fn row (&mut self, cells: [&str; 4], bold: bool) {
+    self.advance(1.0);
+    for (cell, x) in cells.iter().zip(COLUMNS.iter()) {
+      self.text(cell, *x, SIZE, bold);
+    }
+  }
+
+  fn rule (&self) {
+    let y = Mm(self.y - 1.5);
+    self.layer.set_outline_thickness(0.3);
+    self.layer.add_line(Line {
+      points: vec![(Point::new(Mm(MARGIN), y), false), (Point::new(Mm(WIDTH - MARGIN), y), false)],
+      is_closed: false,
+    });
+  }
+}
+
+fn gpa_line (gpa: Option<&Gpa>) -> String {
+  match gpa {
+    Some(gpa) => format!("GPA {:.2} on the nine point scale, {:.2} on the four point scale", gpa.nine, gpa.four),
+    None => "GPA \u{2014}".to_owned(),
+  }
+}
+
+/// Renders an unofficial transcript, the courses grouped by session with the GPA of each and a
+/// summary at the end
+pub fn transcript (grades: &[CourseData], gpa: Option<&Gpa>, student: &Student) -> Result<Vec<u8>, GradesError> {
+  let mut pages = Pages::new("Unofficial Transcript")?;
+
+  pages.advance(1.0);
+  pages.text("Unofficial Transcript", MARGIN, 16.0, true);
+  pages.y -= 3.0;
+  if let Some(ref name) = student.name {
+    pages.advance(1.0);
+    pages.text(&format!("Name: {}", name), MARGIN, SIZE, false);
+  }
+  if let Some(ref id) = student.id {
+    pages.advance(1.0);
+    pages.text(&format!("Student number: {}", id), MARGIN, SIZE, false);
+  }
+  pages.advance(1.0);
+  pages.text(&format!("Generated {} from the York course list, not an official document", chrono::Local::now().format("%Y-%m-%d")), MARGIN, SIZE, false);
+
+  for (session, courses) in crate::sort::by_session(grades) {
+    pages.y -= LINE;
+    // the heading, column names and first course stay together
+    pages.advance(3.0);
+    pages.text(&session, MARGIN, 11.0, true);
+    pages.row(["Course", "Title", "Credits", "Grade"], true);
+    pages.rule();
+
+    for course in &courses {
+      let credits = course.credits.map(|c| format!("{:.2}", c)).unwrap_or_default();
+      pages.row([&course.code(), &truncate(&course.title, TITLE_CHARS), &credits, &display_grade(course)], false);
+    }
+
+    if let Some(gpa) = crate::calculate_gpa(&courses) {
+      pages.advance(1.0);
+      pages.text(&format!("Session {}", gpa_line(Some(&gpa))), COLUMNS[1], SIZE, false);
+    }
+  }
+
+  let earned = grades.iter()
+    .filter(|c| earns_credit(c))
+    .filter_map(|c| c.credits)
+    .fold(0.0, |total, credits| total + credits);
+
+  pages.y -= LINE;
+  pages.advance(3.0);
+  pages.text("Summary", MARGIN, 11.0, true);
+  pages.rule();
+  pages.advance(1.0);
+  pages.text(&format!("Cumulative {}", gpa_line(gpa)), MARGIN, SIZE, false);
+  pages.advance(1.0);
+  pages.text(&format!("Credits earned: {:.2}", earned), MARGIN, SIZE, false);
+
+  pages.doc.save_to_bytes().map_err(pdf_error)
+}
@@ -0,0 +1,64 @@
+use serde::{Deserialize, Serialize};
+
+use crate::CourseData;
+use crate::classify::{GradeKind, earns_credit};
+use crate::filter::matches_prefixes;
+
+/// `[requirements]` in the config file, the credits the degree needs in each bucket
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Requirements {
+  /// Credits in total
+  pub total: Option<f32>,
+  /// Credits at the 3000 level or above
+  pub upper_year: Option<f32>,
+  /// Credits at the 4000 level or above
+  pub level_4000: Option<f32>,
+  /// Credits in the courses listed as `major`
+  pub major: Option<f32>,
+}
+
+/// Progress towards one requirement
+#[derive(Debug, Clone, Serialize)]
+pub struct Bucket {
+  pub name: &'static str,
+  pub required: f32,
+  pub completed: f32,
+  /// Credits of courses without a grade yet
+  pub in_progress: f32,
+  pub remaining: f32,
+}
+
+fn bucket<F: Fn(&CourseData) -> bool> (name: &'static str, required: f32, grades: &[CourseData], counts: F) -> Bucket {
+  let credits = |keep: &dyn Fn(&CourseData) -> bool| grades.iter()
+    .filter(|c| counts(c) && keep(c))
+    .filter_map(|c| c.credits)
+    .fold(0.0, |total, credits| total + credits);
+
+  let completed = credits(&earns_credit);
+  let in_progress = credits(&|c| GradeKind::of(&c.grade) == GradeKind::Pending);
+
+  Bucket { name, required, completed, in_progress, remaining: (required - completed).max(0.0) }
+}
+
+/// Completed and remaining credits of every requirement that is set
+///
+/// `major` holds the course code prefixes of the major, like the `major` config key.
+pub fn progress (grades: &[CourseData], requirements: &Requirements, major: &[String]) -> Vec<Bucket> {
+  let mut buckets = Vec::new();
+
+  if let Some(required) = requirements.total {
+    buckets.push(bucket("total", required, grades, |_| true));
+  }
+  if let Some(required) = requirements.upper_year {
+    buckets.push(bucket("3000 level and above", required, grades, |c| c.level().is_some_and(|l| l >= 3000)));
+  }
+  if let Some(required) = requirements.level_4000 {
+    buckets.push(bucket("4000 level and above", required, grades, |c| c.level().is_some_and(|l| l >= 4000)));
+  }
+  if let Some(required) = requirements.major {
+    buckets.push(bucket("major", required, grades, |c| matches_prefixes(c, major)));
+  }
+
+  buckets
+}
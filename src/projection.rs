@@ -0,0 +1,84 @@
+use std::collections::HashMap;
+
+use schemars::JsonSchema;
+use serde::Serialize;
+
+use crate::{CourseData, Gpa, GradesError, calculate_gpa, nine_point_scale};
+use crate::classify::GradeKind;
+use crate::repeats::mark_repeats;
+
+/// Grade the best case gives every course in progress
+pub static BEST_GRADE: &str = "A+";
+
+/// The courses with no grade yet, the ones being taken this session or waiting on a grade
+pub fn in_progress (grades: &[CourseData]) -> Vec<&CourseData> {
+  grades.iter().filter(|c| GradeKind::of(&c.grade) == GradeKind::Pending).collect()
+}
+
+/// Where the GPA can go once the courses in progress are graded
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct Projection {
+  /// `None` while no course counts towards the GPA
+  pub current: Option<Gpa>,
+  /// With an A+ in every course in progress
+  pub best: Option<Gpa>,
+  /// With the grades of `[expected]` in the config, courses without one are left out
+  pub expected: Option<Gpa>,
+  /// Codes of the courses in progress with no expected grade
+  pub without_expected: Vec<String>,
+}
+
+/// The grade expected in the course from `[expected]`, keyed by course code like `[overrides]`,
+/// `"LE EECS 3101"` or `"EECS 3101"`
+///
+/// Fails with a config error when the grade isn't one of York's letter grades.
+pub fn expected_grade (course: &CourseData, expected: &HashMap<String, String>) -> Result<Option<String>, GradesError> {
+  let (key, grade) = match expected.iter().find(|(key, _)| crate::overrides::matches(course, key)) {
+    Some((key, grade)) => (key, grade.trim().to_ascii_uppercase()),
+    None => return Ok(None),
+  };
+
+  if !nine_point_scale().contains_key(&grade) {
+    return Err(GradesError::Config(format!("[expected] \"{}\" is {}, not a letter grade", key, grade)));
+  }
+  Ok(Some(grade))
+}
+
+// the grade of every course in progress that `grade` gives one, a retake replacing the real
+// attempt the way it will on the transcript
+fn graded<F: Fn(&CourseData) -> Option<String>> (grades: &[CourseData], grade: F, all_attempts: bool) -> Vec<CourseData> {
+  let mut graded = grades.iter().cloned().map(|mut course| {
+    if GradeKind::of(&course.grade) == GradeKind::Pending {
+      if let Some(grade) = grade(&course) {
+        course.grade = grade;
+      }
+    }
+    course
+  }).collect::<Vec<_>>();
+
+  if !all_attempts {
+    mark_repeats(&mut graded);
+  }
+  graded
+}
+
+/// Projects the GPA over the courses in progress, in the best case and with the expected grades
+///
+/// `all_attempts` counts every attempt, like `--count-all-attempts`.
+pub fn project (grades: &[CourseData], expected: &HashMap<String, String>, all_attempts: bool) -> Result<Projection, GradesError> {
+  let mut expected_grades = HashMap::new();
+  for course in in_progress(grades) {
+    expected_grades.insert(course.course.clone(), expected_grade(course, expected)?);
+  }
+  let expected_of = |course: &CourseData| expected_grades.get(&course.course).cloned().flatten();
+
+  Ok(Projection {
+    current: calculate_gpa(grades),
+    best: calculate_gpa(&graded(grades, |_| Some(BEST_GRADE.to_owned()), all_attempts)),
+    expected: calculate_gpa(&graded(grades, expected_of, all_attempts)),
+    without_expected: in_progress(grades).into_iter()
+      .filter(|course| expected_of(course).is_none())
+      .map(|course| course.code())
+      .collect(),
+  })
+}
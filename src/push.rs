@@ -0,0 +1,98 @@
+use serde::Deserialize;
+use serde_json::json;
+
+use crate::GradesError;
+use crate::diff::GradeChange;
+use crate::webhook::Webhook;
+
+static NTFY_SERVER: &str = "https://ntfy.sh";
+static PUSHOVER_URL: &str = "https://api.pushover.net/1/messages.json";
+
+/// Short line for a phone notification, like "New grade: EECS 2030 — A"
+pub fn headline (change: &GradeChange) -> String {
+  let course = change.course();
+  let code = if course.code().is_empty() { course.course.clone() } else { course.code() };
+
+  match change {
+    GradeChange::Changed { old_grade, .. } if old_grade.is_empty() => format!("New grade: {} — {}", code, course.grade),
+    GradeChange::Changed { old_grade, .. } => format!("Grade changed: {} — {} → {}", code, old_grade, course.grade),
+    GradeChange::Added { .. } => format!("New course: {}", code),
+    GradeChange::Removed { .. } => format!("Course removed: {}", code),
+  }
+}
+
+/// Title and body of a push, one headline per change in the body
+pub fn summary (changes: &[GradeChange]) -> (String, String) {
+  let title = match changes {
+    [change] => headline(change),
+    _ => format!("{} grade changes", changes.len()),
+  };
+
+  (title, changes.iter().map(headline).collect::<Vec<_>>().join("\n"))
+}
+
+/// `[notify.ntfy]` in the config file
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct NtfyConfig {
+  /// Topic name on ntfy.sh, or the full URL of a topic on another server
+  pub topic: String,
+}
+
+/// Publishes grade changes to an ntfy topic
+pub struct Ntfy {
+  webhook: Webhook,
+  topic: String,
+}
+
+impl Ntfy {
+  /// A topic name on ntfy.sh, or the full URL of a topic on another server
+  pub fn new (topic: &str) -> Result<Ntfy, GradesError> {
+    let (server, topic) = match topic.rfind('/') {
+      Some(idx) if topic.starts_with("http://") || topic.starts_with("https://") => (&topic[..idx], &topic[idx + 1..]),
+      _ => (NTFY_SERVER, topic),
+    };
+
+    // JSON is published to the server root with the topic in the body
+    Ok(Ntfy { webhook: Webhook::new(server, &[])?, topic: topic.to_owned() })
+  }
+
+  pub async fn send (&self, changes: &[GradeChange]) -> Result<(), GradesError> {
+    let (title, message) = summary(changes);
+    self.webhook.send(&json!({ "topic": self.topic, "title": title, "message": message })).await
+  }
+}
+
+/// `[notify.pushover]` in the config file
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PushoverConfig {
+  /// API token of the application registered on pushover.net
+  pub token: String,
+  /// User or group key to notify
+  pub user: String,
+}
+
+/// Sends grade changes through Pushover
+pub struct Pushover {
+  webhook: Webhook,
+  config: PushoverConfig,
+}
+
+impl Pushover {
+  pub fn new (config: &PushoverConfig) -> Result<Pushover, GradesError> {
+    Ok(Pushover { webhook: Webhook::new(PUSHOVER_URL, &[])?, config: config.clone() })
+  }
+
+  pub async fn send (&self, changes: &[GradeChange]) -> Result<(), GradesError> {
+    let (title, message) = summary(changes);
+    let payload = json!({
+      "token": self.config.token,
+      "user": self.config.user,
+      "title": title,
+      "message": message,
+    });
+
+    self.webhook.send(&payload).await
+  }
+}
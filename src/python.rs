@@ -0,0 +1,116 @@
+use pyo3::create_exception;
+use pyo3::exceptions::PyException;
+use pyo3::prelude::*;
+use pyo3::types::{PyBool, PyDict, PyFloat, PyList, PyLong, PyString};
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+
+use crate::blocking::GradesClient;
+use crate::{CourseData, SecondFactor};
+
+create_exception!(grades_list, GradesError, PyException, "Fetching or reading the grades failed");
+
+fn to_py_err (e: impl std::fmt::Display) -> PyErr {
+  GradesError::new_err(e.to_string())
+}
+
+// Python values go through JSON's, which is what the serde derives of the library already speak
+fn to_python (py: Python<'_>, value: &Value) -> PyResult<PyObject> {
+  Ok(match value {
+    Value::Null => py.None(),
+    Value::Bool(b) => b.into_py(py),
+    Value::Number(n) => match n.as_i64() {
+      Some(i) => i.into_py(py),
+      None => n.as_f64().unwrap_or(f64::NAN).into_py(py),
+    },
+    Value::String(s) => s.into_py(py),
+    Value::Array(items) => {
+      let list = PyList::empty_bound(py);
+      for item in items {
+        list.append(to_python(py, item)?)?;
+      }
+      list.into_py(py)
+    },
+    Value::Object(fields) => {
+      let dict = PyDict::new_bound(py);
+      for (key, item) in fields {
+        dict.set_item(key, to_python(py, item)?)?;
+      }
+      dict.into_py(py)
+    },
+  })
+}
+
+fn from_python (object: &Bound<'_, PyAny>) -> PyResult<Value> {
+  if object.is_none() {
+    Ok(Value::Null)
+  } else if let Ok(b) = object.downcast::<PyBool>() {
+    Ok(Value::Bool(b.is_true()))
+  } else if let Ok(i) = object.downcast::<PyLong>() {
+    Ok(Value::from(i.extract::<i64>()?))
+  } else if let Ok(f) = object.downcast::<PyFloat>() {
+    Ok(Value::from(f.value()))
+  } else if let Ok(s) = object.downcast::<PyString>() {
+    Ok(Value::String(s.to_str()?.to_owned()))
+  } else if let Ok(dict) = object.downcast::<PyDict>() {
+    let mut fields = serde_json::Map::new();
+    for (key, item) in dict.iter() {
+      fields.insert(key.str()?.to_str()?.to_owned(), from_python(&item)?);
+    }
+    Ok(Value::Object(fields))
+  } else {
+    object.iter()?.map(|item| from_python(&item?)).collect::<PyResult<Vec<_>>>().map(Value::Array)
+  }
+}
+
+fn to_object<T: Serialize> (py: Python<'_>, value: &T) -> PyResult<PyObject> {
+  // through the text, so a GPA comes out as 3.4333334 like in the JSON output and not as the
+  // f32 widened to 3.433333396911621
+  let json = serde_json::to_string(value).map_err(to_py_err)?;
+  to_python(py, &serde_json::from_str(&json).map_err(to_py_err)?)
+}
+
+fn from_object<T: DeserializeOwned> (object: &Bound<'_, PyAny>) -> PyResult<T> {
+  serde_json::from_value(from_python(object)?).map_err(to_py_err)
+}
+
+/// Logs in to Passport York, reads the course list and logs out again, returning a dict per course
+///
+/// Duo is answered with a push unless a passcode is given.
+#[pyfunction]
+#[pyo3(signature = (username, password, passcode = None))]
+fn fetch_grades (py: Python<'_>, username: &str, password: &str, passcode: Option<String>) -> PyResult<PyObject> {
+  let factor = match passcode {
+    Some(code) => SecondFactor::Passcode(code),
+    None => SecondFactor::Push,
+  };
+
+  // other Python threads keep running while York is waited on
+  let grades = py.allow_threads(|| -> Result<Vec<CourseData>, crate::GradesError> {
+    let client = GradesClient::new()?;
+    client.login_with(username, password, &factor)?;
+    let grades = client.fetch_grades();
+    client.logout()?;
+    grades
+  }).map_err(to_py_err)?;
+
+  to_object(py, &grades)
+}
+
+/// The four point and nine point GPA of courses from `fetch_grades()`, as a dict
+#[pyfunction]
+fn calculate_gpa (py: Python<'_>, grades: &Bound<'_, PyAny>) -> PyResult<PyObject> {
+  let grades: Vec<CourseData> = from_object(grades)?;
+  to_object(py, &crate::calculate_gpa(&grades))
+}
+
+/// The `grades_list` Python module, which a Rust program embedding Python can also add with
+/// `pyo3::append_to_inittab!`
+#[pymodule]
+pub fn grades_list (m: &Bound<'_, PyModule>) -> PyResult<()> {
+  m.add("GradesError", m.py().get_type_bound::<GradesError>())?;
+  m.add_function(wrap_pyfunction!(fetch_grades, m)?)?;
+  m.add_function(wrap_pyfunction!(calculate_gpa, m)?)?;
+  Ok(())
+}
@@ -0,0 +1,98 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Requests a minute the server answers, `serve --rate-limit` and `--global-rate-limit`, zero
+/// for no limit
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RateLimits {
+  /// For each bearer token
+  pub per_token: u32,
+  /// For all requests together, with or without a token
+  pub global: u32,
+}
+
+impl Default for RateLimits {
+  fn default () -> RateLimits {
+    RateLimits { per_token: 60, global: 600 }
+  }
+}
+
+// buckets of tokens nobody used for a while are dropped once there are this many, so requests
+// with made up tokens can't grow the map forever
+const MAX_BUCKETS: usize = 1024;
+
+/// Up to `per_minute` requests at once, refilled evenly over the minute
+#[derive(Debug)]
+struct Bucket {
+  available: f64,
+  updated: Instant,
+}
+
+impl Bucket {
+  fn full (per_minute: u32, now: Instant) -> Bucket {
+    Bucket { available: f64::from(per_minute), updated: now }
+  }
+
+  fn refill (&mut self, per_minute: u32, now: Instant) {
+    let rate = f64::from(per_minute) / 60.0;
+    let elapsed = now.saturating_duration_since(self.updated).as_secs_f64();
+    self.available = (self.available + elapsed * rate).min(f64::from(per_minute));
+    self.updated = now;
+  }
+
+  /// How long until a request can be taken, zero when one is available
+  fn wait (&self, per_minute: u32) -> Duration {
+    if self.available >= 1.0 || per_minute == 0 {
+      return Duration::ZERO;
+    }
+    Duration::from_secs_f64((1.0 - self.available) * 60.0 / f64::from(per_minute))
+  }
+}
+
+/// Counts the requests of every token and of the whole server against `RateLimits`
+#[derive(Debug)]
+pub struct RateLimiter {
+  limits: RateLimits,
+  global: Mutex<Bucket>,
+  tokens: Mutex<HashMap<String, Bucket>>,
+}
+
+impl RateLimiter {
+  pub fn new (limits: RateLimits) -> RateLimiter {
+    RateLimiter { limits, global: Mutex::new(Bucket::full(limits.global, Instant::now())), tokens: Mutex::default() }
+  }
+
+  /// Takes a request of the token, or of no token, returning how long to wait when one of the
+  /// limits is used up. A refused request uses up neither limit
+  pub fn check (&self, token: Option<&str>, now: Instant) -> Result<(), Duration> {
+    let mut global = self.global.lock().unwrap();
+    global.refill(self.limits.global, now);
+    let mut wait = global.wait(self.limits.global);
+
+    let mut tokens = self.tokens.lock().unwrap();
+    if let Some(token) = token {
+      if tokens.len() >= MAX_BUCKETS && !tokens.contains_key(token) {
+        let per_token = self.limits.per_token;
+        tokens.retain(|_, bucket| {
+          bucket.refill(per_token, now);
+          bucket.available < f64::from(per_token)
+        });
+      }
+
+      let bucket = tokens.entry(token.to_owned()).or_insert_with(|| Bucket::full(self.limits.per_token, now));
+      bucket.refill(self.limits.per_token, now);
+      wait = wait.max(bucket.wait(self.limits.per_token));
+    }
+
+    if wait > Duration::ZERO {
+      return Err(wait);
+    }
+
+    global.available -= 1.0;
+    if let Some(token) = token {
+      tokens.get_mut(token).expect("the bucket was just added").available -= 1.0;
+    }
+    Ok(())
+  }
+}
@@ -0,0 +1,130 @@
+use hmac::{Hmac, Mac, NewMac};
+use regex::{Captures, Regex};
+use sha2::Sha256;
+
+use crate::CourseData;
+use crate::sanitize;
+
+// York student numbers are nine digits
+const STUDENT_NUMBER: &str = r"\b\d{9}\b";
+const EMAIL: &str = r"[\w.+-]+@[\w-]+(\.[\w-]+)+";
+
+/// Takes what identifies the student out of the grades before they are written, `--redact`
+///
+/// Identifiers are replaced by a short hash rather than blanked, so the same account gives the
+/// same placeholder in every export made with the same `Secret` and shared snapshots can still be
+/// compared.
+#[derive(Debug, Clone)]
+pub struct Redactor {
+  known: Option<Regex>,
+  patterns: Vec<Regex>,
+  secret: Secret,
+  /// Also leave out the course sections, `--redact-sections`
+  pub sections: bool,
+}
+
+/// Key of the placeholders, without which a student number can't be found again by hashing
+/// every nine digit number
+pub type Secret = [u8; 32];
+
+/// The placeholder an identifier is replaced with, the same for any casing of it
+pub fn hash (secret: &Secret, identifier: &str) -> String {
+  let mut mac = Hmac::<Sha256>::new_from_slice(secret).expect("HMAC takes keys of any length");
+  mac.update(identifier.to_lowercase().as_bytes());
+  let hex = mac.finalize().into_bytes().iter().take(4).map(|b| format!("{:02x}", b)).collect::<String>();
+  format!("redacted-{}", hex)
+}
+
+// only whole words, so the username "li" leaves "English" alone
+fn whole (identifier: &str) -> String {
+  let word = |c: Option<char>| c.is_some_and(|c| c.is_alphanumeric() || c == '_');
+  format!(
+    "{}{}{}",
+    if word(identifier.chars().next()) { r"\b" } else { "" },
+    regex::escape(identifier),
+    if word(identifier.chars().last()) { r"\b" } else { "" },
+  )
+}
+
+/// Where the secret of this install is kept, `~/.local/share/grades_list/redact.key` on Linux
+#[cfg(feature = "native")]
+pub fn default_secret_path () -> Option<std::path::PathBuf> {
+  dirs::data_dir().map(|dir| dir.join("grades_list").join("redact.key"))
+}
+
+/// The secret kept at `path`, made the first time, so this install always gives an identifier
+/// the same placeholder
+#[cfg(feature = "native")]
+pub fn load_secret (path: &std::path::Path) -> Result<Secret, crate::GradesError> {
+  use std::convert::TryInto;
+
+  match std::fs::read(path) {
+    Ok(saved) => saved.as_slice().try_into()
+      .map_err(|_| crate::GradesError::Storage(format!("{} is not a redaction key, delete it to make a new one", path.display()))),
+    Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+      let secret: Secret = rand::random();
+      crate::files::write_private(path, &secret)?;
+      Ok(secret)
+    },
+    Err(e) => Err(e.into()),
+  }
+}
+
+impl Redactor {
+  /// Replaces the identifiers, like the username or the student's name, wherever they appear as
+  /// whole words, along with anything that looks like a student number or an email address
+  pub fn new<I: IntoIterator<Item = String>> (secret: Secret, identifiers: I, sections: bool) -> Redactor {
+    let known = identifiers.into_iter()
+      .map(|identifier| identifier.trim().to_owned())
+      .filter(|identifier| !identifier.is_empty())
+      .map(|identifier| whole(&identifier))
+      .collect::<Vec<_>>();
+    let known = if known.is_empty() { None } else { Some(Regex::new(&format!("(?i){}", known.join("|"))).unwrap()) };
+    let patterns = [EMAIL, STUDENT_NUMBER].iter().map(|pattern| Regex::new(pattern).unwrap()).collect();
+
+    Redactor { known, patterns, secret, sections }
+  }
+
+  /// The placeholder of an identifier, like the name on a PDF transcript
+  pub fn hash (&self, identifier: &str) -> String {
+    hash(&self.secret, identifier)
+  }
+
+  pub fn text (&self, text: &str) -> String {
+    let replace = |captures: &Captures| self.hash(&captures[0]);
+
+    // a whole email address is replaced before the username in it would be
+    let mut text = text.to_owned();
+    for pattern in &self.patterns {
+      text = pattern.replace_all(&text, replace).into_owned();
+    }
+    match self.known {
+      Some(ref known) => known.replace_all(&text, replace).into_owned(),
+      None => text,
+    }
+  }
+
+  pub fn course (&self, mut course: CourseData) -> CourseData {
+    course.title = self.text(&course.title);
+    course.notes = self.text(&course.notes);
+
+    // "LE/EECS 2030 3.00 A" ends with the section
+    if self.sections && !course.section.is_empty() {
+      if let Some(rest) = course.course.strip_suffix(course.section.as_str()) {
+        course.course = rest.trim_end().to_owned();
+      }
+      course.section.clear();
+    }
+    course
+  }
+
+  pub fn courses (&self, grades: Vec<CourseData>) -> Vec<CourseData> {
+    grades.into_iter().map(|course| self.course(course)).collect()
+  }
+
+  /// A saved page with the session taken out as `sanitize::strip_session()` does, and the
+  /// identifiers anywhere in it
+  pub fn page (&self, page: &str) -> String {
+    self.text(&sanitize::strip_session(page))
+  }
+}
@@ -0,0 +1,34 @@
+use std::collections::HashMap;
+
+use crate::{CourseData, nine_point_scale};
+use crate::sessions;
+
+/// Marks every graded attempt of a course that was taken again later as `superseded`
+///
+/// Per York's repeat policy only the most recent attempt counts towards the GPA, attempts are
+/// matched on the course code and only rows with a letter grade count as an attempt.
+pub fn mark_repeats (grades: &mut [CourseData]) {
+  let scale = nine_point_scale();
+  let mut latest: HashMap<String, usize> = HashMap::new();
+
+  for (idx, course) in grades.iter().enumerate() {
+    if !scale.contains_key(&course.grade) || course.code().is_empty() {
+      continue;
+    }
+
+    let newer = match latest.get(&course.code()) {
+      // later rows win ties, the course list is roughly in order
+      Some(&prev) => sessions::order(&course.session) >= sessions::order(&grades[prev].session),
+      None => true,
+    };
+
+    if newer {
+      latest.insert(course.code(), idx);
+    }
+  }
+
+  for (idx, course) in grades.iter_mut().enumerate() {
+    course.superseded = scale.contains_key(&course.grade)
+      && latest.get(&course.code()).map(|&l| l != idx).unwrap_or(false);
+  }
+}
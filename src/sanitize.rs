@@ -0,0 +1,13 @@
+use regex::Regex;
+
+/// Removes what could let someone reuse the session from a saved page
+///
+/// The body never holds cookies or headers, but WebObjects puts the session id in links and
+/// forms carry hidden tokens, so those values are blanked. The course table is left alone.
+pub fn strip_session (page: &str) -> String {
+  let session_param = Regex::new(r"(?i)\b(wosid|sid|jsessionid|session)=[^&;\x22'\s>]*").unwrap();
+  let hidden_value = Regex::new(r#"(?i)(<input[^>]*type\s*=\s*["']?hidden["']?[^>]*\bvalue\s*=\s*)("[^"]*"|'[^']*'|[^\s>]*)"#).unwrap();
+
+  let page = session_param.replace_all(page, "$1=");
+  hidden_value.replace_all(&page, "$1\"\"").into_owned()
+}
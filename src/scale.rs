@@ -0,0 +1,105 @@
+use std::collections::{BTreeMap, HashMap};
+
+use serde::Serialize;
+
+use crate::{CourseData, GradesError};
+use crate::convert::Conversion;
+
+/// York's letter grades, best first
+pub const LETTERS: &[&str] = &["A+", "A", "B+", "B", "C+", "C", "D+", "D", "E", "F"];
+
+/// Grade points York gives each letter on its nine point scale
+pub const NINE_POINT: &[(&str, f32)] = &[
+  ("A+", 9.0), ("A", 8.0), ("B+", 7.0), ("B", 6.0), ("C+", 5.0),
+  ("C", 4.0), ("D+", 3.0), ("D", 2.0), ("E", 1.0), ("F", 0.0),
+];
+
+/// Grade points York gives each letter on its four point scale
+pub const FOUR_POINT: &[(&str, f32)] = &[
+  ("A+", 4.0), ("A", 3.8), ("B+", 3.3), ("B", 3.0), ("C+", 2.3),
+  ("C", 2.0), ("D+", 1.3), ("D", 1.0), ("E", 0.7), ("F", 0.0),
+];
+
+/// Points for every York letter grade
+#[derive(Debug, Clone, Serialize)]
+pub struct GradeScale {
+  pub name: String,
+  pub points: HashMap<String, f32>,
+}
+
+impl GradeScale {
+  /// A scale giving every letter grade its points, fails if one is missing
+  pub fn new (name: &str, points: HashMap<String, f32>) -> Result<GradeScale, GradesError> {
+    let missing = LETTERS.iter().filter(|l| !points.contains_key(**l)).cloned().collect::<Vec<_>>();
+    if !missing.is_empty() {
+      return Err(GradesError::Config(format!("scale \"{}\" has no points for {}", name, missing.join(", "))));
+    }
+
+    Ok(GradeScale { name: name.to_owned(), points })
+  }
+
+  fn from_table (name: &str, table: &[(&str, f32)]) -> GradeScale {
+    GradeScale { name: name.to_owned(), points: table.iter().map(|(l, p)| (l.to_string(), *p)).collect() }
+  }
+
+  pub fn points (&self, grade: &str) -> Option<f32> {
+    self.points.get(grade).copied()
+  }
+
+  /// Credit weighted average over the courses counting towards the GPA, `None` without any
+  pub fn gpa (&self, grades: &[CourseData]) -> Option<f32> {
+    crate::weighted_average(grades, |grade| self.points(grade))
+  }
+}
+
+/// Every scale GPAs can be calculated on, by name
+///
+/// Holds York's `four` and `nine` point scales, the `convert` scales and any from the config file.
+#[derive(Debug, Clone)]
+pub struct ScaleRegistry {
+  scales: BTreeMap<String, GradeScale>,
+}
+
+impl Default for ScaleRegistry {
+  fn default () -> ScaleRegistry {
+    let mut scales = BTreeMap::new();
+    scales.insert("four".to_owned(), GradeScale::from_table("four", FOUR_POINT));
+    scales.insert("nine".to_owned(), GradeScale::from_table("nine", NINE_POINT));
+
+    for conversion in Conversion::ALL {
+      let points = LETTERS.iter().filter_map(|l| Some((l.to_string(), conversion.points(l)?))).collect();
+      scales.insert(conversion.key().to_owned(), GradeScale { name: conversion.key().to_owned(), points });
+    }
+
+    ScaleRegistry { scales }
+  }
+}
+
+impl ScaleRegistry {
+  /// The built in scales plus the ones from `[scales.<name>]` in the config, which can replace built in ones
+  pub fn with_custom (custom: &HashMap<String, HashMap<String, f32>>) -> Result<ScaleRegistry, GradesError> {
+    let mut registry = ScaleRegistry::default();
+
+    for (name, points) in custom {
+      registry.scales.insert(name.clone(), GradeScale::new(name, points.clone())?);
+    }
+
+    Ok(registry)
+  }
+
+  pub fn get (&self, name: &str) -> Result<&GradeScale, GradesError> {
+    let name = match name {
+      "4" => "four",
+      "9" => "nine",
+      name => name,
+    };
+
+    self.scales.get(name).ok_or_else(|| GradesError::Config(format!(
+      "unknown scale \"{}\", expected one of {}", name, self.names().collect::<Vec<_>>().join(", "),
+    )))
+  }
+
+  pub fn names (&self) -> impl Iterator<Item = &str> {
+    self.scales.keys().map(|k| k.as_str())
+  }
+}
@@ -0,0 +1,210 @@
+use chrono::{DateTime, Datelike, NaiveDate, NaiveTime, Utc, Weekday};
+use scraper::Html;
+use serde::Serialize;
+
+use crate::{GradesError, Selectors, clean_text, column, failure, header_table, ics};
+
+/// A weekly meeting of an enrolled course, a lecture, lab or tutorial
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Meeting {
+  /// Listing like the course list has it, `"LE EECS 3101 3.00 A"`
+  pub course: String,
+  pub title: String,
+  /// Kind of meeting, like `LECT`, `LAB` or `TUTR`
+  pub kind: String,
+  /// Days of the week it meets on, none for a course without scheduled meetings
+  pub days: Vec<Weekday>,
+  pub start: Option<NaiveTime>,
+  pub end: Option<NaiveTime>,
+  pub location: String,
+  /// First and last day of classes, when the timetable lists them
+  pub first_day: Option<NaiveDate>,
+  pub last_day: Option<NaiveDate>,
+}
+
+impl Meeting {
+  /// Days like the timetable shows them, `MWF`
+  pub fn day_letters (&self) -> String {
+    self.days.iter().map(|d| match d {
+      Weekday::Mon => 'M',
+      Weekday::Tue => 'T',
+      Weekday::Wed => 'W',
+      Weekday::Thu => 'R',
+      Weekday::Fri => 'F',
+      Weekday::Sat => 'S',
+      Weekday::Sun => 'U',
+    }).collect()
+  }
+
+  /// Whether it has the days, times and first day of classes a calendar event needs
+  pub fn is_scheduled (&self) -> bool {
+    !self.days.is_empty() && self.start.is_some() && self.end.is_some() && self.first_day.is_some()
+  }
+
+  /// Meeting time like `10:30-11:50`, empty without one
+  pub fn time_range (&self) -> String {
+    match (self.start, self.end) {
+      (Some(start), Some(end)) => format!("{}-{}", start.format("%H:%M"), end.format("%H:%M")),
+      _ => String::new(),
+    }
+  }
+}
+
+/// Days from York's letters, `MWF` or `TR`, or names like `Mon Wed`
+fn parse_days (days: &str) -> Vec<Weekday> {
+  let letter = |c: char| match c.to_ascii_uppercase() {
+    'M' => Some(Weekday::Mon),
+    'T' => Some(Weekday::Tue),
+    'W' => Some(Weekday::Wed),
+    'R' => Some(Weekday::Thu),
+    'F' => Some(Weekday::Fri),
+    'S' => Some(Weekday::Sat),
+    'U' => Some(Weekday::Sun),
+    _ => None,
+  };
+
+  // a token that isn't a day name is a run of letters, `MWF` is three letters as much as a word
+  days.split(|c: char| c.is_whitespace() || c == ',' || c == '/')
+    .filter(|d| !d.is_empty())
+    .flat_map(|d| match d.parse::<Weekday>() {
+      Ok(day) => vec![day],
+      Err(_) => d.chars().filter_map(letter).collect(),
+    })
+    .collect()
+}
+
+fn parse_time (time: &str) -> Option<NaiveTime> {
+  let time = time.trim().to_uppercase();
+  ["%H:%M", "%I:%M %p", "%I:%M%p"].iter().find_map(|f| NaiveTime::parse_from_str(&time, f).ok())
+}
+
+/// Start and end of a range like `10:30-11:50` or `1:00 PM - 2:30 PM`
+fn parse_times (times: &str) -> (Option<NaiveTime>, Option<NaiveTime>) {
+  match times.split_once('-') {
+    Some((start, end)) => (parse_time(start), parse_time(end)),
+    None => (None, None),
+  }
+}
+
+/// A date in the forms SIS uses, `2024-09-04`, `Sep 4, 2024` or `04-Sep-2024`
+pub(crate) fn parse_date (date: &str) -> Option<NaiveDate> {
+  ["%Y-%m-%d", "%b %d, %Y", "%B %d, %Y", "%d-%b-%Y"].iter().find_map(|f| NaiveDate::parse_from_str(date.trim(), f).ok())
+}
+
+/// First and last day of a range like `2024-09-04 - 2024-12-03` or `Sep 4, 2024 to Dec 3, 2024`
+pub(crate) fn parse_dates (dates: &str) -> (Option<NaiveDate>, Option<NaiveDate>) {
+  match dates.split_once(" - ").or_else(|| dates.split_once(" to ")) {
+    Some((first, last)) => (parse_date(first), parse_date(last)),
+    None => (parse_date(dates), None),
+  }
+}
+
+/// Parses a saved copy of the timetable of the current session
+///
+/// The meetings are read from the table with Course, Days and Time headers, with the Title,
+/// Type, Location and Dates when it has them. A course listed once with its meetings under it
+/// has the course cell left empty on those rows, which take the course above them.
+pub fn parse (page: &str, selectors: &Selectors) -> Result<Vec<Meeting>, GradesError> {
+  let compiled = selectors.compile()?;
+  let document = Html::parse_document(page);
+  let has_headers = |header: &[String]| ["course", "days", "time"].iter().all(|h| column(header, &[h]).is_some());
+
+  let data = header_table(&document, &compiled, has_headers).ok_or_else(|| failure::detect(page).unwrap_or_else(|| {
+    GradesError::Layout("could not find the timetable, no table has Course, Days and Time headers".to_owned())
+  }))?;
+
+  let header = &data[0];
+  let course = column(header, &["course"]).unwrap();
+  let days = column(header, &["days", "day"]).unwrap();
+  let time = column(header, &["time", "times"]).unwrap();
+  let title = column(header, &["title"]);
+  let kind = column(header, &["type", "meeting"]);
+  let location = column(header, &["location", "room"]);
+  let dates = column(header, &["dates", "date"]);
+
+  let mut meetings: Vec<Meeting> = Vec::new();
+  for row in &data[1..] {
+    let cell = |i: usize| row.get(i).map(|c| clean_text(c)).unwrap_or_default();
+    let optional = |i: Option<usize>| i.map(cell).unwrap_or_default();
+    if row.iter().all(|c| c.trim().is_empty()) {
+      continue;
+    }
+
+    // a further meeting of the course above
+    let (listing, course_title) = match (cell(course), meetings.last()) {
+      (listing, Some(previous)) if listing.is_empty() => (previous.course.clone(), previous.title.clone()),
+      (listing, _) if listing.is_empty() => continue,
+      (listing, _) => (listing, optional(title)),
+    };
+
+    let (start, end) = parse_times(&cell(time));
+    let (first_day, last_day) = parse_dates(&optional(dates));
+    meetings.push(Meeting {
+      course: listing,
+      title: course_title,
+      kind: optional(kind),
+      days: parse_days(&cell(days)),
+      start,
+      end,
+      location: optional(location),
+      first_day,
+      last_day,
+    });
+  }
+
+  Ok(meetings)
+}
+
+fn by_day (day: Weekday) -> &'static str {
+  match day {
+    Weekday::Mon => "MO",
+    Weekday::Tue => "TU",
+    Weekday::Wed => "WE",
+    Weekday::Thu => "TH",
+    Weekday::Fri => "FR",
+    Weekday::Sat => "SA",
+    Weekday::Sun => "SU",
+  }
+}
+
+// the content lines of the weekly event of a meeting, starting on its first day of classes that
+// falls on one of its days
+fn event (meeting: &Meeting) -> Option<Vec<String>> {
+  if !meeting.is_scheduled() {
+    return None;
+  }
+  let (start, end, first_day) = (meeting.start?, meeting.end?, meeting.first_day?);
+  let first = first_day.iter_days().take(7).find(|d| meeting.days.contains(&d.weekday()))?;
+
+  let summary = if meeting.kind.is_empty() { meeting.course.clone() } else { format!("{} {}", meeting.course, meeting.kind) };
+  let days = meeting.days.iter().map(|d| by_day(*d)).collect::<Vec<_>>().join(",");
+  let mut rule = format!("RRULE:FREQ=WEEKLY;BYDAY={}", days);
+  if let Some(last_day) = meeting.last_day {
+    rule.push_str(&format!(";UNTIL={}T235959", last_day.format("%Y%m%d")));
+  }
+
+  let mut lines = vec![
+    format!("UID:{}-{}-{}-{}@grades_list", ics::slug(&meeting.course), ics::slug(&meeting.kind), meeting.day_letters().to_lowercase(), first.format("%Y%m%d")),
+    format!("DTSTART:{}", first.and_time(start).format("%Y%m%dT%H%M%S")),
+    format!("DTEND:{}", first.and_time(end).format("%Y%m%dT%H%M%S")),
+    rule,
+    format!("SUMMARY:{}", ics::escape(&summary)),
+  ];
+  if !meeting.title.is_empty() {
+    lines.push(format!("DESCRIPTION:{}", ics::escape(&meeting.title)));
+  }
+  if !meeting.location.is_empty() {
+    lines.push(format!("LOCATION:{}", ics::escape(&meeting.location)));
+  }
+
+  Some(lines)
+}
+
+/// Renders the meetings as an iCalendar file with a weekly event for each, repeating on its days
+/// until the last day of classes
+///
+/// The times are floating, so a calendar shows them in its own time zone, which for a student at
+/// York is Toronto's. Meetings without days, times or a first day of classes are left out.
+pub fn ics (meetings: &[Meeting], stamp: DateTime<Utc>) -> String {
+  ics::calendar(&meetings.iter().filter_map(event).collect::<Vec<_>>(), stamp)
+}
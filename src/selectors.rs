@@ -0,0 +1,62 @@
+use scraper::{ElementRef, Selector};
+use serde::Deserialize;
+
+use crate::GradesError;
+
+/// How the grades and a successful login are recognized on York's pages
+///
+/// Set from `[selectors]` in the config file, so a layout change can be worked around without
+/// waiting for a release.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Selectors {
+  /// CSS selector of the tables searched for the Session, Course and Grade headers
+  pub table: String,
+  /// CSS selector of the rows of a table
+  pub row: String,
+  /// CSS selector of the cells of a row
+  pub cell: String,
+  /// Text of the page Passport York shows once the login went through
+  pub authenticated: String,
+}
+
+impl Default for Selectors {
+  fn default () -> Selectors {
+    Selectors {
+      table: "table".to_owned(),
+      row: "tr".to_owned(),
+      cell: "td, th".to_owned(),
+      authenticated: "You have successfully authenticated".to_owned(),
+    }
+  }
+}
+
+fn parse (name: &str, selector: &str) -> Result<Selector, GradesError> {
+  Selector::parse(selector).map_err(|e| GradesError::Config(format!("invalid {} selector \"{}\": {:?}", name, selector, e)))
+}
+
+/// The CSS selectors of `Selectors`, parsed
+pub(crate) struct Compiled {
+  pub table: Selector,
+  pub row: Selector,
+  pub cell: Selector,
+}
+
+impl Selectors {
+  pub(crate) fn compile (&self) -> Result<Compiled, GradesError> {
+    Ok(Compiled {
+      table: parse("table", &self.table)?,
+      row: parse("row", &self.row)?,
+      cell: parse("cell", &self.cell)?,
+    })
+  }
+}
+
+/// Whether `outer` is the closest ancestor of the element matching the selector, so the element
+/// isn't inside another table or row nested in it
+pub(crate) fn directly_in (element: &ElementRef, selector: &Selector, outer: &ElementRef) -> bool {
+  element.ancestors()
+    .filter_map(ElementRef::wrap)
+    .find(|e| selector.matches(e))
+    .map(|e| e.id()) == Some(outer.id())
+}
@@ -0,0 +1,418 @@
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+use axum::{Json, Router};
+use axum::extract::{Extension, Path, Query, Request, State};
+use axum::http::{StatusCode, header::{AUTHORIZATION, CONTENT_TYPE, RETRY_AFTER}};
+use axum::middleware::{self, Next};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{delete, get, post};
+use chrono::{DateTime, Utc};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use tokio::sync::RwLock;
+
+use crate::{CourseData, Gpa, GradesError};
+use crate::filter::CourseFilter;
+use crate::graphql::GradesSchema;
+use crate::rate_limit::{RateLimiter, RateLimits};
+
+/// The most recent fetch, served until the next one replaces it
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct Latest {
+  pub fetched_at: DateTime<Utc>,
+  /// `None` while no course counts towards the GPA
+  pub gpa: Option<Gpa>,
+  pub grades: Vec<CourseData>,
+}
+
+/// How fetching the grades has been going, for `/metrics`
+#[derive(Debug, Clone, Default)]
+pub struct Scrapes {
+  pub successes: u64,
+  pub failures: u64,
+  /// Seconds the last fetch took, whether it worked or not
+  pub last_duration: f64,
+}
+
+/// Everything the server shows, `latest` is `None` until the first fetch
+#[derive(Debug, Clone, Default)]
+pub struct ServerState {
+  pub latest: Option<Latest>,
+  pub scrapes: Scrapes,
+  /// Latest grade changes for `/feed`, newest first
+  pub changes: Vec<crate::feed::Entry>,
+}
+
+impl ServerState {
+  /// Keeps the outcome of a fetch, a failed one leaves the last grades in place
+  pub fn record<E> (&mut self, fetched: Result<Latest, E>, duration: Duration) -> Result<(), E> {
+    self.scrapes.last_duration = duration.as_secs_f64();
+
+    match fetched {
+      Ok(latest) => {
+        self.scrapes.successes += 1;
+        self.latest = Some(latest);
+        Ok(())
+      },
+      Err(e) => {
+        self.scrapes.failures += 1;
+        Err(e)
+      },
+    }
+  }
+}
+
+/// State shared between the server and whatever keeps the grades up to date
+pub type Shared = Arc<RwLock<ServerState>>;
+
+/// An account of a multi-user server
+#[derive(Debug, Clone)]
+pub struct Served {
+  pub username: String,
+  /// Bearer token that reads this account's grades, and only this account's
+  pub token: String,
+  pub state: Shared,
+}
+
+/// The accounts of a multi-user server by profile name
+pub type Accounts = Arc<RwLock<BTreeMap<String, Served>>>;
+
+/// Logs in the accounts registered through `/admin/profiles` and keeps their grades up to date,
+/// left to whatever runs the server
+#[async_trait]
+pub trait Registrar: Send + Sync {
+  /// Fetches the grades of the user once, so wrong credentials are an error, then keeps
+  /// refreshing them into the returned account
+  async fn register (&self, name: &str, username: &str, password: &str) -> Result<Served, GradesError>;
+  /// Stops refreshing the grades of the profile and forgets its credentials
+  async fn remove (&self, name: &str) -> Result<(), GradesError>;
+}
+
+/// Whose grades a token reads
+#[derive(Clone)]
+enum Tokens {
+  Single { token: Arc<String>, shared: Shared },
+  /// The admin token only manages the accounts, each of which has a token of its own
+  Multi { admin: Arc<String>, accounts: Accounts, registrar: Arc<dyn Registrar> },
+}
+
+#[derive(Clone)]
+struct AppState {
+  tokens: Tokens,
+  schema: GradesSchema,
+}
+
+enum Caller {
+  Admin,
+  Account(Shared),
+}
+
+impl Tokens {
+  async fn caller (&self, given: &[u8]) -> Option<Caller> {
+    match self {
+      Tokens::Single { token, shared } => Some(Caller::Account(shared.clone())).filter(|_| same_token(given, token.as_bytes())),
+      Tokens::Multi { admin, accounts, .. } => {
+        if same_token(given, admin.as_bytes()) {
+          return Some(Caller::Admin);
+        }
+        accounts.read().await.values()
+          .find(|served| same_token(given, served.token.as_bytes()))
+          .map(|served| Caller::Account(served.state.clone()))
+      },
+    }
+  }
+}
+
+/// Body of `/sessions/{id}`
+#[derive(Debug, Serialize, JsonSchema)]
+pub(crate) struct SessionGrades<'a> {
+  session: &'a str,
+  fetched_at: DateTime<Utc>,
+  gpa: Option<Gpa>,
+  grades: Vec<CourseData>,
+}
+
+/// Body of `/gpa`
+#[derive(Debug, Serialize, JsonSchema)]
+pub(crate) struct LatestGpa<'a> {
+  fetched_at: DateTime<Utc>,
+  gpa: Option<&'a Gpa>,
+}
+
+/// Body of every response that isn't a success
+#[derive(Debug, Serialize, JsonSchema)]
+pub(crate) struct ErrorBody<'a> {
+  error: &'a str,
+}
+
+/// Routes of the REST API, every request needs `Authorization: Bearer <token>`
+///
+/// * `GET /grades` - GPA and every course
+/// * `GET /gpa` - GPA only
+/// * `GET /sessions/{id}` - courses and GPA of one session, like `FW2023`
+/// * `GET /metrics` - the GPA and fetch counters in the Prometheus text format
+/// * `GET /feed` - an Atom feed of the grade changes, which also takes the token as `?token=`
+///   since feed readers can't send headers
+/// * `POST /graphql` - `grades(session, subject)` and `gpa(scale, session, subject)` queries, see
+///   `crate::graphql`
+/// * `GET /openapi.json` - the OpenAPI document of these routes, the only one served without the
+///   token so clients can be generated from it
+pub fn router (shared: Shared, token: &str) -> Router {
+  let tokens = Tokens::Single { token: Arc::new(token.to_owned()), shared };
+  routes(Router::new(), AppState { tokens, schema: crate::graphql::schema() })
+}
+
+/// Routes of a server for several accounts, each reading the routes of `router()` with its own
+/// token while `admin_token` manages them
+///
+/// * `GET /admin/profiles` - the accounts and how fetching their grades is going
+/// * `POST /admin/profiles` - registers `{"name", "username", "password"}`, answering with the
+///   token of the new account once its grades were fetched
+/// * `DELETE /admin/profiles/{name}` - stops serving the account
+pub fn multi_user_router (accounts: Accounts, admin_token: &str, registrar: Arc<dyn Registrar>) -> Router {
+  let tokens = Tokens::Multi { admin: Arc::new(admin_token.to_owned()), accounts, registrar };
+  let admin = Router::new()
+    .route("/admin/profiles", get(list_profiles).post(register_profile))
+    .route("/admin/profiles/:name", delete(remove_profile));
+
+  routes(admin, AppState { tokens, schema: crate::graphql::schema() })
+}
+
+fn routes (router: Router<AppState>, state: AppState) -> Router {
+  router
+    .route("/grades", get(grades))
+    .route("/gpa", get(gpa))
+    .route("/sessions/:id", get(session))
+    .route("/metrics", get(metrics))
+    .route("/feed", get(feed))
+    .route("/graphql", post(graphql))
+    .layer(middleware::from_fn_with_state(state.clone(), authorize))
+    .route("/openapi.json", get(openapi))
+    .with_state(state)
+}
+
+/// Answers `429 Too Many Requests` once a token, or the server as a whole, went over its limit
+///
+/// Requests without a token or with a wrong one count towards the global limit, so guessing
+/// tokens is slowed down too.
+pub fn limited (router: Router, limits: RateLimits) -> Router {
+  router.layer(middleware::from_fn_with_state(Arc::new(RateLimiter::new(limits)), rate_limit))
+}
+
+/// Serves the API on `addr` until the process ends
+pub async fn serve (addr: &str, router: Router) -> Result<(), GradesError> {
+  let listener = tokio::net::TcpListener::bind(addr).await?;
+  axum::serve(listener, router).await?;
+
+  Ok(())
+}
+
+fn error (status: StatusCode, message: &str) -> Response {
+  (status, Json(ErrorBody { error: message })).into_response()
+}
+
+/// Compares without stopping at the first difference, so the token can't be guessed byte by byte from timings
+fn same_token (given: &[u8], token: &[u8]) -> bool {
+  given.len() == token.len() && given.iter().zip(token).fold(0, |acc, (a, b)| acc | (a ^ b)) == 0
+}
+
+fn bearer_token (req: &Request) -> Option<Cow<'_, str>> {
+  req.headers().get(AUTHORIZATION)
+    .and_then(|value| value.to_str().ok())
+    .and_then(|value| value.strip_prefix("Bearer "))
+    .map(Cow::Borrowed)
+    .or_else(|| feed_token(req).map(Cow::Owned))
+}
+
+async fn rate_limit (State(limiter): State<Arc<RateLimiter>>, req: Request, next: Next) -> Response {
+  match limiter.check(bearer_token(&req).as_deref(), Instant::now()) {
+    Ok(()) => next.run(req).await,
+    Err(wait) => {
+      let mut resp = error(StatusCode::TOO_MANY_REQUESTS, "too many requests, slow down");
+      resp.headers_mut().insert(RETRY_AFTER, (wait.as_secs() + 1).into());
+      resp
+    },
+  }
+}
+
+async fn authorize (State(state): State<AppState>, mut req: Request, next: Next) -> Response {
+  let caller = match bearer_token(&req) {
+    Some(given) => state.tokens.caller(given.as_bytes()).await,
+    None => None,
+  };
+
+  // the admin token reads no grades, and an account's token can't manage the others
+  let admin_route = req.uri().path().starts_with("/admin/");
+  match caller {
+    Some(Caller::Admin) if admin_route => next.run(req).await,
+    Some(Caller::Account(shared)) if !admin_route => {
+      req.extensions_mut().insert(shared);
+      next.run(req).await
+    },
+    _ => error(StatusCode::UNAUTHORIZED, "missing or wrong bearer token"),
+  }
+}
+
+// decoded, since a feed reader percent-encodes a token with `+`, `/` or `=` in it
+fn feed_token (req: &Request) -> Option<String> {
+  if req.uri().path() != "/feed" {
+    return None;
+  }
+
+  let Query(mut query) = Query::<HashMap<String, String>>::try_from_uri(req.uri()).ok()?;
+  query.remove("token")
+}
+
+fn not_fetched () -> Response {
+  error(StatusCode::SERVICE_UNAVAILABLE, "the grades haven't been fetched yet")
+}
+
+async fn grades (Extension(shared): Extension<Shared>) -> Response {
+  match shared.read().await.latest {
+    Some(ref latest) => Json(latest).into_response(),
+    None => not_fetched(),
+  }
+}
+
+async fn gpa (Extension(shared): Extension<Shared>) -> Response {
+  match shared.read().await.latest {
+    Some(ref latest) => Json(LatestGpa { fetched_at: latest.fetched_at, gpa: latest.gpa.as_ref() }).into_response(),
+    None => not_fetched(),
+  }
+}
+
+async fn session (Extension(shared): Extension<Shared>, Path(id): Path<String>) -> Response {
+  let guard = shared.read().await;
+  let latest = match guard.latest {
+    Some(ref latest) => latest,
+    None => return not_fetched(),
+  };
+
+  // same matching as `--session`, so `FW2021` finds "FW 2021-2022"
+  let filter = CourseFilter { session: Some(id.clone()), ..Default::default() };
+  if let Err(e) = filter.validate() {
+    return error(StatusCode::BAD_REQUEST, &e.to_string());
+  }
+  let grades = filter.apply(latest.grades.clone());
+
+  if grades.is_empty() {
+    return error(StatusCode::NOT_FOUND, &format!("no courses in session {}", id));
+  }
+
+  let gpa = crate::calculate_gpa(&grades);
+  Json(SessionGrades { session: &id, fetched_at: latest.fetched_at, gpa, grades }).into_response()
+}
+
+async fn metrics (Extension(shared): Extension<Shared>) -> Response {
+  let body = crate::metrics::render(&*shared.read().await);
+  ([(CONTENT_TYPE, "text/plain; version=0.0.4")], body).into_response()
+}
+
+async fn feed (Extension(shared): Extension<Shared>) -> Response {
+  let guard = shared.read().await;
+  let updated = guard.latest.as_ref().map(|l| l.fetched_at).unwrap_or_else(Utc::now);
+
+  ([(CONTENT_TYPE, "application/atom+xml")], crate::feed::atom(&guard.changes, updated)).into_response()
+}
+
+async fn graphql (State(state): State<AppState>, Extension(shared): Extension<Shared>, Json(req): Json<async_graphql::Request>) -> Json<async_graphql::Response> {
+  Json(state.schema.execute(req.data(shared)).await)
+}
+
+async fn openapi () -> Response {
+  Json(crate::openapi::document()).into_response()
+}
+
+/// Body of `POST /admin/profiles`
+#[derive(Debug, Deserialize, JsonSchema)]
+pub(crate) struct Registration {
+  name: String,
+  username: String,
+  password: String,
+}
+
+/// Answer to `POST /admin/profiles`, the token is only ever shown here
+#[derive(Debug, Serialize, JsonSchema)]
+pub(crate) struct Registered<'a> {
+  name: &'a str,
+  username: &'a str,
+  token: &'a str,
+}
+
+/// An account listed by `GET /admin/profiles`
+#[derive(Debug, Serialize, JsonSchema)]
+pub(crate) struct Profile {
+  name: String,
+  username: String,
+  /// When the grades were last fetched, none until the first fetch worked
+  fetched_at: Option<DateTime<Utc>>,
+  successes: u64,
+  failures: u64,
+}
+
+fn admin (state: &AppState) -> (&Accounts, &Arc<dyn Registrar>) {
+  match state.tokens {
+    Tokens::Multi { ref accounts, ref registrar, .. } => (accounts, registrar),
+    Tokens::Single { .. } => unreachable!("the admin routes are only added by multi_user_router()"),
+  }
+}
+
+async fn list_profiles (State(state): State<AppState>) -> Response {
+  let (accounts, _) = admin(&state);
+
+  let mut profiles = Vec::new();
+  for (name, served) in accounts.read().await.iter() {
+    let served_state = served.state.read().await;
+    profiles.push(Profile {
+      name: name.clone(),
+      username: served.username.clone(),
+      fetched_at: served_state.latest.as_ref().map(|latest| latest.fetched_at),
+      successes: served_state.scrapes.successes,
+      failures: served_state.scrapes.failures,
+    });
+  }
+
+  Json(profiles).into_response()
+}
+
+async fn register_profile (State(state): State<AppState>, Json(registration): Json<Registration>) -> Response {
+  let (accounts, registrar) = admin(&state);
+  let Registration { name, username, password } = registration;
+
+  if name.is_empty() || username.is_empty() {
+    return error(StatusCode::BAD_REQUEST, "the name and username can't be empty");
+  }
+  // checked before logging in, so a taken name doesn't cost a Duo push
+  if let Some((taken, _)) = accounts.read().await.iter().find(|(taken, served)| **taken == name || served.username == username) {
+    return error(StatusCode::CONFLICT, &format!("{} or {} is already registered as \"{}\"", name, username, taken));
+  }
+
+  let served = match registrar.register(&name, &username, &password).await {
+    Ok(served) => served,
+    Err(e @ GradesError::Config(_)) => return error(StatusCode::CONFLICT, &e.to_string()),
+    Err(e @ (GradesError::Auth | GradesError::Mfa(_) | GradesError::Locked | GradesError::PasswordExpired)) =>
+      return error(StatusCode::UNPROCESSABLE_ENTITY, &e.to_string()),
+    Err(e) => return error(StatusCode::BAD_GATEWAY, &e.to_string()),
+  };
+
+  let body = Json(Registered { name: &name, username: &served.username, token: &served.token }).into_response();
+  accounts.write().await.insert(name, served);
+  (StatusCode::CREATED, body).into_response()
+}
+
+async fn remove_profile (State(state): State<AppState>, Path(name): Path<String>) -> Response {
+  let (accounts, registrar) = admin(&state);
+
+  if accounts.write().await.remove(&name).is_none() {
+    return error(StatusCode::NOT_FOUND, &format!("no profile named \"{}\"", name));
+  }
+
+  match registrar.remove(&name).await {
+    Ok(()) => StatusCode::NO_CONTENT.into_response(),
+    Err(e) => error(StatusCode::INTERNAL_SERVER_ERROR, &e.to_string()),
+  }
+}
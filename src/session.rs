@@ -0,0 +1,46 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::{GradesError, SisBackend, files};
+use crate::vault::Vault;
+
+/// Where the session of the given user is kept between runs
+pub fn default_path (username: &str) -> Option<PathBuf> {
+  dirs::data_dir().map(|dir| dir.join("grades_list").join(format!("session-{}", files::name_part(username))))
+}
+
+/// Restores the session saved by `save()`, returning false if there was nothing to restore
+///
+/// A file that can't be decrypted (e.g. the keychain entry was removed) is treated as missing.
+pub fn load (backend: &dyn SisBackend, path: &Path, vault: &Vault) -> Result<bool, GradesError> {
+  let data = match fs::read(path) {
+    Ok(data) => data,
+    Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(false),
+    Err(e) => return Err(e.into()),
+  };
+
+  let plaintext = match vault.open(&data) {
+    Ok(plaintext) => plaintext,
+    Err(_) => return Ok(false),
+  };
+
+  backend.load_session(&plaintext)?;
+  Ok(true)
+}
+
+/// Encrypts the backend's session, York's cookies, with the key in the OS keychain unless a
+/// passphrase is used, and writes it to `path`, readable only by its owner
+pub fn save (backend: &dyn SisBackend, path: &Path, vault: &Vault) -> Result<(), GradesError> {
+  let mut plaintext = Vec::new();
+  backend.save_session(&mut plaintext)?;
+
+  files::write_private(path, &vault.seal(&plaintext))
+}
+
+/// Removes a saved session, if there is one
+pub fn remove (path: &Path) -> Result<(), GradesError> {
+  match fs::remove_file(path) {
+    Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+    _ => Ok(()),
+  }
+}
@@ -0,0 +1,98 @@
+use std::cmp::Ordering;
+
+use schemars::JsonSchema;
+use serde::Serialize;
+
+/// The two sessions of York's academic year
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, JsonSchema)]
+pub enum Period {
+  /// September to April, over two calendar years
+  #[serde(rename = "FW")]
+  FallWinter,
+  /// May to August, after the fall/winter session ending that year
+  #[serde(rename = "SU")]
+  Summer,
+}
+
+impl Period {
+  pub fn code (self) -> &'static str {
+    match self {
+      Period::FallWinter => "FW",
+      Period::Summer => "SU",
+    }
+  }
+}
+
+/// A session of the course list read into its parts, so `FW 2022-2023` doesn't have to be
+/// parsed again by whatever reads the JSON
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, JsonSchema)]
+pub struct ParsedSession {
+  /// The session written the same way whatever spacing and case it had, like `FW 2022-2023`
+  /// or `SU 2023 S1`
+  pub name: String,
+  pub period: Period,
+  pub start_year: u32,
+  pub end_year: u32,
+  /// `S1` or `S2`, the first or second half of the summer, `None` for the whole session
+  pub subsession: Option<String>,
+}
+
+impl Ord for ParsedSession {
+  // the whole summer sorts before its halves, it starts with the first one
+  fn cmp (&self, other: &ParsedSession) -> Ordering {
+    (self.end_year, self.period, &self.subsession, self.start_year)
+      .cmp(&(other.end_year, other.period, &other.subsession, other.start_year))
+  }
+}
+
+impl PartialOrd for ParsedSession {
+  fn partial_cmp (&self, other: &ParsedSession) -> Option<Ordering> {
+    Some(self.cmp(other))
+  }
+}
+
+/// Reads a session like `FW 2022-2023`, `SU 2023` or `SU 2023 S2`, `None` for anything else
+///
+/// A fall/winter session given with one year, like `FW 2022`, is taken to start that year.
+pub fn parse (session: &str) -> Option<ParsedSession> {
+  let upper = session.trim().to_ascii_uppercase();
+  let code = upper.chars().take_while(|c| c.is_ascii_alphabetic()).collect::<String>();
+  let period = match code.as_str() {
+    "FW" => Period::FallWinter,
+    "SU" => Period::Summer,
+    _ => return None,
+  };
+
+  let rest = &upper[code.len()..];
+  let years = rest.split(|c: char| !c.is_ascii_digit())
+    .filter(|p| p.len() == 4)
+    .filter_map(|p| p.parse::<u32>().ok())
+    .collect::<Vec<_>>();
+  let (start_year, end_year) = match (period, years.as_slice()) {
+    (_, [start, end]) => (*start, *end),
+    (Period::FallWinter, [start]) => (*start, start + 1),
+    (Period::Summer, [year]) => (*year, *year),
+    _ => return None,
+  };
+
+  let subsession = rest.split(|c: char| c.is_whitespace() || c == '-')
+    .find(|p| p.len() == 2 && p.starts_with('S') && p[1..].chars().all(|c| c.is_ascii_digit()))
+    .map(str::to_owned);
+
+  let years = match period {
+    Period::FallWinter => format!("{}-{}", start_year, end_year),
+    Period::Summer => start_year.to_string(),
+  };
+  let name = match subsession {
+    Some(ref subsession) => format!("{} {} {}", period.code(), years, subsession),
+    None => format!("{} {}", period.code(), years),
+  };
+
+  Some(ParsedSession { name, period, start_year, end_year, subsession })
+}
+
+/// Chronological key of a session, sessions that can't be read sort after every other
+pub fn order (session: &str) -> (bool, Option<ParsedSession>) {
+  let parsed = parse(session);
+  (parsed.is_none(), parsed)
+}
@@ -0,0 +1,172 @@
+use std::path::Path;
+
+use jsonwebtoken::{Algorithm, EncodingKey, Header};
+use reqwest::{Response, Url};
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+
+use crate::{CourseData, Gpa, GradesError};
+
+static SHEETS_API: &str = "https://sheets.googleapis.com";
+static SCOPE: &str = "https://www.googleapis.com/auth/spreadsheets";
+
+/// The parts of a Google Cloud service account key file needed to sign in
+#[derive(Debug, Clone, Deserialize)]
+pub struct ServiceAccount {
+  pub client_email: String,
+  pub private_key: String,
+  pub token_uri: String,
+}
+
+impl ServiceAccount {
+  /// Reads the JSON key file downloaded from the Google Cloud console
+  pub fn load (path: &Path) -> Result<ServiceAccount, GradesError> {
+    let data = std::fs::read(path)?;
+    serde_json::from_slice(&data)
+      .map_err(|e| GradesError::Config(format!("{} is not a service account key: {}", path.display(), e)))
+  }
+}
+
+#[derive(Serialize)]
+struct Claims<'a> {
+  iss: &'a str,
+  scope: &'a str,
+  aud: &'a str,
+  iat: i64,
+  exp: i64,
+}
+
+#[derive(Deserialize)]
+struct Token {
+  access_token: String,
+}
+
+/// Rows written to the sheet, a header, one row per course and the GPA under them
+pub fn rows (grades: &[CourseData], gpa: Option<&Gpa>, fetched_at: chrono::DateTime<chrono::Utc>) -> Vec<Vec<Value>> {
+  let number = |v: Option<f32>| v.map(|v| json!(v)).unwrap_or_else(|| json!(""));
+
+  let mut rows = vec![
+    vec![json!("Fetched"), json!(fetched_at.to_rfc3339())],
+    ["Session", "Course", "Title", "Grade", "Credits", "Nine", "Four"].iter().map(|h| json!(h)).collect(),
+  ];
+
+  for course in grades {
+    let points = course.points();
+    rows.push(vec![
+      json!(course.session), json!(course.course), json!(course.title), json!(crate::format::display_grade(course)),
+      number(course.credits),
+      number(points.as_ref().map(|p| p.nine_point)),
+      number(points.as_ref().map(|p| p.four_point)),
+    ]);
+  }
+
+  rows.push(vec![json!("GPA"), json!(""), json!(""), json!(""), json!(""), number(gpa.map(|g| g.nine)), number(gpa.map(|g| g.four))]);
+  rows
+}
+
+// Google's errors come as {"error": {"message": ...}}
+async fn check (resp: Response) -> Result<Response, GradesError> {
+  if resp.status().is_success() {
+    return Ok(resp);
+  }
+
+  let status = resp.status();
+  let body = resp.json::<Value>().await.unwrap_or_default();
+  let message = body["error"]["message"].as_str().map(|m| format!(": {}", m)).unwrap_or_default();
+
+  Err(GradesError::Export(format!("Google Sheets responded with {}{}", status, message)))
+}
+
+/// A Google Sheets spreadsheet shared with a service account
+pub struct Sheets {
+  client: reqwest::Client,
+  api: Url,
+  token: String,
+  spreadsheet: String,
+}
+
+impl Sheets {
+  /// Signs in as the service account to edit the spreadsheet with this id
+  pub async fn connect (account: &ServiceAccount, spreadsheet: &str) -> Result<Sheets, GradesError> {
+    Sheets::with_api(account, spreadsheet, SHEETS_API).await
+  }
+
+  /// Like `connect()` against another server than Google's, mostly for tests
+  pub async fn with_api (account: &ServiceAccount, spreadsheet: &str, api: &str) -> Result<Sheets, GradesError> {
+    let client = reqwest::Client::new();
+    let api = Url::parse(api).map_err(|e| GradesError::Config(format!("invalid Sheets API URL {}: {}", api, e)))?;
+
+    let now = chrono::Utc::now().timestamp();
+    let claims = Claims { iss: &account.client_email, scope: SCOPE, aud: &account.token_uri, iat: now, exp: now + 3600 };
+    let key = EncodingKey::from_rsa_pem(account.private_key.as_bytes())
+      .map_err(|e| GradesError::Config(format!("invalid service account private key: {}", e)))?;
+    let assertion = jsonwebtoken::encode(&Header::new(Algorithm::RS256), &claims, &key)
+      .map_err(|e| GradesError::Export(format!("could not sign the token request: {}", e)))?;
+
+    let resp = client.post(&account.token_uri)
+      .form(&[("grant_type", "urn:ietf:params:oauth:grant-type:jwt-bearer"), ("assertion", assertion.as_str())])
+      .send().await?;
+    let token = check(resp).await?.json::<Token>().await?.access_token;
+
+    Ok(Sheets { client, api, token, spreadsheet: spreadsheet.to_owned() })
+  }
+
+  fn url (&self, path: &[&str]) -> Url {
+    let mut url = self.api.clone();
+    url.path_segments_mut()
+      .expect("the API URL is a base")
+      .pop_if_empty()
+      .extend(["v4", "spreadsheets"].iter().chain(path));
+    url
+  }
+
+  async fn titles (&self) -> Result<Vec<String>, GradesError> {
+    let mut url = self.url(&[&self.spreadsheet]);
+    url.query_pairs_mut().append_pair("fields", "sheets.properties.title");
+
+    let resp = check(self.client.get(url).bearer_auth(&self.token).send().await?).await?;
+    let body = resp.json::<Value>().await?;
+
+    Ok(body["sheets"].as_array().into_iter().flatten()
+      .filter_map(|s| s["properties"]["title"].as_str().map(|t| t.to_owned()))
+      .collect())
+  }
+
+  /// Adds the worksheet unless the spreadsheet already has one with this title
+  async fn ensure_sheet (&self, title: &str) -> Result<(), GradesError> {
+    if self.titles().await?.iter().any(|t| t == title) {
+      return Ok(());
+    }
+
+    let body = json!({ "requests": [{ "addSheet": { "properties": { "title": title } } }] });
+    let url = self.url(&[&format!("{}:batchUpdate", self.spreadsheet)]);
+    check(self.client.post(url).bearer_auth(&self.token).json(&body).send().await?).await?;
+
+    Ok(())
+  }
+
+  /// Replaces everything on the worksheet with the rows, or adds them under what's there with
+  /// `append`, creating the worksheet when it's missing
+  pub async fn write (&self, sheet: &str, rows: Vec<Vec<Value>>, append: bool) -> Result<(), GradesError> {
+    self.ensure_sheet(sheet).await?;
+
+    // A1 notation quotes the title and doubles quotes inside it
+    let range = format!("'{}'", sheet.replace('\'', "''"));
+    let body = json!({ "values": rows });
+
+    if append {
+      let mut url = self.url(&[&self.spreadsheet, "values", &format!("{}:append", range)]);
+      url.query_pairs_mut().append_pair("valueInputOption", "RAW").append_pair("insertDataOption", "INSERT_ROWS");
+      check(self.client.post(url).bearer_auth(&self.token).json(&body).send().await?).await?;
+    } else {
+      let url = self.url(&[&self.spreadsheet, "values", &format!("{}:clear", range)]);
+      check(self.client.post(url).bearer_auth(&self.token).json(&json!({})).send().await?).await?;
+
+      let mut url = self.url(&[&self.spreadsheet, "values", &range]);
+      url.query_pairs_mut().append_pair("valueInputOption", "RAW");
+      check(self.client.put(url).bearer_auth(&self.token).json(&body).send().await?).await?;
+    }
+
+    Ok(())
+  }
+}
@@ -0,0 +1,127 @@
+use std::fs;
+use std::path::Path;
+use std::process::{Command, Output};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{CourseData, Gpa, GradesError};
+use crate::diff::{self, GradeChange};
+
+/// Files written into the repository
+pub const JSON_FILE: &str = "grades.json";
+pub const CSV_FILE: &str = "grades.csv";
+
+#[derive(Serialize, Deserialize)]
+struct Document {
+  gpa: Option<Gpa>,
+  grades: Vec<CourseData>,
+}
+
+/// The courses ordered by session, then course, so the same grades always give the same file
+pub fn sorted (grades: &[CourseData]) -> Vec<CourseData> {
+  let mut sorted = grades.to_vec();
+  sorted.sort_by(|a, b| {
+    crate::sessions::order(&a.session).cmp(&crate::sessions::order(&b.session))
+      .then_with(|| a.session.cmp(&b.session))
+      .then_with(|| a.course.cmp(&b.course))
+  });
+  sorted
+}
+
+/// Pretty JSON of the GPA and sorted courses, without a fetch time so unchanged grades give an
+/// unchanged file
+pub fn json (grades: &[CourseData], gpa: Option<&Gpa>) -> String {
+  let document = Document { gpa: gpa.cloned(), grades: sorted(grades) };
+  serde_json::to_string_pretty(&document).expect("grades serialize") + "\n"
+}
+
+/// Commit message of a snapshot, a summary line and each change under it
+pub fn message (changes: Option<&[GradeChange]>, courses: usize, gpa: Option<&Gpa>) -> String {
+  let gpa = match gpa {
+    Some(gpa) => format!("GPA {:.2} / 9, {:.2} / 4", gpa.nine, gpa.four),
+    None => "no GPA yet".to_owned(),
+  };
+
+  match changes {
+    None => format!("First grades snapshot, {} courses, {}\n", courses, gpa),
+    // a title or credits changed, no grade did
+    Some([]) => format!("Update grades snapshot, {}\n", gpa),
+    Some([change]) => format!("{}, {}\n", crate::push::headline(change), gpa),
+    Some(changes) => {
+      let mut message = format!("{} grade changes, {}\n\n", changes.len(), gpa);
+      for change in changes {
+        message.push_str(&format!("- {}\n", change));
+      }
+      message
+    },
+  }
+}
+
+fn git (dir: &Path, args: &[&str]) -> Result<Output, GradesError> {
+  Command::new("git").arg("-C").arg(dir).args(args).output()
+    .map_err(|e| GradesError::Export(format!("could not run git: {}", e)))
+}
+
+fn git_ok (dir: &Path, args: &[&str]) -> Result<(), GradesError> {
+  let output = git(dir, args)?;
+  if output.status.success() {
+    return Ok(());
+  }
+
+  Err(GradesError::Export(format!("git {} failed: {}", args[0], String::from_utf8_lossy(&output.stderr).trim())))
+}
+
+// whether `dir` is the top of a repository, not just somewhere inside another one
+fn is_repository (dir: &Path) -> Result<bool, GradesError> {
+  let output = git(dir, &["rev-parse", "--show-toplevel"])?;
+  if !output.status.success() {
+    return Ok(false);
+  }
+
+  let top = Path::new(String::from_utf8_lossy(&output.stdout).trim()).canonicalize()?;
+  Ok(top == dir.canonicalize()?)
+}
+
+/// Writes the grades into the git repository at `dir` and commits them, creating the repository
+/// if needed
+///
+/// Only the two snapshot files go into the commit, anything else staged in the repository is
+/// left staged. A directory inside another repository gets a repository of its own.
+///
+/// Returns the commit message, or `None` when the grades are the same as in the last snapshot.
+pub fn commit (dir: &Path, grades: &[CourseData], gpa: Option<&Gpa>) -> Result<Option<String>, GradesError> {
+  fs::create_dir_all(dir)?;
+  if !is_repository(dir)? {
+    git_ok(dir, &["init", "--quiet"])?;
+  }
+
+  // compared to the last commit rather than the file, which a failed commit may have left changed
+  let committed = git(dir, &["show", &format!("HEAD:{}", JSON_FILE)])?;
+  let previous = if committed.status.success() {
+    Some(serde_json::from_slice::<Document>(&committed.stdout)
+      .map_err(|e| GradesError::Export(format!("the committed {} is not a grades snapshot: {}", JSON_FILE, e)))?)
+  } else {
+    None
+  };
+
+  let sorted = sorted(grades);
+  fs::write(dir.join(JSON_FILE), json(&sorted, gpa))?;
+  fs::write(dir.join(CSV_FILE), crate::format::csv(&sorted, None, None)?)?;
+
+  git_ok(dir, &["add", JSON_FILE, CSV_FILE])?;
+  if git(dir, &["diff", "--cached", "--quiet", "--", JSON_FILE, CSV_FILE])?.status.success() {
+    return Ok(None);
+  }
+
+  let changes = previous.map(|p| diff::diff(&p.grades, &sorted));
+  let message = message(changes.as_deref(), sorted.len(), gpa);
+  // cron jobs often run without a git identity set up
+  let mut commit = Vec::new();
+  if !git(dir, &["config", "user.email"])?.status.success() {
+    commit.extend(["-c", "user.name=grades_list", "-c", "user.email=grades_list@localhost"]);
+  }
+  commit.extend(["commit", "--quiet", "-m", &message, "--", JSON_FILE, CSV_FILE]);
+  git_ok(dir, &commit)?;
+
+  Ok(Some(message))
+}
@@ -0,0 +1,115 @@
+use std::cmp::Ordering;
+use std::str::FromStr;
+
+use schemars::JsonSchema;
+use serde::Serialize;
+
+use crate::{CourseData, Gpa, nine_point_scale};
+use crate::repeats::mark_repeats;
+use crate::sessions;
+
+/// What to order the courses by, instead of the course list's own order
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SortKey {
+  Grade,
+  Course,
+  Session,
+}
+
+impl SortKey {
+  pub const NAMES: &'static [&'static str] = &["grade", "course", "session"];
+}
+
+impl FromStr for SortKey {
+  type Err = String;
+
+  fn from_str (s: &str) -> Result<SortKey, String> {
+    match s {
+      "grade" => Ok(SortKey::Grade),
+      "course" => Ok(SortKey::Course),
+      "session" => Ok(SortKey::Session),
+      _ => Err(format!("unknown sort key \"{}\", expected one of {}", s, SortKey::NAMES.join(", "))),
+    }
+  }
+}
+
+fn compare (key: SortKey, a: &CourseData, b: &CourseData) -> Ordering {
+  match key {
+    SortKey::Grade => {
+      // grades that aren't letters, like W or an empty grade, come before every letter
+      let scale = nine_point_scale();
+      let points = |c: &CourseData| scale.get(&c.grade).copied().unwrap_or(-1.0);
+      points(a).partial_cmp(&points(b)).unwrap_or(Ordering::Equal)
+    },
+    SortKey::Course => a.code().cmp(&b.code()).then_with(|| a.course.cmp(&b.course)),
+    SortKey::Session => sessions::order(&a.session).cmp(&sessions::order(&b.session)),
+  }
+}
+
+/// Sorts the courses by `key`, courses that compare equal keep their order
+pub fn sort (grades: &mut [CourseData], key: SortKey, descending: bool) {
+  grades.sort_by(|a, b| {
+    let ordering = compare(key, a, b);
+    if descending { ordering.reverse() } else { ordering }
+  });
+}
+
+/// Splits the courses into their sessions, in chronological order
+pub fn by_session (grades: &[CourseData]) -> Vec<(String, Vec<CourseData>)> {
+  let mut groups: Vec<(String, Vec<CourseData>)> = Vec::new();
+
+  for course in grades {
+    match groups.iter_mut().find(|(session, _)| *session == course.session) {
+      Some((_, courses)) => courses.push(course.clone()),
+      None => groups.push((course.session.clone(), vec![course.clone()])),
+    }
+  }
+
+  groups.sort_by_key(|(session, _)| sessions::order(session));
+  groups
+}
+
+/// Cumulative GPA as it stood after a session
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct CumulativeGpa {
+  pub session: String,
+  /// `None` until a session has a course counting towards the GPA
+  pub gpa: Option<Gpa>,
+}
+
+/// The cumulative GPA after each session, in chronological order
+///
+/// Repeats count the way they did at the time, an attempt keeps counting until the session the
+/// course was retaken in. `all_attempts` counts every attempt, like `--count-all-attempts`.
+pub fn cumulative (grades: &[CourseData], all_attempts: bool) -> Vec<CumulativeGpa> {
+  let mut so_far: Vec<CourseData> = Vec::new();
+
+  by_session(grades).into_iter().map(|(session, courses)| {
+    so_far.extend(courses);
+    if !all_attempts {
+      mark_repeats(&mut so_far);
+    }
+
+    CumulativeGpa { session, gpa: crate::calculate_gpa(&so_far) }
+  }).collect()
+}
+
+/// The courses of the most recent sessions, going back until they hold `credits` counted credits
+///
+/// Only whole sessions are taken, the session crossing the limit is included in full the way
+/// admissions committees usually count the last two years. Fewer credits than that gives every
+/// course.
+pub fn last_credits (grades: &[CourseData], credits: f32) -> Vec<CourseData> {
+  let mut recent = Vec::new();
+  let mut counted = 0.0;
+
+  for (_, courses) in by_session(grades).into_iter().rev() {
+    if counted >= credits {
+      break;
+    }
+    counted += crate::gpa_credits(&courses);
+    recent.splice(0..0, courses);
+  }
+
+  recent
+}
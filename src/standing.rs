@@ -0,0 +1,87 @@
+use schemars::JsonSchema;
+use serde::Serialize;
+
+use crate::Gpa;
+
+/// Academic standing implied by a cumulative GPA, best first
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Standing {
+  /// High enough to stay in and graduate from an honours program
+  Honours,
+  Good,
+  Warning,
+  Probation,
+  /// No course counts towards the GPA yet, so there is nothing to assess
+  NotAssessed,
+}
+
+/// Lowest cumulative nine point GPA of each standing, following York's academic standing policy
+pub const THRESHOLDS: &[(Standing, f32)] = &[
+  (Standing::Honours, 5.0),
+  (Standing::Good, 4.0),
+  (Standing::Warning, 2.0),
+  (Standing::Probation, 0.0),
+];
+
+impl Standing {
+  /// Standing of a nine point GPA, `None` while there is no GPA
+  pub fn of (nine: Option<f32>) -> Standing {
+    match nine {
+      Some(nine) => THRESHOLDS.iter().find(|(_, min)| nine >= *min).map(|(standing, _)| *standing).unwrap_or(Standing::Probation),
+      None => Standing::NotAssessed,
+    }
+  }
+
+  pub fn describe (self) -> &'static str {
+    match self {
+      Standing::Honours => "honours standing",
+      Standing::Good => "good standing",
+      Standing::Warning => "academic warning",
+      Standing::Probation => "academic probation",
+      Standing::NotAssessed => "not assessed yet",
+    }
+  }
+
+  fn threshold (self) -> f32 {
+    THRESHOLDS.iter().find(|(standing, _)| *standing == self).map(|(_, min)| *min).unwrap_or(0.0)
+  }
+}
+
+/// The standing one level up and how much the GPA has to rise to reach it
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct NextStanding {
+  pub standing: Standing,
+  pub gpa: f32,
+  pub needed: f32,
+}
+
+/// Where a cumulative GPA stands and how close it is to the standings around it
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct StandingReport {
+  pub standing: Standing,
+  /// `None` while no course counts towards the GPA
+  pub gpa: Option<f32>,
+  /// `None` in honours standing, or with no GPA
+  pub next: Option<NextStanding>,
+  /// How far the GPA can drop before the standing does, `None` on probation or with no GPA
+  pub margin: Option<f32>,
+}
+
+/// Standing of the cumulative GPA on the nine point scale York's thresholds use
+pub fn report (gpa: Option<&Gpa>) -> StandingReport {
+  let gpa = match gpa {
+    Some(gpa) => gpa,
+    None => return StandingReport { standing: Standing::NotAssessed, gpa: None, next: None, margin: None },
+  };
+  let standing = Standing::of(Some(gpa.nine));
+  let idx = THRESHOLDS.iter().position(|(s, _)| *s == standing).unwrap_or(0);
+
+  let next = idx.checked_sub(1).map(|up| {
+    let (standing, min) = THRESHOLDS[up];
+    NextStanding { standing, gpa: min, needed: min - gpa.nine }
+  });
+  let margin = if standing == Standing::Probation { None } else { Some(gpa.nine - standing.threshold()) };
+
+  StandingReport { standing, gpa: Some(gpa.nine), next, margin }
+}
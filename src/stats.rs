@@ -0,0 +1,140 @@
+use serde::Serialize;
+
+use crate::{CourseData, Gpa, nine_point_scale};
+use crate::classify::{GradeKind, earns_credit};
+use crate::scale::LETTERS;
+
+/// How many courses got a letter grade
+#[derive(Debug, Clone, Serialize)]
+pub struct GradeCount {
+  pub grade: &'static str,
+  pub count: usize,
+}
+
+/// The GPA of a single session
+#[derive(Debug, Clone, Serialize)]
+pub struct SessionGpa {
+  pub session: String,
+  pub gpa: Gpa,
+}
+
+/// Credits and GPA of the courses at one level, like the 3000 level
+#[derive(Debug, Clone, Serialize)]
+pub struct LevelGpa {
+  /// `"3000"`, or `"3000+"` for everything at the 3000 level and above
+  pub level: String,
+  /// Credits counted in the GPA
+  pub credits: f32,
+  pub gpa: Option<Gpa>,
+}
+
+/// Summary of a grade list, see `stats()`
+#[derive(Debug, Clone, Serialize)]
+pub struct Stats {
+  /// Every letter grade best first, including the ones never received
+  pub distribution: Vec<GradeCount>,
+  /// Credit weighted mean, the GPA
+  pub mean: Option<Gpa>,
+  /// Middle letter grade, the lower of the two middle ones with an even number of grades
+  pub median: Option<&'static str>,
+  /// Credits of courses with a final grade other than `W`
+  pub credits_attempted: f32,
+  /// Credits of passed courses, counting a repeated course once
+  pub credits_earned: f32,
+  pub best_session: Option<SessionGpa>,
+  pub worst_session: Option<SessionGpa>,
+  /// Each level with graded courses, then the upper year courses together
+  pub levels: Vec<LevelGpa>,
+}
+
+/// Grade distribution, median, credit totals and best and worst sessions of the courses
+///
+/// Attempts replaced by a retake are left out of the distribution and median like they are
+/// left out of the GPA, but their credits still count as attempted.
+pub fn stats (grades: &[CourseData]) -> Stats {
+  let nine = nine_point_scale();
+  let letters = grades.iter()
+    .filter(|c| GradeKind::of(&c.grade) == GradeKind::Letter && !c.superseded)
+    .collect::<Vec<_>>();
+
+  let distribution = LETTERS.iter()
+    .map(|&grade| GradeCount { grade, count: letters.iter().filter(|c| c.grade == grade).count() })
+    .collect::<Vec<_>>();
+
+  let mut ranked = letters.iter()
+    .filter_map(|c| LETTERS.iter().find(|&&l| l == c.grade))
+    .copied()
+    .collect::<Vec<_>>();
+  ranked.sort_by(|a, b| nine[*a].partial_cmp(&nine[*b]).unwrap());
+  let median = if ranked.is_empty() { None } else { Some(ranked[(ranked.len() - 1) / 2]) };
+
+  let mut credits_attempted = 0.0;
+  let mut credits_earned = 0.0;
+  for course in grades {
+    let credits = course.credits.unwrap_or(0.0);
+
+    if matches!(GradeKind::of(&course.grade), GradeKind::Letter | GradeKind::PassFail) {
+      credits_attempted += credits;
+    }
+    if earns_credit(course) {
+      credits_earned += credits;
+    }
+  }
+
+  let sessions = crate::chart::session_gpas(grades);
+  // the earliest session wins a tie
+  let best = sessions.iter().fold(None, |best: Option<&(String, Gpa)>, s| match best {
+    Some(b) if b.1.nine >= s.1.nine => Some(b),
+    _ => Some(s),
+  });
+  let worst = sessions.iter().fold(None, |worst: Option<&(String, Gpa)>, s| match worst {
+    Some(w) if w.1.nine <= s.1.nine => Some(w),
+    _ => Some(s),
+  });
+  let session_gpa = |s: Option<&(String, Gpa)>| s.map(|(session, gpa)| SessionGpa { session: session.clone(), gpa: gpa.clone() });
+
+  Stats {
+    distribution,
+    mean: if sessions.is_empty() { None } else { crate::calculate_gpa(grades) },
+    median,
+    credits_attempted,
+    credits_earned,
+    best_session: session_gpa(best),
+    worst_session: session_gpa(worst),
+    levels: levels(grades),
+  }
+}
+
+fn level_gpa (level: String, grades: &[CourseData]) -> LevelGpa {
+  let credits = crate::gpa_credits(grades);
+  let gpa = crate::calculate_gpa(grades);
+
+  LevelGpa { level, credits, gpa }
+}
+
+/// Credits and GPA per course level from the catalog numbers, and of the 3000 level and above
+///
+/// Many graduate programs look at the upper year GPA on its own. Courses without a catalog number
+/// are left out.
+pub fn levels (grades: &[CourseData]) -> Vec<LevelGpa> {
+  let mut levels = grades.iter().filter_map(|c| c.level()).collect::<Vec<_>>();
+  levels.sort_unstable();
+  levels.dedup();
+
+  let at = |keep: &dyn Fn(u32) -> bool| grades.iter()
+    .filter(|c| c.level().is_some_and(keep))
+    .cloned()
+    .collect::<Vec<_>>();
+
+  let mut gpas = levels.into_iter()
+    .map(|level| level_gpa(level.to_string(), &at(&|l| l == level)))
+    .filter(|l| l.credits > 0.0)
+    .collect::<Vec<_>>();
+
+  let upper = level_gpa("3000+".to_owned(), &at(&|l| l >= 3000));
+  if upper.credits > 0.0 {
+    gpas.push(upper);
+  }
+
+  gpas
+}
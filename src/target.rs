@@ -0,0 +1,53 @@
+use serde::Serialize;
+
+use crate::{CourseData, GradesError, four_point_scale, gpa_credits, nine_point_scale};
+use crate::scale::{GradeScale, LETTERS};
+
+/// What is needed in the remaining credits to reach a cumulative GPA
+#[derive(Debug, Clone, Serialize)]
+pub struct TargetGpa {
+  /// Name of the scale the GPAs are on
+  pub scale: String,
+  pub target: f32,
+  pub current: f32,
+  pub remaining_credits: f32,
+  /// Average needed over the remaining credits, on `scale`
+  pub required: f32,
+  /// Lowest letter grade whose points meet `required`, `None` if even A+ falls short
+  pub grade: Option<String>,
+  /// Points of `grade` on the nine point scale
+  pub nine: Option<f32>,
+  /// Points of `grade` on the four point scale
+  pub four: Option<f32>,
+}
+
+/// Solves for the average grade needed over `remaining_credits` to reach `target` on `scale`
+pub fn solve (grades: &[CourseData], target: f32, scale: &GradeScale, remaining_credits: f32) -> Result<TargetGpa, GradesError> {
+  if remaining_credits <= 0.0 {
+    return Err(GradesError::Config("the remaining credits must be more than zero".to_owned()));
+  }
+
+  let credits = gpa_credits(grades);
+  let current = scale.gpa(grades).unwrap_or(0.0);
+
+  let required = (target * (credits + remaining_credits) - current * credits) / remaining_credits;
+
+  // only York's letters, a custom scale can have more that aren't on the nine and four point scales
+  let mut letters = scale.points.iter().filter(|(letter, _)| LETTERS.contains(&letter.as_str())).collect::<Vec<_>>();
+  letters.sort_by(|a, b| a.1.total_cmp(b.1));
+
+  let grade = letters.iter()
+    .find(|(_, &p)| p >= required)
+    .map(|(letter, _)| (*letter).clone());
+
+  Ok(TargetGpa {
+    scale: scale.name.clone(),
+    target,
+    current,
+    remaining_credits,
+    required,
+    nine: grade.as_ref().and_then(|g| nine_point_scale().get(g).cloned()),
+    four: grade.as_ref().and_then(|g| four_point_scale().get(g).cloned()),
+    grade,
+  })
+}
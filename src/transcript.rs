@@ -0,0 +1,81 @@
+use std::collections::HashMap;
+use std::str::FromStr;
+
+use crate::{CourseData, GradesError, ParseMode, Parsed, Selectors};
+
+/// Page of SIS the grades are read from
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Source {
+  /// The course list, which has the newest grades first
+  CourseList,
+  /// The grade report, with the grade points and notes the course list leaves out
+  Transcript,
+  /// The course list with the grade points and notes of the transcript added
+  Merged,
+}
+
+impl Source {
+  pub const NAMES: &'static [&'static str] = &["courselist", "transcript", "merged"];
+
+  /// The name `--source` takes
+  pub fn name (self) -> &'static str {
+    match self {
+      Source::CourseList => "courselist",
+      Source::Transcript => "transcript",
+      Source::Merged => "merged",
+    }
+  }
+}
+
+impl FromStr for Source {
+  type Err = String;
+
+  fn from_str (s: &str) -> Result<Source, String> {
+    match s {
+      "courselist" => Ok(Source::CourseList),
+      "transcript" => Ok(Source::Transcript),
+      "merged" => Ok(Source::Merged),
+      _ => Err(format!("unknown source \"{}\", expected one of {}", s, Source::NAMES.join(", "))),
+    }
+  }
+}
+
+/// Parses a saved copy of the grade report
+///
+/// Its table has the course list's Session, Course, Title and Grade columns along with Credits,
+/// Grade Points and Notes, so it is read by the same parser.
+pub fn parse (transcript_page: &str, selectors: &Selectors, mode: ParseMode) -> Result<Parsed, GradesError> {
+  crate::parse_courses_with(transcript_page, selectors, mode)
+}
+
+fn key (course: &CourseData) -> (String, String) {
+  (course.session.clone(), course.code())
+}
+
+/// The course list with the grade points, notes and any missing credits of the same course in
+/// the same session from the transcript
+///
+/// Courses only on the transcript are added after the others. The course list's grade is kept,
+/// it shows new grades before the transcript does.
+pub fn merge (course_list: Vec<CourseData>, transcript: Vec<CourseData>) -> Vec<CourseData> {
+  let mut on_transcript = transcript.into_iter().map(|c| (key(&c), c)).collect::<HashMap<_, _>>();
+  let mut order = on_transcript.keys().cloned().collect::<Vec<_>>();
+  order.sort_by(|a, b| crate::sessions::order(&a.0).cmp(&crate::sessions::order(&b.0)).then_with(|| a.cmp(b)));
+
+  let mut merged = course_list.into_iter().map(|mut course| {
+    if let Some(from_transcript) = on_transcript.remove(&key(&course)) {
+      course.grade_points = from_transcript.grade_points;
+      course.notes = from_transcript.notes;
+      course.credits = course.credits.or(from_transcript.credits);
+    }
+    course
+  }).collect::<Vec<_>>();
+
+  merged.extend(order.iter().filter_map(|k| on_transcript.remove(k)));
+  for course in merged.iter_mut() {
+    course.superseded = false;
+  }
+  crate::repeats::mark_repeats(&mut merged);
+
+  merged
+}
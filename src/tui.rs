@@ -0,0 +1,275 @@
+use std::collections::BTreeMap;
+use std::sync::mpsc::{Receiver, TryRecvError};
+use std::time::Duration;
+
+use chrono::{DateTime, Local};
+use ratatui::Frame;
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Modifier, Style};
+use ratatui::text::Line;
+use ratatui::widgets::{Block, Borders, Cell, Paragraph, Row, Table, TableState};
+
+use crate::{CourseData, GradesError};
+
+const COLUMNS: [&str; 5] = ["Session", "Course", "Title", "Grade", "Credits"];
+
+/// Interactive view of the grades, kept separate from the terminal so it can be reasoned about on its own
+pub struct App {
+  grades: Vec<CourseData>,
+  fetched_at: DateTime<Local>,
+  sort: usize,
+  descending: bool,
+  session: Option<String>,
+  subject: Option<String>,
+  table: TableState,
+  refreshing: bool,
+  error: Option<String>,
+}
+
+/// Nine point value of a grade for sorting, grades outside the scale sort first
+fn grade_rank (grade: &str) -> f32 {
+  crate::nine_point_scale().get(grade).copied().unwrap_or(-1.0)
+}
+
+/// The value after `current` in `values`, wrapping around through `None`
+fn cycle (values: &[String], current: &Option<String>) -> Option<String> {
+  match current {
+    None => values.first().cloned(),
+    Some(current) => values.iter().skip_while(|v| *v != current).nth(1).cloned(),
+  }
+}
+
+impl App {
+  pub fn new (grades: Vec<CourseData>) -> App {
+    let mut table = TableState::default();
+    table.select(Some(0));
+
+    App {
+      grades,
+      fetched_at: Local::now(),
+      sort: 0,
+      descending: false,
+      session: None,
+      subject: None,
+      table,
+      refreshing: false,
+      error: None,
+    }
+  }
+
+  /// Courses passing the filters in the chosen order
+  pub fn visible (&self) -> Vec<&CourseData> {
+    let mut visible = self.grades.iter()
+      .filter(|c| self.session.as_ref().is_none_or(|s| &c.session == s))
+      .filter(|c| self.subject.as_ref().is_none_or(|s| &c.subject == s))
+      .collect::<Vec<_>>();
+
+    // stable, so rows that compare equal keep the course list order
+    visible.sort_by(|a, b| {
+      let ordering = match self.sort {
+        0 => a.session.cmp(&b.session),
+        1 => a.course.cmp(&b.course),
+        2 => a.title.cmp(&b.title),
+        3 => grade_rank(&a.grade).partial_cmp(&grade_rank(&b.grade)).unwrap(),
+        _ => a.credits.partial_cmp(&b.credits).unwrap(),
+      };
+      if self.descending { ordering.reverse() } else { ordering }
+    });
+
+    visible
+  }
+
+  fn distinct (&self, field: fn(&CourseData) -> &String) -> Vec<String> {
+    let mut values = self.grades.iter().map(|c| field(c).clone()).filter(|v| !v.is_empty()).collect::<Vec<_>>();
+    values.sort();
+    values.dedup();
+    values
+  }
+
+  /// Sorts by the column, or flips the order when it is already sorted by it
+  fn sort_by (&mut self, column: usize) {
+    if self.sort == column {
+      self.descending = !self.descending;
+    } else {
+      self.sort = column;
+      self.descending = false;
+    }
+  }
+
+  /// The highlighted course
+  pub fn selected (&self) -> Option<&CourseData> {
+    self.visible().get(self.table.selected()?).copied()
+  }
+
+  fn scroll (&mut self, by: i64) {
+    let last = self.visible().len().saturating_sub(1) as i64;
+    let current = self.table.selected().unwrap_or(0) as i64;
+    self.table.select(Some((current + by).clamp(0, last.max(0)) as usize));
+  }
+
+  /// Handles a key press, returns false once the user wants to quit
+  ///
+  /// `r` only marks the grades as refreshing, fetching them is left to the caller.
+  pub fn key (&mut self, code: KeyCode) -> bool {
+    match code {
+      KeyCode::Char('q') | KeyCode::Esc => return false,
+      KeyCode::Down | KeyCode::Char('j') => self.scroll(1),
+      KeyCode::Up | KeyCode::Char('k') => self.scroll(-1),
+      KeyCode::PageDown => self.scroll(10),
+      KeyCode::PageUp => self.scroll(-10),
+      KeyCode::Home | KeyCode::Char('g') => self.table.select(Some(0)),
+      KeyCode::End | KeyCode::Char('G') => self.scroll(i64::MAX / 2),
+      KeyCode::Char(c @ '1'..='5') => self.sort_by(c as usize - '1' as usize),
+      KeyCode::Char('s') => {
+        self.session = cycle(&self.distinct(|c| &c.session), &self.session);
+        self.table.select(Some(0));
+      },
+      KeyCode::Char('u') => {
+        self.subject = cycle(&self.distinct(|c| &c.subject), &self.subject);
+        self.table.select(Some(0));
+      },
+      KeyCode::Char('c') => {
+        self.session = None;
+        self.subject = None;
+      },
+      KeyCode::Char('r') => self.refreshing = true,
+      _ => {},
+    }
+
+    true
+  }
+
+  pub fn refreshing (&self) -> bool {
+    self.refreshing
+  }
+
+  /// Takes in the result of a refresh, keeping the grades shown when it failed
+  pub fn refreshed (&mut self, result: Result<Vec<CourseData>, GradesError>) {
+    self.refreshing = false;
+    match result {
+      Ok(grades) => {
+        self.grades = grades;
+        self.fetched_at = Local::now();
+        self.error = None;
+        self.scroll(0);
+      },
+      Err(e) => self.error = Some(e.to_string()),
+    }
+  }
+
+  pub fn draw (&mut self, frame: &mut Frame) {
+    let [body, status] = Layout::vertical([Constraint::Min(0), Constraint::Length(2)]).areas(frame.area());
+    let [grades_area, sessions_area] = Layout::horizontal([Constraint::Min(0), Constraint::Length(36)]).areas(body);
+
+    let header = Row::new(COLUMNS.iter().enumerate().map(|(i, name)| {
+      let arrow = match (i == self.sort, self.descending) {
+        (true, false) => " ▲",
+        (true, true) => " ▼",
+        (false, _) => "",
+      };
+      Cell::from(format!("{}{}", name, arrow))
+    })).style(Style::default().add_modifier(Modifier::BOLD));
+
+    let rows = self.visible().into_iter().map(|c| Row::new(vec![
+      c.session.clone(),
+      c.course.clone(),
+      c.title.clone(),
+      crate::format::display_grade(c),
+      c.credits.map(|credits| credits.to_string()).unwrap_or_default(),
+    ])).collect::<Vec<_>>();
+
+    let mut title = "Grades".to_owned();
+    if let Some(ref session) = self.session {
+      title.push_str(&format!(" - {}", session));
+    }
+    if let Some(ref subject) = self.subject {
+      title.push_str(&format!(" - {}", subject));
+    }
+
+    let table = Table::new(rows, [
+      Constraint::Length(14),
+      Constraint::Length(28),
+      Constraint::Min(20),
+      Constraint::Length(12),
+      Constraint::Length(8),
+    ])
+      .header(header)
+      .block(Block::default().borders(Borders::ALL).title(title))
+      .row_highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+    frame.render_stateful_widget(table, grades_area, &mut self.table);
+
+    frame.render_widget(self.sessions(), sessions_area);
+
+    let state = match (self.refreshing, &self.error) {
+      (true, _) => "Refreshing...".to_owned(),
+      (false, Some(e)) => format!("Refresh failed: {}", e),
+      (false, None) => format!("Fetched {}", self.fetched_at.format("%Y-%m-%d %H:%M")),
+    };
+    let help = "q quit  j/k scroll  1-5 sort  s session  u subject  c clear  r refresh";
+    frame.render_widget(Paragraph::new(vec![Line::from(state), Line::from(help)]), status);
+  }
+
+  /// Overall and per session GPA
+  fn sessions (&self) -> Table<'static> {
+    let mut sessions = BTreeMap::new();
+    for course in &self.grades {
+      sessions.entry(course.session.clone()).or_insert_with(Vec::new).push(course.clone());
+    }
+
+    let gpa_row = |name: String, grades: &[CourseData]| {
+      crate::calculate_gpa(grades).map(|gpa| Row::new(vec![name, format!("{:.2}", gpa.four), format!("{:.2}", gpa.nine)]))
+    };
+
+    let mut rows = sessions.into_iter().filter_map(|(session, grades)| gpa_row(session, &grades)).collect::<Vec<_>>();
+    rows.extend(gpa_row("Overall".to_owned(), &self.grades).map(|row| row.style(Style::default().add_modifier(Modifier::BOLD))));
+
+    Table::new(rows, [Constraint::Min(14), Constraint::Length(6), Constraint::Length(6)])
+      .header(Row::new(vec!["Session", "Four", "Nine"]).style(Style::default().add_modifier(Modifier::BOLD)))
+      .block(Block::default().borders(Borders::ALL).title("GPA"))
+  }
+}
+
+/// Runs the dashboard until the user quits
+///
+/// Pressing `r` calls `refresh`, which should start fetching the grades in the background and send
+/// the result over the channel it is given the other end of as `results`. Blocks, so call it from a
+/// blocking task.
+pub fn run<F> (grades: Vec<CourseData>, mut refresh: F, results: Receiver<Result<Vec<CourseData>, GradesError>>) -> Result<(), GradesError>
+  where F: FnMut() {
+  let mut terminal = ratatui::init();
+  let mut app = App::new(grades);
+
+  let result = (|| -> Result<(), GradesError> {
+    loop {
+      terminal.draw(|frame| app.draw(frame))?;
+
+      match results.try_recv() {
+        Ok(result) => app.refreshed(result),
+        Err(TryRecvError::Empty) | Err(TryRecvError::Disconnected) => {},
+      }
+
+      if !event::poll(Duration::from_millis(200))? {
+        continue;
+      }
+
+      if let Event::Key(key) = event::read()? {
+        if key.kind != KeyEventKind::Press {
+          continue;
+        }
+
+        let refreshing = app.refreshing;
+        if !app.key(key.code) {
+          return Ok(());
+        }
+        if app.refreshing && !refreshing {
+          refresh();
+        }
+      }
+    }
+  })();
+
+  // the terminal has to be given back even when drawing failed
+  ratatui::restore();
+  result
+}
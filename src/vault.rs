@@ -0,0 +1,123 @@
+use std::convert::TryInto;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use chacha20poly1305::aead::{Aead, NewAead};
+use serde::{Deserialize, Serialize};
+
+use crate::{GradesError, keychain};
+
+const NONCE_LEN: usize = 12;
+const SALT_LEN: usize = 16;
+// sealed with a passphrase's key, so a mistyped passphrase is caught before anything is written with it
+const CHECK: &[u8] = b"grades_list";
+
+/// Where the key of the grades kept between runs comes from, `encryption` in the config
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Encryption {
+  /// A random key kept in the OS keychain, the one saved sessions are always encrypted with
+  Keychain,
+  /// A key derived from a passphrase, for machines without a keychain
+  Passphrase,
+}
+
+/// The salt of the passphrase and `CHECK` sealed with the key it gave
+#[derive(Debug, Serialize, Deserialize)]
+struct Derivation {
+  salt: String,
+  check: String,
+}
+
+/// A key encrypting what is kept in the home directory between runs: the session, the cached
+/// scrapes and the history
+#[derive(Clone)]
+pub struct Vault {
+  cipher: ChaCha20Poly1305,
+}
+
+impl fmt::Debug for Vault {
+  fn fmt (&self, f: &mut fmt::Formatter) -> fmt::Result {
+    f.write_str("Vault")
+  }
+}
+
+/// Where the salt of the passphrase is kept, next to the history
+pub fn default_passphrase_path () -> Option<PathBuf> {
+  dirs::data_dir().map(|dir| dir.join("grades_list").join("passphrase.json"))
+}
+
+impl Vault {
+  pub fn from_key (key: [u8; 32]) -> Vault {
+    Vault { cipher: ChaCha20Poly1305::new(&Key::from(key)) }
+  }
+
+  /// The key kept in the OS keychain, generated the first time it is needed
+  pub fn from_keychain () -> Result<Vault, GradesError> {
+    Ok(Vault::from_key(keychain::session_key()?))
+  }
+
+  /// A key derived from the passphrase with Argon2, with the salt saved to `path` the first time
+  ///
+  /// Fails with `GradesError::Storage` when the passphrase isn't the one the salt was saved with.
+  pub fn from_passphrase (passphrase: &str, path: &Path) -> Result<Vault, GradesError> {
+    let unreadable = |e: &dyn fmt::Display| GradesError::Storage(format!("could not read {}: {}", path.display(), e));
+
+    match fs::read(path) {
+      Ok(data) => {
+        let derivation: Derivation = serde_json::from_slice(&data).map_err(|e| unreadable(&e))?;
+        let salt = base64::decode(&derivation.salt).map_err(|e| unreadable(&e))?;
+        let check = base64::decode(&derivation.check).map_err(|e| unreadable(&e))?;
+
+        let vault = derive(passphrase, &salt)?;
+        match vault.open(&check) {
+          Ok(check) if check == CHECK => Ok(vault),
+          _ => Err(GradesError::Storage("the passphrase is wrong, it isn't the one the grades were encrypted with".to_owned())),
+        }
+      },
+      Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+        let salt: [u8; SALT_LEN] = rand::random();
+        let vault = derive(passphrase, &salt)?;
+        let derivation = Derivation { salt: base64::encode(salt), check: base64::encode(vault.seal(CHECK)) };
+
+        if let Some(dir) = path.parent() {
+          fs::create_dir_all(dir)?;
+        }
+        fs::write(path, serde_json::to_vec_pretty(&derivation).expect("the salt serializes"))?;
+        Ok(vault)
+      },
+      Err(e) => Err(e.into()),
+    }
+  }
+
+  /// Encrypts the data with a random nonce, which is kept in front of it
+  pub fn seal (&self, plaintext: &[u8]) -> Vec<u8> {
+    let nonce: [u8; NONCE_LEN] = rand::random();
+    let ciphertext = self.cipher.encrypt(&Nonce::from(nonce), plaintext).expect("encrypting can't fail");
+
+    let mut sealed = nonce.to_vec();
+    sealed.extend(ciphertext);
+    sealed
+  }
+
+  /// Decrypts what `seal()` returned, failing when it was sealed with another key or altered
+  pub fn open (&self, sealed: &[u8]) -> Result<Vec<u8>, GradesError> {
+    let undecryptable = || GradesError::Storage("could not decrypt the stored data, it was encrypted with another key".to_owned());
+    if sealed.len() < NONCE_LEN {
+      return Err(undecryptable());
+    }
+
+    let (nonce, ciphertext) = sealed.split_at(NONCE_LEN);
+    let nonce: [u8; NONCE_LEN] = nonce.try_into().unwrap();
+    self.cipher.decrypt(&Nonce::from(nonce), ciphertext).map_err(|_| undecryptable())
+  }
+}
+
+fn derive (passphrase: &str, salt: &[u8]) -> Result<Vault, GradesError> {
+  let mut key = [0u8; 32];
+  argon2::Argon2::default().hash_password_into(passphrase.as_bytes(), salt, &mut key)
+    .map_err(|e| GradesError::Storage(format!("could not derive a key from the passphrase: {}", e)))?;
+  Ok(Vault::from_key(key))
+}
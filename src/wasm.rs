@@ -0,0 +1,34 @@
+use js_sys::JSON;
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use wasm_bindgen::prelude::*;
+
+use crate::CourseData;
+
+fn to_js_err (e: impl std::fmt::Display) -> JsError {
+  JsError::new(&e.to_string())
+}
+
+// values cross as JSON text, so the objects have the keys and numbers of the JSON output
+fn to_js<T: Serialize> (value: &T) -> Result<JsValue, JsError> {
+  JSON::parse(&serde_json::to_string(value).map_err(to_js_err)?).map_err(|_| JsError::new("could not hand the value to JavaScript"))
+}
+
+fn from_js<T: DeserializeOwned> (value: &JsValue) -> Result<T, JsError> {
+  let json = JSON::stringify(value).map_err(|_| JsError::new("the value is not JSON"))?;
+  serde_json::from_str(&String::from(json)).map_err(to_js_err)
+}
+
+/// Parses the HTML of the course list, like the `document.documentElement.outerHTML` of a loaded
+/// SIS page, into an array of courses
+#[wasm_bindgen(js_name = parseCourses)]
+pub fn parse_courses (html: &str) -> Result<JsValue, JsError> {
+  to_js(&crate::parse_courses(html).map_err(to_js_err)?)
+}
+
+/// The four point and nine point GPA of courses from `parseCourses()`, null while none counts towards it
+#[wasm_bindgen(js_name = calculateGpa)]
+pub fn calculate_gpa (grades: JsValue) -> Result<JsValue, JsError> {
+  let grades: Vec<CourseData> = from_js(&grades)?;
+  to_js(&crate::calculate_gpa(&grades))
+}
@@ -0,0 +1,82 @@
+use std::time::Duration;
+
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use serde::{Deserialize, Serialize};
+
+use crate::{Gpa, GradesError};
+use crate::diff::GradeChange;
+
+const ATTEMPTS: u32 = 4;
+
+/// JSON body posted to the webhook when grades change
+#[derive(Debug, Serialize)]
+pub struct ChangePayload<'a> {
+  pub changes: &'a [GradeChange],
+  pub gpa: Option<&'a Gpa>,
+}
+
+/// `[notify.webhook]` in the config file, like `--notify-webhook`
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct WebhookConfig {
+  pub url: String,
+  /// `"Name: value"` headers sent with every request
+  #[serde(default)]
+  pub headers: Vec<String>,
+}
+
+/// A user supplied URL that grade changes are POSTed to
+pub struct Webhook {
+  client: reqwest::Client,
+  url: String,
+  headers: HeaderMap,
+}
+
+impl Webhook {
+  /// Creates a webhook sending the given `Name: value` headers with every request
+  pub fn new (url: &str, headers: &[String]) -> Result<Webhook, GradesError> {
+    let mut header_map = HeaderMap::new();
+
+    for header in headers {
+      let idx = header.find(':')
+        .ok_or_else(|| GradesError::Notify(format!("header \"{}\" is not in the form \"Name: value\"", header)))?;
+
+      let name = HeaderName::from_bytes(header[..idx].trim().as_bytes())
+        .map_err(|e| GradesError::Notify(format!("invalid header name in \"{}\": {}", header, e)))?;
+      let value = HeaderValue::from_str(header[idx + 1..].trim())
+        .map_err(|e| GradesError::Notify(format!("invalid header value in \"{}\": {}", header, e)))?;
+
+      header_map.append(name, value);
+    }
+
+    Ok(Webhook { client: reqwest::Client::new(), url: url.to_owned(), headers: header_map })
+  }
+
+  /// Posts the payload as JSON, retrying with a growing delay if the request fails
+  pub async fn send<T: Serialize> (&self, payload: &T) -> Result<(), GradesError> {
+    let mut last_error = None;
+
+    for attempt in 0..ATTEMPTS {
+      if attempt > 0 {
+        tokio::time::sleep(Duration::from_secs(1 << (attempt - 1))).await;
+      }
+
+      let resp = self.client.post(&self.url)
+        .headers(self.headers.clone())
+        .json(payload)
+        .send().await;
+
+      match resp {
+        Ok(resp) if resp.status().is_success() => return Ok(()),
+        Ok(resp) => last_error = Some(GradesError::Notify(format!("webhook responded with {}", resp.status()))),
+        // only the cause is shown, the URL can hold a secret like a bot token
+        Err(e) => {
+          let cause = std::error::Error::source(&e).map(|s| s.to_string()).unwrap_or_else(|| "request failed".to_owned());
+          last_error = Some(GradesError::Notify(format!("could not reach the webhook: {}", cause)));
+        },
+      }
+    }
+
+    Err(last_error.unwrap())
+  }
+}
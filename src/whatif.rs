@@ -0,0 +1,32 @@
+use crate::{CourseData, GradesError, nine_point_scale};
+
+/// Session given to courses added with `hypothetical()`
+pub static WHAT_IF_SESSION: &str = "What-if";
+
+/// Parses a hypothetical course like `"EECS 3101 3.00 A+"` into a row that can be added to the real ones
+///
+/// The faculty is unknown so it is left as `?`. A grade that isn't a letter grade would count for
+/// nothing, so it is a config error rather than a course silently left out of the GPA.
+pub fn hypothetical (spec: &str) -> Result<CourseData, GradesError> {
+  let parts = spec.split_ascii_whitespace().collect::<Vec<_>>();
+
+  let (subject, number, credits, grade) = match parts.as_slice() {
+    [subject, number, credits, grade] => (subject, number, credits, grade),
+    _ => return Err(GradesError::Parse(format!("\"{}\" should look like \"EECS 3101 3.00 A+\"", spec))),
+  };
+
+  credits.parse::<f32>()
+    .map_err(|_| GradesError::Parse(format!("\"{}\" is not a credit value in \"{}\"", credits, spec)))?;
+
+  let grade = grade.to_ascii_uppercase();
+  if !nine_point_scale().contains_key(&grade) {
+    return Err(GradesError::Config(format!("\"{}\" is not a letter grade in \"{}\"", grade, spec)));
+  }
+
+  Ok(CourseData::new(
+    WHAT_IF_SESSION.to_owned(),
+    format!("? {} {} {}", subject.to_ascii_uppercase(), number, credits),
+    String::new(),
+    grade,
+  ))
+}
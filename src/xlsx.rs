@@ -0,0 +1,105 @@
+use rust_xlsxwriter::{Color, Format, Workbook, Worksheet, XlsxError};
+
+use crate::{CourseData, Gpa, GradesError};
+use crate::format::{display_grade, grade_style};
+
+fn xlsx_error (e: XlsxError) -> GradesError {
+  GradesError::Io(std::io::Error::other(e))
+}
+
+// the same colors as the table, from the style `format::grade_style` gives
+fn grade_format (grade: &str) -> Option<Format> {
+  let (fill, text) = match grade_style(grade)? {
+    "Fg" => (0xC6EFCE, 0x006100),
+    "Fy" => (0xFFEB9C, 0x9C5700),
+    _ => (0xFFC7CE, 0x9C0006),
+  };
+  Some(Format::new().set_background_color(Color::RGB(fill)).set_font_color(Color::RGB(text)))
+}
+
+fn header (sheet: &mut Worksheet, columns: &[&str]) -> Result<(), XlsxError> {
+  let bold = Format::new().set_bold();
+  for (col, name) in columns.iter().enumerate() {
+    sheet.write_string_with_format(0, col as u16, *name, &bold)?;
+  }
+  sheet.set_freeze_panes(1, 0)?;
+  Ok(())
+}
+
+// the f32 points would otherwise show their f64 rounding error, 3.433333397 for 3.4333334
+fn points_format () -> Format {
+  Format::new().set_num_format("0.00")
+}
+
+fn write_number (sheet: &mut Worksheet, row: u32, col: u16, value: Option<f32>, format: &Format) -> Result<(), XlsxError> {
+  match value {
+    Some(value) => sheet.write_number_with_format(row, col, value, format).map(|_| ()),
+    None => Ok(()),
+  }
+}
+
+fn grades_sheet (sheet: &mut Worksheet, grades: &[CourseData]) -> Result<(), XlsxError> {
+  sheet.set_name("Grades")?;
+  header(sheet, &["Session", "Course", "Title", "Grade", "Credits", "Nine", "Four", "Nine x Credits", "Four x Credits"])?;
+
+  let credits = Format::new();
+  let two_places = points_format();
+
+  for (i, course) in grades.iter().enumerate() {
+    let row = i as u32 + 1;
+    let points = course.points();
+
+    sheet.write_string(row, 0, &course.session)?;
+    sheet.write_string(row, 1, &course.course)?;
+    sheet.write_string(row, 2, &course.title)?;
+    match grade_format(&course.grade) {
+      Some(ref format) => sheet.write_string_with_format(row, 3, display_grade(course), format)?,
+      None => sheet.write_string(row, 3, display_grade(course))?,
+    };
+    write_number(sheet, row, 4, course.credits, &credits)?;
+    write_number(sheet, row, 5, points.as_ref().map(|p| p.nine_point), &two_places)?;
+    write_number(sheet, row, 6, points.as_ref().map(|p| p.four_point), &two_places)?;
+    write_number(sheet, row, 7, points.as_ref().and_then(|p| p.nine_point_weighted), &two_places)?;
+    write_number(sheet, row, 8, points.as_ref().and_then(|p| p.four_point_weighted), &two_places)?;
+  }
+
+  sheet.autofit();
+  Ok(())
+}
+
+fn sessions_sheet (sheet: &mut Worksheet, grades: &[CourseData], gpa: Option<&Gpa>) -> Result<(), XlsxError> {
+  sheet.set_name("GPA by Session")?;
+  header(sheet, &["Session", "Nine Point", "Four Point"])?;
+
+  let two_places = points_format();
+  let sessions = crate::chart::session_gpas(grades);
+  for (i, (session, session_gpa)) in sessions.iter().enumerate() {
+    let row = i as u32 + 1;
+    sheet.write_string(row, 0, session)?;
+    sheet.write_number_with_format(row, 1, session_gpa.nine, &two_places)?;
+    sheet.write_number_with_format(row, 2, session_gpa.four, &two_places)?;
+  }
+
+  if let Some(gpa) = gpa {
+    let total = sessions.len() as u32 + 1;
+    let bold = Format::new().set_bold();
+    sheet.write_string_with_format(total, 0, "Overall", &bold)?;
+    sheet.write_number_with_format(total, 1, gpa.nine, &two_places.clone().set_bold())?;
+    sheet.write_number_with_format(total, 2, gpa.four, &two_places.set_bold())?;
+  }
+
+  sheet.autofit();
+  Ok(())
+}
+
+/// Renders an Excel workbook with a Grades sheet and a GPA by Session sheet
+///
+/// Both have a frozen header row, and grades are colored like in the table.
+pub fn xlsx (grades: &[CourseData], gpa: Option<&Gpa>) -> Result<Vec<u8>, GradesError> {
+  let mut workbook = Workbook::new();
+
+  grades_sheet(workbook.add_worksheet(), grades).map_err(xlsx_error)?;
+  sessions_sheet(workbook.add_worksheet(), grades, gpa).map_err(xlsx_error)?;
+
+  workbook.save_to_buffer().map_err(xlsx_error)
+}
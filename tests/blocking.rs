@@ -0,0 +1,30 @@
+#![cfg(feature = "blocking")]
+
+use grades_list::Endpoints;
+use grades_list::blocking::GradesClient;
+
+use wiremock::{Mock, MockServer, ResponseTemplate};
+use wiremock::matchers::{method, path};
+
+static COURSE_PATH: &str = "/Apps/WebObjects/ydml.woa/wa/DirectAction/document";
+
+#[test]
+fn fetch_without_a_runtime () {
+  // the mock runs on a runtime of the test's, the client on its own
+  let runtime = tokio::runtime::Runtime::new().unwrap();
+  let server = runtime.block_on(async {
+    let server = MockServer::start().await;
+    let page = std::fs::read_to_string(format!("{}/tests/fixtures/course_list.html", env!("CARGO_MANIFEST_DIR"))).unwrap();
+    Mock::given(method("GET")).and(path(COURSE_PATH))
+      .respond_with(ResponseTemplate::new(200).set_body_raw(page, "text/html"))
+      .mount(&server).await;
+    server
+  });
+
+  let client = GradesClient::with_endpoints(Endpoints::with_base(&server.uri())).unwrap();
+  assert!(client.is_logged_in().unwrap());
+
+  let grades = client.fetch_grades().unwrap();
+  assert_eq!(grades.len(), 6);
+  assert!((client.gpa(&grades).unwrap().nine - 22.0 / 3.0).abs() < 1e-4);
+}
@@ -0,0 +1,109 @@
+#![cfg(feature = "browser")]
+
+use grades_list::{Endpoints, GradesClient, GradesError, SecondFactor, browser};
+
+use serde_json::{Value, json};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+use wiremock::matchers::{body_string_contains, header_exists, method, path, path_regex};
+
+static COURSE_PATH: &str = "/Apps/WebObjects/ydml.woa/wa/DirectAction/document";
+static ELEMENT_KEY: &str = "element-6066-11e4-a52e-4f735466cecf";
+
+fn fixture (name: &str) -> String {
+  std::fs::read_to_string(format!("{}/tests/fixtures/{}", env!("CARGO_MANIFEST_DIR"), name)).unwrap()
+}
+
+fn value (value: Value) -> ResponseTemplate {
+  ResponseTemplate::new(200).set_body_json(json!({ "value": value }))
+}
+
+/// SIS, serving the course list to whoever has the session cookie
+async fn york () -> MockServer {
+  let server = MockServer::start().await;
+  Mock::given(method("GET")).and(path(COURSE_PATH)).and(header_exists("cookie"))
+    .respond_with(ResponseTemplate::new(200).set_body_raw(fixture("course_list.html"), "text/html"))
+    .mount(&server).await;
+  server
+}
+
+/// A WebDriver whose browser is on `url` showing `page` once the form is sent, with the session
+/// cookie of SIS set
+async fn webdriver (url: &str, page: &str) -> MockServer {
+  let server = MockServer::start().await;
+  let session = "/session/headless";
+
+  Mock::given(method("POST")).and(path("/session"))
+    .respond_with(value(json!({ "sessionId": "headless", "capabilities": { "browserName": "chrome" } })))
+    .mount(&server).await;
+  Mock::given(method("DELETE")).and(path(session))
+    .respond_with(value(Value::Null))
+    .mount(&server).await;
+
+  Mock::given(method("POST")).and(path(format!("{}/url", session)))
+    .respond_with(value(Value::Null))
+    .mount(&server).await;
+  Mock::given(method("GET")).and(path(format!("{}/url", session)))
+    .respond_with(value(json!(url)))
+    .mount(&server).await;
+  Mock::given(method("GET")).and(path(format!("{}/source", session)))
+    .respond_with(value(json!(page)))
+    .mount(&server).await;
+  Mock::given(method("GET")).and(path(format!("{}/cookie", session)))
+    .respond_with(value(json!([{ "name": "pybpp", "value": "session-token", "path": "/" }])))
+    .mount(&server).await;
+
+  // the username and password fields and the login button
+  for (selector, id) in [("mli", "username"), ("password", "password"), ("submit", "login")] {
+    Mock::given(method("POST")).and(path(format!("{}/element", session))).and(body_string_contains(selector))
+      .respond_with(value(json!({ ELEMENT_KEY: id })))
+      .mount(&server).await;
+  }
+  Mock::given(method("POST")).and(path_regex(r"^/session/headless/element/\w+/(value|click)$"))
+    .respond_with(value(Value::Null))
+    .mount(&server).await;
+
+  server
+}
+
+#[tokio::test]
+async fn login_through_the_browser () {
+  let york = york().await;
+  let client = GradesClient::with_endpoints(Endpoints::with_base(&york.uri())).unwrap();
+  let course_list = format!("{}{}", york.uri(), COURSE_PATH);
+  let driver = webdriver(&course_list, &fixture("course_list.html")).await;
+
+  browser::login(&client, &driver.uri(), "student", "hunter2", &SecondFactor::Push).await.unwrap();
+  assert_eq!(client.fetch_grades().await.unwrap().len(), 6);
+
+  // the form was filled in and the browser closed after
+  let requests = driver.received_requests().await.unwrap();
+  let typed = requests.iter()
+    .filter(|r| r.url.path().ends_with("/value"))
+    .map(|r| String::from_utf8_lossy(&r.body).into_owned())
+    .collect::<String>();
+  assert!(typed.contains("student") && typed.contains("hunter2"));
+  assert_eq!(requests.last().unwrap().method, wiremock::http::Method::Delete);
+}
+
+#[tokio::test]
+async fn wrong_password_in_the_browser () {
+  let york = york().await;
+  let client = GradesClient::with_endpoints(Endpoints::with_base(&york.uri())).unwrap();
+  let driver = webdriver(&format!("{}/ppylogin/ppylogin", york.uri()), &fixture("login_failed.html")).await;
+
+  match browser::login(&client, &driver.uri(), "student", "wrong", &SecondFactor::Push).await {
+    Err(GradesError::Auth) => {},
+    other => panic!("expected an auth error, got {:?}", other),
+  }
+  assert!(!client.has_cookies());
+}
+
+#[tokio::test]
+async fn no_webdriver () {
+  let client = GradesClient::new().unwrap();
+
+  match browser::login(&client, "http://127.0.0.1:9", "student", "hunter2", &SecondFactor::Push).await {
+    Err(GradesError::Browser(e)) => assert!(e.contains("http://127.0.0.1:9"), "{}", e),
+    other => panic!("expected a browser error, got {:?}", other),
+  }
+}
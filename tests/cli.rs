@@ -0,0 +1,720 @@
+use std::io::{BufRead, BufReader, Write};
+use std::net::TcpListener;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+use std::sync::Arc;
+
+use openssl::asn1::Asn1Time;
+use openssl::bn::BigNum;
+use openssl::hash::MessageDigest;
+use openssl::pkey::{PKey, Private};
+use openssl::rsa::Rsa;
+use openssl::ssl::{SslAcceptor, SslMethod};
+use openssl::x509::{X509, X509Builder, X509NameBuilder};
+use openssl::x509::extension::{BasicConstraints, SubjectAlternativeName};
+
+/// A home of its own for each test, so the config, history and cache of one don't leak into another
+fn home (name: &str) -> PathBuf {
+  let home = std::env::temp_dir().join(format!("grades_list-cli-{}-{}", std::process::id(), name));
+  let _ = std::fs::remove_dir_all(&home);
+  std::fs::create_dir_all(home.join("config").join("grades_list")).unwrap();
+  home
+}
+
+fn write_config (home: &Path, config: &str) {
+  std::fs::write(home.join("config").join("grades_list").join("config.toml"), config).unwrap();
+}
+
+fn fixture (name: &str) -> String {
+  std::fs::read_to_string(format!("{}/tests/fixtures/{}", env!("CARGO_MANIFEST_DIR"), name)).unwrap()
+}
+
+/// Runs grades_list with nothing from the environment it is run in
+fn run (home: &Path, args: &[&str]) -> Output {
+  Command::new(env!("CARGO_BIN_EXE_grades_list"))
+    .args(args)
+    .env("HOME", home)
+    .env("XDG_CONFIG_HOME", home.join("config"))
+    .env("XDG_DATA_HOME", home.join("data"))
+    .env("XDG_CACHE_HOME", home.join("cache"))
+    .env_remove("GRADES_LIST_USERNAME")
+    .env_remove("GRADES_LIST_PASSWORD")
+    .env_remove("NO_COLOR")
+    .env_remove("RUST_LOG")
+    .output()
+    .unwrap()
+}
+
+/// Runs grades_list on the fixture course list
+fn grades_list (home: &Path, args: &[&str]) -> Output {
+  let fixture = format!("{}/tests/fixtures/course_list.html", env!("CARGO_MANIFEST_DIR"));
+  run(home, &[args, &["--from-file", &fixture, "--no-cache"]].concat())
+}
+
+/// York answering a login with the `login` page and then serving the course list, as a cassette
+/// for `--replay`
+fn cassette (home: &Path, login: &str) -> String {
+  let course_list = "https://wrem.sis.yorku.ca/Apps/WebObjects/ydml.woa/wa/DirectAction/document?name=CourseListv1";
+  let passport = "https://passportyork.yorku.ca/ppylogin/ppylogin";
+  let interactions = serde_json::json!([
+    { "method": "GET", "url": course_list, "status": 302, "location": passport, "body": "" },
+    { "method": "GET", "url": passport, "status": 200, "body": fixture("login_form.html") },
+    { "method": "POST", "url": passport, "status": 200, "set_cookies": ["pybpp=recorded; Path=/"], "body": fixture(login) },
+    { "method": "GET", "url": course_list, "status": 200, "body": fixture("course_list.html") },
+    { "method": "GET", "url": "https://passportyork.yorku.ca/ppylogin/ppylogout", "status": 200, "body": "" },
+  ]);
+
+  let path = home.join(format!("{}.json", login));
+  std::fs::write(&path, interactions.to_string()).unwrap();
+  path.to_str().unwrap().to_owned()
+}
+
+/// Certificate for `hosts` signed by `issuer`, or self-signed as a CA without one
+fn certificate (hosts: &[&str], key: &PKey<Private>, issuer: Option<(&X509, &PKey<Private>)>) -> X509 {
+  let mut name = X509NameBuilder::new().unwrap();
+  name.append_entry_by_text("CN", hosts.first().copied().unwrap_or("grades_list test CA")).unwrap();
+  let name = name.build();
+
+  let mut builder = X509Builder::new().unwrap();
+  builder.set_version(2).unwrap();
+  builder.set_serial_number(&BigNum::from_u32(hosts.len() as u32 + 1).unwrap().to_asn1_integer().unwrap()).unwrap();
+  builder.set_subject_name(&name).unwrap();
+  builder.set_issuer_name(issuer.map_or(&name, |(ca, _)| ca.subject_name())).unwrap();
+  builder.set_pubkey(key).unwrap();
+  builder.set_not_before(&Asn1Time::days_from_now(0).unwrap()).unwrap();
+  builder.set_not_after(&Asn1Time::days_from_now(1).unwrap()).unwrap();
+  match issuer {
+    Some(_) => {
+      let mut names = SubjectAlternativeName::new();
+      for host in hosts {
+        names.dns(host);
+      }
+      let names = names.build(&builder.x509v3_context(issuer.map(|(ca, _)| &**ca), None)).unwrap();
+      builder.append_extension(names).unwrap();
+    },
+    None => builder.append_extension(BasicConstraints::new().critical().ca().build().unwrap()).unwrap(),
+  }
+  builder.sign(issuer.map_or(key, |(_, key)| key), MessageDigest::sha256()).unwrap();
+  builder.build()
+}
+
+/// Reads an HTTP request off `stream`, its request line, whether it has the session cookie and
+/// its body, `None` once the client is done with the connection
+fn read_request<R: BufRead> (stream: &mut R) -> Option<(String, bool, String)> {
+  let mut head = Vec::new();
+  loop {
+    let mut line = String::new();
+    if stream.read_line(&mut line).ok()? == 0 {
+      return None;
+    }
+    if line.trim_end().is_empty() {
+      break;
+    }
+    head.push(line.trim_end().to_owned());
+  }
+
+  let header = |name: &str| head.iter().skip(1)
+    .find_map(|h| h.split_once(':').filter(|(n, _)| n.eq_ignore_ascii_case(name)).map(|(_, v)| v.trim().to_owned()));
+  let mut body = vec![0; header("content-length").and_then(|l| l.parse().ok()).unwrap_or(0)];
+  stream.read_exact(&mut body).ok()?;
+  let cookie = header("cookie").is_some_and(|c| c.contains("pybpp="));
+
+  Some((head.first()?.clone(), cookie, String::from_utf8_lossy(&body).into_owned()))
+}
+
+/// Passport York and SIS behind a proxy for `--proxy`, answering any login with a session cookie
+/// and serving `course_list` to whoever has it
+///
+/// The TLS of every tunnel through the proxy ends at it, with a certificate signed by a CA of its
+/// own whose certificate is written to `ca.pem` in `home` for `SSL_CERT_FILE`. Returns the URL of
+/// the proxy.
+fn york (home: &Path, course_list: String) -> String {
+  let ca_key = PKey::from_rsa(Rsa::generate(2048).unwrap()).unwrap();
+  let ca = certificate(&[], &ca_key, None);
+  let key = PKey::from_rsa(Rsa::generate(2048).unwrap()).unwrap();
+  let leaf = certificate(&["passportyork.yorku.ca", "wrem.sis.yorku.ca"], &key, Some((&ca, &ca_key)));
+  std::fs::write(home.join("ca.pem"), ca.to_pem().unwrap()).unwrap();
+
+  let mut acceptor = SslAcceptor::mozilla_intermediate_v5(SslMethod::tls_server()).unwrap();
+  acceptor.set_private_key(&key).unwrap();
+  acceptor.set_certificate(&leaf).unwrap();
+  let acceptor = Arc::new(acceptor.build());
+
+  let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+  let proxy = format!("http://{}", listener.local_addr().unwrap());
+  std::thread::spawn(move || {
+    for stream in listener.incoming().flatten() {
+      let (acceptor, course_list) = (acceptor.clone(), course_list.clone());
+      std::thread::spawn(move || {
+        let mut stream = BufReader::new(stream);
+        match read_request(&mut stream) {
+          Some((connect, _, _)) if connect.starts_with("CONNECT ") => {},
+          _ => return,
+        }
+        let mut stream = stream.into_inner();
+        stream.write_all(b"HTTP/1.1 200 Connection established\r\n\r\n").unwrap();
+
+        let mut tls = match acceptor.accept(stream) {
+          Ok(tls) => BufReader::new(tls),
+          Err(_) => return,
+        };
+        while let Some((request, cookie, body)) = read_request(&mut tls) {
+          let (status, headers, page) = match request.split(' ').take(2).collect::<Vec<_>>()[..] {
+            ["GET", path] if path.starts_with("/Apps/WebObjects/ydml.woa/wa/DirectAction/document") => match cookie {
+              true => ("200 OK", "", course_list.clone()),
+              false => ("302 Found", "Location: https://passportyork.yorku.ca/ppylogin/ppylogin\r\n", String::new()),
+            },
+            ["GET", "/ppylogin/ppylogin"] => ("200 OK", "", fixture("login_form.html")),
+            ["POST", "/ppylogin/ppylogin"] if body.contains("password=") => {
+              ("200 OK", "Set-Cookie: pybpp=session-token; Domain=yorku.ca; Path=/\r\n", fixture("login_success.html"))
+            },
+            ["GET", "/ppylogin/ppylogout"] => ("200 OK", "", String::new()),
+            _ => ("404 Not Found", "", String::new()),
+          };
+          let response = format!("HTTP/1.1 {}\r\nContent-Type: text/html\r\nContent-Length: {}\r\n{}\r\n{}", status, page.len(), headers, page);
+          if tls.get_mut().write_all(response.as_bytes()).is_err() {
+            return;
+          }
+        }
+      });
+    }
+  });
+
+  proxy
+}
+
+fn stdout (output: &Output) -> String {
+  String::from_utf8_lossy(&output.stdout).into_owned()
+}
+
+fn stderr (output: &Output) -> String {
+  String::from_utf8_lossy(&output.stderr).into_owned()
+}
+
+#[test]
+fn profiles () {
+  let home = home("profiles");
+  write_config(&home, r#"
+default_profile = "me"
+
+[profiles.me]
+username = "student"
+format = "json"
+
+[profiles.partner]
+username = "partner"
+format = "csv"
+"#);
+
+  // the default profile's format, unless another profile or a flag is given
+  let json = grades_list(&home, &[]);
+  assert!(json.status.success(), "{}", stderr(&json));
+  assert!(serde_json::from_slice::<serde_json::Value>(&json.stdout).unwrap()["gpa"].is_object());
+  assert!(stdout(&grades_list(&home, &["--profile", "partner"])).starts_with("session,course,"));
+  assert!(stdout(&grades_list(&home, &["--profile", "partner", "--format", "markdown"])).starts_with("## GPA"));
+
+  let unknown = grades_list(&home, &["--profile", "nobody"]);
+  assert!(!unknown.status.success());
+  assert!(stderr(&unknown).contains("no profile named \"nobody\""));
+
+  std::fs::remove_dir_all(&home).unwrap();
+}
+
+#[test]
+fn all_profiles () {
+  let home = home("all-profiles");
+
+  let none = run(&home, &["--all-profiles"]);
+  assert_eq!(none.status.code(), Some(11));
+  assert!(stderr(&none).contains("[profiles.<name>]"));
+
+  write_config(&home, r#"
+[profiles.partner]
+username = "partner"
+
+[profiles.me]
+username = "student"
+"#);
+  for args in [&["--username", "student"][..], &["--replay", "cassette.json"], &["--mfa", "totp"]] {
+    let refused = run(&home, &[&["--all-profiles"], args].concat());
+    assert_eq!(refused.status.code(), Some(11), "{:?}: {}", args, stderr(&refused));
+  }
+
+  // the passwords are piped in a line each, in the order of the profile names, like under cron;
+  // York is out of reach behind a proxy that isn't there, so every account fails to fetch
+  let mut fetch = Command::new(env!("CARGO_BIN_EXE_grades_list"))
+    .args(["--all-profiles", "--no-persist", "--no-daemon", "--proxy", "http://127.0.0.1:1"])
+    .env("HOME", &home)
+    .env("XDG_CONFIG_HOME", home.join("config"))
+    .env("XDG_DATA_HOME", home.join("data"))
+    .env("XDG_CACHE_HOME", home.join("cache"))
+    .env_remove("GRADES_LIST_PASSWORD")
+    .stdin(std::process::Stdio::piped())
+    .stdout(std::process::Stdio::piped())
+    .stderr(std::process::Stdio::piped())
+    .spawn()
+    .unwrap();
+  std::io::Write::write_all(fetch.stdin.as_mut().unwrap(), b"hunter2\nhunter3\n").unwrap();
+  let fetched = fetch.wait_with_output().unwrap();
+
+  assert_eq!(fetched.status.code(), Some(3), "{}", stderr(&fetched));
+  let printed = stdout(&fetched);
+  let (me, partner) = (printed.find("me (student)").unwrap(), printed.find("partner (partner)").unwrap());
+  assert!(me < partner, "{}", printed);
+  assert_eq!(printed.matches("Could not reach York").count(), 2);
+
+  write_config(&home, "[profiles.nameless]\nformat = \"json\"\n");
+  let nameless = run(&home, &["--all-profiles"]);
+  assert_eq!(nameless.status.code(), Some(11));
+  assert!(stderr(&nameless).contains("profile \"nameless\" has no username"));
+
+  std::fs::remove_dir_all(&home).unwrap();
+}
+
+#[test]
+fn all_profiles_non_degree () {
+  let home = home("all-profiles-non-degree");
+  write_config(&home, "non_degree = [\"ECON\"]\n\n[profiles.me]\nusername = \"student\"\n");
+  let work_term = "<tr><td>SU 2023</td><td>LE EECS 3900 0.00 A</td><td>Co-op Work Term</td><td>P</td></tr>\n";
+  let course_list = fixture("course_list.html").replacen("    <tr><td>SU 2023</td>", &format!("    {}    <tr><td>SU 2023</td>", work_term), 1);
+  let proxy = york(&home, course_list);
+
+  let fetch = |include: &[&str]| {
+    let mut fetch = Command::new(env!("CARGO_BIN_EXE_grades_list"))
+      .args([&["--all-profiles", "--json", "--no-persist", "--no-daemon", "--proxy", &proxy], include].concat())
+      .env("HOME", &home)
+      .env("XDG_CONFIG_HOME", home.join("config"))
+      .env("XDG_DATA_HOME", home.join("data"))
+      .env("XDG_CACHE_HOME", home.join("cache"))
+      .env("SSL_CERT_FILE", home.join("ca.pem"))
+      .env_remove("GRADES_LIST_PASSWORD")
+      .stdin(std::process::Stdio::piped())
+      .stdout(std::process::Stdio::piped())
+      .stderr(std::process::Stdio::piped())
+      .spawn()
+      .unwrap();
+    fetch.stdin.as_mut().unwrap().write_all(b"hunter2\n").unwrap();
+    let fetched = fetch.wait_with_output().unwrap();
+    assert_eq!(fetched.status.code(), Some(0), "{}", stderr(&fetched));
+
+    let output = serde_json::from_str::<serde_json::Value>(&stdout(&fetched)).unwrap();
+    let grades = output["profiles"]["me"]["grades"].as_array().unwrap().clone();
+    grades.iter()
+      .filter(|g| g["excluded"] == true)
+      .map(|g| g["course"].as_str().unwrap().to_owned())
+      .collect::<Vec<_>>()
+  };
+
+  // the zero-credit row is left out, the non_degree subjects of the config are the owner's
+  assert_eq!(fetch(&[]), vec!["LE EECS 3900 0.00 A"]);
+  assert!(fetch(&["--include-non-degree"]).is_empty());
+
+  std::fs::remove_dir_all(&home).unwrap();
+}
+
+#[test]
+fn no_color () {
+  let home = home("no-color");
+
+  // stdout isn't a terminal here, so the table is plain even without asking
+  for args in [&[][..], &["--no-color"]] {
+    let table = grades_list(&home, args);
+    assert!(table.status.success(), "{}", stderr(&table));
+    assert!(stdout(&table).contains("A+"));
+    assert!(!stdout(&table).contains('\x1b'));
+  }
+
+  std::fs::remove_dir_all(&home).unwrap();
+}
+
+#[test]
+fn sorted () {
+  let home = home("sorted");
+  let courses = |args: &[&str]| {
+    let output = grades_list(&home, &[args, &["--format", "csv"]].concat());
+    assert!(output.status.success(), "{}", stderr(&output));
+    stdout(&output).lines().skip(1).map(|line| line.split(',').nth(3).unwrap().to_owned()).collect::<Vec<_>>()
+  };
+
+  // oldest session first unless told otherwise
+  assert_eq!(courses(&[]), ["A+", "C", "B+", "B", "W", ""]);
+  assert_eq!(courses(&["--sort", "grade", "--descending"]), ["A+", "B+", "B", "C", "W", ""]);
+  assert_eq!(courses(&["--sort", "grade"]), ["W", "", "C", "B", "B+", "A+"]);
+
+  // each session closes with its own GPA
+  let grouped = stdout(&grades_list(&home, &["--group-by", "session"]));
+  let subtotal = grouped.lines().find(|line| line.contains("FW 2022-2023 GPA")).unwrap();
+  assert!(subtotal.contains("6.50") && subtotal.contains("3.15"));
+
+  std::fs::remove_dir_all(&home).unwrap();
+}
+
+#[test]
+fn check () {
+  let home = home("check");
+
+  let ok = run(&home, &["check", "--username", "student", "--replay", &cassette(&home, "login_success.html")]);
+  assert!(ok.status.success(), "{}", stderr(&ok));
+  assert_eq!(stdout(&ok), "Credentials: ok\nCourse list: ok, 6 courses\n");
+
+  let json = run(&home, &["check", "--username", "student", "--json", "--replay", &cassette(&home, "login_success.html")]);
+  let json = serde_json::from_slice::<serde_json::Value>(&json.stdout).unwrap();
+  assert_eq!(json, serde_json::json!({ "credentials": true, "course_list": true, "courses": 6 }));
+
+  // wrong credentials fail before the course list is asked for
+  let failed = run(&home, &["check", "--username", "student", "--replay", &cassette(&home, "login_failed.html")]);
+  assert_eq!(failed.status.code(), Some(2));
+  assert_eq!(stdout(&failed), "");
+  assert!(stderr(&failed).contains("Could not authenticate"));
+
+  std::fs::remove_dir_all(&home).unwrap();
+}
+
+#[test]
+fn logging () {
+  let home = home("logging");
+  let log = home.join("grades_list.log");
+  let cassette = cassette(&home, "login_success.html");
+
+  // -v is what a cron job would keep around, the steps without who logged in
+  let quiet = run(&home, &["check", "--username", "student", "--replay", &cassette, "-v", "--log-file", log.to_str().unwrap()]);
+  assert!(quiet.status.success(), "{}", stderr(&quiet));
+  assert_eq!(stderr(&quiet), "");
+  let logged = std::fs::read_to_string(&log).unwrap();
+  assert!(logged.contains("parsed the course list courses=6"), "{}", logged);
+  assert!(!logged.contains("student"));
+
+  // the username only from -vv, and the password never
+  let verbose = run(&home, &["student", "hunter2", "check", "--replay", &cassette, "-vvv"]);
+  assert!(stderr(&verbose).contains("logging in username=\"student\""), "{}", stderr(&verbose));
+  assert!(!stderr(&verbose).contains("hunter2"));
+
+  assert_eq!(stderr(&run(&home, &["check", "--username", "student", "--replay", &cassette])), "");
+
+  std::fs::remove_dir_all(&home).unwrap();
+}
+
+#[test]
+fn notify_pushover () {
+  let home = home("notify-pushover");
+  let cassette = cassette(&home, "login_success.html");
+  let fetch = |args: &[&str]| run(&home, &[&["--username", "student", "--replay", &cassette][..], args].concat());
+
+  // the first run only keeps the grades, the next one has something to compare them to
+  assert!(fetch(&[]).status.success());
+  let missing = fetch(&["--notify-pushover"]);
+  assert!(!missing.status.success());
+  assert!(stderr(&missing).contains("--notify-pushover needs `token` and `user` under [notify.pushover]"), "{}", stderr(&missing));
+
+  std::fs::remove_dir_all(&home).unwrap();
+}
+
+#[test]
+fn json_envelope () {
+  let home = home("json-envelope");
+  let json = |args: &[&str]| serde_json::from_slice::<serde_json::Value>(&grades_list(&home, args).stdout).unwrap();
+
+  let output = json(&["--format", "json"]);
+  assert_eq!(output["version"], 2);
+  assert_eq!(output["tool_version"], env!("CARGO_PKG_VERSION"));
+  assert!(output["fetched_at"].as_str().unwrap().parse::<chrono::DateTime<chrono::Utc>>().is_ok());
+  assert_eq!(output["grades"].as_array().unwrap().len(), 6);
+  // --machine leaves the time out so the same grades give the same output
+  assert!(json(&["--machine"]).get("fetched_at").is_none());
+
+  // everything printed is described by --schema, and everything it requires is printed
+  let schema = serde_json::from_slice::<serde_json::Value>(&run(&home, &["--schema"]).stdout).unwrap();
+  let properties = schema["properties"].as_object().unwrap();
+  for key in output.as_object().unwrap().keys() {
+    assert!(properties.contains_key(key), "{} is not in the schema", key);
+  }
+  for key in schema["required"].as_array().unwrap() {
+    assert!(output.get(key.as_str().unwrap()).is_some(), "{} is missing", key);
+  }
+
+  std::fs::remove_dir_all(&home).unwrap();
+}
+
+#[test]
+fn assertions () {
+  let home = home("assertions");
+  let code = |args: &[&str]| grades_list(&home, args).status.code();
+
+  // the fixture's cumulative GPA is 7.33, with one course still waiting on a grade
+  assert_eq!(code(&["--assert-gpa-min", "7.0"]), Some(0));
+  let below = grades_list(&home, &["--assert-gpa-min", "7.5"]);
+  assert_eq!(below.status.code(), Some(16));
+  assert!(stderr(&below).contains("cumulative GPA 7.33 is below 7.50"), "{}", stderr(&below));
+  // the grades are still printed before failing
+  assert!(stdout(&below).contains("EECS 1012"));
+
+  // nothing graded is no GPA at all, not one that passes
+  let page = fixture("course_list.html").lines().filter(|line| !line.contains("<td>FW 202")).collect::<Vec<_>>().join("\n");
+  let ungraded_page = home.join("ungraded.html");
+  std::fs::write(&ungraded_page, page).unwrap();
+  let ungraded = run(&home, &["--from-file", ungraded_page.to_str().unwrap(), "--no-cache", "--assert-gpa-min", "0"]);
+  assert_eq!(ungraded.status.code(), Some(16));
+  assert!(stderr(&ungraded).contains("no cumulative GPA yet to compare to 0.00"), "{}", stderr(&ungraded));
+
+  assert_eq!(code(&["--assert-no-missing", "2999-01-01"]), Some(0));
+  let missing = grades_list(&home, &["--assert-no-missing", "2000-01-01"]);
+  assert_eq!(missing.status.code(), Some(16));
+  assert!(stderr(&missing).contains("no grade yet after 2000-01-01 for EECS 3101"), "{}", stderr(&missing));
+  let not_a_date = grades_list(&home, &["--assert-no-missing", "tomorrow"]);
+  assert_eq!(not_a_date.status.code(), Some(11));
+  assert!(stderr(&not_a_date).contains("Invalid config: --assert-no-missing must be a YYYY-MM-DD date"), "{}", stderr(&not_a_date));
+
+  std::fs::remove_dir_all(&home).unwrap();
+}
+
+#[test]
+fn completions () {
+  let home = home("completions");
+
+  for shell in ["bash", "ZSH", "fish", "powershell", "elvish"].iter() {
+    let script = run(&home, &["completions", shell]);
+    assert!(script.status.success(), "{}", stderr(&script));
+    // every subcommand and the flags of the nested ones too
+    for word in ["serve", "completions", "history", "assert-gpa-min", "multi-user"].iter() {
+      assert!(stdout(&script).contains(word), "{} completions are missing {}", shell, word);
+    }
+  }
+
+  let bash = home.join("grades_list.bash");
+  std::fs::write(&bash, run(&home, &["completions", "bash"]).stdout).unwrap();
+  assert!(Command::new("bash").arg("-n").arg(&bash).status().unwrap().success());
+
+  let unknown = run(&home, &["completions", "tcsh"]);
+  assert!(!unknown.status.success());
+  assert!(stderr(&unknown).contains("possible values: bash, elvish, fish, powershell, zsh"));
+
+  std::fs::remove_dir_all(&home).unwrap();
+}
+
+#[test]
+fn gpa_formats () {
+  let home = home("gpa-formats");
+  let gpa = |args: &[&str]| stdout(&grades_list(&home, &[args, &["--precision", "2", "gpa"]].concat()));
+
+  assert!(gpa(&[]).contains("| 3.43 "));
+  assert_eq!(gpa(&["--format", "json"]), "{\"four\":3.43,\"nine\":7.33}\n");
+  assert_eq!(gpa(&["--format", "csv"]), "four,nine\n3.43,7.33\n");
+  assert_eq!(gpa(&["--format", "yaml"]), "four: 3.43\nnine: 7.33\n");
+  assert_eq!(gpa(&["--format", "toml"]), "four = 3.43\nnine = 7.33\n");
+
+  let path = home.join("gpa.csv");
+  let written = grades_list(&home, &["--precision", "2", "--format", "csv", "--output", path.to_str().unwrap(), "gpa"]);
+  assert_eq!(stdout(&written), "");
+  assert_eq!(std::fs::read_to_string(&path).unwrap(), "four,nine\n3.43,7.33\n");
+
+  let markdown = grades_list(&home, &["--format", "markdown", "gpa"]);
+  assert_eq!(markdown.status.code(), Some(11));
+
+  let too_precise = grades_list(&home, &["--precision", "4294967295", "gpa"]);
+  assert_eq!(too_precise.status.code(), Some(11));
+  assert!(stderr(&too_precise).contains("--precision is at most 6"), "{}", stderr(&too_precise));
+
+  std::fs::remove_dir_all(&home).unwrap();
+}
+
+#[test]
+fn french () {
+  let home = home("french");
+  let cassette = home.join("cassette.json");
+
+  let stats = grades_list(&home, &["--lang", "fr", "--record", cassette.to_str().unwrap(), "stats"]);
+  assert!(stdout(&stats).contains("Par niveau :") && stdout(&stats).contains("| Note médiane "), "{}", stdout(&stats));
+  assert!(!stdout(&stats).contains("Median"));
+  assert!(stderr(&stats).starts_with("Avertissement : "), "{}", stderr(&stats));
+
+  let schedule = format!("{}/tests/fixtures/schedule.html", env!("CARGO_MANIFEST_DIR"));
+  let schedule = run(&home, &["--lang", "fr", "--from-file", &schedule, "schedule"]);
+  assert!(stdout(&schedule).contains("| Cours ") && stdout(&schedule).contains("| Jours "), "{}", stdout(&schedule));
+
+  let degree = format!("{}/tests/fixtures/degree_progress.html", env!("CARGO_MANIFEST_DIR"));
+  let degree = run(&home, &["--lang", "fr", "--from-file", &degree, "degree-progress"]);
+  assert!(stdout(&degree).contains("| Satisfaite ") && !stdout(&degree).contains("Satisfied"), "{}", stdout(&degree));
+
+  let dates = format!("{}/tests/fixtures/dates.html", env!("CARGO_MANIFEST_DIR"));
+  let dates = run(&home, &["--lang", "fr", "--from-file", &dates, "dates"]);
+  assert!(stdout(&dates).contains("Rendez-vous d'inscription"), "{}", stdout(&dates));
+
+  let short_row = home.join("short_row.html");
+  std::fs::write(&short_row, fixture("course_list.html").replacen("    <tr><td>SU 2023</td>", "    <tr><td>SU 2023</td></tr>\n    <tr><td>SU 2023</td>", 1)).unwrap();
+  let skipped = run(&home, &["--lang", "fr", "--from-file", short_row.to_str().unwrap(), "--no-cache"]);
+  assert!(stderr(&skipped).contains("Lignes illisibles dans la liste de cours : "), "{}", stderr(&skipped));
+
+  let cleared = run(&home, &["--lang", "fr", "cache", "clear"]);
+  assert_eq!(stdout(&cleared), "Copies en cache supprimées : 0\n");
+
+  std::fs::remove_dir_all(&home).unwrap();
+}
+
+#[test]
+fn diff_files () {
+  let home = home("diff-files");
+  let course_list = format!("{}/tests/fixtures/course_list.html", env!("CARGO_MANIFEST_DIR"));
+  let save = |html: &str, name: &str| {
+    let output = run(&home, &["--from-file", html, "--no-cache", "--json"]);
+    let path = home.join(name);
+    std::fs::write(&path, output.stdout).unwrap();
+    path.to_str().unwrap().to_owned()
+  };
+
+  // EECS 3101's grade is posted, ECON 1000 is dropped and EECS 3311 is added
+  let changed = fixture("course_list.html")
+    .replace("<td>&nbsp;</td>", "<td>B</td>")
+    .lines()
+    .filter(|line| !line.contains("AP ECON"))
+    .map(|line| match line.contains("LE EECS 3101") {
+      true => format!("{}\n{}", line, line.replace("3101", "3311").replace("Design &amp; Analysis of Algorithms", "Software Design").replace("<td>B</td>", "<td>A</td>")),
+      false => line.to_owned(),
+    })
+    .collect::<Vec<_>>()
+    .join("\n");
+  let changed_path = home.join("changed.html");
+  std::fs::write(&changed_path, changed).unwrap();
+
+  let old = save(&course_list, "old.json");
+  let new = save(changed_path.to_str().unwrap(), "new.json");
+
+  let table = stdout(&run(&home, &["--username", "student", "diff", &old, &new]));
+  assert!(table.contains("| Changed | FW 2023-2024 | LE EECS 3101 3.00 A | Design & Analysis of Algorithms |  -> B |"), "{}", table);
+  assert!(table.contains("| Removed | SU 2023      | AP ECON 1000 3.00 B |"), "{}", table);
+  assert!(table.contains("| Added   | FW 2023-2024 | LE EECS 3311 3.00 A | Software Design"), "{}", table);
+
+  let json: serde_json::Value = serde_json::from_str(&stdout(&run(&home, &["--username", "student", "--format", "json", "diff", &old, &new]))).unwrap();
+  let kinds = json["changes"].as_array().unwrap().iter()
+    .map(|c| (c["kind"].as_str().unwrap(), c["course"]["course"].as_str().unwrap()))
+    .collect::<Vec<_>>();
+  assert_eq!(kinds, [("changed", "LE EECS 3101 3.00 A"), ("added", "LE EECS 3311 3.00 A"), ("removed", "AP ECON 1000 3.00 B")]);
+  assert_eq!(json["changes"][0]["old_grade"], "");
+  // the B and A bring the 7.33 down
+  assert_eq!(json["new_gpa"]["nine"], 7.2);
+  assert!(json["delta"]["nine"].as_f64().unwrap() < 0.0);
+
+  std::fs::remove_dir_all(&home).unwrap();
+}
+
+#[test]
+fn major_gpa () {
+  let home = home("major-gpa");
+
+  let unset = grades_list(&home, &["--major-gpa"]);
+  assert_eq!(unset.status.code(), Some(11));
+  assert!(stderr(&unset).contains("--major-gpa needs `major = [\"EECS\", ...]`"), "{}", stderr(&unset));
+
+  // prefixes match whatever their case, and MATH 1300 isn't a MATH 2 course
+  write_config(&home, "major = [\"eecs\", \"MATH 2\"]\n");
+  let json: serde_json::Value = serde_json::from_str(&stdout(&grades_list(&home, &["--major-gpa", "--format", "json"]))).unwrap();
+  assert_eq!(json["major_gpa"], serde_json::json!({ "four": 3.6499999, "nine": 8.0 }));
+  assert_eq!(json["gpa"]["nine"], 7.3333335);
+
+  let json: serde_json::Value = serde_json::from_str(&stdout(&grades_list(&home, &["--format", "json"]))).unwrap();
+  assert!(json.get("major_gpa").is_none());
+
+  std::fs::remove_dir_all(&home).unwrap();
+}
+
+#[test]
+fn missing_credits () {
+  let home = home("missing-credits");
+  let path = home.join("course_list.html");
+  std::fs::write(&path, fixture("course_list.html").replace("SC MATH 1300 3.00 M", "SC MATH 1300 M")).unwrap();
+
+  let table = run(&home, &["--from-file", path.to_str().unwrap(), "--no-cache"]);
+  assert!(table.status.success());
+  assert_eq!(stderr(&table), "Warning: no credit value in \"SC MATH 1300 M\", left out of the GPA\n");
+
+  let json: serde_json::Value = serde_json::from_str(&stdout(&run(&home, &["--from-file", path.to_str().unwrap(), "--no-cache", "--format", "json"]))).unwrap();
+  assert_eq!(json["warnings"], serde_json::json!(["no credit value in \"SC MATH 1300 M\", left out of the GPA"]));
+  assert_eq!(json["gpa"]["nine"], 8.0);
+
+  std::fs::remove_dir_all(&home).unwrap();
+}
+
+#[test]
+fn count_all_attempts () {
+  let home = home("count-all-attempts");
+  let gpa = |args: &[&str]| stdout(&grades_list(&home, &[args, &["--format", "json", "gpa"]].concat()));
+
+  // EECS 2030 was a C and then a B+, only the B+ counts unless every attempt is asked for
+  assert_eq!(gpa(&["--precision", "2"]), "{\"four\":3.43,\"nine\":7.33}\n");
+  assert_eq!(gpa(&["--precision", "2", "--count-all-attempts"]), "{\"four\":3.08,\"nine\":6.5}\n");
+
+  let json: serde_json::Value = serde_json::from_str(&stdout(&grades_list(&home, &["--count-all-attempts", "--format", "json"]))).unwrap();
+  assert!(json["grades"].as_array().unwrap().iter().all(|c| c["superseded"] == false));
+
+  std::fs::remove_dir_all(&home).unwrap();
+}
+
+/// Runs grades_list like `run`, with `input` piped to it
+fn piped (home: &Path, args: &[&str], input: &str) -> Output {
+  let mut child = Command::new(env!("CARGO_BIN_EXE_grades_list"))
+    .args(args)
+    .env("HOME", home)
+    .env("XDG_CONFIG_HOME", home.join("config"))
+    .env("XDG_DATA_HOME", home.join("data"))
+    .env("XDG_CACHE_HOME", home.join("cache"))
+    .env_remove("GRADES_LIST_USERNAME")
+    .env_remove("GRADES_LIST_PASSWORD")
+    .stdin(std::process::Stdio::piped())
+    .stdout(std::process::Stdio::piped())
+    .stderr(std::process::Stdio::piped())
+    .spawn()
+    .unwrap();
+  child.stdin.take().unwrap().write_all(input.as_bytes()).unwrap();
+  child.wait_with_output().unwrap()
+}
+
+#[test]
+fn saved_page () {
+  let home = home("saved-page");
+
+  // read from the file or from stdin, without a username or anything being cached
+  let from_file = grades_list(&home, &["--format", "json", "gpa"]);
+  let from_stdin = piped(&home, &["--from-file", "-", "--format", "json", "gpa"], &fixture("course_list.html"));
+  assert!(from_stdin.status.success(), "{}", stderr(&from_stdin));
+  assert_eq!(stdout(&from_stdin), stdout(&from_file));
+  assert_eq!(stdout(&from_stdin), "{\"four\":3.4333334,\"nine\":7.3333335}\n");
+  assert!(!home.join("cache").exists());
+
+  let no_table = piped(&home, &["--from-file", "-", "gpa"], &fixture("no_table.html"));
+  assert_eq!(no_table.status.code(), Some(5));
+  assert!(stderr(&no_table).contains("could not find the grades table"), "{}", stderr(&no_table));
+
+  let missing = run(&home, &["--from-file", home.join("missing.html").to_str().unwrap(), "gpa"]);
+  assert_eq!(missing.status.code(), Some(6));
+
+  std::fs::remove_dir_all(&home).unwrap();
+}
+
+#[test]
+fn save_html () {
+  let home = home("save-html");
+
+  // a course list with the session id in its links and a token in its form
+  let page = fixture("course_list.html").replace("<body>", concat!(
+    "<body>\n  <a href=\"/Apps/WebObjects/ydml.woa/wa/logout?wosid=Xy12aB&amp;lang=en\">Log out</a>\n",
+    "  <form><input type=\"hidden\" name=\"token\" value=\"s3cr3t\"><input type=\"text\" name=\"q\" value=\"EECS\"></form>",
+  ));
+  let path = cassette(&home, "login_success.html");
+  let mut interactions: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+  interactions[3]["body"] = page.into();
+  std::fs::write(&path, interactions.to_string()).unwrap();
+
+  let saved = home.join("page.html");
+  let fetch = run(&home, &["--username", "student", "--replay", &path, "--no-cache", "--save-html", saved.to_str().unwrap(), "--format", "json", "gpa"]);
+  assert!(fetch.status.success(), "{}", stderr(&fetch));
+
+  let saved = std::fs::read_to_string(&saved).unwrap();
+  assert!(saved.contains("logout?wosid=&amp;lang=en"), "{}", saved);
+  assert!(saved.contains("<input type=\"hidden\" name=\"token\" value=\"\">"), "{}", saved);
+  assert!(!saved.contains("Xy12aB") && !saved.contains("s3cr3t"));
+  // the rest of the page is kept as it was, so it still parses
+  assert!(saved.contains("name=\"q\" value=\"EECS\""));
+  let reread = run(&home, &["--from-file", home.join("page.html").to_str().unwrap(), "--format", "json", "gpa"]);
+  assert_eq!(stdout(&reread), stdout(&fetch));
+
+  std::fs::remove_dir_all(&home).unwrap();
+}
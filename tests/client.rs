@@ -0,0 +1,700 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use grades_list::{ClientOptions, Endpoints, GradesClient, GradesError, ParseMode, SecondFactor, Selectors, SisBackend};
+use grades_list::cache::{self, Cached};
+use grades_list::cassette::Cassette;
+use grades_list::daemon::{self, Page};
+use grades_list::dates::{self, Kind};
+use grades_list::debug_dump::{self, DebugDump};
+use grades_list::degree::{self, Status};
+use grades_list::doctor::{self, Outcome};
+use grades_list::schedule;
+use grades_list::transcript;
+
+use async_trait::async_trait;
+use wiremock::{Mock, MockServer, ResponseTemplate};
+use wiremock::matchers::{body_string_contains, header_exists, method, path};
+
+static COURSE_PATH: &str = "/Apps/WebObjects/ydml.woa/wa/DirectAction/document";
+static LOGIN_PATH: &str = "/ppylogin/ppylogin";
+static LOGOUT_PATH: &str = "/ppylogin/ppylogout";
+
+fn fixture (name: &str) -> String {
+  std::fs::read_to_string(format!("{}/tests/fixtures/{}", env!("CARGO_MANIFEST_DIR"), name)).unwrap()
+}
+
+fn html (name: &str) -> ResponseTemplate {
+  ResponseTemplate::new(200).set_body_raw(fixture(name), "text/html")
+}
+
+/// A server behaving like Passport York and SIS, accepting `student` / `hunter2`
+async fn york () -> MockServer {
+  let server = MockServer::start().await;
+
+  // with the session cookie the course list is served, without it SIS sends you to log in
+  Mock::given(method("GET")).and(path(COURSE_PATH)).and(header_exists("cookie"))
+    .respond_with(html("course_list.html"))
+    .with_priority(1)
+    .mount(&server).await;
+  Mock::given(method("GET")).and(path(COURSE_PATH))
+    .respond_with(ResponseTemplate::new(302).insert_header("Location", LOGIN_PATH))
+    .mount(&server).await;
+
+  Mock::given(method("GET")).and(path(LOGIN_PATH))
+    .respond_with(html("login_form.html"))
+    .mount(&server).await;
+
+  Mock::given(method("POST")).and(path(LOGIN_PATH))
+    .and(body_string_contains("mli=student"))
+    .and(body_string_contains("password=hunter2"))
+    .and(body_string_contains("__authstate=state-token"))
+    .respond_with(html("login_success.html").insert_header("Set-Cookie", "pybpp=session-token; Path=/"))
+    .with_priority(1)
+    .mount(&server).await;
+  Mock::given(method("POST")).and(path(LOGIN_PATH))
+    .and(body_string_contains("mli=locked"))
+    .respond_with(html("login_locked.html"))
+    .with_priority(2)
+    .mount(&server).await;
+  Mock::given(method("POST")).and(path(LOGIN_PATH))
+    .respond_with(html("login_failed.html"))
+    .mount(&server).await;
+
+  Mock::given(method("GET")).and(path(LOGOUT_PATH))
+    .respond_with(ResponseTemplate::new(200))
+    .mount(&server).await;
+
+  server
+}
+
+fn client (server: &MockServer) -> GradesClient {
+  GradesClient::with_endpoints(Endpoints::with_base(&server.uri())).unwrap()
+}
+
+#[tokio::test]
+async fn login_and_fetch () {
+  let server = york().await;
+  let client = client(&server);
+
+  assert!(!client.is_logged_in().await.unwrap());
+
+  client.login("student", "hunter2").await.unwrap();
+  assert!(client.is_logged_in().await.unwrap());
+
+  let grades = client.fetch_grades().await.unwrap();
+  assert_eq!(grades.len(), 6);
+  assert_eq!(grades[0].session, "FW 2021-2022");
+  assert_eq!(grades[0].subject, "EECS");
+  assert_eq!(grades[0].number, "1012");
+  assert_eq!(grades[0].credits, Some(3.0));
+  assert_eq!(grades[5].title, "Design & Analysis of Algorithms");
+  assert_eq!(grades[5].grade, "");
+
+  // the first attempt of EECS 2030 was repeated
+  assert!(grades[1].superseded);
+  assert!(!grades[2].superseded);
+
+  let gpa = client.gpa(&grades).unwrap();
+  assert!((gpa.nine - 22.0 / 3.0).abs() < 1e-4);
+  assert!((gpa.four - 10.3 / 3.0).abs() < 1e-4);
+
+  client.logout().await.unwrap();
+  assert!(!client.has_cookies());
+}
+
+/// Has `duo` log in through a Duo prompt served by `server` itself, approving a push or the
+/// passcode 123456 and denying any other
+async fn duo (server: &MockServer) {
+  let host = server.uri().trim_start_matches("http://").to_owned();
+  let prompt = format!(r#"<html><body><iframe id="duo_iframe" data-host="{}" data-sig-request="TX|request:APP|app" data-post-action="/ppylogin/duo"></iframe></body></html>"#, host);
+  let ok = |response: serde_json::Value| ResponseTemplate::new(200).set_body_json(serde_json::json!({ "stat": "OK", "response": response }));
+
+  Mock::given(method("POST")).and(path(LOGIN_PATH)).and(body_string_contains("mli=duo"))
+    .respond_with(ResponseTemplate::new(200).set_body_raw(prompt, "text/html"))
+    .with_priority(1)
+    .mount(server).await;
+
+  Mock::given(method("POST")).and(path("/frame/web/v1/auth"))
+    .respond_with(ResponseTemplate::new(302).insert_header("Location", "/frame/prompt?sid=duo-session"))
+    .mount(server).await;
+  Mock::given(method("POST")).and(path("/frame/prompt")).and(body_string_contains("sid=duo-session"))
+    .and(body_string_contains("factor=Duo+Push"))
+    .respond_with(ok(serde_json::json!({ "txid": "approved" })))
+    .mount(server).await;
+  Mock::given(method("POST")).and(path("/frame/prompt")).and(body_string_contains("passcode=123456"))
+    .respond_with(ok(serde_json::json!({ "txid": "approved" })))
+    .mount(server).await;
+  Mock::given(method("POST")).and(path("/frame/prompt")).and(body_string_contains("factor=Passcode"))
+    .respond_with(ok(serde_json::json!({ "txid": "denied" })))
+    .with_priority(6)
+    .mount(server).await;
+
+  Mock::given(method("POST")).and(path("/frame/status")).and(body_string_contains("txid=approved"))
+    .respond_with(ok(serde_json::json!({ "result": "SUCCESS", "result_url": "/frame/status/approved" })))
+    .mount(server).await;
+  Mock::given(method("POST")).and(path("/frame/status")).and(body_string_contains("txid=denied"))
+    .respond_with(ok(serde_json::json!({ "result": "FAILURE", "status": "Incorrect passcode" })))
+    .mount(server).await;
+  Mock::given(method("POST")).and(path("/frame/status/approved"))
+    .respond_with(ok(serde_json::json!({ "cookie": "AUTH|signed" })))
+    .mount(server).await;
+
+  // the signed response and the app half of the request go back to Passport York
+  Mock::given(method("POST")).and(path("/ppylogin/duo")).and(body_string_contains("sig_response=AUTH%7Csigned%3AAPP%7Capp"))
+    .respond_with(html("login_success.html").insert_header("Set-Cookie", "pybpp=session-token; Path=/"))
+    .mount(server).await;
+}
+
+#[tokio::test]
+async fn duo_push_and_passcode () {
+  let server = york().await;
+  duo(&server).await;
+
+  for factor in [SecondFactor::Push, SecondFactor::Passcode("123456".to_owned())] {
+    let client = client(&server);
+    client.login_with("duo", "hunter2", &factor).await.unwrap();
+    assert_eq!(client.fetch_grades().await.unwrap().len(), 6, "{:?}", factor);
+  }
+
+  let client = client(&server);
+  match client.login_with("duo", "hunter2", &SecondFactor::Passcode("000000".to_owned())).await {
+    Err(GradesError::Mfa(e)) => assert_eq!(e, "Incorrect passcode"),
+    other => panic!("expected a Duo error, got {:?}", other),
+  }
+  assert!(!client.is_logged_in().await.unwrap());
+}
+
+#[tokio::test]
+async fn york_backend () {
+  let server = york().await;
+  let backend: Box<dyn SisBackend> = Box::new(client(&server));
+
+  assert_eq!(backend.name(), "york");
+  assert!(backend.york().is_some());
+  backend.login("student", "hunter2", &SecondFactor::Push).await.unwrap();
+  assert_eq!(backend.fetch_grades().await.unwrap().len(), 6);
+
+  // the session carries over to the backend of the next run
+  let mut saved = Vec::new();
+  backend.save_session(&mut saved).unwrap();
+  let next: Box<dyn SisBackend> = Box::new(client(&server));
+  assert!(!next.has_session());
+  next.load_session(&saved).unwrap();
+  assert!(next.has_session() && next.is_logged_in().await.unwrap());
+
+  backend.logout().await.unwrap();
+  assert!(!backend.is_logged_in().await.unwrap());
+}
+
+/// A school with a portal of its own and none of York's pages
+struct Elsewhere;
+
+#[async_trait]
+impl SisBackend for Elsewhere {
+  fn name (&self) -> &'static str { "elsewhere" }
+
+  async fn is_logged_in (&self) -> Result<bool, GradesError> {
+    Ok(true)
+  }
+
+  async fn login (&self, _: &str, _: &str, _: &SecondFactor) -> Result<(), GradesError> {
+    Ok(())
+  }
+
+  async fn fetch_grades (&self) -> Result<Vec<grades_list::CourseData>, GradesError> {
+    Ok(Vec::new())
+  }
+
+  async fn logout (&self) -> Result<(), GradesError> {
+    Ok(())
+  }
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn daemon_of_another_school () {
+  let path = std::env::temp_dir().join(format!("grades_list-elsewhere-{}", std::process::id())).join("daemon.sock");
+  assert!(Elsewhere.york().is_none() && !Elsewhere.has_session());
+
+  let listener = daemon::listen(&path).unwrap();
+  tokio::spawn(daemon::serve(listener, Arc::new(Elsewhere)));
+
+  // the pages are York's, another school has none to give
+  let asked = daemon::request(&path, &[Page::CourseList]).await;
+  std::fs::remove_dir_all(path.parent().unwrap()).unwrap();
+  match asked {
+    Err(GradesError::Daemon(e)) => assert!(e.contains("elsewhere"), "{}", e),
+    other => panic!("expected the daemon to fail, got {:?}", other),
+  }
+}
+
+#[tokio::test]
+async fn recorded_and_replayed () {
+  let path = std::env::temp_dir().join(format!("grades_list-cassette-{}.json", std::process::id()));
+  let server = york().await;
+  let endpoints = Endpoints::with_base(&server.uri());
+
+  let options = ClientOptions { cassette: Some(Arc::new(Cassette::record(&path))), ..Default::default() };
+  let recording = GradesClient::with_options(endpoints.clone(), &options).unwrap();
+  recording.login("student", "hunter2").await.unwrap();
+  let recorded = recording.fetch_grades().await.unwrap();
+  recording.logout().await.unwrap();
+
+  // neither the password nor the session made it into the cassette
+  let cassette = std::fs::read_to_string(&path).unwrap();
+  assert!(!cassette.contains("hunter2") && !cassette.contains("session-token") && !cassette.contains("state-token"));
+
+  // York is gone, the replay doesn't need it or the password
+  drop(server);
+  let options = ClientOptions { cassette: Some(Arc::new(Cassette::replay(&path).unwrap())), ..Default::default() };
+  let replaying = GradesClient::with_options(endpoints, &options).unwrap();
+  replaying.login("anyone", "").await.unwrap();
+  assert_eq!(replaying.fetch_grades().await.unwrap(), recorded);
+
+  // every recorded response was played, there is none left for another course list
+  assert!(replaying.fetch_grades().await.is_err());
+
+  std::fs::remove_file(&path).unwrap();
+}
+
+#[tokio::test]
+async fn cassette_leaves_out_the_student () {
+  let cassette_path = std::env::temp_dir().join(format!("grades_list-cassette-student-{}.json", std::process::id()));
+  let server = MockServer::start().await;
+  let page = fixture("course_list.html")
+    .replace("<body>", "<body><p>Logged in as JDoe42, student number 212345678, jdoe42@my.yorku.ca</p>");
+  Mock::given(method("GET")).and(path(LOGIN_PATH)).respond_with(html("login_form.html")).mount(&server).await;
+  Mock::given(method("POST")).and(path(LOGIN_PATH))
+    .respond_with(html("login_success.html").insert_header("Set-Cookie", "pybpp=session-token; Path=/"))
+    .mount(&server).await;
+  Mock::given(method("GET")).and(path(COURSE_PATH)).and(header_exists("cookie"))
+    .respond_with(ResponseTemplate::new(200).set_body_raw(page, "text/html"))
+    .with_priority(1)
+    .mount(&server).await;
+  Mock::given(method("GET")).and(path(COURSE_PATH))
+    .respond_with(ResponseTemplate::new(302).insert_header("Location", LOGIN_PATH))
+    .mount(&server).await;
+
+  let options = ClientOptions { cassette: Some(Arc::new(Cassette::record(&cassette_path))), ..Default::default() };
+  let recording = GradesClient::with_options(Endpoints::with_base(&server.uri()), &options).unwrap();
+  recording.login("jdoe42", "hunter2").await.unwrap();
+  let recorded = recording.fetch_grades().await.unwrap();
+
+  // the username of the login form, in any case, and the student number and email are hashed out
+  let cassette = std::fs::read_to_string(&cassette_path).unwrap();
+  assert!(!cassette.to_lowercase().contains("jdoe42") && !cassette.contains("212345678"), "{}", cassette);
+  assert!(cassette.contains("redacted-"));
+
+  // the grades stay for the replay to parse
+  let options = ClientOptions { cassette: Some(Arc::new(Cassette::replay(&cassette_path).unwrap())), ..Default::default() };
+  let replaying = GradesClient::with_options(Endpoints::with_base(&server.uri()), &options).unwrap();
+  replaying.login("anyone", "").await.unwrap();
+  assert_eq!(replaying.fetch_grades().await.unwrap(), recorded);
+
+  std::fs::remove_file(&cassette_path).unwrap();
+}
+
+#[tokio::test]
+async fn doctor_checklist () {
+  let server = york().await;
+  let checks = doctor::run(&client(&server), Some(&fixture("course_list.html"))).await;
+  let outcomes = checks.iter().map(|c| (c.name.as_str(), c.outcome)).collect::<Vec<_>>();
+
+  assert_eq!(outcomes, [
+    ("DNS lookup of 127.0.0.1", Outcome::Pass),
+    ("Connection to 127.0.0.1", Outcome::Pass),
+    ("Login page reachable", Outcome::Pass),
+    ("Hidden login form fields", Outcome::Pass),
+    ("Selectors find the courses", Outcome::Pass),
+  ]);
+  assert_eq!(checks[3].detail, "__pybpp, __authstate");
+
+  // without a course list to try them on the selectors are skipped, a missing login form fails
+  Mock::given(method("GET")).and(path(LOGIN_PATH)).respond_with(ResponseTemplate::new(200).set_body_string("<p>Loading...</p>"))
+    .with_priority(1)
+    .mount(&server).await;
+  let checks = doctor::run(&client(&server), None).await;
+  assert_eq!(checks[2].outcome, Outcome::Fail);
+  assert_eq!(checks.last().unwrap().outcome, Outcome::Skip);
+}
+
+#[tokio::test]
+async fn debug_dump_redacted () {
+  let dir = std::env::temp_dir().join(format!("grades_list-dump-{}", std::process::id()));
+  let server = york().await;
+  let dump = Arc::new(DebugDump::new());
+
+  let options = ClientOptions { debug_dump: Some(dump.clone()), ..Default::default() };
+  let client = GradesClient::with_options(Endpoints::with_base(&server.uri()), &options).unwrap();
+  client.login("student", "hunter2").await.unwrap();
+  client.fetch_grades().await.unwrap();
+  dump.write(&dir).unwrap();
+
+  let trace = std::fs::read_to_string(dir.join("trace.json")).unwrap();
+  assert!(trace.contains("\"mli=redacted-") && trace.contains("\"password=<redacted>\""));
+  assert!(trace.contains("pybpp=recorded"));
+  let written = std::fs::read_dir(&dir).unwrap().map(|f| std::fs::read_to_string(f.unwrap().path()).unwrap()).collect::<String>();
+  assert!(!written.contains("hunter2") && !written.contains("session-token") && !written.contains("state-token"));
+  assert!(written.contains("Design &amp; Analysis of Algorithms"));
+  // the grades are left out, the layout of the course list stays
+  assert!(!written.contains("<td>A+</td>") && !written.contains("<td>B+</td>") && written.contains("<td>?</td>"));
+
+  std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[tokio::test]
+async fn debug_dump_keeps_the_last_requests () {
+  let server = york().await;
+  let dump = Arc::new(DebugDump::new());
+
+  let options = ClientOptions { debug_dump: Some(dump.clone()), ..Default::default() };
+  let client = GradesClient::with_options(Endpoints::with_base(&server.uri()), &options).unwrap();
+  client.login("student", "hunter2").await.unwrap();
+  for _ in 0..debug_dump::MAX_EXCHANGES {
+    client.fetch_grades().await.unwrap();
+  }
+
+  // a long watch doesn't keep every request it ever made
+  assert_eq!(dump.len(), debug_dump::MAX_EXCHANGES);
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn daemon_serves_pages () {
+  use std::os::unix::fs::PermissionsExt;
+
+  let path = std::env::temp_dir().join(format!("grades_list-daemon-{}", std::process::id())).join("daemon.sock");
+  let server = york().await;
+  let client = client(&server);
+  client.login("student", "hunter2").await.unwrap();
+
+  assert!(daemon::request(&path, &[Page::CourseList]).await.unwrap().is_none());
+
+  // a directory left open to others is closed before the socket goes in it
+  let dir = path.parent().unwrap();
+  std::fs::create_dir_all(dir).unwrap();
+  std::fs::set_permissions(dir, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+  let listener = daemon::listen(&path).unwrap();
+  assert_eq!(std::fs::metadata(dir).unwrap().permissions().mode() & 0o777, 0o700);
+  assert_eq!(std::fs::metadata(&path).unwrap().permissions().mode() & 0o777, 0o600);
+  assert!(matches!(daemon::listen(&path), Err(GradesError::Daemon(_))));
+  tokio::spawn(daemon::serve(listener, Arc::new(client)));
+
+  let pages = daemon::request(&path, &[Page::CourseList, Page::CourseList]).await.unwrap().unwrap();
+  assert_eq!(pages.len(), 2);
+  assert_eq!(grades_list::parse_courses(&pages[0]).unwrap().len(), 6);
+
+  std::fs::remove_dir_all(path.parent().unwrap()).unwrap();
+}
+
+#[tokio::test]
+async fn wrong_password () {
+  let server = york().await;
+  let client = client(&server);
+
+  match client.login("student", "wrong").await {
+    Err(GradesError::Auth) => {},
+    other => panic!("expected an auth error, got {:?}", other),
+  }
+}
+
+#[tokio::test]
+async fn locked_account () {
+  let server = york().await;
+  let client = client(&server);
+
+  match client.login("locked", "hunter2").await {
+    Err(GradesError::Locked) => {},
+    other => panic!("expected a locked account, got {:?}", other),
+  }
+}
+
+#[test]
+fn maintenance_page () {
+  match grades_list::parse_courses(&fixture("maintenance.html")) {
+    Err(GradesError::Maintenance) => {},
+    other => panic!("expected a maintenance error, got {:?}", other),
+  }
+}
+
+#[tokio::test]
+async fn saved_session_skips_login () {
+  let server = york().await;
+
+  let first = client(&server);
+  first.login("student", "hunter2").await.unwrap();
+
+  let mut saved = Vec::new();
+  first.save_cookies(&mut saved).unwrap();
+
+  let second = client(&server);
+  second.load_cookies(&saved[..]).unwrap();
+  assert!(second.is_logged_in().await.unwrap());
+  assert_eq!(second.fetch_grades().await.unwrap().len(), 6);
+}
+
+#[tokio::test]
+async fn expired_cookies_dropped () {
+  let server = MockServer::start().await;
+  let expires = (chrono::Utc::now() + chrono::Duration::days(1)).format("%a, %d-%b-%Y %H:%M:%S GMT");
+
+  Mock::given(method("GET")).and(path(COURSE_PATH)).and(header_exists("cookie"))
+    .respond_with(html("course_list.html"))
+    .with_priority(2)
+    .mount(&server).await;
+  Mock::given(method("GET")).and(path(COURSE_PATH))
+    .respond_with(ResponseTemplate::new(302).insert_header("Location", LOGIN_PATH))
+    .mount(&server).await;
+  Mock::given(method("GET")).and(path(LOGIN_PATH))
+    .respond_with(html("login_form.html"))
+    .mount(&server).await;
+  Mock::given(method("POST")).and(path(LOGIN_PATH))
+    .respond_with(html("login_success.html").insert_header("Set-Cookie", format!("pybpp=session-token; Path=/; Expires={}", expires).as_str()))
+    .mount(&server).await;
+
+  let first = client(&server);
+  first.login("student", "hunter2").await.unwrap();
+  assert!(first.is_logged_in().await.unwrap());
+
+  // an expiry that is still to come is kept along with the session
+  let mut saved = Vec::new();
+  first.save_cookies(&mut saved).unwrap();
+  let restored = client(&server);
+  restored.load_cookies(&saved[..]).unwrap();
+  assert!(restored.has_cookies() && restored.is_logged_in().await.unwrap());
+
+  // SIS ending the session with an expiry in the past rather than Max-Age=0
+  Mock::given(method("GET")).and(path(COURSE_PATH)).and(header_exists("cookie"))
+    .respond_with(html("course_list.html").insert_header("Set-Cookie", "pybpp=deleted; Path=/; Expires=Thu, 01 Jan 1970 00:00:00 GMT"))
+    .up_to_n_times(1)
+    .with_priority(1)
+    .mount(&server).await;
+
+  assert_eq!(first.fetch_grades().await.unwrap().len(), 6);
+  assert!(!first.has_cookies());
+  assert!(!first.is_logged_in().await.unwrap());
+}
+
+#[test]
+fn missing_table () {
+  match grades_list::parse_courses(&fixture("no_table.html")) {
+    Err(GradesError::Layout(_)) => {},
+    other => panic!("expected a layout error, got {:?}", other),
+  }
+}
+
+#[test]
+fn table_found_by_headers () {
+  let grades = grades_list::parse_courses(&fixture("wrapped_tables.html")).unwrap();
+  assert_eq!(grades.iter().map(|c| c.course.as_str()).collect::<Vec<_>>(), ["LE EECS 2030 3.00 A", "SC MATH 1300 3.00 M"]);
+
+  match grades_list::parse_courses(&fixture("other_table.html")) {
+    Err(GradesError::Layout(msg)) => assert!(msg.contains("--save-html")),
+    other => panic!("expected a layout error, got {:?}", other),
+  }
+}
+
+#[test]
+fn cell_text_normalized () {
+  let grades = grades_list::parse_courses(&fixture("course_names.html")).unwrap();
+
+  assert_eq!(grades[0].title, "Enviro\u{2019}l Studies & Society");
+  // the combining accents are composed into é
+  assert_eq!(grades[1].title, "Litt\u{e9}rature de l\u{2019}\u{e9}poque");
+  assert_eq!(grades[1].grade, "A");
+}
+
+#[test]
+fn spanned_and_nested_cells () {
+  let grades = grades_list::parse_courses(&fixture("spanned_cells.html")).unwrap();
+  let rows = grades.iter().map(|c| (c.session.as_str(), c.course.as_str(), c.title.as_str(), c.grade.as_str())).collect::<Vec<_>>();
+
+  assert_eq!(rows, [
+    ("FW 2022-2023", "LE EECS 2030 3.00 A", "Advanced Object Oriented Programming", "B+"),
+    ("FW 2022-2023", "SC MATH 1300 3.00 M", "Differential Calculus", "B"),
+  ]);
+}
+
+#[test]
+fn malformed_rows () {
+  let page = fixture("course_list.html")
+    .replace("<td>SU 2023</td><td>AP ECON 1000 3.00 B</td>", "<td>SU 2023</td>")
+    .replace("<td>LE EECS 3101 3.00 A</td>", "<td>&nbsp;</td>");
+
+  let parsed = grades_list::parse_courses_with(&page, &Selectors::default(), ParseMode::Lenient).unwrap();
+  assert_eq!(parsed.grades.len(), 4);
+  assert_eq!(parsed.warnings.iter().map(|w| w.row).collect::<Vec<_>>(), [5, 6]);
+  assert_eq!(parsed.warnings[0].to_string(), "row 5 of the course list has 3 columns instead of 4, left out");
+
+  match grades_list::parse_courses_with(&page, &Selectors::default(), ParseMode::Strict) {
+    Err(GradesError::Layout(msg)) => assert_eq!(msg, "row 5 has 3 columns instead of 4"),
+    other => panic!("expected a layout error, got {:?}", other),
+  }
+}
+
+#[test]
+fn transcript_merged () {
+  let transcript = transcript::parse(&fixture("transcript.html"), &Selectors::default(), ParseMode::Strict).unwrap().grades;
+  assert_eq!((transcript[1].credits, transcript[1].grade_points), (Some(3.0), Some(4.0)));
+  assert_eq!(transcript[1].notes, "Repeated, not in GPA");
+
+  let course_list = grades_list::parse_courses(&fixture("course_list.html")).unwrap();
+  let merged = transcript::merge(course_list, transcript);
+  let rows = merged.iter().map(|c| (c.code(), c.grade.as_str(), c.grade_points, c.superseded)).collect::<Vec<_>>();
+
+  assert_eq!(rows, [
+    ("EECS 1012".to_owned(), "A+", Some(9.0), false),
+    ("EECS 2030".to_owned(), "C", Some(4.0), true),
+    ("EECS 2030".to_owned(), "B+", Some(7.0), false),
+    ("MATH 1300".to_owned(), "B", None, false),
+    ("ECON 1000".to_owned(), "W", None, false),
+    ("EECS 3101".to_owned(), "", None, false),
+    ("ECON 1000".to_owned(), "TC", None, false),
+  ]);
+  assert_eq!(merged[6].credits, Some(6.0));
+}
+
+#[test]
+fn degree_progress_report () {
+  let requirements = degree::parse(&fixture("degree_progress.html"), &Selectors::default()).unwrap();
+  let rows = requirements.iter().map(|r| (r.name.as_str(), r.status, r.outstanding)).collect::<Vec<_>>();
+
+  assert_eq!(rows, [
+    ("Total credits", Status::NotSatisfied, Some(36.0)),
+    ("General education", Status::Satisfied, Some(0.0)),
+    ("Major, 3000 level or above", Status::InProgress, Some(6.0)),
+    ("Residency", Status::Satisfied, None),
+  ]);
+  assert_eq!(degree::outstanding(&requirements), 42.0);
+}
+
+#[test]
+fn schedule_meetings () {
+  let meetings = schedule::parse(&fixture("schedule.html"), &Selectors::default()).unwrap();
+  let rows = meetings.iter().map(|m| (m.course.as_str(), m.kind.as_str(), m.day_letters(), m.time_range(), m.location.as_str())).collect::<Vec<_>>();
+
+  assert_eq!(rows, [
+    ("LE EECS 3101 3.00 A", "LECT", "MW".to_owned(), "10:30-11:50".to_owned(), "CLH 110"),
+    ("LE EECS 3101 3.00 A", "TUTR", "F".to_owned(), "13:30-14:30".to_owned(), "LAS 1006"),
+    ("LE EECS 3311 3.00 B", "LECT", "MWF".to_owned(), "09:30-10:20".to_owned(), "LSB 106"),
+    ("LE EECS 3221 3.00 M", "LECT", "TR".to_owned(), "14:30-16:00".to_owned(), "CB 121"),
+    ("SC MATH 1090 3.00 M", "ONLN", String::new(), String::new(), "Online"),
+  ]);
+  assert_eq!(meetings[1].title, "Design and Analysis of Algorithms");
+  assert_eq!(meetings[0].last_day, chrono::NaiveDate::from_ymd_opt(2024, 12, 3));
+}
+
+#[test]
+fn schedule_calendar () {
+  let meetings = schedule::parse(&fixture("schedule.html"), &Selectors::default()).unwrap();
+  let calendar = schedule::ics(&meetings, chrono::Utc::now());
+
+  assert!(calendar.starts_with("BEGIN:VCALENDAR\r\nVERSION:2.0\r\n"));
+  // the online course has no meetings to put in the calendar
+  assert_eq!(calendar.matches("BEGIN:VEVENT").count(), 4);
+  // the tutorial starts on the first Friday of classes
+  assert!(calendar.contains("DTSTART:20240906T133000\r\nDTEND:20240906T143000\r\nRRULE:FREQ=WEEKLY;BYDAY=FR;UNTIL=20241203T235959\r\n"));
+  assert!(calendar.contains("LOCATION:CLH 110\r\n"));
+  // York's letters run together, R for Thursday
+  assert!(calendar.contains("RRULE:FREQ=WEEKLY;BYDAY=MO,WE,FR;UNTIL=20241203T235959\r\n"));
+  assert!(calendar.contains("DTSTART:20240905T143000\r\nDTEND:20240905T160000\r\nRRULE:FREQ=WEEKLY;BYDAY=TU,TH;"));
+}
+
+#[test]
+fn appointments_and_important_dates () {
+  let key_dates = dates::parse(&fixture("dates.html"), &Selectors::default()).unwrap();
+  let rows = key_dates.iter().map(|d| (d.kind, d.when())).collect::<Vec<_>>();
+
+  assert_eq!(rows, [
+    (Kind::Appointment, "2024-07-02 10:00 to 2024-09-17 23:59".to_owned()),
+    (Kind::Academic, "2024-09-04".to_owned()),
+    (Kind::Academic, "2024-10-12 to 2024-10-18".to_owned()),
+    (Kind::Academic, "2024-11-08".to_owned()),
+  ]);
+
+  let calendar = dates::ics(&key_dates, chrono::Utc::now());
+  assert!(calendar.contains("DTSTART;VALUE=DATE:20241012\r\nDTEND;VALUE=DATE:20241019\r\n"));
+  assert_eq!(calendar.matches("TRIGGER:-P1D").count(), 4);
+}
+
+#[test]
+fn no_enrolment_appointment_yet () {
+  let page = "<html><body><p>You have not been given an enrolment appointment yet.</p></body></html>";
+
+  assert!(dates::parse_fetched(page, &Selectors::default()).unwrap().is_empty());
+  assert!(matches!(dates::parse(page, &Selectors::default()), Err(GradesError::Layout(_))));
+  // a page that is down is still an error
+  assert!(matches!(dates::parse_fetched(&fixture("maintenance.html"), &Selectors::default()), Err(GradesError::Maintenance)));
+}
+
+#[test]
+fn configured_selectors () {
+  // a course list redone without tables
+  let page = r#"<div class="grades">
+    <div class="row"><span>Session</span><span>Course</span><span>Title</span><span>Grade</span></div>
+    <div class="row"><span>FW 2022-2023</span><span>LE EECS 2030 3.00 A</span><span>Advanced Object Oriented Programming</span><span>B+</span></div>
+  </div>"#;
+  let selectors = Selectors { table: "div.grades".to_owned(), row: "div.row".to_owned(), cell: "span".to_owned(), ..Selectors::default() };
+  assert_eq!(grades_list::parse_courses_with(page, &selectors, ParseMode::Lenient).unwrap().grades[0].grade, "B+");
+
+  let broken = Selectors { row: "tr[".to_owned(), ..Selectors::default() };
+  match grades_list::parse_courses_with(page, &broken, ParseMode::Lenient) {
+    Err(GradesError::Config(_)) => {},
+    other => panic!("expected a config error, got {:?}", other),
+  }
+}
+
+#[tokio::test]
+async fn slow_server_times_out () {
+  let server = MockServer::start().await;
+  Mock::given(method("GET"))
+    .respond_with(ResponseTemplate::new(200).set_delay(Duration::from_secs(5)))
+    .mount(&server).await;
+
+  let options = ClientOptions { timeout: Some(Duration::from_millis(200)), ..Default::default() };
+  let client = GradesClient::with_options(Endpoints::with_base(&server.uri()), &options).unwrap();
+
+  match client.fetch_grades().await {
+    Err(GradesError::Network(e)) => assert!(e.is_timeout()),
+    other => panic!("expected a timeout, got {:?}", other),
+  }
+}
+
+#[tokio::test]
+async fn through_a_proxy () {
+  // York's hosts don't resolve, only the proxy can be reached and it is sent every request
+  let proxy = york().await;
+  let options = ClientOptions { proxy: Some(proxy.uri()), ..Default::default() };
+  let client = GradesClient::with_options(Endpoints::with_base("http://sis.invalid"), &options).unwrap();
+
+  client.login("student", "hunter2").await.unwrap();
+  assert_eq!(client.fetch_grades().await.unwrap().len(), 6);
+
+  let requests = proxy.received_requests().await.unwrap();
+  assert!(requests.len() >= 3);
+  assert!(requests.iter().all(|r| r.url.host_str() == Some("sis.invalid")));
+
+  // without it the same client can't get anywhere
+  let direct = GradesClient::with_endpoints(Endpoints::with_base("http://sis.invalid")).unwrap();
+  assert!(matches!(direct.fetch_grades().await, Err(GradesError::Network(_))));
+
+  let options = ClientOptions { proxy: Some("not a proxy".to_owned()), ..Default::default() };
+  assert!(GradesClient::with_options(Endpoints::with_base("http://sis.invalid"), &options).is_err());
+}
+
+#[test]
+fn cached_scrape () {
+  let path = std::env::temp_dir().join(format!("grades_list-cache-{}.json", std::process::id()));
+  let grades = grades_list::parse_courses(&fixture("course_list.html")).unwrap();
+
+  assert!(cache::load(&path, None).unwrap().is_none());
+  cache::save(&path, &grades, None).unwrap();
+  let cached = cache::load(&path, None).unwrap().unwrap();
+  std::fs::remove_file(&path).unwrap();
+
+  assert_eq!(cached.grades, grades);
+  assert!(cached.is_fresh(Duration::from_secs(60)));
+  assert!(!Cached { fetched_at: cached.fetched_at - chrono::Duration::hours(7), ..cached }.is_fresh(Duration::from_secs(6 * 60 * 60)));
+}
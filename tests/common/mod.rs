@@ -0,0 +1,14 @@
+//! Helpers shared by the integration tests, each test file only uses some of them
+#![allow(dead_code)]
+
+use grades_list::CourseData;
+
+/// A course taken in FW 2022-2023
+pub fn course (course: &str, grade: &str) -> CourseData {
+  in_session("FW 2022-2023", course, grade)
+}
+
+/// A course taken in `session`, the title doesn't matter to any test
+pub fn in_session (session: &str, course: &str, grade: &str) -> CourseData {
+  CourseData::new(session.to_owned(), course.to_owned(), String::new(), grade.to_owned())
+}
@@ -0,0 +1,252 @@
+use grades_list::CourseData;
+use grades_list::diff::GradeChange;
+use grades_list::encrypt::{self, Recipient};
+use grades_list::notion::{Notion, Upserted};
+use grades_list::redact::{self, Redactor};
+use grades_list::sheets::{ServiceAccount, Sheets};
+use grades_list::snapshot;
+
+use wiremock::{Mock, MockServer, ResponseTemplate};
+use wiremock::matchers::{body_string_contains, header, method, path, query_param};
+
+mod common;
+use common::{course, in_session};
+
+fn account (server: &MockServer) -> ServiceAccount {
+  ServiceAccount {
+    client_email: "grades@project.iam.gserviceaccount.com".to_owned(),
+    // a throwaway key, made afresh so no private key sits in the repository
+    private_key: String::from_utf8(openssl::rsa::Rsa::generate(2048).unwrap().private_key_to_pem().unwrap()).unwrap(),
+    token_uri: format!("{}/token", server.uri()),
+  }
+}
+
+#[tokio::test]
+async fn sheets_replace () {
+  let server = MockServer::start().await;
+
+  Mock::given(method("POST")).and(path("/token"))
+    .and(body_string_contains("grant_type=urn%3Aietf%3Aparams%3Aoauth%3Agrant-type%3Ajwt-bearer"))
+    .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({ "access_token": "token", "expires_in": 3600 })))
+    .expect(1)
+    .mount(&server).await;
+  Mock::given(method("GET")).and(path("/v4/spreadsheets/sheet-id"))
+    .and(header("authorization", "Bearer token"))
+    .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({ "sheets": [{ "properties": { "title": "Sheet1" } }] })))
+    .mount(&server).await;
+  // the worksheet is missing, so it's added before writing
+  Mock::given(method("POST")).and(path("/v4/spreadsheets/sheet-id:batchUpdate"))
+    .and(body_string_contains("\"title\":\"My Grades\""))
+    .respond_with(ResponseTemplate::new(200))
+    .expect(1)
+    .mount(&server).await;
+  Mock::given(method("POST")).and(path("/v4/spreadsheets/sheet-id/values/'My%20Grades':clear"))
+    .respond_with(ResponseTemplate::new(200))
+    .expect(1)
+    .mount(&server).await;
+  Mock::given(method("PUT")).and(path("/v4/spreadsheets/sheet-id/values/'My%20Grades'"))
+    .and(query_param("valueInputOption", "RAW"))
+    .and(body_string_contains("\"LE EECS 2030 3.00 A\""))
+    .respond_with(ResponseTemplate::new(200))
+    .expect(1)
+    .mount(&server).await;
+
+  let grades = vec![course("LE EECS 2030 3.00 A", "B+")];
+  let gpa = grades_list::calculate_gpa(&grades).unwrap();
+
+  let sheets = Sheets::with_api(&account(&server), "sheet-id", &server.uri()).await.unwrap();
+  sheets.write("My Grades", grades_list::sheets::rows(&grades, Some(&gpa), chrono::Utc::now()), false).await.unwrap();
+}
+
+#[tokio::test]
+async fn notion_upsert () {
+  let server = MockServer::start().await;
+
+  Mock::given(method("POST")).and(path("/v1/databases/db-id/query"))
+    .and(header("notion-version", "2022-06-28"))
+    .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+      "results": [{ "id": "page-2030", "properties": { "Course": { "title": [{ "plain_text": "EECS 2030" }] } } }],
+      "has_more": false,
+      "next_cursor": null,
+    })))
+    .mount(&server).await;
+  Mock::given(method("PATCH")).and(path("/v1/pages/page-2030"))
+    .and(body_string_contains("\"Grade\":{\"rich_text\":[{\"text\":{\"content\":\"A\"}}]}"))
+    .respond_with(ResponseTemplate::new(200))
+    .expect(1)
+    .mount(&server).await;
+  Mock::given(method("POST")).and(path("/v1/pages"))
+    .and(body_string_contains("\"database_id\":\"db-id\""))
+    .and(body_string_contains("\"content\":\"EECS 3101\""))
+    .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({ "id": "page-3101" })))
+    .expect(1)
+    .mount(&server).await;
+  // rows without a course code each get a page of their own
+  for title in ["Transfer Credit Calculus", "Transfer Credit English"].iter() {
+    Mock::given(method("POST")).and(path("/v1/pages"))
+      .and(body_string_contains(format!("\"content\":\"FW 2022-2023 {}\"", title)))
+      .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({ "id": format!("page-{}", title) })))
+      .expect(1)
+      .mount(&server).await;
+  }
+
+  // the first attempt at EECS 2030 was replaced by the retake and isn't written
+  let mut grades = vec![course("LE EECS 2030 3.00 A", "D"), course("LE EECS 2030 3.00 A", "A"), course("LE EECS 3101 3.00 A", "B")];
+  grades[0].superseded = true;
+  for title in ["Transfer Credit Calculus", "Transfer Credit English"].iter() {
+    grades.push(CourseData::new("FW 2022-2023".to_owned(), String::new(), title.to_string(), "P".to_owned()));
+  }
+
+  let notion = Notion::with_api("secret", "db-id", &server.uri()).unwrap();
+  assert_eq!(notion.upsert(&grades).await.unwrap(), Upserted { created: 3, updated: 1 });
+}
+
+#[test]
+fn git_snapshot () {
+  let grades = vec![
+    in_session("SU 2023", "LE EECS 3101 3.00 A", "B"),
+    course("LE EECS 2030 3.00 A", "A"),
+  ];
+  let gpa = grades_list::calculate_gpa(&grades).unwrap();
+
+  let sorted = snapshot::sorted(&grades);
+  assert_eq!(sorted[0].session, "FW 2022-2023");
+  assert_eq!(snapshot::json(&grades, Some(&gpa)), snapshot::json(&sorted, Some(&gpa)));
+
+  let changes = vec![GradeChange::Changed { course: grades[0].clone(), old_grade: String::new() }];
+  assert_eq!(snapshot::message(Some(&changes), 2, Some(&gpa)), "New grade: EECS 3101 — B, GPA 7.00 / 9, 3.40 / 4\n");
+  assert!(snapshot::message(None, 2, Some(&gpa)).starts_with("First grades snapshot, 2 courses"));
+}
+
+#[test]
+fn git_snapshot_commit () {
+  let git = |dir: &std::path::Path, args: &[&str]| {
+    let output = std::process::Command::new("git").arg("-C").arg(dir).args(args).output().unwrap();
+    String::from_utf8(output.stdout).unwrap()
+  };
+  let outer = std::env::temp_dir().join(format!("grades_list-snapshot-{}", std::process::id()));
+  let _ = std::fs::remove_dir_all(&outer);
+  std::fs::create_dir_all(&outer).unwrap();
+  git(&outer, &["init", "--quiet"]);
+  std::fs::write(outer.join("notes.txt"), "not grades").unwrap();
+  git(&outer, &["add", "notes.txt"]);
+
+  // a directory inside an unrelated repository gets its own
+  let dir = outer.join("grades");
+  let grades = vec![course("LE EECS 2030 3.00 A", "A")];
+  assert!(snapshot::commit(&dir, &grades, None).unwrap().unwrap().starts_with("First grades snapshot"));
+  assert!(dir.join(".git").is_dir());
+  assert_eq!(git(&outer, &["diff", "--cached", "--name-only"]), "notes.txt\n");
+
+  // what was already staged stays staged and out of the snapshot
+  std::fs::write(dir.join("notes.txt"), "not grades either").unwrap();
+  git(&dir, &["add", "notes.txt"]);
+  assert!(snapshot::commit(&dir, &grades, None).unwrap().is_none());
+  let message = snapshot::commit(&dir, &[course("LE EECS 2030 3.00 A", "B")], None).unwrap().unwrap();
+  assert!(message.starts_with("Grade changed: EECS 2030"), "{}", message);
+  assert_eq!(git(&dir, &["show", "--name-only", "--format=", "HEAD"]), "grades.csv\ngrades.json\n");
+  assert_eq!(git(&dir, &["diff", "--cached", "--name-only"]), "notes.txt\n");
+
+  std::fs::remove_dir_all(&outer).unwrap();
+}
+
+#[test]
+fn redacted () {
+  let secret = [7; 32];
+  let mut grades = vec![course("LE EECS 2030 3.00 A", "A"), course("LE EECS 3101 3.00 M", "B")];
+  grades[0].notes = "Deferred standing for jdoe, 212345678, jdoe@my.yorku.ca".to_owned();
+
+  let redactor = Redactor::new(secret, vec!["JDoe".to_owned()], false);
+  let redacted = redactor.courses(grades.clone());
+  assert_eq!(redacted[0].notes, format!("Deferred standing for {}, {}, {}", redact::hash(&secret, "jdoe"), redact::hash(&secret, "212345678"), redact::hash(&secret, "jdoe@my.yorku.ca")));
+  assert!(redact::hash(&secret, "jdoe").starts_with("redacted-"));
+  assert_eq!(redacted[1].section, "M");
+
+  // without the secret the placeholder of a student number can't be worked out
+  assert_ne!(redact::hash(&secret, "212345678"), redact::hash(&[8; 32], "212345678"));
+
+  let redacted = Redactor::new(secret, None, true).courses(grades);
+  assert_eq!(redacted[1].course, "LE EECS 3101 3.00");
+  assert_eq!(redacted[1].section, "");
+  assert_eq!(redacted[1].number, "3101");
+
+  let page = r#"<a href="/Apps/WebObjects/x.woa?wosid=abc123">jdoe</a>"#;
+  assert_eq!(Redactor::new(secret, Some("jdoe".to_owned()), false).page(page), format!(r#"<a href="/Apps/WebObjects/x.woa?wosid=">{}</a>"#, redact::hash(&secret, "jdoe")));
+}
+
+#[test]
+fn short_username_redacted () {
+  let secret = [7; 32];
+  let redactor = Redactor::new(secret, Some("li".to_owned()), false);
+
+  assert_eq!(redactor.text("English and Calculus"), "English and Calculus");
+  assert_eq!(redactor.text("Li, (li) and li_2"), format!("{0}, ({0}) and li_2", redact::hash(&secret, "li")));
+}
+
+#[test]
+fn redaction_secret_kept () {
+  let dir = std::env::temp_dir().join(format!("grades_list-secret-{}", std::process::id()));
+  let path = dir.join("redact.key");
+
+  let secret = redact::load_secret(&path).unwrap();
+  assert_eq!(redact::load_secret(&path).unwrap(), secret);
+  #[cfg(unix)]
+  assert_eq!(std::os::unix::fs::PermissionsExt::mode(&std::fs::metadata(&path).unwrap().permissions()) & 0o777, 0o600);
+
+  std::fs::write(&path, b"short").unwrap();
+  assert!(redact::load_secret(&path).is_err());
+
+  std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn encrypted_to_age () {
+  let identity = age::x25519::Identity::generate();
+  let recipient: Recipient = identity.to_public().to_string().parse().unwrap();
+
+  let encrypted = encrypt::encrypt(b"EECS 2030,A", std::slice::from_ref(&recipient)).unwrap();
+  assert!(encrypted.starts_with(b"age-encryption.org/v1"));
+  assert_eq!(age::decrypt(&identity, &encrypted).unwrap(), b"EECS 2030,A");
+
+  assert!("age1notakey".parse::<Recipient>().is_err());
+  let mixed = encrypt::encrypt(b"", &[recipient, "grades@example.com".parse().unwrap()]);
+  assert!(matches!(mixed, Err(grades_list::GradesError::Config(_))));
+}
+
+fn gpg (home: &std::path::Path, args: &[&str], input: &[u8]) -> Vec<u8> {
+  use std::io::Write;
+  use std::process::{Command, Stdio};
+
+  let mut gpg = Command::new("gpg").arg("--homedir").arg(home).arg("--batch").args(args)
+    .stdin(Stdio::piped()).stdout(Stdio::piped()).stderr(Stdio::null())
+    .spawn().unwrap();
+  gpg.stdin.take().unwrap().write_all(input).unwrap();
+  let output = gpg.wait_with_output().unwrap();
+  assert!(output.status.success(), "gpg {:?} failed", args);
+  output.stdout
+}
+
+#[test]
+fn encrypted_to_imported_gpg_key () {
+  let dir = std::env::temp_dir().join(format!("grades_list-gpg-{}", std::process::id()));
+  let (laptop, server) = (dir.join("laptop"), dir.join("server"));
+  for home in [&laptop, &server] {
+    std::fs::create_dir_all(home).unwrap();
+    #[cfg(unix)]
+    std::fs::set_permissions(home, std::os::unix::fs::PermissionsExt::from_mode(0o700)).unwrap();
+  }
+
+  // the key of another machine, imported but neither signed nor trusted
+  gpg(&laptop, &["--passphrase", "", "--quick-gen-key", "grades@example.com", "future-default", "default", "never"], b"");
+  let public = gpg(&laptop, &["--export", "grades@example.com"], b"");
+  gpg(&server, &["--import"], &public);
+
+  std::env::set_var("GNUPGHOME", &server);
+  let encrypted = encrypt::encrypt(b"EECS 2030,A", &["grades@example.com".parse().unwrap()]).unwrap();
+  assert_eq!(gpg(&laptop, &["--decrypt"], &encrypted), b"EECS 2030,A");
+
+  for home in [&laptop, &server] {
+    let _ = std::process::Command::new("gpgconf").arg("--homedir").arg(home).args(["--kill", "gpg-agent"]).status();
+  }
+  std::fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,22 @@
+#![cfg(feature = "ffi")]
+
+use std::ffi::{CStr, CString};
+use std::ptr;
+
+use grades_list::ffi::{grades_fetch_json, grades_free};
+
+#[test]
+fn null_arguments_are_errors () {
+  let password = CString::new("password").unwrap();
+  let json = unsafe { grades_fetch_json(ptr::null(), password.as_ptr(), ptr::null()) };
+  assert!(!json.is_null());
+
+  let output: serde_json::Value = serde_json::from_str(unsafe { CStr::from_ptr(json) }.to_str().unwrap()).unwrap();
+  assert_eq!(output["exit_code"], 11);
+  assert!(output["error"].as_str().unwrap().contains("username is null"));
+
+  unsafe {
+    grades_free(json);
+    grades_free(ptr::null_mut());
+  }
+}
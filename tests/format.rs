@@ -0,0 +1,209 @@
+use grades_list::{CourseData, Gpa, GradesError};
+use grades_list::format::{self, Precision, Rounding};
+use grades_list::i18n::{Lang, Text};
+use serde::Serialize;
+use serde_json::Value;
+
+mod common;
+use common::course;
+
+#[test]
+fn ndjson_lines () {
+  let grades = vec![course("LE EECS 2030 3.00 A", "A+"), course("AP ECON 1000 3.00 B", "W")];
+  let gpa = grades_list::calculate_gpa(&grades).unwrap();
+
+  let records = format::ndjson(&grades, Some(&gpa)).lines()
+    .map(|line| serde_json::from_str::<Value>(line).unwrap())
+    .collect::<Vec<_>>();
+
+  assert_eq!(records.len(), 3);
+  assert_eq!((records[0]["type"].as_str(), records[0]["nine_point_weighted"].as_f64()), (Some("course"), Some(27.0)));
+  assert!(records[1].get("nine_point").is_none());
+  assert_eq!((records[2]["type"].as_str(), records[2]["nine"].as_f64()), (Some("gpa"), Some(9.0)));
+}
+
+#[test]
+fn csv_footer () {
+  let grades = vec![course("LE EECS 2030 3.00 A", "A+")];
+  let gpa = Gpa { four: 4.0, nine: 9.0 };
+
+  let csv = format::csv(&grades, Some(&gpa), None).unwrap();
+  let lines = csv.lines().collect::<Vec<_>>();
+  assert_eq!(lines.len(), 4);
+  assert!(lines[0].starts_with("session,course,title,grade,"));
+  assert!(lines[1].starts_with("FW 2022-2023,LE EECS 2030 3.00 A,,A+,LE,EECS,2030,3.0,A,"));
+  assert_eq!(lines[2], "GPA,,Four Point,4,,,,,,,,,,,");
+  assert_eq!(lines[3], "GPA,,Nine Point,9,,,,,,,,,,,");
+
+  // the header is written by the first course, so with none it has to be written for the footer
+  let empty = format::csv(&[], Some(&gpa), None).unwrap();
+  assert_eq!(empty.lines().next(), lines.first().copied());
+  assert_eq!(empty.lines().count(), 3);
+
+  // the credit totals go in the credits column, as `--gpa-row` writes them
+  let credits = grades_list::classify::credit_summary(&grades);
+  let with_credits = format::csv(&grades, Some(&gpa), Some(&credits)).unwrap();
+  assert_eq!(with_credits.lines().skip(4).collect::<Vec<_>>(), [
+    "Credits,,Attempted,,,,,3,,,,,,,",
+    "Credits,,Earned,,,,,3,,,,,,,",
+    "Credits,,In GPA,,,,,3,,,,,,,",
+    "Credits,,Excluded,,,,,0,,,,,,,",
+  ]);
+
+  // and nothing at all without a footer either
+  assert_eq!(format::csv(&[], None, None).unwrap(), "");
+}
+
+#[test]
+fn markdown_and_html_escaped () {
+  let mut grades = vec![course("LE EECS 3101 3.00 A", "B")];
+  grades[0].title = "Design & Analysis | <Algorithms>\n\"II\"".to_owned();
+
+  let markdown = format::markdown(&grades, None);
+  assert!(markdown.contains("| - | - |\n"), "{}", markdown);
+  assert!(markdown.contains("| FW 2022-2023 | LE EECS 3101 3.00 A | Design & Analysis \\| <Algorithms> \"II\" | B |\n"), "{}", markdown);
+
+  let html = format::html(&grades, Some(&Gpa { four: 3.0, nine: 6.0 }));
+  assert!(html.contains("<tr><td>3</td><td>6</td></tr>"), "{}", html);
+  assert!(html.contains("<td>Design &amp; Analysis | &lt;Algorithms&gt;\n&quot;II&quot;</td>"), "{}", html);
+  assert!(!html.contains("<Algorithms>"));
+}
+
+#[derive(Serialize)]
+struct Doc<'a> {
+  gpa: &'a Gpa,
+  grades: &'a [CourseData],
+}
+
+#[test]
+fn yaml_and_toml () {
+  let grades = vec![course("LE EECS 2030 3.00 A", "B+")];
+  let gpa = grades_list::calculate_gpa(&grades).unwrap();
+  let doc = Doc { gpa: &gpa, grades: &grades };
+
+  let yaml = format::yaml(&doc).unwrap();
+  assert!(yaml.contains("grade: B+"));
+
+  // no null in TOML, so the missing override is left out
+  let toml = format::toml(&doc).unwrap();
+  assert!(toml.contains("[gpa]\nfour = 3.3\nnine = 7.0"), "{}", toml);
+  assert!(!toml.contains("overridden"));
+}
+
+#[test]
+fn xlsx_workbook () {
+  let grades = vec![course("LE EECS 2030 3.00 A", "B+"), course("LE EECS 3101 3.00 A", "")];
+  let gpa = grades_list::calculate_gpa(&grades).unwrap();
+
+  let data = grades_list::xlsx::xlsx(&grades, Some(&gpa)).unwrap();
+  // an xlsx file is a zip archive of XML parts, the text of every cell kept in sharedStrings.xml
+  let mut workbook = zip::ZipArchive::new(std::io::Cursor::new(data)).unwrap();
+  let mut part = |name: &str| {
+    let mut xml = String::new();
+    std::io::Read::read_to_string(&mut workbook.by_name(name).unwrap(), &mut xml).unwrap();
+    xml
+  };
+
+  let strings = part("xl/sharedStrings.xml");
+  for text in ["Session", "LE EECS 2030 3.00 A", "LE EECS 3101 3.00 A", "B+", "FW 2022-2023", "Overall"].iter() {
+    assert!(strings.contains(&format!(">{}<", text)), "{} is not in the workbook", text);
+  }
+  let workbook_xml = part("xl/workbook.xml");
+  assert!(workbook_xml.contains(r#"name="Grades""#) && workbook_xml.contains(r#"name="GPA by Session""#));
+
+  // B+ is 7 on the nine point scale and 3.3 on the four point one, both on the course and overall
+  let courses = part("xl/worksheets/sheet1.xml");
+  assert_eq!(courses.matches("<row ").count(), 3);
+  let cell = |xml: &str, cell: &str, value: f32| xml.contains(&format!(r#"<c r="{}" s="3"><v>{}</v>"#, cell, f64::from(value)));
+  assert!(cell(&courses, "F2", 7.0) && cell(&courses, "G2", 3.3));
+  let sessions = part("xl/worksheets/sheet2.xml");
+  assert_eq!(sessions.matches("<row ").count(), 3);
+  assert!(cell(&sessions, "B2", 7.0) && cell(&sessions, "C2", 3.3));
+  // the overall row is bold, a style of its own
+  assert!(sessions.contains(&format!(r#"<c r="B3" s="4"><v>{}</v>"#, f64::from(gpa.nine))));
+  assert!(sessions.contains(&format!(r#"<c r="C3" s="4"><v>{}</v>"#, f64::from(gpa.four))));
+
+  // with nothing graded there is no overall row to write
+  let ungraded = grades_list::xlsx::xlsx(&grades[1..], None).unwrap();
+  let mut workbook = zip::ZipArchive::new(std::io::Cursor::new(ungraded)).unwrap();
+  let mut xml = String::new();
+  std::io::Read::read_to_string(&mut workbook.by_name("xl/worksheets/sheet2.xml").unwrap(), &mut xml).unwrap();
+  assert_eq!(xml.matches("<row ").count(), 1);
+}
+
+#[test]
+fn pdf_transcript () {
+  let grades = (0..80).map(|i| course(&format!("LE EECS {} 3.00 A", 1000 + i), "B")).collect::<Vec<_>>();
+  let gpa = grades_list::calculate_gpa(&grades).unwrap();
+  let student = grades_list::pdf::Student { name: Some("Jane Doe".to_owned()), id: None };
+
+  let data = grades_list::pdf::transcript(&grades, Some(&gpa), &student).unwrap();
+  assert!(data.starts_with(b"%PDF"));
+  // 80 courses don't fit on one page
+  assert!(String::from_utf8_lossy(&data).contains("/Count 2"));
+
+  // nothing graded yet shows a dash where the GPA goes, 0x97 in the WinAnsiEncoding of the
+  // builtin fonts, and text is written as hex strings
+  let ungraded = grades_list::pdf::transcript(&[course("LE EECS 3101 3.00 A", "")], None, &student).unwrap();
+  let doc = printpdf::lopdf::Document::load_mem(&ungraded).unwrap();
+  let content = String::from_utf8(doc.get_page_content(doc.get_pages()[&1]).unwrap()).unwrap();
+  let hex = |text: &[u8]| format!("<{}>", text.iter().map(|b| format!("{:02X}", b)).collect::<String>());
+  assert!(content.contains(&hex(b"Cumulative GPA \x97")), "{}", content);
+  assert!(content.contains(&hex(b"EECS 3101")));
+}
+
+#[test]
+fn french () {
+  let lang: Lang = "fr".parse().unwrap();
+  assert!("de".parse::<Lang>().is_err());
+
+  assert_eq!(lang.text(Text::Grades), "Notes :");
+  assert_eq!(lang.format(Text::SessionGpa, &[&"FW 2022-2023"]), "MPC FW 2022-2023");
+  assert_eq!(Lang::En.format(Text::ExcludedSummary, &[&1, &3, &0, &0]), "Withdrawn: 1 (3 credits), pass/fail: 0 (0 credits)");
+
+  let mut repeated = course("LE EECS 2030 3.00 A", "C");
+  repeated.superseded = true;
+  assert_eq!(format::display_grade(&repeated), "C (repeated later)");
+  assert_eq!(format::display_grade_in(&repeated, lang), "C (reprise plus tard)");
+
+  assert_eq!(GradesError::Config("--parallel".to_owned()).localized(lang), "Configuration invalide : --parallel");
+  assert_eq!(GradesError::Auth.localized(Lang::En), GradesError::Auth.to_string());
+}
+
+#[test]
+fn machine_numbers () {
+
+  let gpa = Gpa { four: 3.433_333_4, nine: 7.333_333_5 };
+  let mut value = serde_json::json!({ "gpa": gpa, "credits": 3, "grade_points": null, "grades": [null, { "points": 9.900_001 }] });
+  format::round_numbers(&mut value, format::MACHINE_DECIMALS);
+  assert_eq!(value.to_string(), r#"{"credits":3,"gpa":{"four":3.4333,"nine":7.3333},"grade_points":null,"grades":[null,{"points":9.9}]}"#);
+
+  format::drop_nulls(&mut value);
+  assert_eq!(value.to_string(), r#"{"credits":3,"gpa":{"four":3.4333,"nine":7.3333},"grades":[{"points":9.9}]}"#);
+}
+
+#[test]
+fn gpa_precision () {
+  let precision = |decimals, rounding| Precision { decimals, rounding };
+
+  assert_eq!(precision(4, Rounding::Round).apply(7.333_333_5).to_string(), "7.3333");
+  assert_eq!(precision(2, Rounding::Round).apply(3.435), 3.44);
+  assert_eq!(precision(2, Rounding::Truncate).apply(3.439), 3.43);
+  assert_eq!(precision(2, Rounding::Ceil).apply(3.431), 3.44);
+  // stored as 3.2999999, which mustn't truncate to 3.2
+  assert_eq!(precision(1, Rounding::Truncate).apply(3.3), 3.3);
+  assert_eq!(precision(2, Rounding::Ceil).apply(7.0), 7.0);
+
+  assert_eq!(Precision::new(6, Rounding::Round).unwrap().apply(3.433_333_4), 3.433_333);
+  assert!(matches!(Precision::new(7, Rounding::Round), Err(GradesError::Config(_))));
+
+  assert_eq!(precision(2, Rounding::Truncate).show(7.0), "7.00");
+  assert_eq!(precision(1, Rounding::Truncate).gpa(&Gpa { four: 3.433_333_4, nine: 7.333_333_5 }).nine, 7.3);
+  assert!("floor".parse::<Rounding>().is_err());
+}
+
+#[test]
+fn grade_colors () {
+  let styles = ["A+", "A", "B+", "C", "D+", "E", "F", "W", "", "a"].iter().map(|g| format::grade_style(g)).collect::<Vec<_>>();
+  assert_eq!(styles, [Some("Fg"), Some("Fg"), Some("Fy"), Some("Fy"), Some("Fr"), Some("Fr"), Some("Fr"), None, None, None]);
+}
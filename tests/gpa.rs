@@ -0,0 +1,496 @@
+use std::collections::HashMap;
+
+use grades_list::{CourseData, Gpa, GradesError};
+use grades_list::classify::{self, CreditSummary};
+use grades_list::config::{Config, LocalChanges};
+use grades_list::convert::Conversion;
+use grades_list::filter::CourseFilter;
+use grades_list::projection;
+use grades_list::scale::{LETTERS, ScaleRegistry};
+use grades_list::sessions::{self, Period};
+use grades_list::sort::SortKey;
+use grades_list::target;
+use grades_list::whatif;
+
+mod common;
+use common::{course, in_session};
+
+fn grades () -> Vec<CourseData> {
+  vec![
+    course("LE EECS 2030 3.00 A", "A+"),
+    course("SC MATH 1300 6.00 M", "B"),
+    course("AP ECON 1000 3.00 B", "W"),
+  ]
+}
+
+#[test]
+fn course_codes () {
+  let listed = course("LE EECS 2030 3.00 A", "A");
+  assert_eq!((listed.faculty.as_str(), listed.subject.as_str(), listed.number.as_str(), listed.credits, listed.section.as_str()), ("LE", "EECS", "2030", Some(3.0), "A"));
+  assert_eq!(listed.code(), "EECS 2030");
+
+  // older listings with a slash after the faculty, and extra spaces
+  let slashed = course("SC/MATH  1300  6.00 M", "B");
+  assert_eq!((slashed.faculty.as_str(), slashed.subject.as_str(), slashed.number.as_str(), slashed.credits, slashed.section.as_str()), ("SC", "MATH", "1300", Some(6.0), "M"));
+
+  // a short code still parses as far as it goes, without credits
+  let short = course("GS ENG", "P");
+  assert_eq!((short.subject.as_str(), short.number.as_str(), short.credits, short.section.as_str()), ("ENG", "", None, ""));
+  assert_eq!(short.code(), "ENG");
+  assert_eq!(course("LE EECS 2030 three A", "A").credits, None);
+}
+
+#[test]
+fn missing_credits () {
+  let grades = vec![
+    course("LE EECS 2030 3.00 A", "A+"),
+    course("SC MATH 1300 M", "B"),
+    course("AP ECON 1000 B", "W"),
+    course("GS ENG", ""),
+  ];
+
+  // only the letter graded row is warned about, a withdrawal or pending grade counts for nothing anyway
+  assert_eq!(grades_list::credit_warnings(&grades), ["no credit value in \"SC MATH 1300 M\", left out of the GPA"]);
+  assert_eq!(grades_list::calculate_gpa(&grades).map(|gpa| gpa.nine), Some(9.0));
+  assert_eq!(grades_list::gpa_credits(&grades), 3.0);
+
+  let mut excluded = grades.clone();
+  excluded[1].excluded = true;
+  assert!(grades_list::credit_warnings(&excluded).is_empty());
+}
+
+#[test]
+fn conversions () {
+  let grades = grades();
+
+  assert!((Conversion::Lsac.gpa(&grades).unwrap() - (4.33 * 3.0 + 3.0 * 6.0) / 9.0).abs() < 1e-4);
+  assert!((Conversion::Percentage.gpa(&grades).unwrap() - (95.0 * 3.0 + 72.0 * 6.0) / 9.0).abs() < 1e-4);
+  assert_eq!(Conversion::Omsas.points("W"), None);
+  assert_eq!(Conversion::Omsas.gpa(&grades[2..]), None);
+}
+
+#[test]
+fn custom_scale () {
+  let mut points = HashMap::new();
+  points.insert("dept".to_owned(), LETTERS.iter().map(|l| (l.to_string(), if l.starts_with('A') { 4.0 } else { 2.0 })).collect());
+  let scales = ScaleRegistry::with_custom(&points).unwrap();
+
+  assert!((scales.get("dept").unwrap().gpa(&grades()).unwrap() - (4.0 * 3.0 + 2.0 * 6.0) / 9.0).abs() < 1e-4);
+  assert_eq!(scales.get("9").unwrap().points("B+"), Some(7.0));
+  assert!(scales.get("missing").is_err());
+
+  // every letter needs points, or courses would silently drop out of the GPA
+  points.get_mut("dept").unwrap().remove("E");
+  assert!(ScaleRegistry::with_custom(&points).is_err());
+}
+
+#[test]
+fn target_grade () {
+  let scales = ScaleRegistry::default();
+  let nine = scales.get("nine").unwrap();
+
+  // a nine point GPA of 7 over 9 credits needs an 8 over the next 9 to reach 7.5
+  let solved = target::solve(&grades(), 7.5, nine, 9.0).unwrap();
+  assert_eq!((solved.current, solved.required), (7.0, 8.0));
+  assert_eq!((solved.grade.as_deref(), solved.nine, solved.four), (Some("A"), Some(8.0), Some(3.8)));
+
+  let unreachable = target::solve(&grades(), 9.0, nine, 3.0).unwrap();
+  assert_eq!((unreachable.grade, unreachable.nine, unreachable.four), (None, None, None));
+
+  assert!(matches!(target::solve(&grades(), 7.5, nine, 0.0), Err(GradesError::Config(_))));
+
+  // letters York doesn't give are never the answer, and a NaN in a config scale doesn't panic
+  let mut points = HashMap::new();
+  let mut dept = nine.points.clone();
+  dept.insert("A-".to_owned(), 8.5);
+  dept.insert("F".to_owned(), f32::NAN);
+  points.insert("dept".to_owned(), dept);
+  let scales = ScaleRegistry::with_custom(&points).unwrap();
+  let solved = target::solve(&grades(), 7.6, scales.get("dept").unwrap(), 9.0).unwrap();
+  assert_eq!((solved.grade.as_deref(), solved.nine), (Some("A+"), Some(9.0)));
+}
+
+#[test]
+fn what_if_courses () {
+  let course = whatif::hypothetical("eecs 3101 3.00 b+").unwrap();
+  assert_eq!((course.session.as_str(), course.course.as_str(), course.grade.as_str()), ("What-if", "? EECS 3101 3.00", "B+"));
+  assert_eq!(course.credits, Some(3.0));
+
+  let mut grades = grades();
+  grades.push(course);
+  assert!((grades_list::calculate_gpa(&grades).unwrap().nine - (9.0 * 3.0 + 6.0 * 6.0 + 7.0 * 3.0) / 12.0).abs() < 1e-4);
+
+  assert!(matches!(whatif::hypothetical("EECS 3101 3.00 Q"), Err(GradesError::Config(e)) if e.contains("\"Q\"")));
+  assert!(matches!(whatif::hypothetical("EECS 3101 three A"), Err(GradesError::Parse(_))));
+  assert!(matches!(whatif::hypothetical("EECS 3101 A"), Err(GradesError::Parse(_))));
+}
+
+#[test]
+fn course_points () {
+  let grades = grades();
+  let points = grades.iter().filter_map(|c| c.points()).collect::<Vec<_>>();
+  let gpa = grades_list::calculate_gpa(&grades).unwrap();
+
+  let weighted = points.iter().filter_map(|p| p.nine_point_weighted).sum::<f32>();
+  assert!((weighted / grades_list::gpa_credits(&grades) - gpa.nine).abs() < 1e-4);
+
+  assert_eq!(course("LE EECS 2030 3.00 A", "W").points(), None);
+  let ungraded = course("LE EECS 2030 A", "B+").points().unwrap();
+  assert_eq!((ungraded.nine_point, ungraded.four_point, ungraded.nine_point_weighted), (7.0, 3.3, None));
+}
+
+#[test]
+fn repeated_courses () {
+  let mut grades = vec![
+    in_session("FW 2023-2024", "LE EECS 2030 3.00 A", "B"),
+    in_session("FW 2021-2022", "LE EECS 2030 3.00 A", "D"),
+    in_session("SU 2022", "LE EECS 2030 3.00 M", "C"),
+    in_session("W 2024", "LE EECS 2030 3.00 Z", "W"),
+    in_session("FW 2022-2023", "SC MATH 1300 3.00 M", "A"),
+  ];
+  grades_list::repeats::mark_repeats(&mut grades);
+
+  // the latest graded attempt counts whatever the section or the order of the rows, a later
+  // withdrawal isn't an attempt
+  let superseded = grades.iter().map(|c| c.superseded).collect::<Vec<_>>();
+  assert_eq!(superseded, [false, true, true, false, false]);
+  assert_eq!(grades_list::calculate_gpa(&grades).unwrap().nine, 7.0);
+
+  // the same course twice in one session, the later row wins
+  let mut same_session = vec![course("LE EECS 3101 3.00 A", "C"), course("LE EECS 3101 3.00 A", "A")];
+  grades_list::repeats::mark_repeats(&mut same_session);
+  assert_eq!((same_session[0].superseded, same_session[1].superseded), (true, false));
+}
+
+#[test]
+fn cumulative_by_session () {
+  let mut grades = vec![
+    in_session("FW 2022-2023", "LE EECS 2030 3.00 A", "A"),
+    in_session("FW 2021-2022", "LE EECS 2030 3.00 A", "C"),
+    in_session("FW 2021-2022", "SC MATH 1300 3.00 M", "B"),
+  ];
+  grades_list::repeats::mark_repeats(&mut grades);
+
+  let cumulative = grades_list::sort::cumulative(&grades, false);
+  let sessions = cumulative.iter().map(|c| (c.session.as_str(), c.gpa.as_ref().map(|g| g.nine))).collect::<Vec<_>>();
+
+  // the C still counted until the course was retaken
+  assert_eq!(sessions, vec![("FW 2021-2022", Some(5.0)), ("FW 2022-2023", Some(7.0))]);
+}
+
+#[test]
+fn session_chart () {
+  let sessions = grades_list::chart::session_gpas(&grades());
+  assert_eq!(sessions.len(), 1);
+
+  let chart = grades_list::chart::bars(&sessions);
+  assert_eq!(chart, format!("FW 2022-2023  {}{}  7.00 / 3.33\n", "█".repeat(28), " ".repeat(8)));
+
+  let path = std::env::temp_dir().join("grades_list_chart.svg");
+  grades_list::chart::svg(&sessions, &path).unwrap();
+  assert!(std::fs::read_to_string(&path).unwrap().contains("FW 2022-2023"));
+  std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn grade_stats () {
+  let mut grades = grades();
+  grades.push(course("LE EECS 3101 3.00 A", "F"));
+  grades.push(course("LE EECS 1001 1.00 A", "P"));
+  let stats = grades_list::stats::stats(&grades);
+
+  let count = |grade: &str| stats.distribution.iter().find(|c| c.grade == grade).unwrap().count;
+  assert_eq!((count("A+"), count("B"), count("F"), count("A")), (1, 1, 1, 0));
+  assert_eq!(stats.median, Some("B"));
+  assert_eq!((stats.credits_attempted, stats.credits_earned), (13.0, 10.0));
+  assert_eq!(stats.best_session.unwrap().session, "FW 2022-2023");
+}
+
+#[test]
+fn academic_standing () {
+  use grades_list::standing::{self, Standing};
+
+  let report = standing::report(Some(&Gpa { four: 2.4, nine: 4.5 }));
+  assert_eq!(report.standing, Standing::Good);
+  let next = report.next.unwrap();
+  assert_eq!((next.standing, next.needed), (Standing::Honours, 0.5));
+  assert_eq!(report.margin, Some(0.5));
+
+  assert_eq!(Standing::of(Some(5.0)), Standing::Honours);
+  assert_eq!(standing::report(Some(&Gpa { four: 0.7, nine: 1.0 })).margin, None);
+
+  // nothing graded yet is neither good standing nor probation
+  assert_eq!(Standing::of(None), Standing::NotAssessed);
+  let report = standing::report(None);
+  assert_eq!(report.standing, Standing::NotAssessed);
+  assert!(report.gpa.is_none() && report.next.is_none() && report.margin.is_none());
+}
+
+#[test]
+fn degree_progress () {
+  use grades_list::progress::{self, Requirements};
+
+  let mut grades = grades();
+  grades.push(course("LE EECS 3101 3.00 A", ""));
+  grades.push(course("LE EECS 4101 3.00 A", "F"));
+  let requirements = Requirements { total: Some(120.0), upper_year: Some(36.0), level_4000: None, major: Some(60.0) };
+
+  let buckets = progress::progress(&grades, &requirements, &["EECS".to_owned()]);
+  let totals = buckets.iter().map(|b| (b.name, b.completed, b.in_progress, b.remaining)).collect::<Vec<_>>();
+  assert_eq!(totals, vec![
+    ("total", 9.0, 3.0, 111.0),
+    ("3000 level and above", 0.0, 3.0, 36.0),
+    ("major", 3.0, 3.0, 57.0),
+  ]);
+}
+
+#[test]
+fn gpa_by_level () {
+  let mut grades = grades();
+  grades.push(course("LE EECS 3101 3.00 A", "C"));
+  grades.push(course("LE EECS 4101 3.00 A", "A"));
+
+  let levels = grades_list::stats::levels(&grades);
+  let summary = levels.iter().map(|l| (l.level.as_str(), l.credits, l.gpa.as_ref().unwrap().nine)).collect::<Vec<_>>();
+  assert_eq!(summary, vec![
+    ("1000", 6.0, 6.0),
+    ("2000", 3.0, 9.0),
+    ("3000", 3.0, 4.0),
+    ("4000", 3.0, 8.0),
+    ("3000+", 6.0, 6.0),
+  ]);
+}
+
+#[test]
+fn last_credits () {
+  let grades = vec![
+    in_session("FW 2021-2022", "LE EECS 1012 3.00 A", "C"),
+    in_session("FW 2022-2023", "LE EECS 2030 3.00 A", "A"),
+    in_session("SU 2023", "LE EECS 2031 3.00 A", "B"),
+    in_session("SU 2023", "LE EECS 2001 3.00 A", "W"),
+  ];
+
+  // the session crossing the limit is taken whole
+  let recent = grades_list::sort::last_credits(&grades, 4.0);
+  assert_eq!(recent.iter().map(|c| c.number.as_str()).collect::<Vec<_>>(), vec!["2030", "2031", "2001"]);
+  assert_eq!(grades_list::calculate_gpa(&recent).unwrap().nine, 7.0);
+
+  assert_eq!(grades_list::sort::last_credits(&grades, 60.0).len(), 4);
+}
+
+#[test]
+fn extra_courses () {
+  use grades_list::extra;
+
+  let path = std::env::temp_dir().join("grades_list_extra_courses.csv");
+  std::fs::write(&path, "session,course,title,grade,in_gpa\nSU 2023,TR ECON 1000 6.00,Transfer credit,B,false\nFW 2023-2024,LE EECS 2030 3.00 A,,a,true\n").unwrap();
+  let entered = extra::load_csv(&path).unwrap();
+  std::fs::remove_file(&path).unwrap();
+  assert!(extra::load_csv(&path).unwrap().is_empty());
+
+  let mut grades = vec![course("LE EECS 2030 3.00 A", "C")];
+  extra::merge(&mut grades, &entered);
+
+  // the retake entered by hand replaces the first attempt, the transfer credit stays out of the GPA
+  assert!(grades[0].superseded);
+  assert!(grades[1].manual && grades[1].excluded);
+  assert_eq!(grades[2].grade, "A");
+  assert_eq!(grades_list::calculate_gpa(&grades).unwrap().nine, 8.0);
+}
+
+#[test]
+fn grade_overrides () {
+  let mut grades = vec![
+    in_session("FW 2021-2022", "LE EECS 3101 3.00 A", "C"),
+    course("LE EECS 3101 3.00 A", "B"),
+    course("SC MATH 1300 3.00 M", ""),
+  ];
+  grades_list::repeats::mark_repeats(&mut grades);
+  let listed = grades.clone();
+
+  let mut overrides = HashMap::new();
+  overrides.insert("le eecs 3101".to_owned(), "A".to_owned());
+  overrides.insert("MATH 1300".to_owned(), "b+".to_owned());
+  grades_list::overrides::apply(&mut grades, &overrides);
+
+  // only the latest attempt is changed
+  assert_eq!((grades[0].grade.as_str(), grades[0].overridden.as_deref()), ("C", None));
+  assert_eq!((grades[1].grade.as_str(), grades[1].overridden.as_deref()), ("A", Some("B")));
+  assert_eq!(grades_list::calculate_gpa(&grades).unwrap().nine, 7.5);
+
+  // the history compares grades as York lists them
+  assert!(grades_list::diff::diff(&listed, &grades).is_empty());
+}
+
+#[test]
+fn local_changes_are_the_owners () {
+  let config: Config = toml::from_str(r#"
+    [overrides]
+    "LE EECS 3101" = "A"
+
+    [[extra_courses]]
+    session = "FW 2020-2021"
+    course = "SC BIOL 1000 3.00"
+    grade = "B"
+    in_gpa = false
+  "#).unwrap();
+  let changes = LocalChanges {
+    extra_courses: config.extra_courses.clone(),
+    overrides: config.overrides.clone(),
+    non_degree: vec!["ESL".to_owned()],
+    include_non_degree: false,
+  };
+  let fetched = vec![
+    course("LE EECS 3101 3.00 A", "C"),
+    course("SC MATH 1300 3.00 M", "B"),
+    course("LE EECS 3900 0.00 A", "A+"),
+    course("GL ESL 1000 3.00 A", "C"),
+  ];
+  let excluded = |grades: &[CourseData]| grades.iter().filter(|c| c.excluded).map(|c| c.code()).collect::<Vec<_>>();
+
+  let owner = changes.apply(fetched.clone());
+  assert_eq!(owner.len(), 5);
+  assert_eq!((owner[0].grade.as_str(), owner[0].overridden.as_deref()), ("A", Some("C")));
+  assert_eq!(excluded(&owner), vec!["EECS 3900", "ESL 1000", "BIOL 1000"]);
+
+  // another account of serve --multi-user gets its grades as York lists them, but for the
+  // zero-credit rows
+  let other = LocalChanges::default().apply(fetched.clone());
+  assert_eq!(other[..2], fetched[..2]);
+  assert_eq!(excluded(&other), vec!["EECS 3900"]);
+
+  let included = LocalChanges { include_non_degree: true, ..changes }.apply(fetched.clone());
+  assert_eq!(excluded(&included), vec!["BIOL 1000"]);
+}
+
+#[test]
+fn credit_totals () {
+  let mut grades = grades();
+  grades.push(course("LE EECS 2030 3.00 A", "F"));
+  grades.push(course("LE EECS 3101 3.00 A", ""));
+  grades.push(course("AP PHIL 1100 3.00 A", "P"));
+  let mut transfer = course("SC BIOL 1000 3.00 A", "B");
+  transfer.excluded = true;
+  grades.push(transfer);
+
+  assert_eq!(classify::credit_summary(&grades), CreditSummary { attempted: 21.0, earned: 15.0, in_gpa: 12.0, excluded: 9.0 });
+
+  let csv = grades_list::format::csv(&grades[..1], None, Some(&classify::credit_summary(&grades))).unwrap();
+  assert!(csv.ends_with("Credits,,In GPA,,,,,12,,,,,,,\nCredits,,Excluded,,,,,9,,,,,,,\n"));
+
+  // nothing in the GPA is 0, not -0
+  let withdrawn = classify::credit_summary(&[course("AP ECON 1000 3.00 B", "W")]);
+  assert_eq!(withdrawn.in_gpa.to_string(), "0");
+}
+
+#[test]
+fn non_degree_rows () {
+  let mut grades = grades();
+  grades.push(course("LE EECS 3900 0.00 A", "A+"));
+  grades.push(course("GL ESL 1000 3.00 A", "C"));
+  let gpa = grades_list::calculate_gpa(&grades).unwrap().nine;
+
+  assert!(classify::non_degree(&grades[3], &[]));
+  assert!(!classify::non_degree(&grades[4], &[]));
+  assert!(classify::non_degree(&grades[4], &["esl".to_owned()]));
+
+  classify::exclude_non_degree(&mut grades, &["ESL".to_owned()]);
+  assert!(grades[3].excluded && grades[4].excluded && !grades[0].excluded);
+  assert!(grades_list::calculate_gpa(&grades).unwrap().nine > gpa);
+  assert_eq!(classify::credit_summary(&grades).in_gpa, 9.0);
+}
+
+#[test]
+fn projected_gpa () {
+  let mut grades = grades();
+  grades.push(course("LE EECS 3101 3.00 A", ""));
+  grades.push(course("LE EECS 3311 3.00 A", "IP"));
+  let expected = [("EECS 3101".to_owned(), "c".to_owned())].iter().cloned().collect::<HashMap<_, _>>();
+
+  let in_progress = projection::in_progress(&grades);
+  assert_eq!(in_progress.iter().map(|c| c.code()).collect::<Vec<_>>(), vec!["EECS 3101", "EECS 3311"]);
+
+  let projected = projection::project(&grades, &expected, false).unwrap();
+  let current = projected.current.unwrap().nine;
+  // A+ in both over A+ in 3.00 and B in 6.00 credits
+  assert!((projected.best.unwrap().nine - 117.0 / 15.0).abs() < 1e-5);
+  assert!(projected.expected.unwrap().nine < current);
+  assert_eq!(projected.without_expected, vec!["EECS 3311"]);
+
+  // the courses in progress aren't also listed as left out of the GPA
+  assert!(classify::excluded(&grades).iter().all(|e| !in_progress.contains(&e.course)));
+
+  let typo = [("EECS 3311".to_owned(), "B-".to_owned())].iter().cloned().collect::<HashMap<_, _>>();
+  match projection::project(&grades, &typo, false) {
+    Err(GradesError::Config(e)) => assert!(e.contains("B-"), "{}", e),
+    other => panic!("expected a config error, got {:?}", other),
+  }
+}
+
+#[test]
+fn sessions_in_order () {
+  let parsed = sessions::parse(" su 2023  s2").unwrap();
+  assert_eq!(parsed.name, "SU 2023 S2");
+  assert_eq!((parsed.period, parsed.start_year, parsed.end_year), (Period::Summer, 2023, 2023));
+  assert_eq!(sessions::parse("FW 2022-2023").unwrap().start_year, 2022);
+  assert!(sessions::parse("What-if").is_none());
+
+  let mut grades = ["What-if", "SU 2023 S2", "FW 2023-2024", "SU 2023 S1", "SU 2023", "FW 2022-2023", "SU 2022"].iter()
+    .map(|session| in_session(session, "LE EECS 2030 3.00 A", "A"))
+    .collect::<Vec<_>>();
+  grades_list::sort::sort(&mut grades, SortKey::Session, false);
+
+  let order = grades.iter().map(|c| c.session.as_str()).collect::<Vec<_>>();
+  assert_eq!(order, vec!["SU 2022", "FW 2022-2023", "SU 2023", "SU 2023 S1", "SU 2023 S2", "FW 2023-2024", "What-if"]);
+}
+
+#[test]
+fn session_filter () {
+  let grades = ["FW 2021-2022", "FW 2022-2023", "SU 2023", "SU 2023 S1", "What-if"].iter()
+    .map(|session| in_session(session, "LE EECS 2030 3.00 A", "A"))
+    .collect::<Vec<_>>();
+  let sessions = |wanted: &str| CourseFilter { session: Some(wanted.to_owned()), ..Default::default() }.apply(grades.clone())
+    .into_iter().map(|c| c.session).collect::<Vec<_>>();
+
+  // 2022 is in both fall/winter sessions, FW2022 names only the one starting then
+  assert_eq!(sessions("FW2022"), ["FW 2022-2023"]);
+  assert_eq!(sessions("fw 2021-2022"), ["FW 2021-2022"]);
+  assert_eq!(sessions("2022"), ["FW 2021-2022", "FW 2022-2023"]);
+  assert_eq!(sessions("SU2023"), ["SU 2023", "SU 2023 S1"]);
+  assert_eq!(sessions("SU2023 S1"), ["SU 2023 S1"]);
+  assert_eq!(sessions("what-if"), ["What-if"]);
+}
+
+#[test]
+fn lowercase_grades () {
+  let grades = vec![course("LE EECS 2030 3.00 A", " a+"), course("LE EECS 2011 3.00 A", "Aeg")];
+  assert_eq!(grades[0].grade, "A+");
+  assert_eq!(grades[1].grade, "Aeg");
+  assert_eq!(grades_list::calculate_gpa(&grades).unwrap().nine, 9.0);
+  assert_eq!(grades[0].points().unwrap().four_point, 4.0);
+
+  // the same for grades handed over as JSON, like to calculateGpa()
+  let json = r#"{ "session": "FW 2022-2023", "course": "LE EECS 2030 3.00 A", "title": "", "grade": "b", "credits": 3.0 }"#;
+  let parsed = serde_json::from_str::<CourseData>(json).unwrap();
+  assert_eq!(grades_list::calculate_gpa(&[parsed]).unwrap().nine, 6.0);
+}
+
+#[test]
+fn sort_keys () {
+  let mut grades = vec![
+    course("LE EECS 3101 3.00 A", "B"),
+    course("LE EECS 2030 3.00 A", "W"),
+    course("SC MATH 1300 3.00 M", "A+"),
+    course("LE EECS 2011 3.00 A", "B"),
+  ];
+  let codes = |grades: &[CourseData]| grades.iter().map(|c| c.code()).collect::<Vec<_>>();
+
+  grades_list::sort::sort(&mut grades, SortKey::Grade, false);
+  // W isn't a letter grade and goes first, the two Bs keep their order
+  assert_eq!(codes(&grades), ["EECS 2030", "EECS 3101", "EECS 2011", "MATH 1300"]);
+  grades_list::sort::sort(&mut grades, SortKey::Grade, true);
+  assert_eq!(codes(&grades), ["MATH 1300", "EECS 3101", "EECS 2011", "EECS 2030"]);
+  grades_list::sort::sort(&mut grades, SortKey::Course, false);
+  assert_eq!(codes(&grades), ["EECS 2011", "EECS 2030", "EECS 3101", "MATH 1300"]);
+  assert_eq!("level".parse::<SortKey>().unwrap_err(), "unknown sort key \"level\", expected one of grade, course, session");
+}
@@ -0,0 +1,24 @@
+use grades_list::netrc::{self, Entry};
+
+fn entry (login: &str, password: &str) -> Entry {
+  Entry { login: Some(login.to_owned()), password: Some(password.to_owned()) }
+}
+
+#[test]
+fn machine_and_default () {
+  let contents = "\
+# York
+machine example.com login other password secret
+macdef init
+machine passportyork.yorku.ca login wrong
+
+machine passportyork.yorku.ca
+  login jdoe
+  password hunter2
+default login anonymous password guest
+";
+
+  assert_eq!(netrc::find(contents, netrc::MACHINE), Some(entry("jdoe", "hunter2")));
+  assert_eq!(netrc::find(contents, "ftp.example.com"), Some(entry("anonymous", "guest")));
+  assert_eq!(netrc::find("machine example.com login a password b", netrc::MACHINE), None);
+}
@@ -0,0 +1,26 @@
+// Run with `npm test` after `npm run build`, which loads the addon through the index.js napi wrote
+const test = require("node:test");
+const assert = require("node:assert");
+
+const { calculateGpa } = require("../../index.js");
+
+const course = (course, grade, credits) => ({ session: "FW 2022-2023", course, title: "", grade, credits });
+
+test("GPA of the objects fetchGrades() returns", () => {
+  const grades = [
+    course("LE EECS 2030 3.00 A", "A+", 3),
+    course("SC MATH 1300 6.00 M", "B", 6),
+    course("AP ECON 1000 3.00 B", "W", 3),
+  ];
+
+  const gpa = calculateGpa(grades);
+  assert.ok(Math.abs(gpa.four - (4.0 * 3 + 3.0 * 6) / 9) < 1e-4, JSON.stringify(gpa));
+  assert.strictEqual(gpa.nine, 7);
+
+  // nothing graded yet
+  assert.strictEqual(calculateGpa(grades.slice(2)), null);
+});
+
+test("courses missing their keys throw", () => {
+  assert.throws(() => calculateGpa([{ course: "LE EECS 2030 3.00 A" }]), /session/);
+});
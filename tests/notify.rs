@@ -0,0 +1,195 @@
+use std::sync::{Arc, Mutex};
+
+use async_trait::async_trait;
+
+use grades_list::{Gpa, GradesError};
+use grades_list::{chat, push};
+use grades_list::diff::GradeChange;
+use grades_list::email::{self, Email, EmailConfig};
+use grades_list::notify::{self, Notifier};
+use grades_list::webhook::{ChangePayload, Webhook};
+
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::net::TcpListener;
+
+mod common;
+use common::in_session;
+
+fn changes () -> Vec<GradeChange> {
+  vec![
+    GradeChange::Changed { course: in_session("FW 2022-2023", "LE EECS 2030 3.00 A", "A"), old_grade: "B+".to_owned() },
+    GradeChange::Changed { course: in_session("FW 2023-2024", "LE EECS 3101 3.00 A", "B"), old_grade: String::new() },
+  ]
+}
+
+#[test]
+fn default_message () {
+  let message = chat::render(None, &changes(), Some(&Gpa { four: 3.5, nine: 7.25 }));
+
+  assert_eq!(message, "Grade changed for LE EECS 2030 3.00 A: B+ -> A\nGrade posted for LE EECS 3101 3.00 A: B\nGPA: 3.50 / 4, 7.25 / 9");
+}
+
+#[test]
+fn templated_message () {
+  let message = chat::render(Some("{course} ({session}): {old_grade}→{grade}"), &changes()[..1], Some(&Gpa { four: 3.5, nine: 7.25 }));
+
+  assert_eq!(message, "EECS 2030 (FW 2022-2023): B+→A\nGPA: 3.50 / 4, 7.25 / 9");
+}
+
+#[test]
+fn push_headline () {
+  let changes = changes();
+
+  assert_eq!(push::headline(&changes[0]), "Grade changed: EECS 2030 — B+ → A");
+  assert_eq!(push::headline(&changes[1]), "New grade: EECS 3101 — B");
+}
+
+/// Answers one SMTP session like a relay accepting everything, returning the message it was given
+async fn smtp (listener: TcpListener) -> String {
+  let (stream, _) = listener.accept().await.unwrap();
+  let (read, mut write) = stream.into_split();
+  let mut lines = BufReader::new(read).lines();
+  let mut message = String::new();
+
+  write.write_all(b"220 localhost ESMTP\r\n").await.unwrap();
+  while let Some(line) = lines.next_line().await.unwrap() {
+    let reply: &[u8] = match line.split(' ').next().unwrap().to_ascii_uppercase().as_str() {
+      "EHLO" | "HELO" => b"250 localhost\r\n",
+      "DATA" => {
+        write.write_all(b"354 go ahead\r\n").await.unwrap();
+        while let Some(line) = lines.next_line().await.unwrap() {
+          if line == "." {
+            break;
+          }
+          message.push_str(&line);
+          message.push('\n');
+        }
+        b"250 queued\r\n"
+      },
+      "QUIT" => {
+        write.write_all(b"221 bye\r\n").await.unwrap();
+        break;
+      },
+      _ => b"250 ok\r\n",
+    };
+    write.write_all(reply).await.unwrap();
+  }
+
+  message
+}
+
+#[tokio::test]
+async fn email_summary () {
+  let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+  let port = listener.local_addr().unwrap().port();
+  let received = tokio::spawn(smtp(listener));
+
+  let config = EmailConfig {
+    server: "127.0.0.1".to_owned(),
+    port: Some(port),
+    encryption: email::Encryption::None,
+    username: None,
+    password: None,
+    from: Some("grades@localhost".to_owned()),
+    to: "student@localhost".to_owned(),
+  };
+  Email::new(&config).unwrap().send(&changes()[..1], Some(&Gpa { four: 3.5, nine: 7.25 })).await.unwrap();
+
+  let message = received.await.unwrap();
+  assert!(message.contains("To: student@localhost"), "{}", message);
+  assert!(message.contains("Subject: Grade changed for LE EECS 2030 3.00 A: B+ -> A"));
+  assert!(message.contains("GPA: 3.50 (four point), 7.25 (nine point)"));
+  assert!(email::body(&changes(), None).ends_with("No GPA yet\n"));
+}
+
+/// Notes how many changes it was sent each time, and fails every time if `down`
+struct Recorder {
+  sent: Arc<Mutex<Vec<usize>>>,
+  down: bool,
+}
+
+#[async_trait]
+impl Notifier for Recorder {
+  fn name (&self) -> &'static str { "recorder" }
+
+  async fn notify (&self, changes: &[GradeChange], _gpa: Option<&Gpa>) -> Result<(), GradesError> {
+    self.sent.lock().unwrap().push(changes.len());
+    if self.down {
+      return Err(GradesError::Notify("connection refused".to_owned()));
+    }
+    Ok(())
+  }
+}
+
+#[tokio::test]
+async fn send_all () {
+  let (down, up) = (Arc::new(Mutex::new(Vec::new())), Arc::new(Mutex::new(Vec::new())));
+  let notifiers: Vec<Box<dyn Notifier>> = vec![
+    Box::new(Recorder { sent: down.clone(), down: true }),
+    Box::new(Recorder { sent: up.clone(), down: false }),
+  ];
+
+  // nothing changed, nothing is sent
+  assert!(notify::send_all(&notifiers, &[], None).await.is_empty());
+  assert!(down.lock().unwrap().is_empty() && up.lock().unwrap().is_empty());
+
+  // the first one failing doesn't keep the second from being sent to, and says which one failed
+  let errors = notify::send_all(&notifiers, &changes(), None).await;
+  assert_eq!(errors.len(), 1);
+  assert!(matches!(errors[0], GradesError::Notify(ref msg) if msg == "recorder: connection refused"), "{}", errors[0]);
+  assert_eq!(*down.lock().unwrap(), [2]);
+  assert_eq!(*up.lock().unwrap(), [2]);
+}
+
+/// Answers one POST per connection with each of `statuses` in turn, then the last one forever
+async fn http (listener: TcpListener, statuses: Vec<u16>, received: Arc<Mutex<usize>>) {
+  for i in 0.. {
+    let (stream, _) = listener.accept().await.unwrap();
+    let (read, mut write) = stream.into_split();
+    let mut reader = BufReader::new(read);
+    let mut length = 0;
+
+    loop {
+      let mut line = String::new();
+      reader.read_line(&mut line).await.unwrap();
+      if line.trim().is_empty() {
+        break;
+      }
+      if let Some(value) = line.to_ascii_lowercase().strip_prefix("content-length:") {
+        length = value.trim().parse().unwrap();
+      }
+    }
+    let mut body = vec![0; length];
+    reader.read_exact(&mut body).await.unwrap();
+    *received.lock().unwrap() += 1;
+
+    let status = statuses[i.min(statuses.len() - 1)];
+    let reply = format!("HTTP/1.1 {} Status\r\nContent-Length: 0\r\nConnection: close\r\n\r\n", status);
+    write.write_all(reply.as_bytes()).await.unwrap();
+  }
+}
+
+#[tokio::test(start_paused = true)]
+async fn webhook_retries () {
+  let changes = changes();
+  let payload = ChangePayload { changes: &changes, gpa: None };
+
+  // a 5xx is retried until the webhook takes it
+  let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+  let url = format!("http://{}/hook", listener.local_addr().unwrap());
+  let received = Arc::new(Mutex::new(0));
+  tokio::spawn(http(listener, vec![503, 502, 200], received.clone()));
+
+  Webhook::new(&url, &[]).unwrap().send(&payload).await.unwrap();
+  assert_eq!(*received.lock().unwrap(), 3);
+
+  // one that stays down is given up on after the fourth attempt, with the last status
+  let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+  let url = format!("http://{}/hook", listener.local_addr().unwrap());
+  let received = Arc::new(Mutex::new(0));
+  tokio::spawn(http(listener, vec![500], received.clone()));
+
+  let err = Webhook::new(&url, &[]).unwrap().send(&payload).await.unwrap_err();
+  assert!(matches!(err, GradesError::Notify(ref msg) if msg == "webhook responded with 500 Internal Server Error"), "{}", err);
+  assert_eq!(*received.lock().unwrap(), 4);
+}
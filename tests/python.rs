@@ -0,0 +1,44 @@
+#![cfg(feature = "python")]
+
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+use pyo3::wrap_pymodule;
+
+/// Runs `code` in an interpreter that has the module imported as `grades_list`
+fn run (code: &str) -> PyResult<()> {
+  pyo3::prepare_freethreaded_python();
+  Python::with_gil(|py| {
+    let globals = PyDict::new_bound(py);
+    globals.set_item("grades_list", wrap_pymodule!(grades_list::python::grades_list)(py))?;
+    py.run_bound(code, Some(&globals), None)
+  })
+}
+
+#[test]
+fn gpa_of_dicts () {
+  run(r#"
+grades = [
+  {"session": "FW 2022-2023", "course": "LE EECS 2030 3.00 A", "title": "", "grade": "A+", "credits": 3.0},
+  {"session": "FW 2022-2023", "course": "SC MATH 1300 6.00 M", "title": "", "grade": "B", "credits": 6.0},
+  {"session": "FW 2022-2023", "course": "AP ECON 1000 3.00 B", "title": "", "grade": "W", "credits": 3.0},
+]
+gpa = grades_list.calculate_gpa(grades)
+assert abs(gpa["four"] - (4.0 * 3 + 3.0 * 6) / 9) < 1e-4, gpa
+assert gpa["nine"] == 7.0, gpa
+
+# nothing graded yet
+assert grades_list.calculate_gpa(grades[2:]) is None
+"#).unwrap();
+}
+
+#[test]
+fn bad_courses_raise () {
+  run(r#"
+try:
+  grades_list.calculate_gpa([{"course": "LE EECS 2030 3.00 A"}])
+except grades_list.GradesError as e:
+  assert "session" in str(e), e
+else:
+  raise AssertionError("no GradesError")
+"#).unwrap();
+}
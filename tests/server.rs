@@ -0,0 +1,271 @@
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use axum::body::{self, Body};
+use axum::http::{Request, StatusCode};
+use serde_json::Value;
+use tokio::sync::RwLock;
+use tower::ServiceExt;
+
+use async_trait::async_trait;
+
+use grades_list::GradesError;
+use grades_list::rate_limit::{RateLimiter, RateLimits};
+use grades_list::server::{self, Accounts, Latest, Registrar, Served, ServerState, Shared};
+
+fn fetched () -> Shared {
+  let page = std::fs::read_to_string(format!("{}/tests/fixtures/course_list.html", env!("CARGO_MANIFEST_DIR"))).unwrap();
+  let grades = grades_list::parse_courses(&page).unwrap();
+  let gpa = grades_list::calculate_gpa(&grades).unwrap();
+
+  let mut state = ServerState::default();
+  state.record::<()>(Ok(Latest { fetched_at: chrono::Utc::now(), gpa: Some(gpa), grades }), Duration::from_millis(250)).unwrap();
+
+  Arc::new(RwLock::new(state))
+}
+
+async fn get (shared: Shared, uri: &str, token: Option<&str>) -> (StatusCode, String) {
+  let mut req = Request::get(uri);
+  if let Some(token) = token {
+    req = req.header("Authorization", format!("Bearer {}", token));
+  }
+
+  let resp = server::router(shared, "secret").oneshot(req.body(Body::empty()).unwrap()).await.unwrap();
+  let status = resp.status();
+  let bytes = body::to_bytes(resp.into_body(), usize::MAX).await.unwrap();
+
+  (status, String::from_utf8(bytes.to_vec()).unwrap())
+}
+
+async fn get_json (shared: Shared, uri: &str) -> (StatusCode, Value) {
+  let (status, body) = get(shared, uri, Some("secret")).await;
+  (status, serde_json::from_str(&body).unwrap())
+}
+
+#[tokio::test]
+async fn requires_token () {
+  assert_eq!(get(fetched(), "/grades", None).await.0, StatusCode::UNAUTHORIZED);
+  assert_eq!(get(fetched(), "/grades", Some("wrong")).await.0, StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn grades_and_gpa () {
+  let (status, grades) = get_json(fetched(), "/grades").await;
+  assert_eq!(status, StatusCode::OK);
+  assert_eq!(grades["grades"].as_array().unwrap().len(), 6);
+
+  let (status, gpa) = get_json(fetched(), "/gpa").await;
+  assert_eq!(status, StatusCode::OK);
+  assert!((gpa["gpa"]["nine"].as_f64().unwrap() - 22.0 / 3.0).abs() < 1e-4);
+}
+
+#[tokio::test]
+async fn one_session () {
+  let (status, session) = get_json(fetched(), "/sessions/FW2021").await;
+  assert_eq!(status, StatusCode::OK);
+  assert!(session["grades"].as_array().unwrap().iter().all(|c| c["session"] == "FW 2021-2022"));
+
+  // 2022 is also the end of FW 2021-2022, which is a different session
+  let (_, session) = get_json(fetched(), "/sessions/FW2022").await;
+  let sessions = session["grades"].as_array().unwrap().iter().map(|c| c["session"].as_str().unwrap()).collect::<Vec<_>>();
+  assert_eq!(sessions, ["FW 2022-2023", "FW 2022-2023"]);
+
+  assert_eq!(get(fetched(), "/sessions/SU1999", Some("secret")).await.0, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn before_first_fetch () {
+  assert_eq!(get(Shared::default(), "/gpa", Some("secret")).await.0, StatusCode::SERVICE_UNAVAILABLE);
+}
+
+#[tokio::test]
+async fn metrics () {
+  let (status, body) = get(fetched(), "/metrics", Some("secret")).await;
+  assert_eq!(status, StatusCode::OK);
+
+  assert!(body.contains("grades_list_scrapes_total{result=\"success\"} 1\n"));
+  assert!(body.contains("grades_list_scrape_duration_seconds 0.25\n"));
+  assert!(body.contains("grades_list_gpa{scale=\"nine\"} 7.33"));
+  assert!(body.contains("grades_list_session_gpa{session=\"FW 2021-2022\",scale=\"four\"}"));
+
+  // no GPA before the first fetch, but the scrape counters are always there
+  let (_, body) = get(Shared::default(), "/metrics", Some("secret")).await;
+  assert!(body.contains("grades_list_scrapes_total{result=\"failure\"} 0\n"));
+  assert!(!body.contains("grades_list_gpa"));
+}
+
+#[tokio::test]
+async fn feed_of_changes () {
+  let path = std::env::temp_dir().join(format!("grades_list_feed_{}.sqlite3", std::process::id()));
+  let mut history = grades_list::history::History::open(&path).unwrap();
+
+  let course = |grade: &str| grades_list::CourseData::new("FW 2023-2024".to_owned(), "LE EECS 3101 3.00 A".to_owned(), String::new(), grade.to_owned());
+  let mut feed = grades_list::feed::Feed::new("student");
+  for grade in ["", "", "B+", "A"] {
+    let grades = vec![course(grade)];
+    let id = history.record("student", &grades, Some(&grades_list::Gpa { four: 3.3, nine: 7.0 })).unwrap();
+    // the second snapshot on is only compared to the one before, and comes out like a rebuild
+    let kept = feed.recorded(&history, id).unwrap().iter().map(|e| e.id.clone()).collect::<Vec<_>>();
+    let rebuilt = grades_list::feed::entries(&history, "student", 50).unwrap().iter().map(|e| e.id.clone()).collect::<Vec<_>>();
+    assert_eq!(kept, rebuilt);
+  }
+
+  let entries = grades_list::feed::entries(&history, "student", 50).unwrap();
+  std::fs::remove_file(&path).unwrap();
+  assert_eq!(entries.len(), 2);
+  assert_eq!(entries[0].title, "Grade changed: EECS 3101 — B+ → A");
+  assert_eq!(entries[1].title, "New grade: EECS 3101 — B+");
+
+  let shared = fetched();
+  shared.write().await.changes = entries;
+
+  // feed readers can't send headers, so the token can go in the query, but only for the feed
+  let (status, body) = get(shared.clone(), "/feed?token=secret", None).await;
+  assert_eq!(status, StatusCode::OK);
+  assert!(body.contains("<title>New grade: EECS 3101 — B+</title>"));
+  assert_eq!(get(shared.clone(), "/grades?token=secret", None).await.0, StatusCode::UNAUTHORIZED);
+
+  // a token with characters a URL can't hold comes percent-encoded
+  let req = Request::get("/feed?format=atom&token=a%2Bb%2Fc%3D").body(Body::empty()).unwrap();
+  assert_eq!(server::router(shared, "a+b/c=").oneshot(req).await.unwrap().status(), StatusCode::OK);
+}
+
+async fn graphql (shared: Shared, query: &str) -> Value {
+  let req = Request::post("/graphql")
+    .header("Authorization", "Bearer secret")
+    .header("Content-Type", "application/json")
+    .body(Body::from(serde_json::json!({ "query": query }).to_string()))
+    .unwrap();
+
+  let resp = server::router(shared, "secret").oneshot(req).await.unwrap();
+  assert_eq!(resp.status(), StatusCode::OK);
+  let bytes = body::to_bytes(resp.into_body(), usize::MAX).await.unwrap();
+  serde_json::from_slice(&bytes).unwrap()
+}
+
+#[tokio::test]
+async fn graphql_queries () {
+  let resp = graphql(fetched(), r#"{ grades(session: "FW2021") { course grade } nine: gpa four: gpa(scale: FOUR) }"#).await;
+  let grades = resp["data"]["grades"].as_array().unwrap();
+  assert!(!grades.is_empty());
+  assert!(grades.iter().all(|c| c.as_object().unwrap().len() == 2));
+  assert!((resp["data"]["nine"].as_f64().unwrap() - 22.0 / 3.0).abs() < 1e-4);
+  assert!(resp["data"]["four"].as_f64().unwrap() <= 4.0);
+
+  let resp = graphql(fetched(), r#"{ gpa(subject: "NONE") }"#).await;
+  assert_eq!(resp["data"]["gpa"], Value::Null);
+
+  let resp = graphql(Shared::default(), "{ gpa }").await;
+  assert!(resp["errors"][0]["message"].as_str().unwrap().contains("haven't been fetched"));
+}
+
+#[tokio::test]
+async fn openapi_without_token () {
+  let (status, body) = get(fetched(), "/openapi.json", None).await;
+  assert_eq!(status, StatusCode::OK);
+
+  let doc: Value = serde_json::from_str(&body).unwrap();
+  assert_eq!(doc["openapi"], "3.0.3");
+  assert!(doc["paths"]["/sessions/{id}"]["get"]["responses"]["404"].is_object());
+  for path in ["/grades", "/metrics", "/feed"] {
+    assert!(doc["paths"][path]["get"]["responses"]["401"].is_object(), "{}", path);
+  }
+  assert!(doc["paths"]["/graphql"]["post"]["responses"]["401"].is_object());
+  assert_eq!(doc["paths"]["/grades"]["get"]["responses"]["200"]["content"]["application/json"]["schema"]["$ref"], "#/components/schemas/Latest");
+  assert!(doc["components"]["schemas"]["CourseData"]["properties"]["grade"].is_object());
+}
+
+/// Registers anyone whose password is "right", with the grades of the fixture
+#[derive(Default)]
+struct FakeRegistrar {
+  removed: Mutex<Vec<String>>,
+}
+
+#[async_trait]
+impl Registrar for FakeRegistrar {
+  async fn register (&self, name: &str, username: &str, password: &str) -> Result<Served, GradesError> {
+    if password != "right" {
+      return Err(GradesError::Auth);
+    }
+    Ok(Served { username: username.to_owned(), token: format!("{}-token", name), state: fetched() })
+  }
+
+  async fn remove (&self, name: &str) -> Result<(), GradesError> {
+    self.removed.lock().unwrap().push(name.to_owned());
+    Ok(())
+  }
+}
+
+async fn send (router: &axum::Router, method: &str, uri: &str, token: &str, body: Option<Value>) -> (StatusCode, Value) {
+  let req = Request::builder().method(method).uri(uri)
+    .header("Authorization", format!("Bearer {}", token))
+    .header("Content-Type", "application/json")
+    .body(body.map(|body| Body::from(body.to_string())).unwrap_or_else(Body::empty))
+    .unwrap();
+
+  let resp = router.clone().oneshot(req).await.unwrap();
+  let status = resp.status();
+  let bytes = body::to_bytes(resp.into_body(), usize::MAX).await.unwrap();
+  (status, serde_json::from_slice(&bytes).unwrap_or(Value::Null))
+}
+
+#[tokio::test]
+async fn multi_user_accounts () {
+  let registrar = Arc::new(FakeRegistrar::default());
+  let router = server::multi_user_router(Accounts::default(), "admin", registrar.clone());
+  let alice = serde_json::json!({ "name": "alice", "username": "alice1", "password": "right" });
+
+  let (status, _) = send(&router, "POST", "/admin/profiles", "admin", Some(serde_json::json!({ "name": "bob", "username": "bob1", "password": "wrong" }))).await;
+  assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+
+  let (status, registered) = send(&router, "POST", "/admin/profiles", "admin", Some(alice.clone())).await;
+  assert_eq!(status, StatusCode::CREATED);
+  assert_eq!(registered["token"], "alice-token");
+  assert_eq!(send(&router, "POST", "/admin/profiles", "admin", Some(alice)).await.0, StatusCode::CONFLICT);
+
+  // each token only reaches its own routes
+  let (status, grades) = send(&router, "GET", "/grades", "alice-token", None).await;
+  assert_eq!(status, StatusCode::OK);
+  assert_eq!(grades["grades"].as_array().unwrap().len(), 6);
+  assert_eq!(send(&router, "GET", "/grades", "admin", None).await.0, StatusCode::UNAUTHORIZED);
+  assert_eq!(send(&router, "GET", "/admin/profiles", "alice-token", None).await.0, StatusCode::UNAUTHORIZED);
+
+  let (status, profiles) = send(&router, "GET", "/admin/profiles", "admin", None).await;
+  assert_eq!(status, StatusCode::OK);
+  assert_eq!(profiles[0]["name"], "alice");
+  assert_eq!(profiles[0]["successes"], 1);
+
+  assert_eq!(send(&router, "DELETE", "/admin/profiles/alice", "admin", None).await.0, StatusCode::NO_CONTENT);
+  assert_eq!(send(&router, "DELETE", "/admin/profiles/alice", "admin", None).await.0, StatusCode::NOT_FOUND);
+  assert_eq!(send(&router, "GET", "/grades", "alice-token", None).await.0, StatusCode::UNAUTHORIZED);
+  assert_eq!(*registrar.removed.lock().unwrap(), ["alice"]);
+}
+
+#[tokio::test]
+async fn rate_limited () {
+  let router = server::limited(server::router(fetched(), "secret"), RateLimits { per_token: 2, global: 100 });
+  assert_eq!(send(&router, "GET", "/gpa", "secret", None).await.0, StatusCode::OK);
+  assert_eq!(send(&router, "GET", "/gpa", "secret", None).await.0, StatusCode::OK);
+  assert_eq!(send(&router, "GET", "/gpa", "secret", None).await.0, StatusCode::TOO_MANY_REQUESTS);
+  // another token has its own limit
+  assert_eq!(send(&router, "GET", "/gpa", "wrong", None).await.0, StatusCode::UNAUTHORIZED);
+
+  let router = server::limited(server::router(fetched(), "secret"), RateLimits { per_token: 100, global: 1 });
+  assert_eq!(send(&router, "GET", "/gpa", "wrong", None).await.0, StatusCode::UNAUTHORIZED);
+  let req = Request::get("/gpa").header("Authorization", "Bearer secret").body(Body::empty()).unwrap();
+  let resp = router.oneshot(req).await.unwrap();
+  assert_eq!(resp.status(), StatusCode::TOO_MANY_REQUESTS);
+  let retry_after: u64 = resp.headers()["retry-after"].to_str().unwrap().parse().unwrap();
+  assert!((1..=60).contains(&retry_after));
+}
+
+#[test]
+fn rate_limit_refills () {
+  let limiter = RateLimiter::new(RateLimits { per_token: 2, global: 0 });
+  let start = Instant::now();
+  assert!(limiter.check(Some("a"), start).is_ok());
+  assert!(limiter.check(Some("a"), start).is_ok());
+  assert_eq!(limiter.check(Some("a"), start), Err(Duration::from_secs(30)));
+  assert!(limiter.check(Some("b"), start).is_ok());
+  assert!(limiter.check(Some("a"), start + Duration::from_secs(30)).is_ok());
+}
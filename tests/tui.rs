@@ -0,0 +1,81 @@
+use grades_list::{CourseData, GradesError};
+use grades_list::tui::App;
+
+use ratatui::Terminal;
+use ratatui::backend::TestBackend;
+use ratatui::crossterm::event::KeyCode;
+
+fn grades () -> Vec<CourseData> {
+  let page = std::fs::read_to_string(format!("{}/tests/fixtures/course_list.html", env!("CARGO_MANIFEST_DIR"))).unwrap();
+  grades_list::parse_courses(&page).unwrap()
+}
+
+fn courses (app: &App) -> Vec<String> {
+  app.visible().iter().map(|c| format!("{} {}", c.code(), c.grade)).collect()
+}
+
+fn screen (app: &mut App) -> String {
+  let mut terminal = Terminal::new(TestBackend::new(140, 16)).unwrap();
+  terminal.draw(|frame| app.draw(frame)).unwrap();
+  let buffer = terminal.backend().buffer();
+  buffer.content().chunks(buffer.area.width as usize)
+    .map(|line| line.iter().map(|cell| cell.symbol()).collect::<String>() + "\n")
+    .collect()
+}
+
+#[test]
+fn sorting_and_filtering () {
+  let mut app = App::new(grades());
+  assert_eq!(courses(&app).len(), 6);
+
+  // the grade column, lowest first then flipped by pressing it again
+  app.key(KeyCode::Char('4'));
+  assert_eq!(courses(&app)[5], "EECS 1012 A+");
+  app.key(KeyCode::Char('4'));
+  assert_eq!(courses(&app)[0], "EECS 1012 A+");
+  assert!(screen(&mut app).contains("Grade ▼"));
+
+  // the first session, then the next one
+  app.key(KeyCode::Char('s'));
+  assert_eq!(courses(&app), ["EECS 1012 A+", "EECS 2030 C"]);
+  app.key(KeyCode::Char('s'));
+  assert_eq!(courses(&app), ["EECS 2030 B+", "MATH 1300 B"]);
+  assert!(screen(&mut app).contains("Grades - FW 2022-2023"));
+
+  // subjects go in alphabetical order, ECON has no course that session
+  app.key(KeyCode::Char('u'));
+  assert!(courses(&app).is_empty());
+  app.key(KeyCode::Char('u'));
+  assert_eq!(courses(&app), ["EECS 2030 B+"]);
+  app.key(KeyCode::Char('c'));
+  assert_eq!(courses(&app).len(), 6);
+
+  // scrolling stops at the last course
+  app.key(KeyCode::End);
+  app.key(KeyCode::Down);
+  assert_eq!(app.selected(), app.visible().last().copied());
+  assert!(!app.key(KeyCode::Char('q')));
+}
+
+#[test]
+fn refreshing () {
+  let mut app = App::new(grades());
+
+  app.key(KeyCode::Char('r'));
+  assert!(app.refreshing());
+  assert!(screen(&mut app).contains("Refreshing..."));
+
+  // a failed refresh keeps the grades there were
+  app.refreshed(Err(GradesError::Layout("no grades table".to_owned())));
+  assert!(!app.refreshing());
+  assert!(screen(&mut app).contains("Refresh failed"));
+  assert_eq!(courses(&app).len(), 6);
+
+  app.key(KeyCode::End);
+  app.key(KeyCode::Char('r'));
+  app.refreshed(Ok(grades()[..2].to_vec()));
+  assert_eq!(courses(&app).len(), 2);
+  // the selection moved back onto a course that is still there
+  assert!(app.selected().is_some());
+  assert!(screen(&mut app).contains("Fetched "));
+}
@@ -0,0 +1,177 @@
+use std::path::PathBuf;
+
+use grades_list::history::History;
+use grades_list::vault::Vault;
+use grades_list::transcript::Source;
+use grades_list::{CourseData, Gpa, GradesClient, GradesError, cache, files, session};
+
+fn temp (name: &str) -> PathBuf {
+  std::env::temp_dir().join(format!("grades_list-vault-{}-{}", std::process::id(), name))
+}
+
+fn grades () -> Vec<CourseData> {
+  let page = std::fs::read_to_string(format!("{}/tests/fixtures/course_list.html", env!("CARGO_MANIFEST_DIR"))).unwrap();
+  grades_list::parse_courses(&page).unwrap()
+}
+
+#[test]
+fn passphrase_is_checked () {
+  let path = temp("passphrase.json");
+  let vault = Vault::from_passphrase("correct horse", &path).unwrap();
+  let sealed = vault.seal(b"A+");
+
+  assert_eq!(Vault::from_passphrase("correct horse", &path).unwrap().open(&sealed).unwrap(), b"A+");
+  let wrong = Vault::from_passphrase("battery staple", &path);
+  std::fs::remove_file(&path).unwrap();
+  assert!(matches!(wrong, Err(GradesError::Storage(_))));
+}
+
+#[test]
+fn encrypted_cache () {
+  let path = temp("cache.json");
+  let vault = Vault::from_key([7; 32]);
+
+  cache::save(&path, &grades(), Some(&vault)).unwrap();
+  let data = std::fs::read(&path).unwrap();
+  assert!(!String::from_utf8_lossy(&data).contains("EECS"));
+
+  assert_eq!(cache::load(&path, Some(&vault)).unwrap().unwrap().grades, grades());
+  // another key, or none, is only a miss
+  assert!(cache::load(&path, Some(&Vault::from_key([8; 32]))).unwrap().is_none());
+  assert!(cache::load(&path, None).unwrap().is_none());
+  std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn history_encrypted_on_open () {
+  let path = temp("history.sqlite3");
+  let gpa = Gpa { four: 3.3, nine: 7.0 };
+
+  let mut plain = History::open(&path).unwrap();
+  plain.record("student", &grades(), Some(&gpa)).unwrap();
+  drop(plain);
+
+  let mut history = History::open_encrypted(&path, Vault::from_key([7; 32])).unwrap();
+  history.record("student", &grades()[..2], Some(&gpa)).unwrap();
+  let snapshots = history.list("student").unwrap();
+  assert_eq!(snapshots.iter().map(|s| s.courses).collect::<Vec<_>>(), [6, 2]);
+  assert_eq!(history.get("student", snapshots[0].id).unwrap().unwrap().grades, grades());
+  assert_eq!(snapshots[1].gpa.as_ref().unwrap().nine, 7.0);
+  drop(history);
+
+  let data = std::fs::read(&path).unwrap();
+  assert!(!String::from_utf8_lossy(&data).contains("EECS"));
+  assert!(matches!(History::open(&path).unwrap().list("student"), Err(GradesError::Storage(_))));
+  std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn history_without_gpa () {
+  // a first session, nothing graded yet
+  let in_progress = grades().into_iter().filter(|c| c.grade.is_empty()).collect::<Vec<_>>();
+  assert!(grades_list::calculate_gpa(&in_progress).is_none());
+
+  for vault in [None, Some(Vault::from_key([7; 32]))] {
+    let path = temp(&format!("ungraded-{}.sqlite3", vault.is_some()));
+    let mut history = match vault {
+      Some(vault) => History::open_encrypted(&path, vault).unwrap(),
+      None => History::open(&path).unwrap(),
+    };
+    history.record("student", &in_progress, None).unwrap();
+    history.record("student", &grades(), Some(&Gpa { four: 3.3, nine: 7.0 })).unwrap();
+
+    let snapshots = history.list("student").unwrap();
+    let first = history.get("student", snapshots[0].id).unwrap().unwrap();
+    drop(history);
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(snapshots.iter().map(|s| s.gpa.is_some()).collect::<Vec<_>>(), [false, true]);
+    assert_eq!(first.grades, in_progress);
+    assert!(first.summary.gpa.is_none());
+  }
+}
+
+#[test]
+fn history_from_before_gpa_could_be_missing () {
+  let path = temp("not-null.sqlite3");
+  let old = rusqlite::Connection::open(&path).unwrap();
+  old.execute_batch("
+    CREATE TABLE snapshots (id INTEGER PRIMARY KEY, username TEXT NOT NULL, fetched_at TEXT NOT NULL, four REAL NOT NULL, nine REAL NOT NULL);
+    CREATE TABLE courses (snapshot_id INTEGER NOT NULL REFERENCES snapshots(id) ON DELETE CASCADE, session TEXT NOT NULL, course TEXT NOT NULL, title TEXT NOT NULL, grade TEXT NOT NULL);
+    INSERT INTO snapshots VALUES (1, 'student', '2024-01-05T12:00:00Z', 3.3, 7.0);
+    INSERT INTO courses VALUES (1, 'FW 2023-2024', 'LE EECS 2030 3.00 A', 'Advanced Object Oriented Programming', 'B+');
+    -- in the way of the copy, which fails and is rolled back
+    CREATE TABLE snapshots_nullable (id INTEGER);
+  ").unwrap();
+
+  assert!(History::open(&path).is_err());
+  old.execute_batch("DROP TABLE snapshots_nullable").unwrap();
+  drop(old);
+
+  let mut history = History::open(&path).unwrap();
+  history.record("student", &[], None).unwrap();
+  let snapshots = history.list("student").unwrap();
+  assert_eq!(snapshots.iter().map(|s| s.gpa.is_some()).collect::<Vec<_>>(), [true, false]);
+  assert_eq!(history.get("student", 1).unwrap().unwrap().grades.len(), 1);
+  drop(history);
+  std::fs::remove_file(&path).unwrap();
+}
+
+#[cfg(unix)]
+#[test]
+fn session_file_is_private () {
+  use std::os::unix::fs::PermissionsExt;
+
+  let path = temp("session");
+  std::fs::write(&path, b"left readable by an older version").unwrap();
+  std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o644)).unwrap();
+
+  session::save(&GradesClient::new().unwrap(), &path, &Vault::from_key([7; 32])).unwrap();
+  let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+  std::fs::remove_file(&path).unwrap();
+  assert_eq!(mode & 0o777, 0o600);
+
+  let path = session::default_path("../../etc/passwd").unwrap();
+  assert_eq!(path.file_name().unwrap(), "session-.._.._etc_passwd");
+}
+
+#[test]
+fn atomic_write () {
+  let dir = temp("atomic");
+  std::fs::create_dir_all(&dir).unwrap();
+  let path = dir.join("grades.csv");
+
+  files::write_atomically(&path, b"first").unwrap();
+  files::write_atomically(&path, b"second").unwrap();
+  assert_eq!(std::fs::read(&path).unwrap(), b"second");
+  assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 1, "the temporary file was left behind");
+
+  // a directory can't be replaced by a file, the temporary one goes and the directory stays
+  let taken = dir.join("taken");
+  std::fs::create_dir(&taken).unwrap();
+  assert!(matches!(files::write_atomically(&taken, b"grades"), Err(GradesError::Io(_))));
+  assert!(taken.is_dir());
+  assert!(!dir.join(".taken.tmp").exists());
+
+  assert!(matches!(files::write_atomically(std::path::Path::new("/"), b"grades"), Err(GradesError::Config(_))));
+
+  std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[cfg(unix)]
+#[test]
+fn cache_file_is_private () {
+  use std::os::unix::fs::PermissionsExt;
+
+  let path = temp("cache");
+  std::fs::write(&path, b"left readable by an older version").unwrap();
+  std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o644)).unwrap();
+
+  cache::save(&path, &grades(), None).unwrap();
+  let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+  std::fs::remove_file(&path).unwrap();
+  assert_eq!(mode & 0o777, 0o600);
+
+  let path = cache::default_path("../../etc/passwd", Source::CourseList).unwrap();
+  assert_eq!(path.file_name().unwrap(), "grades-.._.._etc_passwd.json");
+}
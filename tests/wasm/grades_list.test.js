@@ -0,0 +1,36 @@
+// Run with `npm run test:wasm`, which builds for wasm32 and has wasm-bindgen write the bindings
+// for Node.js into tests/wasm/pkg
+const test = require("node:test");
+const assert = require("node:assert");
+const fs = require("node:fs");
+const path = require("node:path");
+
+const { parseCourses, calculateGpa } = require("./pkg/grades_list.js");
+
+const fixture = (name) => fs.readFileSync(path.join(__dirname, "..", "fixtures", name), "utf8");
+
+test("courses and GPA of the SIS page", () => {
+  const grades = parseCourses(fixture("course_list.html"));
+  assert.strictEqual(grades.length, 6);
+  assert.strictEqual(grades[0].course, "LE EECS 1012 3.00 M");
+  assert.strictEqual(grades[0].title, "Net-Centric Introduction to Computing");
+  assert.strictEqual(grades[0].grade, "A+");
+  assert.strictEqual(grades[0].credits, 3);
+  // the C of the first attempt is replaced by the B+ of the repeat
+  assert.ok(grades[1].superseded);
+
+  const gpa = calculateGpa(grades);
+  assert.ok(Math.abs(gpa.four - 3.4333) < 1e-4, JSON.stringify(gpa));
+  assert.ok(Math.abs(gpa.nine - 7.3333) < 1e-4, JSON.stringify(gpa));
+
+  // nothing graded yet
+  assert.strictEqual(calculateGpa(grades.slice(4)), null);
+});
+
+test("a page without the grades table throws", () => {
+  assert.throws(() => parseCourses(fixture("no_table.html")), /grades table/);
+});
+
+test("courses missing their keys throw", () => {
+  assert.throws(() => calculateGpa([{ course: "LE EECS 2030 3.00 A" }]), /session/);
+});